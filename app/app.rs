@@ -10,7 +10,8 @@ use coinshift::{
     miner::{self, Miner},
     node::{self, Node},
     types::{
-        self, Address, FilledTransaction, OutPoint, Output, Transaction,
+        self, Address, AuthorizedTransaction, FilledTransaction, OutPoint,
+        Output, Transaction,
         proto::mainchain::{
             self,
             generated::{validator_service_server, wallet_service_server},
@@ -35,6 +36,8 @@ use crate::cli::Config;
 pub enum Error {
     #[error("CUSF mainchain proto error")]
     CusfMainchain(#[from] coinshift::types::proto::Error),
+    #[error(transparent)]
+    InvalidL1Address(#[from] types::L1AddressError),
     #[error("io error")]
     Io(#[from] std::io::Error),
     #[error("miner error")]
@@ -60,6 +63,34 @@ pub enum Error {
     Wallet(#[from] wallet::Error),
     #[error("L1 config validation failed: {0}")]
     L1ConfigValidation(#[from] coinshift::parent_chain_rpc::Error),
+    #[error("No L1 RPC config for {0:?}")]
+    NoL1RpcConfig(types::ParentChainType),
+    #[error("L1 transaction for swap {0} is not known")]
+    NoSwapL1Txid(types::SwapId),
+    #[error("L1 transaction for swap {0} does not pay its L1 recipient")]
+    NoSwapL1Fill(types::SwapId),
+    #[error("No locked outputs found for swap {0}")]
+    NoSwapLockedOutputs(types::SwapId),
+    #[error("No wallet outputs owned by the creator of swap {0}")]
+    NoSwapCreatorOutputs(types::SwapId),
+    #[error(
+        "Open swap {0} requires an L2 claimer address (or one set when the L1 transaction was submitted)"
+    )]
+    NoSwapClaimer(types::SwapId),
+    #[error("Failed to build SPV proof for swap {swap_id}")]
+    BuildSpvProof {
+        swap_id: types::SwapId,
+        source: coinshift::parent_chain_rpc::Error,
+    },
+    #[error("SPV proofs are not supported for {0}")]
+    NoSpvParams(types::ParentChainType),
+    #[error("Failed to get L1 headers for {parent_chain}")]
+    GetL1Headers {
+        parent_chain: types::ParentChainType,
+        source: coinshift::parent_chain_rpc::Error,
+    },
+    #[error("Tracked L1 headers for {0} are up to date with the L1 node")]
+    L1HeadersUpToDate(types::ParentChainType),
 }
 
 impl From<node::Error> for Error {
@@ -155,21 +186,35 @@ impl App {
 
     /// Periodic task to sync L1 blocks for deposit scanning.
     /// Updates mainchain_reachable so the GUI and mine() can require mainchain to be up.
+    /// If an L1 endpoint for the mainchain pushes new blocks, syncs as they
+    /// are announced, and polls less often.
     async fn l1_sync_task(
         node: Arc<Node>,
         mainchain_reachable: Arc<AtomicBool>,
+        mainchain: types::ParentChainType,
     ) -> Result<(), Error> {
+        use coinshift::parent_chain_rpc::L1Notification;
         use futures::FutureExt;
         use std::time::Duration;
         const SYNC_INTERVAL: Duration = Duration::from_secs(10);
 
         tracing::info!(
-            "L1 sync task started, will check every {} seconds",
-            SYNC_INTERVAL.as_secs()
+            "L1 sync task started, will check every {} seconds, or when {:?} announces a block",
+            SYNC_INTERVAL.as_secs(),
+            mainchain
         );
 
+        let mut l1_changes = node.l1_changes().watch();
         loop {
-            tokio::time::sleep(SYNC_INTERVAL).await;
+            let _: Option<node::L1Change> = l1_changes
+                .wait([mainchain], SYNC_INTERVAL, |change| {
+                    change.parent_chain == mainchain
+                        && matches!(
+                            change.notification,
+                            L1Notification::Block(_)
+                        )
+                })
+                .await;
             tracing::trace!("L1 sync task: checking for new L1 blocks");
 
             // Get current L1 chain tip (mainchain must be up for mining and block sync)
@@ -315,27 +360,28 @@ impl App {
     fn spawn_l1_sync_task(
         node: Arc<Node>,
         mainchain_reachable: Arc<AtomicBool>,
+        mainchain: types::ParentChainType,
     ) -> JoinHandle<()> {
         spawn(
-            Self::l1_sync_task(node, mainchain_reachable).unwrap_or_else(
-                |err| {
+            Self::l1_sync_task(node, mainchain_reachable, mainchain)
+                .unwrap_or_else(|err| {
                     let err = anyhow::Error::from(err);
                     tracing::error!("L1 sync task error: {err:#}")
-                },
-            ),
+                }),
         )
     }
 
     /// Periodic task to check and update swap confirmations dynamically
     /// This works in both GUI and headless mode
+    /// Checks early when an L1 endpoint announces a block, or one of the
+    /// watched L1 transactions, on a watched parent chain.
     async fn swap_confirmation_check_task(
         node: Arc<Node>,
     ) -> Result<(), Error> {
-        use coinshift::parent_chain_rpc::{ParentChainRpcClient, RpcConfig};
-        use coinshift::types::{ParentChainType, SwapState, SwapTxId};
-        use serde::{Deserialize, Serialize};
-        use std::collections::HashMap;
-        use std::path::PathBuf;
+        use coinshift::{
+            parent_chain_rpc::L1Notification,
+            types::{ParentChainType, SwapState, SwapTxId},
+        };
         use std::time::Duration;
 
         const CHECK_INTERVAL: Duration = Duration::from_secs(10);
@@ -345,37 +391,28 @@ impl App {
             CHECK_INTERVAL.as_secs()
         );
 
-        // Helper to load RPC config (same as in GUI)
-        fn load_rpc_config(parent_chain: ParentChainType) -> Option<RpcConfig> {
-            #[derive(Clone, Serialize, Deserialize)]
-            struct LocalRpcConfig {
-                url: String,
-                user: String,
-                password: String,
-            }
-
-            let config_path = dirs::data_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join("coinshift")
-                .join("l1_rpc_configs.json");
-
-            if let Ok(file_content) = std::fs::read_to_string(&config_path)
-                && let Ok(configs) = serde_json::from_str::<
-                    HashMap<ParentChainType, LocalRpcConfig>,
-                >(&file_content)
-                && let Some(local_config) = configs.get(&parent_chain)
-            {
-                return Some(RpcConfig {
-                    url: local_config.url.clone(),
-                    user: local_config.user.clone(),
-                    password: local_config.password.clone(),
-                });
-            }
-            None
-        }
-
+        let mut l1_changes = node.l1_changes().watch();
+        // L1 transactions of the swaps checked last time
+        let mut watched = Vec::<(ParentChainType, bitcoin::Txid)>::new();
         loop {
-            tokio::time::sleep(CHECK_INTERVAL).await;
+            let watched_chains: std::collections::HashSet<_> = watched
+                .iter()
+                .map(|(parent_chain, _)| *parent_chain)
+                .collect();
+            let _: Option<node::L1Change> = l1_changes
+                .wait(
+                    watched_chains.iter().copied(),
+                    CHECK_INTERVAL,
+                    |change| match change.notification {
+                        L1Notification::Block(_) => {
+                            watched_chains.contains(&change.parent_chain)
+                        }
+                        L1Notification::Tx(txid) => {
+                            watched.contains(&(change.parent_chain, txid))
+                        }
+                    },
+                )
+                .await;
             tracing::trace!(
                 "Swap confirmation check task: checking for swap confirmations"
             );
@@ -389,7 +426,8 @@ impl App {
                 }
             };
 
-            let swaps = match node.state().load_all_swaps(&rotxn) {
+            let swaps = match node.state().load_all_swaps_with_l1_status(&rotxn)
+            {
                 Ok(swaps) => swaps,
                 Err(err) => {
                     tracing::debug!("Failed to load swaps: {err:#}");
@@ -409,6 +447,14 @@ impl App {
 
             drop(rotxn);
 
+            watched = swaps_to_check
+                .iter()
+                .filter_map(|swap| {
+                    let txid = swap.l1_txid.to_bitcoin_txid()?;
+                    Some((swap.parent_chain, txid))
+                })
+                .collect();
+
             if swaps_to_check.is_empty() {
                 continue;
             }
@@ -419,6 +465,34 @@ impl App {
                 swaps_to_check.len()
             );
 
+            // Fetch confirmations before opening the write transaction, so
+            // that it is not held during L1 requests
+            let mut fetched = Vec::with_capacity(swaps_to_check.len());
+            for swap in swaps_to_check {
+                // Get the backend for this swap's parent chain
+                let Some(backend) = node.l1_backend(swap.parent_chain) else {
+                    continue;
+                };
+                // L1 txid in canonical order for parent chain getrawtransaction
+                let l1_txid_hex = swap.l1_txid.to_hex();
+
+                // Fetch current confirmations from the backend
+                match backend.get_transaction_confirmations(&l1_txid_hex).await
+                {
+                    Ok(new_confirmations) => {
+                        fetched.push((swap, new_confirmations));
+                    }
+                    Err(err) => {
+                        tracing::debug!(
+                            swap_id = %swap.id,
+                            l1_txid = %l1_txid_hex,
+                            error = %err,
+                            "Failed to fetch confirmations from RPC (this is normal if RPC is unavailable)"
+                        );
+                    }
+                }
+            }
+
             let mut updated_count = 0;
             let mut rwtxn = match node.env().write_txn() {
                 Ok(txn) => txn,
@@ -428,92 +502,60 @@ impl App {
                 }
             };
 
-            for swap in swaps_to_check {
-                // Get RPC config for this swap's parent chain
-                if let Some(rpc_config) = load_rpc_config(swap.parent_chain) {
-                    // L1 txid in canonical order for parent chain getrawtransaction
-                    let l1_txid_hex = swap.l1_txid.to_hex();
-
-                    // Fetch current confirmations from RPC
-                    let client = ParentChainRpcClient::new(rpc_config);
-                    match client.get_transaction_confirmations(&l1_txid_hex) {
-                        Ok(new_confirmations) => {
-                            // Get current confirmations from swap state
-                            let current_confirmations = match swap.state {
-                                SwapState::WaitingConfirmations(current, _) => {
-                                    current
-                                }
-                                _ => 0,
-                            };
-
-                            // Only update if confirmations have increased
-                            if new_confirmations > current_confirmations {
-                                tracing::info!(
-                                    swap_id = %swap.id,
-                                    old_confirmations = %current_confirmations,
-                                    new_confirmations = %new_confirmations,
-                                    required = %swap.required_confirmations,
-                                    "Updating swap confirmations dynamically (headless mode)"
-                                );
-
-                                // Get current block info for reference
-                                let block_hash = match node
-                                    .state()
-                                    .try_get_tip(&rwtxn)
-                                {
-                                    Ok(Some(hash)) => hash,
-                                    Ok(None) | Err(_) => {
-                                        tracing::warn!(
-                                            "Could not get block hash for swap update"
-                                        );
-                                        continue;
-                                    }
-                                };
-                                let block_height = match node
-                                    .state()
-                                    .try_get_height(&rwtxn)
-                                {
-                                    Ok(Some(height)) => height,
-                                    Ok(None) | Err(_) => {
-                                        tracing::warn!(
-                                            "Could not get block height for swap update"
-                                        );
-                                        continue;
-                                    }
-                                };
-
-                                // Update swap with new confirmations
-                                if let Err(err) =
-                                    node.state().update_swap_l1_txid(
-                                        &mut rwtxn,
-                                        &swap.id,
-                                        swap.l1_txid.clone(),
-                                        new_confirmations,
-                                        None, // l1_claimer_address - not needed for confirmation updates
-                                        None, // l2_claimer_address - not changed on confirmation update
-                                        block_hash,
-                                        block_height,
-                                    )
-                                {
-                                    tracing::error!(
-                                        swap_id = %swap.id,
-                                        error = %err,
-                                        "Failed to update swap confirmations"
-                                    );
-                                } else {
-                                    updated_count += 1;
-                                }
-                            }
-                        }
-                        Err(err) => {
-                            tracing::debug!(
-                                swap_id = %swap.id,
-                                l1_txid = %l1_txid_hex,
-                                error = %err,
-                                "Failed to fetch confirmations from RPC (this is normal if RPC is unavailable)"
-                            );
-                        }
+            for (swap, new_confirmations) in fetched {
+                // Get current confirmations from swap state
+                let current_confirmations = match swap.state {
+                    SwapState::WaitingConfirmations(current, _) => current,
+                    _ => 0,
+                };
+
+                // Only update if confirmations have increased
+                if new_confirmations <= current_confirmations {
+                    continue;
+                }
+                tracing::info!(
+                    swap_id = %swap.id,
+                    old_confirmations = %current_confirmations,
+                    new_confirmations = %new_confirmations,
+                    required = %swap.required_confirmations,
+                    "Updating swap confirmations dynamically (headless mode)"
+                );
+
+                // Get current block info for reference
+                let block_hash = match node.state().try_get_tip(&rwtxn) {
+                    Ok(Some(hash)) => hash,
+                    Ok(None) | Err(_) => {
+                        tracing::warn!(
+                            "Could not get block hash for swap update"
+                        );
+                        continue;
                     }
+                };
+                let block_height = match node.state().try_get_height(&rwtxn) {
+                    Ok(Some(height)) => height,
+                    Ok(None) | Err(_) => {
+                        tracing::warn!(
+                            "Could not get block height for swap update"
+                        );
+                        continue;
+                    }
+                };
+
+                // Update swap with new confirmations
+                if let Err(err) = node.state().update_swap_confirmations(
+                    &mut rwtxn,
+                    &swap.id,
+                    new_confirmations,
+                    block_hash,
+                    block_height,
+                ) {
+                    tracing::error!(
+                        swap_id = %swap.id,
+                        error = %err,
+                        "Failed to update swap confirmations"
+                    );
+                } else {
+                    updated_count += 1;
                 }
             }
 
@@ -624,8 +666,10 @@ impl App {
             .unwrap_or_else(|| std::path::PathBuf::from("."))
             .join("coinshift")
             .join("l1_rpc_configs.json");
-        coinshift::parent_chain_rpc::validate_l1_config_file(
-            &l1_rpc_config_path,
+        runtime.block_on(
+            coinshift::parent_chain_rpc::validate_l1_config_file(
+                &l1_rpc_config_path,
+            ),
         )?;
 
         let wallet = Wallet::new(&config.datadir.join("wallet.mdb"))?;
@@ -681,6 +725,9 @@ impl App {
         let node_config = node::NodeConfig {
             datadir: config.datadir.clone(),
             bind_addr: config.net_addr,
+            seed_peers: config.seed_peers.clone(),
+            pinned_peers: config.pinned_peers.clone(),
+            request_quotas: config.request_quotas,
             cusf_mainchain,
             cusf_mainchain_wallet,
             network: config.network,
@@ -772,8 +819,11 @@ impl App {
         // Spawn L1 sync task to periodically check for new deposits and mainchain reachability
         tracing::info!("Spawning L1 sync task for deposit scanning");
         let mainchain_reachable = Arc::new(AtomicBool::new(false));
-        let _l1_sync_task =
-            Self::spawn_l1_sync_task(node.clone(), mainchain_reachable.clone());
+        let _l1_sync_task = Self::spawn_l1_sync_task(
+            node.clone(),
+            mainchain_reachable.clone(),
+            config.network.into(),
+        );
         tracing::info!("L1 sync task spawned");
 
         // Spawn swap confirmation check task to periodically update swap confirmations
@@ -822,7 +872,15 @@ impl App {
                 return Err(err.into());
             }
         };
+        self.send(authorized_transaction)
+    }
 
+    /// Submit an authorized transaction to the node, and update the wallet
+    fn send(
+        &self,
+        authorized_transaction: AuthorizedTransaction,
+    ) -> Result<(), Error> {
+        let txid = authorized_transaction.transaction.txid();
         tracing::debug!(%txid, "sign_and_send: Submitting transaction to node");
         match self.node.submit_transaction(authorized_transaction) {
             Ok(()) => {
@@ -859,6 +917,303 @@ impl App {
         Ok(())
     }
 
+    /// Broadcast a SwapCancel transaction for a swap, returning the swap's
+    /// locked outputs to the wallet once it has expired. Before then, the
+    /// SwapCancel is the creator's cancellation request, see
+    /// [`types::Swap::cancel_request_expiry`]. Requests, and cancellations
+    /// of L1 → L2 offers that are still awaiting a lock, spend one of the
+    /// creator's coins back to the wallet instead.
+    pub fn cancel_swap(
+        &self,
+        swap: &types::Swap,
+    ) -> Result<types::Txid, Error> {
+        let next_height =
+            self.node.try_get_height()?.map_or(0, |height| height + 1);
+        let cancel_outputs: HashMap<OutPoint, Output> =
+            if matches!(swap.state, types::SwapState::AwaitingLock)
+                || swap.cancel_request_expiry(next_height).is_some()
+            {
+                let creator_outputs: HashMap<OutPoint, Output> = self
+                    .wallet
+                    .get_utxos()?
+                    .into_iter()
+                    .filter(|(_, output)| {
+                        Some(output.address) == swap.l2_creator_address
+                            && output.content.is_value()
+                    })
+                    .take(1)
+                    .collect();
+                if creator_outputs.is_empty() {
+                    return Err(Error::NoSwapCreatorOutputs(swap.id));
+                }
+                creator_outputs
+            } else {
+                let locked_outputs: HashMap<OutPoint, Output> = self
+                    .node
+                    .get_all_utxos()?
+                    .into_iter()
+                    .filter(|(_, output)| {
+                        matches!(
+                            output.content,
+                            types::OutputContent::SwapPending {
+                                swap_id: locked_swap_id,
+                                ..
+                            } if locked_swap_id == swap.id.0
+                        )
+                    })
+                    .collect();
+                if locked_outputs.is_empty() {
+                    return Err(Error::NoSwapLockedOutputs(swap.id));
+                }
+                // SwapPending outputs are not tracked as wallet coins, but
+                // must be known to the wallet in order to sign for them
+                self.wallet.put_utxos(&locked_outputs)?;
+                locked_outputs
+            };
+        let accumulator = self.node.get_tip_accumulator()?;
+        let tx = self.wallet.create_swap_cancel_tx(
+            &accumulator,
+            swap.id,
+            cancel_outputs.into_iter().collect(),
+        )?;
+        let txid = tx.txid();
+        self.sign_and_send(tx)?;
+        Ok(txid)
+    }
+
+    /// Broadcast a SwapLock transaction, accepting an L1 → L2 offer by
+    /// locking the swap's L2 amount from this wallet. The L1 payment is
+    /// then expected at `l1_recipient_address`.
+    pub fn lock_swap(
+        &self,
+        swap: &types::Swap,
+        l1_recipient_address: String,
+        fee: bitcoin::Amount,
+    ) -> Result<types::Txid, Error> {
+        let () = swap.parent_chain.validate_address(&l1_recipient_address)?;
+        let accumulator = self.node.get_tip_accumulator()?;
+        let is_locked = |outpoint: &OutPoint| -> bool {
+            let Ok(rotxn) = self.node.env().read_txn() else {
+                return false;
+            };
+            self.node
+                .state()
+                .is_output_locked_to_swap(&rotxn, outpoint)
+                .is_ok_and(|locked| locked.is_some())
+        };
+        let tx = self.wallet.create_swap_lock_tx(
+            &accumulator,
+            swap.id,
+            swap.l2_amount,
+            l1_recipient_address,
+            fee,
+            is_locked,
+        )?;
+        let txid = tx.txid();
+        self.sign_and_send(tx)?;
+        Ok(txid)
+    }
+
+    /// Broadcast a SwapClaim transaction for a swap, paying the swap's locked
+    /// outputs to the swap recipient. Keyless-claimable swaps can be claimed
+    /// from any node; other open swaps only from the swap creator's wallet.
+    pub fn claim_swap(
+        &self,
+        swap: &types::Swap,
+        l2_claimer_address: Option<Address>,
+    ) -> Result<types::Txid, Error> {
+        let locked_outputs: Vec<(OutPoint, Output)> = self
+            .node
+            .get_all_utxos()?
+            .into_iter()
+            .filter(|(_, output)| {
+                matches!(
+                    output.content,
+                    types::OutputContent::SwapPending {
+                        swap_id: locked_swap_id,
+                        ..
+                    } if locked_swap_id == swap.id.0
+                )
+            })
+            .collect();
+        if locked_outputs.is_empty() {
+            return Err(Error::NoSwapLockedOutputs(swap.id));
+        }
+        // Pre-specified swaps pay swap.l2_recipient; open swaps pay the
+        // stored or provided claimer address
+        let recipient = swap
+            .l2_recipient
+            .or(swap.l2_claimer_address)
+            .or(l2_claimer_address)
+            .ok_or(Error::NoSwapClaimer(swap.id))?;
+        let proof = self.build_swap_claim_proof(swap)?;
+        // An L1 payment that leaves part of the swap unfilled only releases
+        // its share of the L2 amount
+        let released_value = match &proof {
+            Some(proof) if swap.partial_fills => {
+                let l1_fill = swap
+                    .l1_payment_value(&proof.transaction)
+                    .unwrap_or(bitcoin::Amount::ZERO);
+                if l1_fill < swap.remaining_l1_amount() {
+                    Some(
+                        swap.l2_amount_for_fill(l1_fill)
+                            .ok_or(Error::NoSwapL1Fill(swap.id))?,
+                    )
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        if !swap.is_keyless_claimable() {
+            // The L1 payment does not name the claimer of an open swap
+            // without a commitment, so the creator must sign the claim.
+            // SwapPending outputs are not tracked as wallet coins, but must
+            // be known to the wallet in order to sign for them
+            self.wallet
+                .put_utxos(&locked_outputs.iter().cloned().collect())?;
+        }
+        let accumulator = self.node.get_tip_accumulator()?;
+        let l2_claimer_for_tx =
+            swap.l2_recipient.is_none().then_some(recipient);
+        let tx = self.wallet.create_swap_claim_tx(
+            &accumulator,
+            swap.id,
+            recipient,
+            locked_outputs,
+            l2_claimer_for_tx,
+            proof.map(|proof| proof.to_bytes()),
+            released_value,
+        )?;
+        let txid = tx.txid();
+        let authorized_transaction = if swap.is_keyless_claimable() {
+            self.wallet.authorize_swap_claim(tx)?
+        } else {
+            self.wallet.authorize(tx)?
+        };
+        self.send(authorized_transaction)?;
+        Ok(txid)
+    }
+
+    /// Build the SPV proof of the L1 payment for a swap claim, using the
+    /// configured L1 RPC node. Returns `None` if the swap's parent chain
+    /// does not use SPV proofs.
+    ///
+    /// The proof is anchored to the best tracked L1 header chain, or to the
+    /// chain's SPV checkpoint if no headers are tracked yet.
+    pub fn build_swap_claim_proof(
+        &self,
+        swap: &types::Swap,
+    ) -> Result<Option<types::SpvProof>, Error> {
+        let Some(spv_params) = swap.parent_chain.spv_params() else {
+            return Ok(None);
+        };
+        if swap.l1_txid == types::SwapTxId::Hash32([0; 32])
+            || swap.l1_txid.to_bitcoin_txid().is_none()
+        {
+            return Err(Error::NoSwapL1Txid(swap.id));
+        }
+        let client = self.l1_rpc_client(swap.parent_chain)?;
+        let anchor = self.l1_header_anchor(swap.parent_chain, &spv_params)?;
+        let proof = self
+            .runtime
+            .block_on(client.build_spv_proof(
+                &swap.l1_txid.to_hex_rpc(),
+                swap.required_confirmations,
+                Some(anchor),
+            ))
+            .map_err(|source| Error::BuildSpvProof {
+                swap_id: swap.id,
+                source,
+            })?;
+        Ok(Some(proof))
+    }
+
+    /// Broadcast a TrackL1Headers transaction with the next L1 headers
+    /// after the best tracked L1 header chain, from the configured L1 RPC
+    /// node. Claims can only prove a limited number of headers, so the
+    /// tracked headers must be brought within that limit of an L1 payment
+    /// before it can be claimed.
+    pub fn track_l1_headers(
+        &self,
+        parent_chain: types::ParentChainType,
+        fee: bitcoin::Amount,
+    ) -> Result<types::Txid, Error> {
+        let spv_params = parent_chain
+            .spv_params()
+            .ok_or(Error::NoSpvParams(parent_chain))?;
+        let client = self.l1_rpc_client(parent_chain)?;
+        let anchor = self.l1_header_anchor(parent_chain, &spv_params)?;
+        let headers = self
+            .runtime
+            .block_on(client.get_headers_after(&anchor))
+            .map_err(|source| Error::GetL1Headers {
+                parent_chain,
+                source,
+            })?;
+        if headers.is_empty() {
+            return Err(Error::L1HeadersUpToDate(parent_chain));
+        }
+        let accumulator = self.node.get_tip_accumulator()?;
+        let is_locked = |outpoint: &OutPoint| -> bool {
+            let Ok(rotxn) = self.node.env().read_txn() else {
+                return false;
+            };
+            self.node
+                .state()
+                .is_output_locked_to_swap(&rotxn, outpoint)
+                .is_ok_and(|locked| locked.is_some())
+        };
+        let tx = self.wallet.create_track_l1_headers_tx(
+            &accumulator,
+            parent_chain,
+            headers.iter().map(bitcoin::consensus::serialize).collect(),
+            fee,
+            is_locked,
+        )?;
+        let txid = tx.txid();
+        self.sign_and_send(tx)?;
+        Ok(txid)
+    }
+
+    /// RPC client for the configured L1 node of a parent chain
+    fn l1_rpc_client(
+        &self,
+        parent_chain: types::ParentChainType,
+    ) -> Result<coinshift::parent_chain_rpc::ParentChainRpcClient, Error> {
+        let l1_rpc_config_path = dirs::data_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("coinshift")
+            .join("l1_rpc_configs.json");
+        let rpc_config =
+            coinshift::parent_chain_rpc::load_rpc_config_from_path(
+                &l1_rpc_config_path,
+                parent_chain,
+            )
+            .ok_or(Error::NoL1RpcConfig(parent_chain))?;
+        Ok(coinshift::parent_chain_rpc::ParentChainRpcClient::new(
+            rpc_config,
+            &self.node.l1_status().client(parent_chain),
+        ))
+    }
+
+    /// Tip of the best tracked L1 header chain, or the SPV checkpoint if no
+    /// headers are tracked yet
+    fn l1_header_anchor(
+        &self,
+        parent_chain: types::ParentChainType,
+        spv_params: &types::SpvParams,
+    ) -> Result<bitcoin::BlockHash, Error> {
+        let rotxn = self.node.env().read_txn().map_err(node::Error::from)?;
+        let anchor = self
+            .node
+            .state()
+            .get_l1_header_tip(&rotxn, parent_chain)
+            .map_err(node::Error::from)?
+            .unwrap_or(spv_params.checkpoint.block_hash);
+        Ok(anchor)
+    }
+
     pub fn get_new_main_address(
         &self,
     ) -> Result<bitcoin::Address<bitcoin::address::NetworkChecked>, Error> {
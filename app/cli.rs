@@ -1,12 +1,18 @@
 use std::{
+    collections::HashSet,
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::NonZeroU32,
     ops::Deref,
     path::PathBuf,
     sync::LazyLock,
 };
 
 use clap::{Arg, Parser, Subcommand};
-use coinshift::types::{Network, THIS_SIDECHAIN};
+use coinshift::{
+    net::{NodeId, RequestQuotas},
+    parent_chain_rpc::BackendKind,
+    types::{Network, ParentChainType, THIS_SIDECHAIN},
+};
 
 use crate::util::saturating_pred_level;
 
@@ -98,15 +104,31 @@ impl clap::Args for DatadirArg {
 /// Optional subcommand: init writes L1 config and exits.
 #[derive(Clone, Debug, Subcommand)]
 pub(super) enum AppSubcommand {
-    /// Write L1 RPC config (Bitcoin Signet and/or Bitcoin Cash Testnet4) and exit.
+    /// Write L1 RPC config for a parent chain and exit.
     /// Does not start the app. Use before first run or to update L1 config from CLI.
+    /// The node must be reachable, and on a network known for the parent chain.
     Init {
-        /// Enable Bitcoin Signet (predefined: localhost:38332)
+        /// Parent chain served by the node (btc, bch, ltc, signet, regtest).
+        /// Detected from the node if not set.
+        #[arg(long)]
+        l1_parent_chain: Option<ParentChainType>,
+        /// RPC URL of the L1 node
         #[arg(long)]
-        l1_signet: bool,
-        /// Enable Bitcoin Cash Testnet4 (predefined: 173.230.135.236:28332)
+        l1_url: String,
+        #[arg(default_value = "", long)]
+        l1_user: String,
+        #[arg(default_value = "", long)]
+        l1_password: String,
+        /// Read L1 RPC credentials from a Bitcoin Core cookie file instead
+        #[arg(long, conflicts_with_all = ["l1_user", "l1_password"])]
+        l1_cookie_file: Option<PathBuf>,
+        /// API served at the L1 URL: `bitcoin_core` or `esplora`
+        #[arg(default_value = "bitcoin_core", long)]
+        l1_backend: BackendKind,
+        /// ZMQ publisher of the L1 node, for `hashblock` and `rawtx`
+        /// notifications instead of polling
         #[arg(long)]
-        l1_bch_testnet4: bool,
+        l1_zmq_url: Option<String>,
     },
 }
 
@@ -153,19 +175,44 @@ pub(super) struct RunArgs {
     /// Socket address to use for P2P networking
     #[arg(default_value_t = DEFAULT_NET_ADDR, long, short)]
     net_addr: SocketAddr,
+    /// Bootstrap peer to connect to at startup, and learn other peer
+    /// addresses from. May be repeated.
+    #[arg(long = "seed-peer")]
+    seed_peers: Vec<SocketAddr>,
+    /// Node ID of a peer to allow. If set, peers are only connected to,
+    /// in either direction, if their node ID is pinned. May be repeated.
+    #[arg(long = "pinned-peer")]
+    pinned_peers: Vec<NodeId>,
     /// Set the network. Setting this may affect other defaults.
     #[arg(default_value_t, long, value_enum)]
     network: Network,
+    /// Maximum `GetBlock` requests per second to serve for each peer.
+    /// Peers that exceed a request quota are disconnected, and eventually
+    /// banned.
+    #[arg(default_value_t = RequestQuotas::default().get_block, long)]
+    peer_get_block_quota: NonZeroU32,
+    /// Maximum `GetHeaders` requests per second to serve for each peer
+    #[arg(default_value_t = RequestQuotas::default().get_headers, long)]
+    peer_get_headers_quota: NonZeroU32,
+    /// Maximum `GetPeers` requests per second to serve for each peer
+    #[arg(default_value_t = RequestQuotas::default().get_peers, long)]
+    peer_get_peers_quota: NonZeroU32,
+    /// Maximum transactions per second to accept from each peer
+    #[arg(default_value_t = RequestQuotas::default().push_transaction, long)]
+    peer_push_tx_quota: NonZeroU32,
+    /// Maximum transaction announcements per second to accept from each
+    /// peer
+    #[arg(
+        default_value_t = RequestQuotas::default().announce_transactions,
+        long
+    )]
+    peer_announce_txs_quota: NonZeroU32,
+    /// Maximum `GetTransactions` requests per second to serve for each peer
+    #[arg(default_value_t = RequestQuotas::default().get_transactions, long)]
+    peer_get_txs_quota: NonZeroU32,
     /// Socket address to host the RPC server
     #[arg(default_value_t = DEFAULT_RPC_ADDR, long, short)]
     rpc_addr: SocketAddr,
-
-    /// Enable Bitcoin Signet in L1 config before start (predefined: localhost:38332)
-    #[arg(long)]
-    pub(super) l1_signet: bool,
-    /// Enable Bitcoin Cash Testnet4 in L1 config before start (predefined: 173.230.135.236:28332)
-    #[arg(long)]
-    pub(super) l1_bch_testnet4: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -181,6 +228,9 @@ pub struct Config {
     pub net_addr: SocketAddr,
     pub network: Network,
     pub rpc_addr: SocketAddr,
+    pub seed_peers: Vec<SocketAddr>,
+    pub pinned_peers: HashSet<NodeId>,
+    pub request_quotas: RequestQuotas,
 }
 
 impl RunArgs {
@@ -217,6 +267,16 @@ impl RunArgs {
             net_addr: self.net_addr,
             network: self.network,
             rpc_addr: self.rpc_addr,
+            seed_peers: self.seed_peers,
+            pinned_peers: self.pinned_peers.into_iter().collect(),
+            request_quotas: RequestQuotas {
+                get_block: self.peer_get_block_quota,
+                get_headers: self.peer_get_headers_quota,
+                push_transaction: self.peer_push_tx_quota,
+                get_peers: self.peer_get_peers_quota,
+                announce_transactions: self.peer_announce_txs_quota,
+                get_transactions: self.peer_get_txs_quota,
+            },
         })
     }
 }
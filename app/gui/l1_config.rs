@@ -4,53 +4,59 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use coinshift::parent_chain_rpc::{self, RpcConfig as LibRpcConfig};
+use coinshift::parent_chain_rpc::{self, BackendKind, L1Endpoints, RpcConfig};
 use coinshift::types::ParentChainType;
 use eframe::egui::{self, Button, Color32, ComboBox, RichText, TextEdit};
 use poll_promise::Promise;
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-
-#[derive(Clone, Default, Deserialize, Serialize)]
-struct RpcConfig {
-    url: String,
-    user: String,
-    password: String,
-}
 
 #[derive(Clone)]
 enum ConnectionStatus {
     Unknown,
-    Connected { block_height: u64 },
+    Connected { block_height: u32 },
     Disconnected { error: String },
     Checking,
 }
 
+fn parent_chain_label(chain: ParentChainType) -> String {
+    format!("{} ({})", chain.coin_name(), chain.ticker())
+}
+
+fn backend_label(backend: BackendKind) -> &'static str {
+    match backend {
+        BackendKind::BitcoinCore => "Bitcoin Core RPC",
+        BackendKind::Esplora => "Esplora REST API",
+    }
+}
+
 pub struct L1Config {
     selected_parent_chain: ParentChainType,
     rpc_url: String,
     rpc_user: String,
     rpc_password: String,
-    configs: HashMap<ParentChainType, RpcConfig>,
+    rpc_cookie_file: String,
+    rpc_backend: BackendKind,
+    zmq_url: String,
+    configs: HashMap<ParentChainType, L1Endpoints>,
     connection_status: Arc<Mutex<ConnectionStatus>>,
-    status_promise: Option<Promise<anyhow::Result<u64>>>,
+    status_promise: Option<Promise<anyhow::Result<u32>>>,
+    /// Config to persist once its connection check succeeds
+    pending_save: Option<(ParentChainType, RpcConfig)>,
 }
 
 impl Default for L1Config {
     fn default() -> Self {
-        let supported = parent_chain_rpc::supported_l1_parent_chain_types();
-        let first = supported
-            .first()
-            .copied()
-            .unwrap_or(ParentChainType::Signet);
         Self {
-            selected_parent_chain: first,
+            selected_parent_chain: ParentChainType::Signet,
             rpc_url: String::new(),
             rpc_user: String::new(),
             rpc_password: String::new(),
+            rpc_cookie_file: String::new(),
+            rpc_backend: BackendKind::default(),
+            zmq_url: String::new(),
             configs: HashMap::new(),
             connection_status: Arc::new(Mutex::new(ConnectionStatus::Unknown)),
             status_promise: None,
+            pending_save: None,
         }
     }
 }
@@ -71,80 +77,53 @@ impl L1Config {
 
     fn load(&mut self, _ctx: &egui::Context) {
         let config_path = Self::config_file_path();
-        if let Ok(file_content) = std::fs::read_to_string(&config_path)
-            && let Ok(stored_configs) = serde_json::from_str::<
-                HashMap<ParentChainType, RpcConfig>,
-            >(&file_content)
-        {
-            // Only keep configs that match supported predefined configs
-            self.configs = HashMap::new();
-            for (chain, rpc) in &stored_configs {
-                let lib_rpc = LibRpcConfig {
-                    url: rpc.url.clone(),
-                    user: rpc.user.clone(),
-                    password: rpc.password.clone(),
-                };
-                if parent_chain_rpc::is_supported_l1_config(*chain, &lib_rpc) {
-                    self.configs.insert(*chain, rpc.clone());
-                }
-            }
-            if let Some(config) = self.configs.get(&self.selected_parent_chain)
-            {
-                self.rpc_url = config.url.clone();
-                self.rpc_user = config.user.clone();
-                self.rpc_password = config.password.clone();
-            } else {
-                self.load_predefined_for_selected();
-            }
-        } else {
-            self.load_predefined_for_selected();
-        }
+        self.configs =
+            parent_chain_rpc::load_rpc_configs_from_path(&config_path)
+                .unwrap_or_default();
+        self.load_selected_chain_fields();
     }
 
-    /// Fill URL/user/password from the predefined config for the selected chain.
-    fn load_predefined_for_selected(&mut self) {
-        let predefined = parent_chain_rpc::supported_l1_configs();
-        if let Some((_, rpc)) = predefined
-            .into_iter()
-            .find(|(c, _)| *c == self.selected_parent_chain)
-        {
-            self.rpc_url = rpc.url;
-            self.rpc_user = rpc.user;
-            self.rpc_password = rpc.password;
-        } else {
-            self.rpc_url.clear();
-            self.rpc_user.clear();
-            self.rpc_password.clear();
-        }
+    /// Fill the form from the saved config for the selected chain.
+    fn load_selected_chain_fields(&mut self) {
+        let config = self
+            .configs
+            .get(&self.selected_parent_chain)
+            .and_then(|endpoints| endpoints.primary().cloned())
+            .unwrap_or_else(|| RpcConfig {
+                url: String::new(),
+                user: String::new(),
+                password: String::new(),
+                cookie_file: None,
+                backend: BackendKind::default(),
+                zmq_url: None,
+            });
+        self.rpc_url = config.url;
+        self.rpc_user = config.user;
+        self.rpc_password = config.password;
+        self.rpc_cookie_file = config
+            .cookie_file
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.rpc_backend = config.backend;
+        self.zmq_url = config.zmq_url.unwrap_or_default();
     }
 
-    fn save(&mut self, _ctx: &egui::Context) {
-        // Only save predefined config for the selected chain
-        let config = if let Some((_, rpc)) =
-            parent_chain_rpc::supported_l1_configs()
-                .into_iter()
-                .find(|(c, _)| *c == self.selected_parent_chain)
-        {
-            RpcConfig {
-                url: rpc.url,
-                user: rpc.user,
-                password: rpc.password,
-            }
-        } else {
-            return;
-        };
-
-        tracing::info!(
-            chain = ?self.selected_parent_chain,
-            url = %config.url,
-            user = %config.user,
-            "L1 Config: saving configuration"
-        );
-
-        self.configs
-            .insert(self.selected_parent_chain, config.clone());
+    /// Config entered in the form
+    fn form_config(&self) -> RpcConfig {
+        let cookie_file = self.rpc_cookie_file.trim();
+        let zmq_url = self.zmq_url.trim();
+        RpcConfig {
+            url: self.rpc_url.trim().to_string(),
+            user: self.rpc_user.clone(),
+            password: self.rpc_password.clone(),
+            cookie_file: (!cookie_file.is_empty())
+                .then(|| PathBuf::from(cookie_file)),
+            backend: self.rpc_backend,
+            zmq_url: (!zmq_url.is_empty()).then(|| zmq_url.to_owned()),
+        }
+    }
 
-        // Persist to file
+    fn persist(&self) {
         let config_path = Self::config_file_path();
         if let Some(parent_dir) = config_path.parent() {
             drop(std::fs::create_dir_all(parent_dir));
@@ -156,104 +135,76 @@ impl L1Config {
             path = %config_path.display(),
             "L1 Config: configuration persisted to file"
         );
+    }
 
-        // Auto-check connection when saving
-        if !config.url.is_empty() {
-            self.check_connection(&config.url, &config.user, &config.password);
-        }
+    /// Check the entered config, and save it if the node is on the selected
+    /// chain. Unverified configs are not saved, since the app validates the
+    /// config file on startup.
+    fn save(&mut self, _ctx: &egui::Context) {
+        let config = self.form_config();
+        tracing::info!(
+            chain = ?self.selected_parent_chain,
+            url = %config.url,
+            user = %config.user,
+            "L1 Config: checking configuration before saving"
+        );
+        self.check_connection(config.clone());
+        self.pending_save = Some((self.selected_parent_chain, config));
     }
 
     fn load_selected_chain_config(&mut self) {
-        self.load_predefined_for_selected();
+        self.load_selected_chain_fields();
         // Reset connection status when switching chains
         *self.connection_status.lock().unwrap() = ConnectionStatus::Unknown;
         self.status_promise = None;
+        self.pending_save = None;
     }
 
-    fn check_connection(&mut self, url: &str, user: &str, password: &str) {
-        if url.is_empty() {
+    fn check_connection(&mut self, config: RpcConfig) {
+        if config.url.is_empty() {
             return;
         }
 
         tracing::info!(
-            url = %url,
-            has_auth = !user.is_empty(),
+            url = %config.url,
+            has_auth = !config.user.is_empty() || config.cookie_file.is_some(),
             "L1 Config: testing connection"
         );
 
-        let url = url.to_string();
-        let user = user.to_string();
-        let password = password.to_string();
+        let parent_chain = self.selected_parent_chain;
         let status = self.connection_status.clone();
 
         *status.lock().unwrap() = ConnectionStatus::Checking;
 
         let promise = Promise::spawn_thread("l1_rpc_check", move || {
-            Self::fetch_block_height(&url, &user, &password)
+            Self::fetch_block_height(parent_chain, &config)
         });
 
         self.status_promise = Some(promise);
+        self.pending_save = None;
     }
 
+    /// Check that the node is on the expected chain, and get its block height.
+    /// The endpoint is not in use yet, so it is tested with a client of its
+    /// own rather than the node's shared client.
     fn fetch_block_height(
-        url: &str,
-        user: &str,
-        password: &str,
-    ) -> anyhow::Result<u64> {
-        use std::time::Duration;
-
-        // Use jsonrpc "1.0" to match nodes that accept curl-style requests (e.g. BCH test4)
-        let request = json!({
-            "jsonrpc": "1.0",
-            "id": "coinshift",
-            "method": "getblockchaininfo",
-            "params": []
-        });
-
-        tracing::info!(
-            url = %url,
-            request = %serde_json::to_string(&request).unwrap_or_default(),
-            "L1 Config: connection test request"
-        );
-
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(10))
+        parent_chain: ParentChainType,
+        config: &RpcConfig,
+    ) -> anyhow::Result<u32> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
             .build()?;
-
-        let mut request_builder = client.post(url).json(&request);
-
-        // Add HTTP basic authentication if user and password are provided
-        if !user.is_empty() {
-            request_builder = request_builder.basic_auth(user, Some(password));
-        }
-
-        let response = request_builder.send()?;
-        let status = response.status();
-        let json: serde_json::Value = response.json()?;
-
-        tracing::info!(
-            status = %status,
-            response = %serde_json::to_string_pretty(&json).unwrap_or_else(|_| json.to_string()),
-            "L1 Config: connection test response"
-        );
-
-        if let Some(error) = json.get("error")
-            && !error.is_null()
-        {
-            anyhow::bail!("RPC error: {}", error);
-        }
-
-        let result = json
-            .get("result")
-            .ok_or_else(|| anyhow::anyhow!("No result in response"))?;
-
-        let blocks = result
-            .get("blocks")
-            .and_then(|v| v.as_u64())
-            .ok_or_else(|| anyhow::anyhow!("No blocks field in response"))?;
-
-        tracing::info!(block_height = blocks, "L1 Config: connection test OK");
-        Ok(blocks)
+        let block_height = runtime.block_on(async {
+            let _: ParentChainType =
+                parent_chain_rpc::detect_chain_type(config, Some(parent_chain))
+                    .await?;
+            config
+                .build_backend(&parent_chain_rpc::L1Client::default())
+                .get_block_height()
+                .await
+        })?;
+        tracing::info!(block_height, "L1 Config: connection test OK");
+        Ok(block_height)
     }
 
     fn update_status(&mut self) {
@@ -270,13 +221,27 @@ impl L1Config {
                         ConnectionStatus::Connected {
                             block_height: *block_height,
                         };
+                    if let Some((chain, config)) = self.pending_save.take() {
+                        // Redundant endpoints are only edited in the
+                        // config file, so keep them
+                        self.configs
+                            .entry(chain)
+                            .and_modify(|endpoints| {
+                                endpoints.set_primary(config.clone())
+                            })
+                            .or_insert_with(|| config.into());
+                        self.persist();
+                    }
                 }
                 Err(err) => {
                     tracing::info!(error = %err, "L1 Config: connection test failed");
+                    let error = if self.pending_save.take().is_some() {
+                        format!("{err:#} (configuration not saved)")
+                    } else {
+                        format!("{err:#}")
+                    };
                     *self.connection_status.lock().unwrap() =
-                        ConnectionStatus::Disconnected {
-                            error: format!("{err:#}"),
-                        };
+                        ConnectionStatus::Disconnected { error };
                 }
             }
             self.status_promise = None;
@@ -298,31 +263,18 @@ impl L1Config {
         ui.label("Each parent chain can have its own RPC configuration.");
         ui.add_space(10.0);
 
-        // Parent chain selection (only supported options)
+        // Parent chain selection
         ui.horizontal(|ui| {
             ui.label("Parent Chain:");
             let previous_chain = self.selected_parent_chain;
-            let supported = parent_chain_rpc::supported_l1_parent_chain_types();
-            let label = match self.selected_parent_chain {
-                ParentChainType::Signet => "Bitcoin Signet (sBTC)",
-                ParentChainType::BCH => "Bitcoin Cash Testnet 4 (BCH)",
-                _ => "Select network",
-            };
             ComboBox::from_id_salt("l1_config_parent_chain")
-                .selected_text(label)
+                .selected_text(parent_chain_label(self.selected_parent_chain))
                 .show_ui(ui, |ui| {
-                    for chain in supported {
-                        let option_label = match chain {
-                            ParentChainType::Signet => "Bitcoin Signet (sBTC)",
-                            ParentChainType::BCH => {
-                                "Bitcoin Cash Testnet 4 (BCH)"
-                            }
-                            _ => continue,
-                        };
+                    for chain in ParentChainType::all() {
                         ui.selectable_value(
                             &mut self.selected_parent_chain,
                             *chain,
-                            option_label,
+                            parent_chain_label(*chain),
                         );
                     }
                 });
@@ -343,10 +295,11 @@ impl L1Config {
         // Show chain-specific info
         ui.horizontal(|ui| {
             ui.label(RichText::new("Default RPC Port:").weak());
-            ui.label(format!(
-                "{}",
-                self.selected_parent_chain.default_rpc_port()
-            ));
+            ui.label(
+                self.selected_parent_chain
+                    .default_rpc_port()
+                    .map_or_else(|| "-".to_owned(), |port| port.to_string()),
+            );
             ui.label(RichText::new("|").weak());
             ui.label(RichText::new("Required Confirmations:").weak());
             ui.label(format!(
@@ -357,10 +310,28 @@ impl L1Config {
 
         ui.add_space(10.0);
 
+        ui.horizontal(|ui| {
+            ui.label("Backend:");
+            ComboBox::from_id_salt("l1_config_backend")
+                .selected_text(backend_label(self.rpc_backend))
+                .show_ui(ui, |ui| {
+                    for backend in
+                        [BackendKind::BitcoinCore, BackendKind::Esplora]
+                    {
+                        ui.selectable_value(
+                            &mut self.rpc_backend,
+                            backend,
+                            backend_label(backend),
+                        );
+                    }
+                });
+        });
+
+        ui.add_space(5.0);
+
         ui.horizontal(|ui| {
             ui.label("RPC URL:");
-            ui.add_enabled(
-                false,
+            ui.add(
                 TextEdit::singleline(&mut self.rpc_url)
                     .hint_text(
                         self.selected_parent_chain.default_rpc_url_hint(),
@@ -369,39 +340,68 @@ impl L1Config {
             );
         });
         ui.label(
-            RichText::new("Only predefined networks are supported. URL cannot be changed.")
-                .small()
-                .color(Color32::GRAY),
+            RichText::new(
+                "The node must be on a network known for the selected parent chain.",
+            )
+            .small()
+            .color(Color32::GRAY),
         );
 
-        ui.add_space(5.0);
+        if self.rpc_backend == BackendKind::BitcoinCore {
+            let use_cookie = !self.rpc_cookie_file.trim().is_empty();
 
-        ui.horizontal(|ui| {
-            ui.label("RPC User:");
-            ui.add_enabled(
-                false,
-                TextEdit::singleline(&mut self.rpc_user)
-                    .hint_text("rpcuser")
-                    .desired_width(300.0),
-            );
-        });
+            ui.add_space(5.0);
 
-        ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label("RPC Cookie File:");
+                ui.add(
+                    TextEdit::singleline(&mut self.rpc_cookie_file)
+                        .hint_text("leave empty to use user and password")
+                        .desired_width(300.0),
+                );
+            });
 
-        ui.horizontal(|ui| {
-            ui.label("RPC Password:");
-            ui.add_enabled(
-                false,
-                TextEdit::singleline(&mut self.rpc_password)
-                    .hint_text("rpcpassword")
-                    .password(true)
-                    .desired_width(300.0),
-            );
-        });
+            ui.add_space(5.0);
+
+            ui.horizontal(|ui| {
+                ui.label("RPC User:");
+                ui.add_enabled(
+                    !use_cookie,
+                    TextEdit::singleline(&mut self.rpc_user)
+                        .hint_text("rpcuser")
+                        .desired_width(300.0),
+                );
+            });
+
+            ui.add_space(5.0);
+
+            ui.horizontal(|ui| {
+                ui.label("RPC Password:");
+                ui.add_enabled(
+                    !use_cookie,
+                    TextEdit::singleline(&mut self.rpc_password)
+                        .hint_text("rpcpassword")
+                        .password(true)
+                        .desired_width(300.0),
+                );
+            });
+
+            ui.add_space(5.0);
+
+            ui.horizontal(|ui| {
+                ui.label("ZMQ URL:");
+                ui.add(
+                    TextEdit::singleline(&mut self.zmq_url)
+                        .hint_text("tcp://127.0.0.1:28332 (optional)")
+                        .desired_width(300.0),
+                );
+            });
+        }
 
         // Show current saved configuration
-        if let Some(saved_config) =
+        if let Some(saved_endpoints) =
             self.configs.get(&self.selected_parent_chain)
+            && let Some(saved_config) = saved_endpoints.primary()
         {
             ui.horizontal(|ui| {
                 ui.label("Current saved URL:");
@@ -421,6 +421,13 @@ impl L1Config {
                     );
                 });
             }
+            if saved_endpoints.endpoints.len() > 1 {
+                ui.label(format!(
+                    "{} redundant endpoints, quorum policy: {:?}",
+                    saved_endpoints.endpoints.len() - 1,
+                    saved_endpoints.quorum,
+                ));
+            }
         } else {
             ui.label("No RPC URL configured for this parent chain");
         }
@@ -437,16 +444,13 @@ impl L1Config {
 
         match status {
             ConnectionStatus::Unknown => {
-                // Allow check using current URL (predefined when chain selected) even if not saved yet
+                // Allow check using the entered config even if not saved yet
                 if !self.rpc_url.is_empty() {
-                    let url = self.rpc_url.clone();
-                    let user = self.rpc_user.clone();
-                    let password = self.rpc_password.clone();
                     ui.horizontal(|ui| {
                         ui.label(RichText::new("●").color(Color32::GRAY));
                         ui.label("Status: Unknown");
                         if ui.button("Check Connection").clicked() {
-                            self.check_connection(&url, &user, &password);
+                            self.check_connection(self.form_config());
                         }
                     });
                 }
@@ -470,13 +474,8 @@ impl L1Config {
                     );
                     ui.label(format!("Latest Block Height: {}", block_height));
                 });
-                if !self.rpc_url.is_empty() {
-                    let url = self.rpc_url.clone();
-                    let user = self.rpc_user.clone();
-                    let password = self.rpc_password.clone();
-                    if ui.button("Refresh").clicked() {
-                        self.check_connection(&url, &user, &password);
-                    }
+                if !self.rpc_url.is_empty() && ui.button("Refresh").clicked() {
+                    self.check_connection(self.form_config());
                 }
             }
             ConnectionStatus::Disconnected { error } => {
@@ -490,13 +489,8 @@ impl L1Config {
                 });
                 let error_msg = format!("Error: {}", error);
                 ui.label(RichText::new(error_msg).small().color(Color32::RED));
-                if !self.rpc_url.is_empty() {
-                    let url = self.rpc_url.clone();
-                    let user = self.rpc_user.clone();
-                    let password = self.rpc_password.clone();
-                    if ui.button("Retry").clicked() {
-                        self.check_connection(&url, &user, &password);
-                    }
+                if !self.rpc_url.is_empty() && ui.button("Retry").clicked() {
+                    self.check_connection(self.form_config());
                 }
             }
         }
@@ -522,22 +516,11 @@ impl L1Config {
                     chain = ?self.selected_parent_chain,
                     "L1 Config: clearing configuration"
                 );
-                self.rpc_url.clear();
-                self.rpc_user.clear();
-                self.rpc_password.clear();
                 self.configs.remove(&self.selected_parent_chain);
                 // Persist the updated configs to file
-                let config_path = Self::config_file_path();
-                if let Some(parent_dir) = config_path.parent() {
-                    drop(std::fs::create_dir_all(parent_dir));
-                }
-                if let Ok(json) = serde_json::to_string_pretty(&self.configs) {
-                    drop(std::fs::write(&config_path, json));
-                }
-                // Reset connection status
-                *self.connection_status.lock().unwrap() =
-                    ConnectionStatus::Unknown;
-                self.status_promise = None;
+                self.persist();
+                // Reset form and connection status
+                self.load_selected_chain_config();
             }
         });
 
@@ -564,22 +547,8 @@ impl L1Config {
         // Chain-specific setup hints
         ui.add_space(10.0);
         ui.label(egui::RichText::new("Setup Hints:").strong());
-        match self.selected_parent_chain {
-            ParentChainType::BTC => {
-                ui.label("Use Bitcoin Core with -txindex=1 for full transaction lookup.");
-            }
-            ParentChainType::BCH => {
-                ui.label("Use Bitcoin Cash Node (BCHN) or Bitcoin ABC with -txindex=1.");
-            }
-            ParentChainType::LTC => {
-                ui.label("Use Litecoin Core with -txindex=1 for full transaction lookup.");
-            }
-            ParentChainType::Signet => {
-                ui.label("Use Bitcoin Core with -signet -txindex=1 flags.");
-            }
-            ParentChainType::Regtest => {
-                ui.label("Use Bitcoin Core with -regtest -txindex=1 flags for local testing.");
-            }
+        if let Some(params) = self.selected_parent_chain.params() {
+            ui.label(params.setup_hint.as_str());
         }
     }
 }
@@ -1,35 +1,41 @@
-use coinshift::parent_chain_rpc;
-use coinshift::types::{Address, ParentChainType};
+use coinshift::types::{
+    Address, L1MatchPolicy, ParentChainType, Swap, SwapDirection,
+};
 use eframe::egui::{self, Button, Color32, ComboBox, RichText, TextEdit};
 
 use crate::app::App;
 
 #[derive(Debug)]
 pub struct CreateSwap {
+    direction: SwapDirection,
     parent_chain: ParentChainType,
     l1_recipient_address: String,
     l1_amount: String,
     l2_recipient: Option<String>,
     l2_amount: String,
     required_confirmations: String,
+    expires_in_blocks: String,
+    require_l1_commitment: bool,
+    allow_partial_fills: bool,
+    l1_match_policy: L1MatchPolicy,
     is_open_swap: bool,
     error_message: Option<String>,
 }
 
 impl Default for CreateSwap {
     fn default() -> Self {
-        let supported = parent_chain_rpc::supported_l1_parent_chain_types();
-        let first = supported
-            .first()
-            .copied()
-            .unwrap_or(ParentChainType::Signet);
         Self {
-            parent_chain: first,
+            direction: SwapDirection::L2ToL1,
+            parent_chain: ParentChainType::Signet,
             l1_recipient_address: String::new(),
             l1_amount: String::new(),
             l2_recipient: None,
             l2_amount: String::new(),
             required_confirmations: String::new(),
+            expires_in_blocks: String::new(),
+            require_l1_commitment: false,
+            allow_partial_fills: false,
+            l1_match_policy: L1MatchPolicy::default(),
             is_open_swap: false,
             error_message: None,
         }
@@ -38,6 +44,22 @@ impl Default for CreateSwap {
 
 impl CreateSwap {
     pub fn show(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(
+                &mut self.direction,
+                SwapDirection::L2ToL1,
+                "L2 → L1",
+            );
+            ui.selectable_value(
+                &mut self.direction,
+                SwapDirection::L1ToL2,
+                "L1 → L2",
+            );
+        });
+        if self.direction == SwapDirection::L1ToL2 {
+            self.show_l1_to_l2(app, ui);
+            return;
+        }
         ui.heading("Create Swap (L2 → L1)");
         ui.add_space(4.0);
         ui.label(
@@ -51,33 +73,7 @@ impl CreateSwap {
         ui.add_space(4.0);
         ui.label(RichText::new("What you want (L1)").strong());
 
-        ui.horizontal(|ui| {
-            ui.label("Parent chain:");
-            let supported = parent_chain_rpc::supported_l1_parent_chain_types();
-            let label = match self.parent_chain {
-                ParentChainType::Signet => "Bitcoin Signet (sBTC)",
-                ParentChainType::BCH => "Bitcoin Cash Testnet 4 (BCH)",
-                _ => "Select network",
-            };
-            ComboBox::from_id_salt("parent_chain")
-                .selected_text(label)
-                .show_ui(ui, |ui| {
-                    for chain in supported {
-                        let option_label = match chain {
-                            ParentChainType::Signet => "Bitcoin Signet (sBTC)",
-                            ParentChainType::BCH => {
-                                "Bitcoin Cash Testnet 4 (BCH)"
-                            }
-                            _ => continue,
-                        };
-                        ui.selectable_value(
-                            &mut self.parent_chain,
-                            *chain,
-                            option_label,
-                        );
-                    }
-                });
-        });
+        self.show_parent_chain(ui);
 
         ui.horizontal(|ui| {
             ui.label("Your L1 address:");
@@ -86,6 +82,16 @@ impl CreateSwap {
                     .hint_text("Where you receive the L1 coins (e.g. bc1...)"),
             );
         });
+        let l1_recipient_address_check = self
+            .parent_chain
+            .validate_address(&self.l1_recipient_address);
+        if let Err(err) = &l1_recipient_address_check
+            && !self.l1_recipient_address.is_empty()
+        {
+            ui.label(
+                RichText::new(err.to_string()).small().color(Color32::RED),
+            );
+        }
 
         ui.horizontal(|ui| {
             ui.label(format!(
@@ -143,30 +149,11 @@ impl CreateSwap {
         ui.add_space(8.0);
         ui.label(RichText::new("Options").strong());
 
-        ui.horizontal(|ui| {
-            ui.label("Required L1 confirmations:");
-            ui.add(
-                TextEdit::singleline(&mut self.required_confirmations)
-                    .hint_text("leave empty for default"),
-            );
-            ui.label(format!(
-                "(default: {})",
-                self.parent_chain.default_confirmations()
-            ));
-        });
+        self.show_options(ui);
 
         ui.separator();
 
-        // Display error message if any
-        if let Some(error_msg) = &self.error_message {
-            ui.add_space(5.0);
-            ui.label(
-                RichText::new(format!("Error: {}", error_msg))
-                    .small()
-                    .color(Color32::RED),
-            );
-            ui.separator();
-        }
+        self.show_error(ui);
 
         // Parse inputs
         let l1_amount = bitcoin::Amount::from_str_in(
@@ -182,6 +169,7 @@ impl CreateSwap {
             .parse::<u32>()
             .ok()
             .or_else(|| Some(self.parent_chain.default_confirmations()));
+        let expires_in_blocks = self.parse_expires_in_blocks();
 
         let l2_recipient: Option<Address> = if self.is_open_swap {
             None
@@ -193,7 +181,8 @@ impl CreateSwap {
             && (l2_recipient.is_some() || self.is_open_swap)
             && l2_amount.is_ok()
             && l1_amount.is_ok()
-            && !self.l1_recipient_address.is_empty();
+            && expires_in_blocks.is_ok()
+            && l1_recipient_address_check.is_ok();
 
         if ui
             .add_enabled(is_valid, Button::new("Create Swap"))
@@ -217,6 +206,8 @@ impl CreateSwap {
             // Extract amounts for logging (before they're moved)
             let l1_amount_val = l1_amount.expect("should not happen");
             let l2_amount_val = l2_amount.expect("should not happen");
+            let expires_in_blocks =
+                expires_in_blocks.expect("should not happen");
 
             // Create a closure that checks if an outpoint is locked to a swap
             // We create a new read transaction each time to avoid lifetime issues
@@ -241,6 +232,10 @@ impl CreateSwap {
                 l2_recipient,
                 l2_amount_val,
                 required_confirmations,
+                expires_in_blocks,
+                self.require_l1_commitment,
+                self.allow_partial_fills,
+                self.effective_l1_match_policy(),
                 bitcoin::Amount::ZERO,
                 is_locked,
             ) {
@@ -255,6 +250,7 @@ impl CreateSwap {
                         l2_recipient = ?l2_recipient,
                         l2_amount = %l2_amount_val,
                         required_confirmations = ?required_confirmations,
+                        ?expires_in_blocks,
                         is_open_swap = %self.is_open_swap,
                         "Successfully created swap transaction"
                     );
@@ -270,6 +266,7 @@ impl CreateSwap {
                         l2_recipient = ?l2_recipient,
                         l2_amount = %l2_amount_val,
                         required_confirmations = ?required_confirmations,
+                        ?expires_in_blocks,
                         is_open_swap = %self.is_open_swap,
                         error = %err,
                         error_debug = ?err,
@@ -305,4 +302,317 @@ impl CreateSwap {
             self.parent_chain = ParentChainType::BTC; // Keep parent chain selection
         }
     }
+
+    /// Form for an L1 → L2 offer. An L2 holder accepts it later by locking
+    /// the L2 amount from the swap list.
+    fn show_l1_to_l2(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
+        ui.heading("Create Swap (L1 → L2)");
+        ui.add_space(4.0);
+        ui.label(
+            RichText::new("You offer to pay L1 coins and request L2. Once an L2 holder locks the L2 amount and provides their L1 address, send the L1 payment and claim the L2 coins.")
+                .small()
+                .color(Color32::GRAY),
+        );
+        ui.separator();
+
+        ui.add_space(4.0);
+        ui.label(RichText::new("What you offer (L1)").strong());
+
+        self.show_parent_chain(ui);
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Amount you pay ({})",
+                self.parent_chain.ticker()
+            ));
+            ui.add(
+                TextEdit::singleline(&mut self.l1_amount)
+                    .hint_text("e.g. 0.001"),
+            );
+        });
+
+        ui.add_space(8.0);
+        ui.label(RichText::new("What you want (L2)").strong());
+
+        ui.horizontal(|ui| {
+            ui.label("Your L2 address:");
+            ui.add(
+                TextEdit::singleline(
+                    self.l2_recipient.get_or_insert_with(String::new),
+                )
+                .hint_text("Where you receive the L2 coins"),
+            );
+            if ui.button("Use My Address").clicked()
+                && let Some(app) = app
+            {
+                match app.wallet.get_new_address() {
+                    Ok(addr) => {
+                        self.l2_recipient = Some(addr.to_string());
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to get address: {err:#}");
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("L2 amount you want:");
+            ui.add(
+                TextEdit::singleline(&mut self.l2_amount)
+                    .hint_text("e.g. 0.001"),
+            );
+        });
+
+        ui.add_space(8.0);
+        ui.label(RichText::new("Options").strong());
+
+        self.show_options(ui);
+
+        ui.separator();
+
+        self.show_error(ui);
+
+        let l1_amount = bitcoin::Amount::from_str_in(
+            &self.l1_amount,
+            bitcoin::Denomination::Bitcoin,
+        );
+        let l2_amount = bitcoin::Amount::from_str_in(
+            &self.l2_amount,
+            bitcoin::Denomination::Bitcoin,
+        );
+        let required_confirmations =
+            self.required_confirmations.parse::<u32>().ok();
+        let expires_in_blocks = self.parse_expires_in_blocks();
+        let l2_recipient: Option<Address> =
+            self.l2_recipient.as_ref().and_then(|s| s.parse().ok());
+
+        let is_valid = app.is_some()
+            && l2_recipient.is_some()
+            && l1_amount.is_ok()
+            && l2_amount.is_ok()
+            && expires_in_blocks.is_ok();
+
+        if !ui
+            .add_enabled(is_valid, Button::new("Create Swap"))
+            .clicked()
+        {
+            return;
+        }
+        self.error_message = None;
+
+        let app = app.unwrap();
+        let (
+            Ok(l1_amount),
+            Ok(l2_amount),
+            Some(l2_recipient),
+            Ok(expires_in_blocks),
+        ) = (l1_amount, l2_amount, l2_recipient, expires_in_blocks)
+        else {
+            return;
+        };
+        let accumulator = match app.node.get_tip_accumulator() {
+            Ok(acc) => acc,
+            Err(err) => {
+                let error_msg = format!("Failed to get accumulator: {err:#}");
+                tracing::error!("{}", error_msg);
+                self.error_message = Some(error_msg);
+                return;
+            }
+        };
+        let node = &app.node;
+        let is_locked = |outpoint: &coinshift::types::OutPoint| -> bool {
+            let Ok(rotxn) = node.env().read_txn() else {
+                return false;
+            };
+            node.state()
+                .is_output_locked_to_swap(&rotxn, outpoint)
+                .is_ok_and(|locked| locked.is_some())
+        };
+        let (tx, swap_id) = match app.wallet.create_l1_to_l2_swap_create_tx(
+            &accumulator,
+            self.parent_chain,
+            l1_amount,
+            l2_recipient,
+            l2_amount,
+            required_confirmations,
+            expires_in_blocks,
+            self.require_l1_commitment,
+            self.l1_match_policy,
+            bitcoin::Amount::ZERO,
+            is_locked,
+        ) {
+            Ok(result) => result,
+            Err(err) => {
+                let error_msg =
+                    format!("Failed to create swap transaction: {err:#}");
+                tracing::error!(
+                    parent_chain = ?self.parent_chain,
+                    l1_amount = %l1_amount,
+                    %l2_recipient,
+                    l2_amount = %l2_amount,
+                    error = %err,
+                    "Failed to create L1 → L2 swap transaction"
+                );
+                self.error_message = Some(error_msg);
+                return;
+            }
+        };
+        let txid = tx.txid();
+        if let Err(err) = app.sign_and_send(tx) {
+            let error_msg = format!("Failed to send transaction: {err:#}");
+            tracing::error!(
+                swap_id = %swap_id,
+                txid = %txid,
+                error = %err,
+                "Failed to send transaction: node error"
+            );
+            self.error_message = Some(error_msg);
+            return;
+        }
+
+        app.node.add_created_pending_swap(swap_id);
+        tracing::info!("Swap created: swap_id={}, txid={}", swap_id, txid);
+        let parent_chain = self.parent_chain;
+        *self = Self::default();
+        self.direction = SwapDirection::L1ToL2;
+        self.parent_chain = parent_chain;
+    }
+
+    fn show_parent_chain(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Parent chain:");
+            let label = |chain: ParentChainType| {
+                format!("{} ({})", chain.coin_name(), chain.ticker())
+            };
+            ComboBox::from_id_salt("parent_chain")
+                .selected_text(label(self.parent_chain))
+                .show_ui(ui, |ui| {
+                    // Swaps are only accepted on chains with SPV proofs
+                    for chain in ParentChainType::all()
+                        .iter()
+                        .filter(|chain| chain.spv_params().is_some())
+                    {
+                        ui.selectable_value(
+                            &mut self.parent_chain,
+                            *chain,
+                            label(*chain),
+                        );
+                    }
+                });
+        });
+    }
+
+    fn show_options(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Required L1 confirmations:");
+            ui.add(
+                TextEdit::singleline(&mut self.required_confirmations)
+                    .hint_text("leave empty for default"),
+            );
+            ui.label(format!(
+                "(default: {})",
+                self.parent_chain.default_confirmations()
+            ));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Expires after (L2 blocks):");
+            ui.add(
+                TextEdit::singleline(&mut self.expires_in_blocks)
+                    .hint_text("leave empty for no expiry"),
+            );
+            ui.label(format!("(max: {})", Swap::MAX_EXPIRY_BLOCKS));
+        });
+
+        ui.checkbox(
+            &mut self.require_l1_commitment,
+            "Require OP_RETURN commitment in the L1 payment",
+        )
+        .on_hover_text(
+            "The L1 payment must commit to this swap and the filler's L2 \
+             address, so that it cannot be matched to another swap",
+        );
+
+        if self.direction == SwapDirection::L2ToL1 {
+            ui.checkbox(&mut self.allow_partial_fills, "Allow partial fills")
+                .on_hover_text(
+                    "Several L1 payments may each fill part of the swap, and \
+                     release a proportional share of the L2 amount",
+                );
+        }
+
+        // Partial fills always sum the outputs of each payment
+        if !(self.direction == SwapDirection::L2ToL1
+            && self.allow_partial_fills)
+        {
+            ui.horizontal(|ui| {
+                ui.label("L1 payment match:");
+                let label = |policy: L1MatchPolicy| match policy {
+                    L1MatchPolicy::Exact => "Exact amount",
+                    L1MatchPolicy::AtLeast => "At least the amount",
+                    L1MatchPolicy::SumOfOutputs => "Sum of outputs",
+                };
+                ComboBox::from_id_salt("l1_match_policy")
+                    .selected_text(label(self.l1_match_policy))
+                    .show_ui(ui, |ui| {
+                        for policy in [
+                            L1MatchPolicy::Exact,
+                            L1MatchPolicy::AtLeast,
+                            L1MatchPolicy::SumOfOutputs,
+                        ] {
+                            ui.selectable_value(
+                                &mut self.l1_match_policy,
+                                policy,
+                                label(policy),
+                            );
+                        }
+                    });
+            })
+            .response
+            .on_hover_text(
+                "How an L1 payment is matched to the L1 amount: a single \
+                 output of exactly the amount, a single output of at least \
+                 the amount, or several outputs to the recipient adding up \
+                 to at least the amount",
+            );
+        }
+    }
+
+    /// The match policy to record, which must be exact with partial fills
+    fn effective_l1_match_policy(&self) -> L1MatchPolicy {
+        if self.allow_partial_fills {
+            L1MatchPolicy::Exact
+        } else {
+            self.l1_match_policy
+        }
+    }
+
+    fn show_error(&self, ui: &mut egui::Ui) {
+        // Display error message if any
+        if let Some(error_msg) = &self.error_message {
+            ui.add_space(5.0);
+            ui.label(
+                RichText::new(format!("Error: {}", error_msg))
+                    .small()
+                    .color(Color32::RED),
+            );
+            ui.separator();
+        }
+    }
+
+    /// Empty means no expiry; anything else must be a valid expiry
+    fn parse_expires_in_blocks(&self) -> Result<Option<u32>, ()> {
+        if self.expires_in_blocks.trim().is_empty() {
+            Ok(None)
+        } else {
+            self.expires_in_blocks
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|n| (1..=Swap::MAX_EXPIRY_BLOCKS).contains(n))
+                .map(Some)
+                .ok_or(())
+        }
+    }
 }
@@ -1,11 +1,8 @@
-use std::{
-    collections::HashMap,
-    time::{Duration, Instant},
-};
+use std::time::{Duration, Instant};
 
-use coinshift::parent_chain_rpc::{ParentChainRpcClient, RpcConfig};
 use coinshift::types::{
-    Address, ParentChainType, Swap, SwapId, SwapState, SwapTxId,
+    Address, L1MatchPolicy, Swap, SwapCommitment, SwapDirection, SwapId,
+    SwapState, SwapTxId,
 };
 use eframe::egui::{self, Button, ScrollArea};
 
@@ -15,6 +12,7 @@ use crate::gui::util::show_btc_amount;
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum SwapStatusFilter {
     All,
+    AwaitingLock,
     Pending,
     WaitingConfirmations,
     ReadyToClaim,
@@ -29,6 +27,7 @@ pub struct SwapList {
     l2_recipient_input: String, // L2 address that will receive the L2 amount (for L1 transaction detection)
     fetching_confirmations: bool,
     claimer_address_input: String, // L2 claimer address when claiming (for open swaps)
+    l1_lock_address_input: String, // L1 address when locking an L1 → L2 swap
     last_confirmation_check: Option<Instant>,
     checking_confirmations: bool,
     success_message: Option<String>, // Success message after claiming (contains txid)
@@ -47,6 +46,7 @@ impl Default for SwapList {
             l2_recipient_input: String::new(),
             fetching_confirmations: false,
             claimer_address_input: String::new(),
+            l1_lock_address_input: String::new(),
             last_confirmation_check: None,
             checking_confirmations: false,
             success_message: None,
@@ -76,13 +76,14 @@ impl SwapList {
             }
         };
 
-        let mut swaps_result = match app.node.state().load_all_swaps(&rotxn) {
-            Ok(swaps) => swaps,
-            Err(err) => {
-                tracing::error!("Failed to list swaps: {err:#}");
-                return;
-            }
-        };
+        let mut swaps_result =
+            match app.node.state().load_all_swaps_with_l1_status(&rotxn) {
+                Ok(swaps) => swaps,
+                Err(err) => {
+                    tracing::error!("Failed to list swaps: {err:#}");
+                    return;
+                }
+            };
 
         // Also get pending swaps from mempool
         drop(rotxn); // Release the transaction before getting mempool transactions
@@ -94,10 +95,14 @@ impl SwapList {
                     parent_chain,
                     l1_txid_bytes: _,
                     required_confirmations,
+                    expires_in_blocks: _,
                     l2_recipient,
                     l2_amount,
                     l1_recipient_address,
                     l1_amount,
+                    require_l1_commitment,
+                    allow_partial_fills,
+                    l1_match_policy,
                 } = &tx.transaction.data
                 {
                     // Check if this swap is already in the confirmed list
@@ -109,7 +114,10 @@ impl SwapList {
                             coinshift::types::SwapTxId::from_bytes(&[0u8; 32]);
                         let swap = coinshift::types::Swap::new(
                             swap_id_obj,
-                            coinshift::types::SwapDirection::L2ToL1,
+                            tx.transaction
+                                .data
+                                .swap_direction()
+                                .expect("SwapCreate has a swap direction"),
                             *parent_chain,
                             l1_txid,
                             Some(*required_confirmations),
@@ -120,6 +128,9 @@ impl SwapList {
                             0,    // Height 0 for pending (not yet in a block)
                             None, // No expiration
                             None, // Creator unknown for mempool display
+                            *require_l1_commitment,
+                            *allow_partial_fills,
+                            *l1_match_policy,
                         );
                         swaps_result.push(swap);
                         tracing::debug!(
@@ -228,6 +239,7 @@ impl SwapList {
             egui::ComboBox::from_id_salt("status_filter")
                 .selected_text(match self.status_filter {
                     SwapStatusFilter::All => "All",
+                    SwapStatusFilter::AwaitingLock => "Awaiting Lock",
                     SwapStatusFilter::Pending => "Pending",
                     SwapStatusFilter::WaitingConfirmations => {
                         "Waiting Confirmations"
@@ -242,6 +254,11 @@ impl SwapList {
                         SwapStatusFilter::All,
                         "All",
                     );
+                    ui.selectable_value(
+                        &mut self.status_filter,
+                        SwapStatusFilter::AwaitingLock,
+                        "Awaiting Lock",
+                    );
                     ui.selectable_value(
                         &mut self.status_filter,
                         SwapStatusFilter::Pending,
@@ -286,6 +303,9 @@ impl SwapList {
             .iter()
             .filter(|swap| match status_filter {
                 SwapStatusFilter::All => true,
+                SwapStatusFilter::AwaitingLock => {
+                    matches!(swap.state, SwapState::AwaitingLock)
+                }
                 SwapStatusFilter::Pending => {
                     matches!(swap.state, SwapState::Pending)
                 }
@@ -377,6 +397,7 @@ impl SwapList {
                 ui.label(egui::RichText::new("💡 Tip: Click 'Mine / Refresh Block' in the bottom panel to include this swap in a block").small().color(egui::Color32::GRAY));
             }
             ui.label(format!("Chain: {:?}", swap.parent_chain));
+            ui.label(format!("Direction: {:?}", swap.direction));
             ui.label(format!("State: {:?}", swap.state));
             ui.label(format!("L2 Amount: {}", show_btc_amount(swap.l2_amount)));
             if let Some(l1_amount) = swap.l1_amount {
@@ -393,6 +414,20 @@ impl SwapList {
             if let Some(addr) = &swap.l1_claimer_address {
                 ui.label(format!("L1 Claimer: {}", addr));
             }
+            if swap.l1_commitment_required {
+                ui.label("L1 Commitment: required");
+            }
+            if swap.l1_match_policy != L1MatchPolicy::Exact {
+                ui.label(format!("L1 Match Policy: {}", swap.l1_match_policy));
+            }
+            if swap.partial_fills {
+                ui.label(format!(
+                    "Filled: {} of {} ({} L1 payments)",
+                    show_btc_amount(swap.l1_amount_filled),
+                    show_btc_amount(swap.l1_amount.unwrap_or_default()),
+                    swap.filled_l1_txids.len()
+                ));
+            }
 
             // Show L1 transaction ID (canonical order)
             ui.label(format!("L1 TxID: {}", swap.l1_txid.to_hex()));
@@ -400,26 +435,57 @@ impl SwapList {
             // Cancel and Delete buttons (only for swap creator)
             ui.separator();
             let can_manage = self.can_manage_swap(app, swap);
+            // Before a swap in a block expires, cancelling it only requests
+            // the cancellation, which brings its expiry forward
+            let next_height = app
+                .and_then(|app| app.node.try_get_height().ok().flatten())
+                .map_or(0, |height| height + 1);
+            let request_expiry = if swap.created_at_height == 0 {
+                None
+            } else {
+                swap.cancel_request_expiry(next_height)
+            };
+            // Expired L1 → L2 swaps are cancelled by the L2 holder that locked them
+            let can_cancel = if request_expiry.is_none()
+                && swap.direction == SwapDirection::L1ToL2
+                && matches!(swap.state, SwapState::Pending)
+            {
+                app.is_some()
+            } else {
+                can_manage
+            };
+            // A request cannot postpone the expiry
+            let expires_in_grace = request_expiry.is_some_and(|request_expiry| {
+                swap.expires_at_height
+                    .is_some_and(|expires_at| expires_at <= request_expiry)
+            });
             ui.horizontal(|ui| {
-                // Cancel button (only for pending swaps, only for creator)
-                if matches!(swap.state, SwapState::Pending) {
+                // Cancel button (only for pending or awaiting-lock swaps)
+                if matches!(swap.state, SwapState::Pending | SwapState::AwaitingLock) {
                     if ui
                         .add_enabled(
-                            can_manage,
+                            can_cancel && !expires_in_grace,
                             Button::new(egui::RichText::new("❌ Cancel Swap").color(egui::Color32::ORANGE)),
                         )
                         .clicked()
                     && let Some(app) = app {
                         self.cancel_swap(app, swap);
                     }
-                    ui.label("(Unlocks outputs and marks as cancelled)");
-                    if !can_manage && app.is_some() {
+                    if let Some(request_expiry) = request_expiry {
+                        ui.label(format!("(Requests cancellation: the swap can still be claimed until it expires at height {request_expiry})"));
+                    } else {
+                        ui.label("(Returns locked outputs once the cancel transaction is mined)");
+                    }
+                    if !can_cancel && app.is_some() {
                         ui.label(egui::RichText::new("— only swap creator can cancel").small().color(egui::Color32::GRAY));
+                    } else if expires_in_grace && let Some(expires_at) = swap.expires_at_height {
+                        ui.label(egui::RichText::new(format!("— can be cancelled from height {expires_at}")).small().color(egui::Color32::GRAY));
                     }
                 }
 
-                // Delete button (only for pending or cancelled swaps, only for creator)
-                if matches!(swap.state, SwapState::Pending | SwapState::Cancelled) {
+                // Delete button (only for swaps that are not in a block yet, only for creator).
+                // Swaps in a block are consensus records, and are cancelled instead.
+                if swap.created_at_height == 0 {
                     if ui
                         .add_enabled(
                             can_manage,
@@ -429,7 +495,7 @@ impl SwapList {
                     && let Some(app) = app {
                         self.delete_swap(app, swap);
                     }
-                    ui.label("(Permanently removes from database)");
+                    ui.label("(Removes the swap transaction from the mempool)");
                     if !can_manage && app.is_some() {
                         ui.label(egui::RichText::new("— only swap creator can delete").small().color(egui::Color32::GRAY));
                     }
@@ -438,6 +504,32 @@ impl SwapList {
 
             // Action buttons based on state
             match &swap.state {
+                SwapState::AwaitingLock => {
+                    ui.separator();
+                    ui.label(egui::RichText::new("L1 → L2 Offer").heading());
+                    ui.label(format!(
+                        "The creator offers {} for {} on L2. Lock the L2 amount to accept, and receive the L1 payment at your L1 address.",
+                        swap.l1_amount.map(show_btc_amount).unwrap_or_default(),
+                        show_btc_amount(swap.l2_amount),
+                    ));
+                    ui.horizontal(|ui| {
+                        ui.label("Your L1 address:");
+                        ui.add(egui::TextEdit::singleline(&mut self.l1_lock_address_input).desired_width(400.0));
+                        if ui
+                            .add_enabled(
+                                app.is_some() && !self.l1_lock_address_input.trim().is_empty(),
+                                Button::new("Lock L2"),
+                            )
+                            .clicked()
+                        && let Some(app) = app {
+                            self.lock_swap(app, swap);
+                        }
+                    });
+                }
+                SwapState::Pending if swap.l1_commitment_required => {
+                    ui.separator();
+                    self.show_l1_commitment(ui, swap);
+                }
                 SwapState::Pending => {
                     ui.separator();
                     ui.label(egui::RichText::new("⚠️ IMPORTANT: For users filling this swap").heading().color(egui::Color32::RED));
@@ -562,14 +654,6 @@ impl SwapList {
         swap_id: &SwapId,
         l2_claimer_address: Option<Address>,
     ) {
-        let accumulator = match app.node.get_tip_accumulator() {
-            Ok(acc) => acc,
-            Err(err) => {
-                tracing::error!("Failed to get accumulator: {err:#}");
-                return;
-            }
-        };
-
         let rotxn = match app.node.env().read_txn() {
             Ok(txn) => txn,
             Err(err) => {
@@ -578,114 +662,28 @@ impl SwapList {
             }
         };
 
-        let swap = match app.node.state().get_swap(&rotxn, swap_id) {
-            Ok(Some(swap)) => swap,
-            Ok(None) => {
-                tracing::error!("Swap not found");
-                return;
-            }
-            Err(err) => {
-                tracing::error!("Failed to get swap: {err:#}");
-                return;
-            }
-        };
-
-        // Get locked outputs for this swap
-        // Note: We must query the node directly, not the wallet, because the wallet
-        // filters out SwapPending outputs. Locked outputs are identified by checking
-        // if the output content is SwapPending with the matching swap_id.
-        let all_utxos = match app.node.get_all_utxos() {
-            Ok(utxos) => utxos,
-            Err(err) => {
-                tracing::error!("Failed to get all UTXOs from node: {err:#}");
-                return;
-            }
-        };
-
-        // Find locked outputs for this swap (same pattern as RPC server)
-        let mut locked_outputs = Vec::new();
-        for (outpoint, output) in all_utxos {
-            match &output.content {
-                coinshift::types::OutputContent::SwapPending {
-                    swap_id: locked_swap_id,
-                    ..
-                } => {
-                    if *locked_swap_id == swap_id.0 {
-                        tracing::info!(
-                            "Found locked output for swap {}: {:?}",
-                            swap_id,
-                            outpoint
-                        );
-                        locked_outputs.push((outpoint, output));
-                    } else {
-                        tracing::debug!(
-                            "Output {:?} is SwapPending for different swap_id: {:?}",
-                            outpoint,
-                            locked_swap_id
-                        );
-                    }
+        let swap =
+            match app.node.state().get_swap_with_l1_status(&rotxn, swap_id) {
+                Ok(Some(swap)) => swap,
+                Ok(None) => {
+                    tracing::error!("Swap not found");
+                    return;
                 }
-                _ => {
-                    // Not a SwapPending output, skip
+                Err(err) => {
+                    tracing::error!("Failed to get swap: {err:#}");
+                    return;
                 }
-            }
-        }
-
-        if locked_outputs.is_empty() {
-            tracing::error!("No locked outputs found for swap");
-            return;
-        }
-
-        // Add locked outputs to wallet temporarily so they can be used for signing
-        // SwapPending outputs are normally filtered out, but we need them in the wallet
-        // for the authorize() call to find the address and signing key
-        use std::collections::HashMap;
-        let locked_utxos: HashMap<_, _> =
-            locked_outputs.iter().cloned().collect();
-        if let Err(err) = app.wallet.put_utxos(&locked_utxos) {
-            tracing::error!(
-                "Failed to add locked outputs to wallet for signing: {err:#}"
-            );
-            return;
-        }
-
-        // Determine recipient: pre-specified uses swap.l2_recipient; open uses stored or provided claimer address
-        let recipient = swap
-            .l2_recipient
-            .or(swap.l2_claimer_address)
-            .or(l2_claimer_address)
-            .ok_or_else(|| {
-                tracing::error!("Open swap requires claimer address (or set when L1 tx was submitted)");
-            })
-            .ok();
-
-        let recipient = match recipient {
-            Some(addr) => addr,
-            None => return,
-        };
+            };
+        drop(rotxn);
 
-        let l2_claimer_for_tx =
-            swap.l2_recipient.is_none().then_some(recipient);
-        let tx = match app.wallet.create_swap_claim_tx(
-            &accumulator,
-            *swap_id,
-            recipient,
-            locked_outputs,
-            l2_claimer_for_tx,
-        ) {
-            Ok(tx) => tx,
+        let txid = match app.claim_swap(&swap, l2_claimer_address) {
+            Ok(txid) => txid,
             Err(err) => {
-                tracing::error!("Failed to create claim transaction: {err:#}");
+                tracing::error!("Failed to claim swap: {err:#}");
                 return;
             }
         };
 
-        let txid = tx.txid();
-        if let Err(err) = app.sign_and_send(tx) {
-            tracing::error!("Failed to send transaction: {err:#}");
-            return;
-        }
-
         tracing::info!("Swap claimed: swap_id={}, txid={}", swap_id, txid);
         self.claimer_address_input.clear();
         self.success_message = Some(format!(
@@ -741,7 +739,7 @@ impl SwapList {
                 }
             };
 
-            match app.node.state().get_swap(&rotxn, &swap_id) {
+            match app.node.state().get_swap_with_l1_status(&rotxn, &swap_id) {
                 Ok(Some(swap)) => {
                     tracing::info!("Found swap by ID: {}", swap_id);
                     self.searched_swap = Some(swap);
@@ -755,10 +753,14 @@ impl SwapList {
                                 parent_chain,
                                 l1_txid_bytes: _,
                                 required_confirmations,
+                                expires_in_blocks: _,
                                 l2_recipient,
                                 l2_amount,
                                 l1_recipient_address,
                                 l1_amount,
+                                require_l1_commitment,
+                                allow_partial_fills,
+                                l1_match_policy,
                             } = &tx.transaction.data
                                 && coinshift::types::SwapId(*tx_swap_id)
                                     == swap_id
@@ -769,7 +771,12 @@ impl SwapList {
                                     );
                                 let swap = coinshift::types::Swap::new(
                                     swap_id,
-                                    coinshift::types::SwapDirection::L2ToL1,
+                                    tx.transaction
+                                        .data
+                                        .swap_direction()
+                                        .expect(
+                                            "SwapCreate has a swap direction",
+                                        ),
                                     *parent_chain,
                                     l1_txid,
                                     Some(*required_confirmations),
@@ -780,6 +787,9 @@ impl SwapList {
                                     0, // Height 0 for pending
                                     None,
                                     None, // Creator unknown for mempool display
+                                    *require_l1_commitment,
+                                    *allow_partial_fills,
+                                    *l1_match_policy,
                                 );
                                 self.searched_swap = Some(swap);
                                 tracing::info!(
@@ -812,14 +822,15 @@ impl SwapList {
                 }
             };
 
-            let all_swaps = match app.node.state().load_all_swaps(&rotxn) {
-                Ok(swaps) => swaps,
-                Err(err) => {
-                    self.search_error =
-                        Some(format!("Failed to load swaps: {}", err));
-                    return;
-                }
-            };
+            let all_swaps =
+                match app.node.state().load_all_swaps_with_l1_status(&rotxn) {
+                    Ok(swaps) => swaps,
+                    Err(err) => {
+                        self.search_error =
+                            Some(format!("Failed to load swaps: {}", err));
+                        return;
+                    }
+                };
 
             // Try to find swap by partial hex match
             let search_lower = search_input.to_lowercase();
@@ -871,42 +882,7 @@ impl SwapList {
         }
     }
 
-    fn load_rpc_config(
-        &self,
-        parent_chain: ParentChainType,
-    ) -> Option<RpcConfig> {
-        use dirs;
-        use serde::{Deserialize, Serialize};
-        use std::path::PathBuf;
-
-        #[derive(Clone, Serialize, Deserialize)]
-        struct LocalRpcConfig {
-            url: String,
-            user: String,
-            password: String,
-        }
-
-        let config_path = dirs::data_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("coinshift")
-            .join("l1_rpc_configs.json");
-
-        if let Ok(file_content) = std::fs::read_to_string(&config_path)
-            && let Ok(configs) = serde_json::from_str::<
-                HashMap<ParentChainType, LocalRpcConfig>,
-            >(&file_content)
-            && let Some(local_config) = configs.get(&parent_chain)
-        {
-            return Some(RpcConfig {
-                url: local_config.url.clone(),
-                user: local_config.user.clone(),
-                password: local_config.password.clone(),
-            });
-        }
-        None
-    }
-
-    fn fetch_confirmations_from_rpc(&mut self, _app: &App, swap: &Swap) {
+    fn fetch_confirmations_from_rpc(&mut self, app: &App, swap: &Swap) {
         if self.l1_txid_input.is_empty() {
             tracing::warn!(
                 swap_id = %swap.id,
@@ -922,27 +898,23 @@ impl SwapList {
             "Starting to fetch confirmations from RPC"
         );
 
-        if let Some(rpc_config) = self.load_rpc_config(swap.parent_chain) {
+        if let Some(backend) = app.node.l1_backend(swap.parent_chain) {
             tracing::debug!(
                 swap_id = %swap.id,
-                rpc_url = %rpc_config.url,
-                rpc_user = %rpc_config.user,
+                endpoint = %backend.endpoint(),
                 "Loaded RPC config"
             );
 
             self.fetching_confirmations = true;
             let txid_hex = self.l1_txid_input.clone();
-            // Normalize to canonical: accept either format from user
-            let txid_for_rpc = SwapTxId::from_hex(&txid_hex)
-                .map(|t| t.to_hex())
-                .or_else(|_| {
-                    SwapTxId::from_hex_rpc(&txid_hex).map(|t| t.to_hex())
-                })
+            let txid_for_rpc = SwapTxId::from_hex_rpc(&txid_hex)
+                .map(|t| t.to_hex_rpc())
                 .unwrap_or(txid_hex.clone());
 
-            // Spawn a thread to fetch confirmations
-            let client = ParentChainRpcClient::new(rpc_config);
-            match client.get_transaction_confirmations(&txid_for_rpc) {
+            match app
+                .runtime
+                .block_on(backend.get_transaction_confirmations(&txid_for_rpc))
+            {
                 Ok(confirmations) => {
                     tracing::info!(
                         swap_id = %swap.id,
@@ -1033,7 +1005,8 @@ impl SwapList {
         };
 
         // Debug: List all swaps in database for comparison
-        let all_db_swaps = app.node.state().load_all_swaps(&rotxn);
+        let all_db_swaps =
+            app.node.state().load_all_swaps_with_l1_status(&rotxn);
         if let Ok(swaps) = &all_db_swaps {
             let swap_ids: Vec<String> =
                 swaps.iter().map(|s| s.id.to_string()).collect();
@@ -1051,7 +1024,8 @@ impl SwapList {
             );
         }
 
-        let swap_in_db = app.node.state().get_swap(&rotxn, &swap.id);
+        let swap_in_db =
+            app.node.state().get_swap_with_l1_status(&rotxn, &swap.id);
         let swap_exists_in_db = match swap_in_db {
             Ok(Some(db_swap)) => {
                 tracing::debug!(
@@ -1129,74 +1103,48 @@ impl SwapList {
         let l1_txid_hex = l1_txid.to_hex();
 
         // Fetch transaction from RPC to validate amount and recipient address
-        let confirmations = if let Some(rpc_config) =
-            self.load_rpc_config(swap.parent_chain)
+        let confirmations = if let Some(backend) =
+            app.node.l1_backend(swap.parent_chain)
         {
             tracing::debug!(
                 swap_id = %swap.id,
                 l1_txid = %l1_txid_hex,
-                rpc_url = %rpc_config.url,
+                endpoint = %backend.endpoint(),
                 "Fetching transaction from RPC for validation and confirmations"
             );
 
-            let client = ParentChainRpcClient::new(rpc_config);
-            match client.get_transaction(&l1_txid_hex) {
+            match app.runtime.block_on(backend.get_transaction(&l1_txid_hex)) {
                 Ok(tx_info) => {
                     let conf = tx_info.confirmations;
 
-                    // Validate transaction matches swap requirements
-                    let mut found_matching_output = false;
-
                     // Check if swap has expected L1 recipient and amount
                     if let (Some(expected_recipient), Some(expected_amount)) =
                         (&swap.l1_recipient_address, swap.l1_amount)
                     {
                         let expected_amount_sats = expected_amount.to_sat();
-
-                        // Check all outputs for matching address and amount
-                        for vout in &tx_info.vout {
-                            let vout_value_sats =
-                                (vout.value * 100_000_000.0) as u64;
-                            let matches_address = vout
-                                .script_pub_key
-                                .address
-                                .as_ref()
-                                .map(|addr| addr == expected_recipient)
-                                .unwrap_or(false)
-                                || vout
-                                    .script_pub_key
-                                    .addresses
-                                    .as_ref()
-                                    .map(|addrs| {
-                                        addrs.contains(expected_recipient)
-                                    })
-                                    .unwrap_or(false);
-
-                            if matches_address
-                                && vout_value_sats == expected_amount_sats
-                            {
-                                found_matching_output = true;
-                                tracing::info!(
-                                    swap_id = %swap.id,
-                                    l1_txid = %l1_txid_hex,
-                                    recipient = %expected_recipient,
-                                    amount_sats = %expected_amount_sats,
-                                    "Transaction validated: matches swap requirements"
-                                );
-                                break;
-                            }
-                        }
-
-                        if !found_matching_output {
+                        if !tx_info.pays_amount_to(
+                            expected_recipient,
+                            expected_amount_sats,
+                            swap.l1_match_policy,
+                        ) {
                             tracing::error!(
                                 swap_id = %swap.id,
                                 l1_txid = %l1_txid_hex,
                                 expected_recipient = %expected_recipient,
                                 expected_amount_sats = %expected_amount_sats,
+                                l1_match_policy = %swap.l1_match_policy,
                                 "Transaction validation failed: No output matches expected recipient address and amount"
                             );
                             return;
                         }
+                        tracing::info!(
+                            swap_id = %swap.id,
+                            l1_txid = %l1_txid_hex,
+                            recipient = %expected_recipient,
+                            amount_sats = %expected_amount_sats,
+                            l1_match_policy = %swap.l1_match_policy,
+                            "Transaction validated: matches swap requirements"
+                        );
                     } else {
                         // For swaps without expected recipient/amount, we can't validate
                         // This might be an open swap or a swap without L1 details
@@ -1315,7 +1263,7 @@ impl SwapList {
         self.refresh_swaps(app);
     }
 
-    /// True if the current user may cancel/delete this swap (creator only).
+    /// True if the current user may cancel this swap, or delete it before it is in a block (creator only).
     fn can_manage_swap(&self, app: Option<&App>, swap: &Swap) -> bool {
         let Some(app) = app else {
             return false;
@@ -1334,6 +1282,78 @@ impl SwapList {
         }
     }
 
+    /// Show the OP_RETURN data that a filler must include in the L1 payment
+    /// of a swap that requires a commitment. Such swaps cannot be updated
+    /// with an L1 txid by hand.
+    fn show_l1_commitment(&mut self, ui: &mut egui::Ui, swap: &Swap) {
+        ui.label(egui::RichText::new("L1 Commitment").heading());
+        ui.label(format!(
+            "The L1 payment must include an OP_RETURN output with the data \
+             below, and is detected automatically once it has {} \
+             confirmations.",
+            swap.required_confirmations
+        ));
+        // Open swaps are claimed by the L2 address in the commitment
+        let l2_claimer_address = match swap.l2_recipient {
+            Some(l2_recipient) => l2_recipient,
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label("Your L2 address:");
+                    ui.add(
+                        egui::TextEdit::singleline(
+                            &mut self.l2_recipient_input,
+                        )
+                        .desired_width(400.0),
+                    );
+                });
+                let Ok(l2_address) =
+                    self.l2_recipient_input.trim().parse::<Address>()
+                else {
+                    return;
+                };
+                l2_address
+            }
+        };
+        let data = hex::encode(
+            SwapCommitment {
+                swap_id: swap.id,
+                l2_claimer_address,
+            }
+            .to_data(),
+        );
+        ui.horizontal(|ui| {
+            ui.label("OP_RETURN data:");
+            ui.label(egui::RichText::new(&data).monospace());
+            if ui.button("📋 Copy").clicked() {
+                ui.ctx().copy_text(data.clone());
+            }
+        });
+    }
+
+    fn lock_swap(&mut self, app: &App, swap: &Swap) {
+        let l1_recipient_address = self.l1_lock_address_input.trim().to_owned();
+        match app.lock_swap(swap, l1_recipient_address, bitcoin::Amount::ZERO) {
+            Ok(txid) => {
+                tracing::info!(
+                    swap_id = %swap.id,
+                    %txid,
+                    "Submitted swap lock transaction"
+                );
+                self.l1_lock_address_input.clear();
+                self.success_message =
+                    Some(format!("Swap locked: txid={txid}"));
+                self.refresh_swaps(app);
+            }
+            Err(err) => {
+                tracing::error!(
+                    swap_id = %swap.id,
+                    error = %err,
+                    "Failed to lock swap"
+                );
+            }
+        }
+    }
+
     fn cancel_swap(&mut self, app: &App, swap: &Swap) {
         let swap_id = swap.id;
         let is_pending = swap.created_at_height == 0;
@@ -1377,22 +1397,13 @@ impl SwapList {
                 "Pending swap not found in mempool"
             );
         } else {
-            let creator = swap.l2_creator_address.as_ref();
-            let mut rwtxn = match app.node.env().write_txn() {
-                Ok(txn) => txn,
-                Err(err) => {
-                    tracing::error!("Failed to get write transaction: {err:#}");
-                    return;
-                }
-            };
-
-            match app.node.state().cancel_swap(&mut rwtxn, &swap_id, creator) {
-                Ok(()) => {
-                    if let Err(err) = rwtxn.commit() {
-                        tracing::error!("Failed to commit: {err:#}");
-                        return;
-                    }
-                    tracing::info!(swap_id = %swap_id, "Cancelled swap");
+            match app.cancel_swap(swap) {
+                Ok(txid) => {
+                    tracing::info!(
+                        swap_id = %swap_id,
+                        %txid,
+                        "Submitted swap cancel transaction"
+                    );
                     self.refresh_swaps(app);
                 }
                 Err(err) => {
@@ -1406,73 +1417,46 @@ impl SwapList {
         }
     }
 
+    /// Remove a swap that is not in a block yet from the mempool
     fn delete_swap(&mut self, app: &App, swap: &Swap) {
         let swap_id = swap.id;
-        let is_pending = swap.created_at_height == 0;
-
-        if is_pending {
-            if !app.node.is_created_pending_swap(&swap_id) {
-                tracing::error!(swap_id = %swap_id, "Only the swap creator can delete a pending swap");
-                return;
-            }
-            if let Ok(mempool_txs) = app.node.get_all_transactions() {
-                for tx in mempool_txs {
-                    if let coinshift::types::TxData::SwapCreate {
-                        swap_id: tx_swap_id,
-                        ..
-                    } = &tx.transaction.data
-                        && coinshift::types::SwapId(*tx_swap_id) == swap_id
-                    {
-                        let txid = tx.transaction.txid();
-                        if let Err(err) = app.node.remove_from_mempool(txid) {
-                            tracing::error!(
-                                swap_id = %swap_id,
-                                txid = %txid,
-                                error = %err,
-                                "Failed to remove pending swap from mempool"
-                            );
-                            return;
-                        }
-                        app.node.remove_created_pending_swap(&swap_id);
-                        tracing::info!(
+        if !app.node.is_created_pending_swap(&swap_id) {
+            tracing::error!(swap_id = %swap_id, "Only the swap creator can delete a pending swap");
+            return;
+        }
+        if let Ok(mempool_txs) = app.node.get_all_transactions() {
+            for tx in mempool_txs {
+                if let coinshift::types::TxData::SwapCreate {
+                    swap_id: tx_swap_id,
+                    ..
+                } = &tx.transaction.data
+                    && coinshift::types::SwapId(*tx_swap_id) == swap_id
+                {
+                    let txid = tx.transaction.txid();
+                    if let Err(err) = app.node.remove_from_mempool(txid) {
+                        tracing::error!(
                             swap_id = %swap_id,
                             txid = %txid,
-                            "Removed pending swap from mempool"
+                            error = %err,
+                            "Failed to remove pending swap from mempool"
                         );
-                        self.refresh_swaps(app);
                         return;
                     }
-                }
-            }
-            tracing::error!(
-                swap_id = %swap_id,
-                "Pending swap not found in mempool"
-            );
-        } else {
-            let creator = swap.l2_creator_address.as_ref();
-            let mut rwtxn = match app.node.env().write_txn() {
-                Ok(txn) => txn,
-                Err(err) => {
-                    tracing::error!("Failed to get write transaction: {err:#}");
+                    app.node.remove_created_pending_swap(&swap_id);
+                    tracing::info!(
+                        swap_id = %swap_id,
+                        txid = %txid,
+                        "Removed pending swap from mempool"
+                    );
+                    self.refresh_swaps(app);
                     return;
                 }
-            };
-
-            if let Err(err) =
-                app.node.state().delete_swap(&mut rwtxn, &swap_id, creator)
-            {
-                tracing::error!("Failed to delete swap: {err:#}");
-                return;
-            }
-
-            if let Err(err) = rwtxn.commit() {
-                tracing::error!("Failed to commit: {err:#}");
-                return;
             }
-
-            tracing::info!(swap_id = %swap_id, "Deleted swap");
-            self.refresh_swaps(app);
         }
+        tracing::error!(
+            swap_id = %swap_id,
+            "Pending swap not found in mempool"
+        );
     }
 
     /// Dynamically check and update confirmations for swaps in WaitingConfirmations state
@@ -1495,7 +1479,8 @@ impl SwapList {
             }
         };
 
-        let swaps = match app.node.state().load_all_swaps(&rotxn) {
+        let swaps = match app.node.state().load_all_swaps_with_l1_status(&rotxn)
+        {
             Ok(swaps) => swaps,
             Err(err) => {
                 tracing::error!("Failed to load swaps: {err:#}");
@@ -1527,29 +1512,31 @@ impl SwapList {
             swaps_to_check.len()
         );
 
-        // Run blocking RPC in a dedicated thread to avoid tokio "blocking is not allowed" panic
         let work: Vec<_> = swaps_to_check
             .iter()
             .filter_map(|swap| {
-                self.load_rpc_config(swap.parent_chain).map(|rpc_config| {
-                    (swap.id, rpc_config, swap.l1_txid.to_hex())
-                })
+                app.node
+                    .l1_backend(swap.parent_chain)
+                    .map(|backend| (swap.id, backend, swap.l1_txid.to_hex()))
             })
             .collect();
 
-        let results: Vec<(SwapId, u32)> = std::thread::spawn(move || {
-            work.into_iter()
-                .filter_map(|(swap_id, rpc_config, l1_txid_hex)| {
-                    let client = ParentChainRpcClient::new(rpc_config);
-                    client
+        // Fetch confirmations concurrently, before opening the write
+        // transaction
+        let results: Vec<(SwapId, u32)> = app
+            .runtime
+            .block_on(futures::future::join_all(work.into_iter().map(
+                |(swap_id, backend, l1_txid_hex)| async move {
+                    backend
                         .get_transaction_confirmations(&l1_txid_hex)
+                        .await
                         .ok()
                         .map(|c| (swap_id, c))
-                })
-                .collect()
-        })
-        .join()
-        .unwrap_or_default();
+                },
+            )))
+            .into_iter()
+            .flatten()
+            .collect();
 
         let mut updated_count = 0;
         let mut rwtxn = match app.node.env().write_txn() {
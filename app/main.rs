@@ -1,8 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use clap::Parser as _;
-use coinshift::parent_chain_rpc;
-use coinshift::types::ParentChainType;
+use coinshift::{parent_chain_rpc, types::ParentChainRegistry};
 use mimalloc::MiMalloc;
 use tokio::{signal::ctrl_c, sync::oneshot};
 use tracing_subscriber::{
@@ -206,59 +205,66 @@ fn l1_config_path() -> PathBuf {
         .join("l1_rpc_configs.json")
 }
 
-fn write_l1_config_from_flags(
-    l1_signet: bool,
-    l1_bch_testnet4: bool,
-) -> anyhow::Result<()> {
-    let path = l1_config_path();
-    let mut chains = Vec::new();
-    if l1_signet {
-        chains.push(ParentChainType::Signet);
-    }
-    if l1_bch_testnet4 {
-        chains.push(ParentChainType::BCH);
-    }
-    parent_chain_rpc::write_l1_config_file(&path, &chains)?;
-    Ok(())
+fn parent_chains_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("coinshift")
+        .join("parent_chains.json")
 }
 
 fn main() -> anyhow::Result<()> {
     // Configure the allocator before Tokio spins up worker threads.
     configure_mimalloc();
+    // Custom parent chains can be named in arguments
+    let () = ParentChainRegistry::load_from_path(&parent_chains_path())?
+        .install()?;
     let cli = cli::Cli::parse();
 
     // Handle init subcommand: write L1 config and exit
     if let Some(cli::AppSubcommand::Init {
-        l1_signet,
-        l1_bch_testnet4,
-    }) = &cli.command
+        l1_parent_chain,
+        l1_url,
+        l1_user,
+        l1_password,
+        l1_cookie_file,
+        l1_backend,
+        l1_zmq_url,
+    }) = cli.command
     {
+        let rpc_config = parent_chain_rpc::RpcConfig {
+            url: l1_url,
+            user: l1_user,
+            password: l1_password,
+            cookie_file: l1_cookie_file,
+            backend: l1_backend,
+            zmq_url: l1_zmq_url,
+        };
+        let parent_chain = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(parent_chain_rpc::detect_chain_type(
+                &rpc_config,
+                l1_parent_chain,
+            ))?;
         let path = l1_config_path();
-        let mut chains = Vec::new();
-        if *l1_signet {
-            chains.push(ParentChainType::Signet);
-        }
-        if *l1_bch_testnet4 {
-            chains.push(ParentChainType::BCH);
-        }
-        parent_chain_rpc::write_l1_config_file(&path, &chains)?;
+        parent_chain_rpc::save_rpc_config_to_path(
+            &path,
+            parent_chain,
+            rpc_config,
+        )?;
         tracing_subscriber::fmt()
             .with_writer(std::io::stdout)
             .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stdout()))
             .with_target(false)
             .init();
         tracing::info!(
-            "L1 config written to {} (Signet: {}, BCH Testnet4: {})",
+            "L1 config for {} written to {}",
+            parent_chain.coin_name(),
             path.display(),
-            l1_signet,
-            l1_bch_testnet4
         );
         return Ok(());
     }
 
-    if cli.run.l1_signet || cli.run.l1_bch_testnet4 {
-        write_l1_config_from_flags(cli.run.l1_signet, cli.run.l1_bch_testnet4)?;
-    }
     let config = cli.run.get_config()?;
     let (line_buffer, _rolling_log_guard) = set_tracing_subscriber(
         config.log_dir.as_deref(),
@@ -1,12 +1,18 @@
-use std::net::SocketAddr;
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
 
 use bitcoin::Amount;
 use coinshift::{
-    net::Peer,
+    net::{BannedPeer, NodeId, Peer, misbehavior},
+    node::WriteTxnStats,
+    parent_chain_rpc::{self, L1ChainStatus},
     state,
     types::{
-        Address, ParentChainType, PointedOutput, Swap, SwapId, SwapState,
-        SwapTxId, Txid, WithdrawalBundle,
+        Address, L1MatchPolicy, ParentChainType, PointedOutput, Swap,
+        SwapCommitment, SwapDirection, SwapId, SwapState, SwapTxId, Txid,
+        WithdrawalBundle,
     },
     wallet::Balance,
 };
@@ -30,7 +36,7 @@ pub struct RpcServerImpl {
 }
 
 impl RpcServerImpl {
-    /// Resolve creator address for a swap: only the creator may cancel/delete. Returns Ok(Some(addr)) if we own the swap, Err if not allowed.
+    /// Resolve creator address for a swap: only the creator may cancel. Returns Ok(Some(addr)) if we own the swap, Err if not allowed.
     fn resolve_swap_creator(
         &self,
         swap_id: &SwapId,
@@ -95,6 +101,16 @@ impl RpcServer for RpcServerImpl {
         .unwrap()
     }
 
+    async fn ban_peer(
+        &self,
+        ip: IpAddr,
+        duration_secs: Option<u64>,
+    ) -> RpcResult<()> {
+        let duration = duration_secs
+            .map_or(misbehavior::DEFAULT_BAN_DURATION, Duration::from_secs);
+        self.app.node.ban_peer(ip, duration).map_err(custom_err)
+    }
+
     async fn connect_peer(&self, addr: SocketAddr) -> RpcResult<()> {
         self.app.node.connect_peer(addr).map_err(custom_err)
     }
@@ -209,11 +225,30 @@ impl RpcServer for RpcServerImpl {
         Ok(height)
     }
 
+    async fn list_banned(&self) -> RpcResult<Vec<BannedPeer>> {
+        self.app.node.list_banned().map_err(custom_err)
+    }
+
     async fn list_peers(&self) -> RpcResult<Vec<Peer>> {
         let peers = self.app.node.get_active_peers();
         Ok(peers)
     }
 
+    async fn get_node_id(&self) -> RpcResult<NodeId> {
+        Ok(self.app.node.node_id())
+    }
+
+    async fn l1_status(&self) -> RpcResult<Vec<L1ChainStatus>> {
+        let l1_rpc_config_path = dirs::data_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("coinshift")
+            .join("l1_rpc_configs.json");
+        let configs =
+            parent_chain_rpc::load_rpc_configs_from_path(&l1_rpc_config_path)
+                .unwrap_or_default();
+        Ok(self.app.node.l1_status().report(configs))
+    }
+
     async fn list_utxos(&self) -> RpcResult<Vec<PointedOutput>> {
         let utxos = self.app.node.get_all_utxos().map_err(custom_err)?;
         let res = utxos
@@ -297,6 +332,14 @@ impl RpcServer for RpcServerImpl {
         Ok(txid)
     }
 
+    async fn unban_peer(&self, ip: IpAddr) -> RpcResult<()> {
+        if self.app.node.unban_peer(ip).map_err(custom_err)? {
+            Ok(())
+        } else {
+            Err(custom_err_msg(format!("peer {ip} is not banned")))
+        }
+    }
+
     async fn withdraw(
         &self,
         mainchain_address: bitcoin::Address<bitcoin::address::NetworkUnchecked>,
@@ -322,6 +365,10 @@ impl RpcServer for RpcServerImpl {
         Ok(txid)
     }
 
+    async fn write_txn_stats(&self) -> RpcResult<WriteTxnStats> {
+        Ok(self.app.node.write_txn_stats())
+    }
+
     async fn create_swap(
         &self,
         parent_chain: ParentChainType,
@@ -330,8 +377,15 @@ impl RpcServer for RpcServerImpl {
         l2_recipient: Option<Address>, // Optional - None = open swap
         l2_amount_sats: u64,
         required_confirmations: Option<u32>,
+        expires_in_blocks: Option<u32>,
+        require_l1_commitment: bool,
+        allow_partial_fills: bool,
+        l1_match_policy: Option<L1MatchPolicy>,
         fee_sats: u64,
     ) -> RpcResult<(SwapId, Txid)> {
+        let () = parent_chain
+            .validate_address(&l1_recipient_address)
+            .map_err(custom_err)?;
         let accumulator =
             self.app.node.get_tip_accumulator().map_err(custom_err)?;
 
@@ -374,6 +428,63 @@ impl RpcServer for RpcServerImpl {
                 l2_recipient, // Optional
                 Amount::from_sat(l2_amount_sats),
                 required_confirmations,
+                expires_in_blocks,
+                require_l1_commitment,
+                allow_partial_fills,
+                l1_match_policy.unwrap_or_default(),
+                Amount::from_sat(fee_sats),
+                is_locked,
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        self.app.node.add_created_pending_swap(swap_id);
+        Ok((swap_id, txid))
+    }
+
+    async fn create_l1_to_l2_swap(
+        &self,
+        parent_chain: ParentChainType,
+        l1_amount_sats: u64,
+        l2_recipient: Option<Address>,
+        l2_amount_sats: u64,
+        required_confirmations: Option<u32>,
+        expires_in_blocks: Option<u32>,
+        require_l1_commitment: bool,
+        l1_match_policy: Option<L1MatchPolicy>,
+        fee_sats: u64,
+    ) -> RpcResult<(SwapId, Txid)> {
+        let accumulator =
+            self.app.node.get_tip_accumulator().map_err(custom_err)?;
+        let l2_recipient = match l2_recipient {
+            Some(l2_recipient) => l2_recipient,
+            None => self.app.wallet.get_new_address().map_err(custom_err)?,
+        };
+        let node = &self.app.node;
+        let is_locked = |outpoint: &coinshift::types::OutPoint| -> bool {
+            let Ok(rotxn) = node.env().read_txn() else {
+                tracing::warn!(
+                    "Failed to create read transaction for locked output check"
+                );
+                return false;
+            };
+            node.state()
+                .is_output_locked_to_swap(&rotxn, outpoint)
+                .is_ok_and(|locked| locked.is_some())
+        };
+        let (tx, swap_id) = self
+            .app
+            .wallet
+            .create_l1_to_l2_swap_create_tx(
+                &accumulator,
+                parent_chain,
+                Amount::from_sat(l1_amount_sats),
+                l2_recipient,
+                Amount::from_sat(l2_amount_sats),
+                required_confirmations,
+                expires_in_blocks,
+                require_l1_commitment,
+                l1_match_policy.unwrap_or_default(),
                 Amount::from_sat(fee_sats),
                 is_locked,
             )
@@ -384,6 +495,51 @@ impl RpcServer for RpcServerImpl {
         Ok((swap_id, txid))
     }
 
+    async fn lock_swap(
+        &self,
+        swap_id: SwapId,
+        l1_recipient_address: String,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
+        let swap = self
+            .app
+            .node
+            .state()
+            .get_swap(&rotxn, &swap_id)
+            .map_err(custom_err)?
+            .ok_or_else(|| custom_err_msg("Swap not found"))?;
+        drop(rotxn);
+        if !matches!(swap.state, SwapState::AwaitingLock) {
+            return Err(custom_err_msg(format!(
+                "Swap is not awaiting a lock (state: {:?})",
+                swap.state
+            )));
+        }
+        let () = swap
+            .parent_chain
+            .validate_address(&l1_recipient_address)
+            .map_err(custom_err)?;
+        self.app
+            .lock_swap(&swap, l1_recipient_address, Amount::from_sat(fee_sats))
+            .map_err(custom_err)
+    }
+
+    async fn track_l1_headers(
+        &self,
+        parent_chain: ParentChainType,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        // Building the transaction queries the L1 node for headers
+        let app = self.app.clone();
+        tokio::task::spawn_blocking(move || {
+            app.track_l1_headers(parent_chain, Amount::from_sat(fee_sats))
+                .map_err(custom_err)
+        })
+        .await
+        .unwrap()
+    }
+
     async fn reconstruct_swaps(&self) -> RpcResult<u32> {
         let mut rwtxn = self.app.node.env().write_txn().map_err(custom_err)?;
         let count = self
@@ -446,6 +602,18 @@ impl RpcServer for RpcServerImpl {
         Ok(())
     }
 
+    async fn get_swap_commitment(
+        &self,
+        swap_id: SwapId,
+        l2_claimer_address: Address,
+    ) -> RpcResult<String> {
+        let commitment = SwapCommitment {
+            swap_id,
+            l2_claimer_address,
+        };
+        Ok(hex::encode(commitment.to_data()))
+    }
+
     async fn get_swap_status(
         &self,
         swap_id: SwapId,
@@ -455,7 +623,7 @@ impl RpcServer for RpcServerImpl {
             .app
             .node
             .state()
-            .get_swap(&rotxn, &swap_id)
+            .get_swap_with_l1_status(&rotxn, &swap_id)
             .map_err(custom_err)?;
         Ok(swap)
     }
@@ -471,7 +639,7 @@ impl RpcServer for RpcServerImpl {
             .app
             .node
             .state()
-            .get_swap(&rotxn, &swap_id)
+            .get_swap_with_l1_status(&rotxn, &swap_id)
             .map_err(custom_err)?
             .ok_or_else(|| custom_err_msg("Swap not found"))?;
 
@@ -482,95 +650,14 @@ impl RpcServer for RpcServerImpl {
             )));
         }
 
-        // Get locked outputs for this swap
-        // Note: We must query the node directly, not the wallet, because the wallet
-        // filters out SwapPending outputs. Locked outputs are identified by checking
-        // if the output content is SwapPending with the matching swap_id.
-        let all_utxos = self.app.node.get_all_utxos().map_err(custom_err)?;
-
-        // Find locked outputs for this swap (same pattern as verify_swap_locks_utxos in integration tests)
-        let mut locked_outputs = Vec::new();
-        for (outpoint, output) in all_utxos {
-            match &output.content {
-                coinshift::types::OutputContent::SwapPending {
-                    swap_id: locked_swap_id,
-                    ..
-                } => {
-                    if *locked_swap_id == swap_id.0 {
-                        tracing::info!(
-                            "Found locked output for swap {}: {:?}",
-                            swap_id,
-                            outpoint
-                        );
-                        locked_outputs.push((outpoint, output));
-                    } else {
-                        tracing::debug!(
-                            "Output {:?} is SwapPending for different swap_id: {:?}",
-                            outpoint,
-                            locked_swap_id
-                        );
-                    }
-                }
-                other => {
-                    tracing::trace!(
-                        "Output {:?} is not SwapPending (content: {:?})",
-                        outpoint,
-                        other
-                    );
-                }
-            }
-        }
-
-        if locked_outputs.is_empty() {
-            return Err(custom_err_msg(format!(
-                "No locked outputs found for swap {}",
-                swap_id
-            )));
-        }
-
-        // Determine recipient: pre-specified uses swap.l2_recipient; open uses stored or provided claimer address
-        let recipient = swap
-            .l2_recipient
-            .or(swap.l2_claimer_address)
-            .or(l2_claimer_address)
-            .ok_or_else(|| {
-                custom_err_msg("Open swap requires l2_claimer_address (or set when L1 tx was submitted)")
-            })?;
-
-        // Add locked outputs to wallet temporarily so they can be used for signing
-        // SwapPending outputs are normally filtered out, but we need them in the wallet
-        // for the authorize() call to find the address and signing key
-        use std::collections::HashMap;
-        let locked_utxos: HashMap<_, _> =
-            locked_outputs.iter().cloned().collect();
-        self.app
-            .wallet
-            .put_utxos(&locked_utxos)
-            .map_err(custom_err)?;
-        tracing::debug!(
-            swap_id = %swap_id,
-            num_locked_outputs = locked_outputs.len(),
-            "Added locked outputs to wallet for signing"
-        );
-
-        let accumulator =
-            self.app.node.get_tip_accumulator().map_err(custom_err)?;
-        let l2_claimer_for_tx =
-            swap.l2_recipient.is_none().then_some(recipient);
-        let tx = self
-            .app
-            .wallet
-            .create_swap_claim_tx(
-                &accumulator,
-                swap_id,
-                recipient,
-                locked_outputs,
-                l2_claimer_for_tx,
-            )
-            .map_err(custom_err)?;
-        let txid = tx.txid();
-        self.app.sign_and_send(tx).map_err(custom_err)?;
-        Ok(txid)
+        // Building the claim may query the L1 node for an SPV proof
+        let app = self.app.clone();
+        tokio::task::spawn_blocking(move || {
+            app.claim_swap(&swap, l2_claimer_address)
+                .map_err(custom_err)
+        })
+        .await
+        .unwrap()
     }
 
     async fn list_swaps(&self) -> RpcResult<Vec<Swap>> {
@@ -579,7 +666,7 @@ impl RpcServer for RpcServerImpl {
             .app
             .node
             .state()
-            .load_all_swaps(&rotxn)
+            .load_all_swaps_with_l1_status(&rotxn)
             .map_err(custom_err)?;
         Ok(swaps)
     }
@@ -593,35 +680,37 @@ impl RpcServer for RpcServerImpl {
             .app
             .node
             .state()
-            .get_swaps_by_recipient(&rotxn, &recipient)
+            .get_swaps_by_recipient_with_l1_status(&rotxn, &recipient)
             .map_err(custom_err)?;
         Ok(swaps)
     }
 
-    async fn cancel_swap(&self, swap_id: SwapId) -> RpcResult<()> {
-        let creator =
-            self.resolve_swap_creator(&swap_id).map_err(custom_err)?;
-        let mut rwtxn = self.app.node.env().write_txn().map_err(custom_err)?;
-        self.app
+    async fn cancel_swap(&self, swap_id: SwapId) -> RpcResult<Txid> {
+        let rotxn = self.app.node.env().read_txn().map_err(custom_err)?;
+        let swap = self
+            .app
             .node
             .state()
-            .cancel_swap(&mut rwtxn, &swap_id, creator.as_ref())
-            .map_err(custom_err)?;
-        rwtxn.commit().map_err(custom_err)?;
-        Ok(())
-    }
-
-    async fn delete_swap(&self, swap_id: SwapId) -> RpcResult<()> {
-        let creator =
-            self.resolve_swap_creator(&swap_id).map_err(custom_err)?;
-        let mut rwtxn = self.app.node.env().write_txn().map_err(custom_err)?;
-        self.app
+            .get_swap(&rotxn, &swap_id)
+            .map_err(custom_err)?
+            .ok_or_else(|| custom_err_msg("Swap not found"))?;
+        drop(rotxn);
+        let next_height = self
+            .app
             .node
-            .state()
-            .delete_swap(&mut rwtxn, &swap_id, creator.as_ref())
-            .map_err(custom_err)?;
-        rwtxn.commit().map_err(custom_err)?;
-        Ok(())
+            .try_get_height()
+            .map_err(custom_err)?
+            .map_or(0, |height| height + 1);
+        // Expired L1 → L2 swaps are cancelled by the L2 holder that locked
+        // them, rather than the creator
+        if swap.direction == SwapDirection::L2ToL1
+            || matches!(swap.state, SwapState::AwaitingLock)
+            || swap.cancel_request_expiry(next_height).is_some()
+        {
+            let _: Option<Address> =
+                self.resolve_swap_creator(&swap_id).map_err(custom_err)?;
+        }
+        self.app.cancel_swap(&swap).map_err(custom_err)
     }
 }
 
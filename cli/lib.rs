@@ -1,13 +1,18 @@
 use std::{
-    collections::HashMap, net::SocketAddr, path::PathBuf, time::Duration,
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
 };
 
 use clap::{Parser, Subcommand};
 use http::HeaderMap;
 use jsonrpsee::{core::client::ClientT, http_client::HttpClientBuilder};
 
-use coinshift::parent_chain_rpc::RpcConfig;
-use coinshift::types::{Address, ParentChainType, SwapId, Txid};
+use coinshift::parent_chain_rpc::{self, BackendKind, L1Endpoints, RpcConfig};
+use coinshift::types::{
+    Address, L1MatchPolicy, ParentChainRegistry, ParentChainType, SwapId, Txid,
+};
 use coinshift_app_rpc_api::RpcClient;
 use tracing_subscriber::{filter::Targets, layer::SubscriberExt as _};
 
@@ -18,6 +23,20 @@ fn l1_config_path() -> PathBuf {
         .join("l1_rpc_configs.json")
 }
 
+fn parent_chains_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("coinshift")
+        .join("parent_chains.json")
+}
+
+/// Register the custom parent chains configured for the app, so that they
+/// can be named in arguments. Must be called before parsing arguments.
+pub fn load_parent_chains() -> anyhow::Result<()> {
+    ParentChainRegistry::load_from_path(&parent_chains_path())?.install()?;
+    Ok(())
+}
+
 fn parse_swap_id(s: &str) -> anyhow::Result<SwapId> {
     let bytes = hex::decode(s)
         .map_err(|e| anyhow::anyhow!("invalid swap_id hex: {}", e))?;
@@ -28,17 +47,7 @@ fn parse_swap_id(s: &str) -> anyhow::Result<SwapId> {
 }
 
 fn parse_parent_chain(s: &str) -> anyhow::Result<ParentChainType> {
-    match s.to_lowercase().as_str() {
-        "btc" => Ok(ParentChainType::BTC),
-        "bch" => Ok(ParentChainType::BCH),
-        "ltc" => Ok(ParentChainType::LTC),
-        "signet" => Ok(ParentChainType::Signet),
-        "regtest" => Ok(ParentChainType::Regtest),
-        _ => Err(anyhow::anyhow!(
-            "unknown parent_chain '{}', use: btc, bch, ltc, signet, regtest",
-            s
-        )),
-    }
+    Ok(s.parse()?)
 }
 
 #[derive(Clone, Debug, Subcommand)]
@@ -46,6 +55,13 @@ fn parse_parent_chain(s: &str) -> anyhow::Result<ParentChainType> {
 pub enum Command {
     /// Get balance in sats
     Balance,
+    /// Ban a peer IP address, disconnecting from any peers at that address
+    BanPeer {
+        ip: IpAddr,
+        /// Ban duration in seconds. Defaults to 24 hours.
+        #[arg(long)]
+        duration_secs: Option<u64>,
+    },
     /// Connect to a peer
     ConnectPeer { addr: SocketAddr },
     /// Create a swap (L2 → L1). Optional l2_recipient = open swap.
@@ -62,6 +78,48 @@ pub enum Command {
         l2_amount_sats: u64,
         #[arg(long)]
         required_confirmations: Option<u32>,
+        /// Number of sidechain blocks after which the swap expires, and the
+        /// locked coins can be returned to the creator
+        #[arg(long)]
+        expires_in_blocks: Option<u32>,
+        /// Require the L1 payment to carry an OP_RETURN commitment to the
+        /// swap (see get-swap-commitment)
+        #[arg(long)]
+        require_l1_commitment: bool,
+        /// Accept several L1 payments, each releasing its share of the L2
+        /// amount
+        #[arg(long)]
+        allow_partial_fills: bool,
+        /// How an L1 payment matches the L1 amount: exact, at_least or
+        /// sum_of_outputs. Defaults to exact
+        #[arg(long)]
+        l1_match_policy: Option<L1MatchPolicy>,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Create an L1 → L2 swap offer. An L2 holder accepts it with
+    /// lock-swap. Without l2_recipient, a new wallet address is used.
+    CreateL1ToL2Swap {
+        #[arg(long, value_parser = parse_parent_chain)]
+        parent_chain: ParentChainType,
+        #[arg(long)]
+        l1_amount_sats: u64,
+        #[arg(long)]
+        l2_recipient: Option<Address>,
+        #[arg(long)]
+        l2_amount_sats: u64,
+        #[arg(long)]
+        required_confirmations: Option<u32>,
+        /// Number of sidechain blocks after which the swap expires
+        #[arg(long)]
+        expires_in_blocks: Option<u32>,
+        /// Require the L1 payment to carry an OP_RETURN commitment to the
+        /// swap (see get-swap-commitment)
+        #[arg(long)]
+        require_l1_commitment: bool,
+        /// As for create-swap
+        #[arg(long)]
+        l1_match_policy: Option<L1MatchPolicy>,
         #[arg(long)]
         fee_sats: u64,
     },
@@ -99,6 +157,8 @@ pub enum Command {
     },
     /// Get a new address
     GetNewAddress,
+    /// Get this node's ID, which other nodes can pin with --pinned-peer
+    GetNodeId,
     /// Get wallet addresses, sorted by base58 encoding
     GetWalletAddresses,
     /// Get wallet UTXOs
@@ -112,6 +172,14 @@ pub enum Command {
         #[arg(long)]
         l2_claimer_address: Option<Address>,
     },
+    /// Get the hex-encoded OP_RETURN data to include in the L1 payment for
+    /// a swap that requires an L1 commitment
+    GetSwapCommitment {
+        #[arg(long, value_parser = parse_swap_id)]
+        swap_id: SwapId,
+        #[arg(long)]
+        l2_claimer_address: Address,
+    },
     /// Get status of a swap by ID
     GetSwapStatus {
         #[arg(long, value_parser = parse_swap_id)]
@@ -119,8 +187,12 @@ pub enum Command {
     },
     /// Get the height of the latest failed withdrawal bundle
     LatestFailedWithdrawalBundleHeight,
+    /// Show configured L1 endpoints and recent disagreements between them
+    L1Status,
     /// List peers
     ListPeers,
+    /// List banned peer IP addresses
+    ListBanned,
     /// List all UTXOs
     ListUtxos,
     /// List all swaps
@@ -129,16 +201,29 @@ pub enum Command {
     ListSwapsByRecipient { recipient: Address },
     /// Recover wallet from mnemonic phrase (sets seed, then shows addresses and balance)
     RecoverFromMnemonic { mnemonic: String },
+    /// Lock L2 coins to an L1 → L2 swap offer, receiving the L1 payment at
+    /// l1_recipient_address
+    LockSwap {
+        #[arg(long, value_parser = parse_swap_id)]
+        swap_id: SwapId,
+        #[arg(long)]
+        l1_recipient_address: String,
+        #[arg(long)]
+        fee_sats: u64,
+    },
     /// Reconstruct all swaps from the blockchain
     ReconstructSwaps,
-    /// Cancel a swap (only Pending swaps). Unlocks outputs and marks as cancelled.
-    CancelSwap {
-        /// Swap ID (64 hex chars)
-        #[arg(value_parser = parse_swap_id)]
-        swap_id: SwapId,
+    /// Extend the tracked L1 headers with the next L1 headers from the
+    /// configured L1 node. Claims can only prove a limited number of L1
+    /// headers after the tracked ones.
+    TrackL1Headers {
+        #[arg(long, value_parser = parse_parent_chain)]
+        parent_chain: ParentChainType,
+        #[arg(long)]
+        fee_sats: u64,
     },
-    /// Delete a swap from the database (only Pending or Cancelled).
-    DeleteSwap {
+    /// Cancel a swap by broadcasting a SwapCancel transaction. Swaps that have not expired can still be claimed for a grace period.
+    CancelSwap {
         /// Swap ID (64 hex chars)
         #[arg(value_parser = parse_swap_id)]
         swap_id: SwapId,
@@ -157,16 +242,28 @@ pub enum Command {
     RemoveFromMempool { txid: Txid },
     /// Set the wallet seed from a mnemonic seed phrase
     SetSeedFromMnemonic { mnemonic: String },
-    /// Set L1 RPC config for a parent chain (url required; user/password optional)
+    /// Set L1 RPC config for a parent chain (url required; user/password optional).
+    /// The node must be on a network known for the parent chain. If the
+    /// parent chain is not set, it is detected from the node.
     SetL1Config {
         #[arg(long, value_parser = parse_parent_chain)]
-        parent_chain: ParentChainType,
+        parent_chain: Option<ParentChainType>,
         #[arg(long)]
         url: String,
         #[arg(long, default_value = "")]
         user: String,
         #[arg(long, default_value = "")]
         password: String,
+        /// Read credentials from a Bitcoin Core cookie file instead
+        #[arg(long, conflicts_with_all = ["user", "password"])]
+        cookie_file: Option<PathBuf>,
+        /// API served at the URL: `bitcoin_core` or `esplora`
+        #[arg(default_value = "bitcoin_core", long)]
+        backend: BackendKind,
+        /// ZMQ publisher of the node, for `hashblock` and `rawtx`
+        /// notifications instead of polling
+        #[arg(long)]
+        zmq_url: Option<String>,
     },
     /// Get total sidechain wealth
     SidechainWealth,
@@ -191,6 +288,8 @@ pub enum Command {
         #[arg(long)]
         l2_claimer_address: Option<Address>,
     },
+    /// Remove the ban for a peer IP address
+    UnbanPeer { ip: IpAddr },
     /// Initiate a withdrawal to the specified mainchain address
     Withdraw {
         mainchain_address: bitcoin::Address<bitcoin::address::NetworkUnchecked>,
@@ -201,6 +300,9 @@ pub enum Command {
         #[arg(long)]
         mainchain_fee_sats: u64,
     },
+    /// Show the time for which block connection has held the database
+    /// write transaction, in microseconds
+    WriteTxnStats,
 }
 
 #[derive(Clone, Debug, Parser)]
@@ -236,6 +338,10 @@ where
             let balance = rpc_client.balance().await?;
             serde_json::to_string_pretty(&balance)?
         }
+        Command::BanPeer { ip, duration_secs } => {
+            let () = rpc_client.ban_peer(ip, duration_secs).await?;
+            String::default()
+        }
         Command::ConnectPeer { addr } => {
             let () = rpc_client.connect_peer(addr).await?;
             String::default()
@@ -247,8 +353,13 @@ where
             l2_recipient,
             l2_amount_sats,
             required_confirmations,
+            expires_in_blocks,
+            require_l1_commitment,
+            allow_partial_fills,
+            l1_match_policy,
             fee_sats,
         } => {
+            let () = parent_chain.validate_address(&l1_recipient_address)?;
             let (swap_id, txid) = rpc_client
                 .create_swap(
                     parent_chain,
@@ -257,6 +368,10 @@ where
                     l2_recipient,
                     l2_amount_sats,
                     required_confirmations,
+                    expires_in_blocks,
+                    require_l1_commitment,
+                    allow_partial_fills,
+                    l1_match_policy,
                     fee_sats,
                 )
                 .await?;
@@ -270,6 +385,32 @@ where
                 rpc_client.claim_swap(swap_id, l2_claimer_address).await?;
             format!("Swap claimed: txid={}", txid)
         }
+        Command::CreateL1ToL2Swap {
+            parent_chain,
+            l1_amount_sats,
+            l2_recipient,
+            l2_amount_sats,
+            required_confirmations,
+            expires_in_blocks,
+            require_l1_commitment,
+            l1_match_policy,
+            fee_sats,
+        } => {
+            let (swap_id, txid) = rpc_client
+                .create_l1_to_l2_swap(
+                    parent_chain,
+                    l1_amount_sats,
+                    l2_recipient,
+                    l2_amount_sats,
+                    required_confirmations,
+                    expires_in_blocks,
+                    require_l1_commitment,
+                    l1_match_policy,
+                    fee_sats,
+                )
+                .await?;
+            format!("Swap created: id={} txid={}", swap_id, txid)
+        }
         Command::CreateDeposit {
             address,
             value_sats,
@@ -299,6 +440,10 @@ where
             let block_hash = rpc_client.get_best_sidechain_block_hash().await?;
             serde_json::to_string_pretty(&block_hash)?
         }
+        Command::GetNodeId => {
+            let node_id = rpc_client.get_node_id().await?;
+            format!("{node_id}")
+        }
         Command::GetBmmInclusions { block_hash } => {
             let bmm_inclusions =
                 rpc_client.get_bmm_inclusions(block_hash).await?;
@@ -307,16 +452,9 @@ where
         Command::GenerateMnemonic => rpc_client.generate_mnemonic().await?,
         Command::GetL1Config { chain } => {
             let path = l1_config_path();
-            let configs: HashMap<ParentChainType, RpcConfig> = if path.exists()
-            {
-                let s = std::fs::read_to_string(&path).map_err(|e| {
-                    anyhow::anyhow!("read config: {}: {}", path.display(), e)
-                })?;
-                serde_json::from_str(&s).unwrap_or_default()
-            } else {
-                HashMap::new()
-            };
-            let out: HashMap<ParentChainType, RpcConfig> = match chain {
+            let configs = parent_chain_rpc::load_rpc_configs_from_path(&path)
+                .unwrap_or_default();
+            let out: HashMap<ParentChainType, L1Endpoints> = match chain {
                 Some(c) => {
                     configs.into_iter().filter(|(k, _)| *k == c).collect()
                 }
@@ -340,6 +478,14 @@ where
             let blockcount = rpc_client.getblockcount().await?;
             format!("{blockcount}")
         }
+        Command::GetSwapCommitment {
+            swap_id,
+            l2_claimer_address,
+        } => {
+            rpc_client
+                .get_swap_commitment(swap_id, l2_claimer_address)
+                .await?
+        }
         Command::GetSwapStatus { swap_id } => {
             let status = rpc_client.get_swap_status(swap_id).await?;
             serde_json::to_string_pretty(&status)?
@@ -349,6 +495,14 @@ where
                 rpc_client.latest_failed_withdrawal_bundle_height().await?;
             serde_json::to_string_pretty(&height)?
         }
+        Command::L1Status => {
+            let status = rpc_client.l1_status().await?;
+            serde_json::to_string_pretty(&status)?
+        }
+        Command::ListBanned => {
+            let banned = rpc_client.list_banned().await?;
+            serde_json::to_string_pretty(&banned)?
+        }
         Command::ListPeers => {
             let peers = rpc_client.list_peers().await?;
             serde_json::to_string_pretty(&peers)?
@@ -377,17 +531,31 @@ where
                 balance.available.to_sat()
             )
         }
+        Command::LockSwap {
+            swap_id,
+            l1_recipient_address,
+            fee_sats,
+        } => {
+            let txid = rpc_client
+                .lock_swap(swap_id, l1_recipient_address, fee_sats)
+                .await?;
+            format!("Swap locked: txid={}", txid)
+        }
         Command::ReconstructSwaps => {
             let count = rpc_client.reconstruct_swaps().await?;
             format!("Reconstructed {} swaps from blockchain", count)
         }
-        Command::CancelSwap { swap_id } => {
-            rpc_client.cancel_swap(swap_id).await?;
-            "Swap cancelled".to_string()
+        Command::TrackL1Headers {
+            parent_chain,
+            fee_sats,
+        } => {
+            let txid =
+                rpc_client.track_l1_headers(parent_chain, fee_sats).await?;
+            format!("L1 headers submitted: txid={}", txid)
         }
-        Command::DeleteSwap { swap_id } => {
-            rpc_client.delete_swap(swap_id).await?;
-            "Swap deleted".to_string()
+        Command::CancelSwap { swap_id } => {
+            let txid = rpc_client.cancel_swap(swap_id).await?;
+            format!("Swap cancel submitted: txid={}", txid)
         }
         Command::UpdateSwapL1Txid {
             swap_id,
@@ -432,33 +600,30 @@ where
             url,
             user,
             password,
+            cookie_file,
+            backend,
+            zmq_url,
         } => {
-            let path = l1_config_path();
-            let mut configs: HashMap<ParentChainType, RpcConfig> = if path
-                .exists()
-            {
-                let s = std::fs::read_to_string(&path).map_err(|e| {
-                    anyhow::anyhow!("read config: {}: {}", path.display(), e)
-                })?;
-                serde_json::from_str(&s).unwrap_or_default()
-            } else {
-                HashMap::new()
+            let config = RpcConfig {
+                url,
+                user,
+                password,
+                cookie_file,
+                backend,
+                zmq_url,
             };
-            configs.insert(
+            let parent_chain =
+                parent_chain_rpc::detect_chain_type(&config, parent_chain)
+                    .await?;
+            let path = l1_config_path();
+            parent_chain_rpc::save_rpc_config_to_path(
+                &path,
                 parent_chain,
-                RpcConfig {
-                    url: url.clone(),
-                    user: user.clone(),
-                    password: password.clone(),
-                },
-            );
-            if let Some(parent) = path.parent() {
-                drop(std::fs::create_dir_all(parent));
-            }
-            std::fs::write(&path, serde_json::to_string_pretty(&configs)?)
-                .map_err(|e| {
-                    anyhow::anyhow!("write config: {}: {}", path.display(), e)
-                })?;
+                config,
+            )
+            .map_err(|e| {
+                anyhow::anyhow!("write config: {}: {}", path.display(), e)
+            })?;
             format!(
                 "L1 RPC config saved for {} at {}",
                 parent_chain.coin_name(),
@@ -481,6 +646,10 @@ where
             let txid = rpc_client.transfer(dest, value_sats, fee_sats).await?;
             format!("{txid}")
         }
+        Command::UnbanPeer { ip } => {
+            let () = rpc_client.unban_peer(ip).await?;
+            String::default()
+        }
         Command::Withdraw {
             mainchain_address,
             amount_sats,
@@ -497,6 +666,10 @@ where
                 .await?;
             format!("{txid}")
         }
+        Command::WriteTxnStats => {
+            let stats = rpc_client.write_txn_stats().await?;
+            serde_json::to_string_pretty(&stats)?
+        }
     })
 }
 
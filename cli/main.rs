@@ -1,8 +1,9 @@
 use clap::Parser;
-use coinshift_app_cli_lib::Cli;
+use coinshift_app_cli_lib::{Cli, load_parent_chains};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let () = load_parent_chains()?;
     let cli = Cli::parse();
     let res = cli.run().await?;
     #[allow(clippy::print_stdout)]
@@ -66,7 +66,7 @@ async fn confirmations_block_inclusion_task(
     )
     .await?;
 
-    let l1_recipient = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    let l1_recipient = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
 
     let (swap_id, txid) = sidechain
         .rpc_client
@@ -77,6 +77,10 @@ async fn confirmations_block_inclusion_task(
             Some(sidechain.rpc_client.get_new_address().await?),
             SWAP_L2_AMOUNT,
             Some(1),
+            None,
+            false,
+            false,
+            None,
             SWAP_FEE,
         )
         .await?;
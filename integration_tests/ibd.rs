@@ -159,6 +159,15 @@ async fn initial_block_download_task(
             coinshift_nodes.syncer.rpc_client.getblockcount().await?;
         anyhow::ensure!(syncer_blocks == BMM_BLOCKS);
     }
+    // Check that the syncer reports the write transactions held while
+    // connecting the synced blocks
+    {
+        let stats = coinshift_nodes.syncer.rpc_client.write_txn_stats().await?;
+        tracing::debug!(?stats, "Syncer write transaction stats");
+        anyhow::ensure!(stats.count > 0);
+        anyhow::ensure!(stats.last_micros <= stats.max_micros);
+        anyhow::ensure!(stats.max_micros <= stats.total_micros);
+    }
     drop(coinshift_nodes.syncer);
     drop(coinshift_nodes.sender);
     tracing::info!(
@@ -12,10 +12,16 @@ use crate::{
     l1_verification_rpc_only::l1_verification_rpc_only_trial,
     multi_node_verification::multi_node_verification_trial,
     setup::{Init, PostSetup},
+    swap_cancel::swap_cancel_trial,
+    swap_claim_two_node::swap_claim_two_node_trial,
     swap_creation::{
         swap_creation_fixed_trial, swap_creation_open_fill_trial,
         swap_creation_open_trial,
     },
+    swap_expiry::swap_expiry_trial,
+    swap_l1_commitment::swap_l1_commitment_trial,
+    swap_l1_to_l2::swap_l1_to_l2_trial,
+    swap_partial_fills::swap_partial_fills_trial,
     unknown_withdrawal::unknown_withdrawal_trial,
     util::BinPaths,
 };
@@ -80,6 +86,36 @@ pub fn tests(
             file_registry.clone(),
             failure_collector.clone(),
         ),
+        swap_cancel_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        swap_expiry_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        swap_claim_two_node_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        swap_l1_to_l2_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        swap_l1_commitment_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
+        swap_partial_fills_trial(
+            bin_paths.clone(),
+            file_registry.clone(),
+            failure_collector.clone(),
+        ),
         l1_txid_uniqueness_trial(
             bin_paths.clone(),
             file_registry.clone(),
@@ -64,7 +64,7 @@ async fn l1_txid_uniqueness_task(
     )
     .await?;
 
-    let l1_recipient = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    let l1_recipient = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
 
     // Create first swap (pre-specified)
     let (swap_id_a, txid_a) = sidechain
@@ -76,6 +76,10 @@ async fn l1_txid_uniqueness_task(
             Some(sidechain.rpc_client.get_new_address().await?),
             SWAP_L2_AMOUNT,
             Some(1),
+            None,
+            false,
+            false,
+            None,
             SWAP_FEE,
         )
         .await?;
@@ -98,6 +102,10 @@ async fn l1_txid_uniqueness_task(
             Some(sidechain.rpc_client.get_new_address().await?),
             SWAP_L2_AMOUNT,
             Some(1),
+            None,
+            false,
+            false,
+            None,
             SWAP_FEE,
         )
         .await?;
@@ -1,9 +1,9 @@
-//! Test that swap L1 verification uses RPC only (no BMM / header chain / merkle proof).
+//! Test that L1 payments observed over RPC only change the node's local view.
 //!
-//! Documents COINSHIFT_HOW_IT_WORKS.md item 3: BMM reports, header chain, and
-//! merkle proof are not used for swap L1 verification in this repo. A swap can
-//! reach ReadyToClaim with only RPC-based L1 tx detection (or manual
-//! update_swap_l1_txid).
+//! Documents COINSHIFT_HOW_IT_WORKS.md item 3: a swap can reach ReadyToClaim
+//! in the node's local view with only RPC-based L1 tx detection (or manual
+//! update_swap_l1_txid), but claims must still prove the L1 payment with an
+//! SPV proof.
 
 use bip300301_enforcer_integration_tests::{
     integration_test::deposit,
@@ -43,7 +43,8 @@ async fn wait_for_swap_in_block(
     Ok(())
 }
 
-/// Swap reaches ReadyToClaim with RPC-only L1 verification (no BMM/merkle).
+/// Swap reaches ReadyToClaim locally with RPC-only L1 verification, and
+/// cannot be claimed without an SPV proof.
 async fn l1_verification_rpc_only_task(
     bin_paths: BinPaths,
     res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
@@ -66,7 +67,7 @@ async fn l1_verification_rpc_only_task(
     )
     .await?;
 
-    let l1_recipient = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    let l1_recipient = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
 
     let (swap_id, txid) = sidechain
         .rpc_client
@@ -77,6 +78,10 @@ async fn l1_verification_rpc_only_task(
             None, // open swap
             SWAP_L2_AMOUNT,
             Some(1),
+            None,
+            false,
+            false,
+            None,
             SWAP_FEE,
         )
         .await?;
@@ -89,7 +94,7 @@ async fn l1_verification_rpc_only_task(
     .await?;
     sleep(std::time::Duration::from_millis(500)).await;
 
-    // Set L1 txid via RPC (no BMM, no merkle proof) — L1 verification is RPC-only in this repo
+    // Set L1 txid via RPC (no BMM, no merkle proof)
     let fake_l1_txid_hex = "cc".repeat(32);
     sidechain
         .rpc_client
@@ -108,8 +113,15 @@ async fn l1_verification_rpc_only_task(
         status.state
     );
 
+    let claim_err = sidechain
+        .rpc_client
+        .claim_swap(swap_id, None)
+        .await
+        .expect_err("claim without a provable L1 payment should fail");
+    tracing::info!(%swap_id, error = %claim_err, "Claim rejected");
+
     tracing::info!(
-        "L1 verification RPC-only test passed: swap reached ReadyToClaim without BMM/header chain/merkle proof"
+        "L1 verification RPC-only test passed: swap reached ReadyToClaim locally, and the claim was refused"
     );
     crate::swap_creation::cleanup_swapper(sidechain, enforcer_post_setup).await
 }
@@ -12,8 +12,15 @@ mod l1_rpc_dependency;
 mod l1_txid_uniqueness;
 mod l1_verification_rpc_only;
 mod multi_node_verification;
+mod regtest_l1;
 mod setup;
+mod swap_cancel;
+mod swap_claim_two_node;
 mod swap_creation;
+mod swap_expiry;
+mod swap_l1_commitment;
+mod swap_l1_to_l2;
+mod swap_partial_fills;
 mod unknown_withdrawal;
 mod util;
 
@@ -340,7 +340,8 @@ async fn multi_node_verification_task(
 
     // 2. Bob creates a swap
     tracing::info!("Bob creating a swap");
-    let l1_recipient_address = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    let l1_recipient_address =
+        "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
     let bob_l2_recipient = nodes.bob.rpc_client.get_new_address().await?;
     let (swap_id_bob, swap_txid_bob) = nodes
         .bob
@@ -352,6 +353,10 @@ async fn multi_node_verification_task(
             Some(bob_l2_recipient),
             SWAP_L2_AMOUNT,
             Some(1),
+            None,
+            false,
+            false,
+            None,
             SWAP_FEE,
         )
         .await?;
@@ -378,6 +383,10 @@ async fn multi_node_verification_task(
             Some(alice_l2_recipient),
             SWAP_L2_AMOUNT,
             Some(1),
+            None,
+            false,
+            false,
+            None,
             SWAP_FEE,
         )
         .await?;
@@ -0,0 +1,131 @@
+//! L1 payments on the enforcer's Regtest node, for swap claims
+
+use bip300301_enforcer_integration_tests::{
+    mine::mine,
+    setup::PostSetup as EnforcerPostSetup,
+    util::{BitcoinCli, CommandExt as _},
+};
+use coinshift::types::{SwapId, SwapState};
+use coinshift_app_rpc_api::RpcClient as _;
+use tokio::time::sleep;
+
+use crate::{setup::PostSetup, swap_claim_two_node::swap_state};
+
+/// Bitcoin Core wallet that pays for swaps
+const L1_WALLET: &str = "coinshift-swaps";
+
+/// Coinbase outputs can be spent after this many blocks
+const COINBASE_MATURITY: u32 = 100;
+
+const DETECTION_TIMEOUT: std::time::Duration =
+    std::time::Duration::from_secs(30);
+
+fn wallet_cli(post_setup: &EnforcerPostSetup) -> BitcoinCli {
+    BitcoinCli {
+        rpc_wallet: Some(L1_WALLET.to_owned()),
+        ..post_setup.bitcoin_cli.clone()
+    }
+}
+
+/// Get a new address from the L1 wallet
+pub async fn new_l1_address(
+    post_setup: &EnforcerPostSetup,
+) -> anyhow::Result<String> {
+    let address = wallet_cli(post_setup)
+        .command::<String, _, String, _, _>([], "getnewaddress", [])
+        .run_utf8()
+        .await?;
+    Ok(address.trim().to_owned())
+}
+
+/// Create the L1 wallet, and mine a spendable coinbase output to it
+pub async fn fund_l1_wallet(
+    post_setup: &mut EnforcerPostSetup,
+) -> anyhow::Result<()> {
+    let _: String = post_setup
+        .bitcoin_cli
+        .command::<String, _, String, _, _>(
+            [],
+            "createwallet",
+            [L1_WALLET.to_owned()],
+        )
+        .run_utf8()
+        .await?;
+    let address = new_l1_address(post_setup).await?;
+    let _: String = post_setup
+        .bitcoin_cli
+        .command::<String, _, String, _, _>(
+            [],
+            "generatetoaddress",
+            [(COINBASE_MATURITY + 1).to_string(), address],
+        )
+        .run_utf8()
+        .await?;
+    Ok(())
+}
+
+/// Pay `amount` to `address` from the L1 wallet, and mine blocks until the
+/// payment has `confirmations`
+pub async fn pay_l1(
+    post_setup: &mut EnforcerPostSetup,
+    address: &str,
+    amount: bitcoin::Amount,
+    confirmations: u32,
+) -> anyhow::Result<bitcoin::Txid> {
+    let txid = wallet_cli(post_setup)
+        .command::<String, _, String, _, _>(
+            [],
+            "sendtoaddress",
+            [
+                address.to_owned(),
+                amount.to_string_in(bitcoin::Denomination::Bitcoin),
+            ],
+        )
+        .run_utf8()
+        .await?;
+    let txid = txid.trim().parse()?;
+    let () = mine::<PostSetup>(post_setup, confirmations, Some(true)).await?;
+    Ok(txid)
+}
+
+/// Report a confirmed L1 payment to `node`, and wait for the swap to be
+/// ready to claim. The node may also find the payment with its own L1 RPC.
+pub async fn report_l1_payment(
+    node: &PostSetup,
+    swap_id: SwapId,
+    l1_txid: bitcoin::Txid,
+    confirmations: u32,
+) -> anyhow::Result<()> {
+    let deadline = tokio::time::Instant::now() + DETECTION_TIMEOUT;
+    loop {
+        match swap_state(node, swap_id).await? {
+            SwapState::ReadyToClaim => return Ok(()),
+            SwapState::Pending => {
+                if let Err(err) = node
+                    .rpc_client
+                    .update_swap_l1_txid(
+                        swap_id,
+                        l1_txid.to_string(),
+                        confirmations,
+                        None,
+                    )
+                    .await
+                {
+                    tracing::debug!(
+                        %swap_id,
+                        error = %err,
+                        "L1 payment not reported"
+                    );
+                }
+            }
+            state => {
+                tracing::debug!(%swap_id, ?state, "Waiting for L1 payment");
+            }
+        }
+        anyhow::ensure!(
+            tokio::time::Instant::now() < deadline,
+            "Swap {swap_id} is not ready to claim after L1 payment {l1_txid}"
+        );
+        sleep(std::time::Duration::from_millis(500)).await;
+    }
+}
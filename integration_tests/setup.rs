@@ -9,7 +9,9 @@ use bip300301_enforcer_integration_tests::{
     util::AbortOnDrop,
 };
 use bip300301_enforcer_lib::types::SidechainNumber;
-use coinshift::types::{OutPoint, OutputContent, PointedOutput};
+use coinshift::types::{
+    OutPoint, OutputContent, ParentChainType, PointedOutput,
+};
 use coinshift_app_rpc_api::RpcClient as _;
 use futures::{TryFutureExt as _, channel::mpsc, future};
 use reserve_port::ReservedPort;
@@ -86,6 +88,9 @@ pub struct PostSetup {
     pub rpc_client: jsonrpsee::http_client::HttpClient,
     /// Address for receiving deposits
     pub deposit_address: coinshift::types::Address,
+    /// L1 RPC config file read by coinshift_app, which is kept in the data
+    /// dir instead of the user's data home
+    pub l1_rpc_config_path: PathBuf,
     // MUST occur after tasks in order to ensure that tasks are dropped
     // before reserved ports are freed
     pub reserved_ports: ReservedPorts,
@@ -131,6 +136,28 @@ impl PostSetup {
     pub fn net_addr(&self) -> SocketAddrV4 {
         SocketAddrV4::new(Ipv4Addr::LOCALHOST, self.net_port())
     }
+
+    /// Point coinshift_app at the enforcer's Regtest node, so that it can
+    /// look up L1 payments and build SPV proofs for claims
+    pub fn set_regtest_l1_rpc(
+        &self,
+        post_setup: &EnforcerPostSetup,
+    ) -> std::io::Result<()> {
+        let bitcoin_cli = &post_setup.bitcoin_cli;
+        let config = coinshift::parent_chain_rpc::RpcConfig {
+            url: format!("http://127.0.0.1:{}", bitcoin_cli.rpc_port),
+            user: bitcoin_cli.rpc_user.clone(),
+            password: bitcoin_cli.rpc_pass.clone(),
+            cookie_file: None,
+            backend: coinshift::parent_chain_rpc::BackendKind::BitcoinCore,
+            zmq_url: None,
+        };
+        coinshift::parent_chain_rpc::save_rpc_config_to_path(
+            &self.l1_rpc_config_path,
+            ParentChainType::Regtest,
+            config,
+        )
+    }
 }
 
 impl Sidechain for PostSetup {
@@ -158,6 +185,10 @@ impl Sidechain for PostSetup {
         };
         std::fs::create_dir(&coinshift_dir)
             .map_err(Self::SetupError::CreateCoinshiftDir)?;
+        // coinshift_app reads its L1 RPC config from the data home
+        let data_home = coinshift_dir.join("data_home");
+        let l1_rpc_config_path =
+            data_home.join("coinshift").join("l1_rpc_configs.json");
         let coinshift_app = CoinshiftApp {
             path: init.coinshift_app,
             data_dir: coinshift_dir,
@@ -170,12 +201,22 @@ impl Sidechain for PostSetup {
             rpc_port: reserved_ports.rpc.port(),
         };
         let coinshift_app_task = coinshift_app
-            .spawn_command_with_args::<String, String, _, _, _>([], [], {
-                let res_tx = res_tx.clone();
-                move |err| {
-                    let _err: Result<(), _> = res_tx.unbounded_send(Err(err));
-                }
-            });
+            .spawn_command_with_args::<String, String, _, _, _>(
+                [
+                    (
+                        "XDG_DATA_HOME".to_owned(),
+                        data_home.display().to_string(),
+                    ),
+                ],
+                [],
+                {
+                    let res_tx = res_tx.clone();
+                    move |err| {
+                        let _err: Result<(), _> =
+                            res_tx.unbounded_send(Err(err));
+                    }
+                },
+            );
         tracing::debug!("Started Coinshift");
         sleep(Duration::from_secs(1)).await;
         let rpc_client = jsonrpsee::http_client::HttpClient::builder()
@@ -190,6 +231,7 @@ impl Sidechain for PostSetup {
             _coinshift_app_task: coinshift_app_task,
             rpc_client,
             deposit_address,
+            l1_rpc_config_path,
             reserved_ports,
         })
     }
@@ -0,0 +1,243 @@
+//! Test that cancelling a swap is a consensus-level SwapCancel transaction,
+//! which returns the locked SwapPending output to the creator once mined.
+//! Before a swap expires, a SwapCancel only requests the cancellation: it
+//! brings the expiry forward, and the swap can be claimed until then.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::Sidechain as _,
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{
+    OutPoint, OutputContent, ParentChainType, PointedOutput, Swap, SwapId,
+    SwapState,
+};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::util::BinPaths;
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+const SWAP_EXPIRY_BLOCKS: u32 = 2;
+
+pub fn locked_to_swap(utxos: &[PointedOutput], swap_id: SwapId) -> bool {
+    utxos.iter().any(|utxo| {
+        matches!(
+            utxo.output.content,
+            OutputContent::SwapPending {
+                swap_id: locked_swap_id,
+                ..
+            } if locked_swap_id == swap_id.0
+        )
+    })
+}
+
+async fn swap_cancel_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut sidechain, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "swap-cancel",
+        )
+        .await?;
+
+    let deposit_address = sidechain.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut sidechain,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+
+    let (swap_id, _swap_txid) = sidechain
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz".to_string(),
+            SWAP_L1_AMOUNT,
+            Some(sidechain.rpc_client.get_new_address().await?),
+            SWAP_L2_AMOUNT,
+            Some(1),
+            Some(SWAP_EXPIRY_BLOCKS),
+            false,
+            false,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    anyhow::ensure!(
+        locked_to_swap(&sidechain.rpc_client.list_utxos().await?, swap_id),
+        "Swap {} has no locked output after creation",
+        swap_id
+    );
+
+    // The swap already expires within the cancellation grace period, so a
+    // cancellation request would not bring its expiry forward
+    let _err = sidechain
+        .rpc_client
+        .cancel_swap(swap_id)
+        .await
+        .expect_err("cancel_swap before expiry should fail");
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    // Cancelling broadcasts a transaction; the swap is unchanged until it
+    // is mined
+    let cancel_txid = sidechain.rpc_client.cancel_swap(swap_id).await?;
+    let status = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap not found"))?;
+    anyhow::ensure!(
+        matches!(status.state, SwapState::Pending),
+        "Swap should stay Pending until the cancel is mined: {:?}",
+        status.state
+    );
+
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    let status = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap not found"))?;
+    anyhow::ensure!(
+        matches!(status.state, SwapState::Cancelled),
+        "Swap should be Cancelled after the cancel is mined: {:?}",
+        status.state
+    );
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    anyhow::ensure!(
+        !locked_to_swap(&utxos, swap_id),
+        "Swap {} still has a locked output after cancellation",
+        swap_id
+    );
+    let refunded = utxos.iter().any(|utxo| {
+        let OutPoint::Regular { txid, .. } = utxo.outpoint else {
+            return false;
+        };
+        let OutputContent::Value(value) = utxo.output.content else {
+            return false;
+        };
+        txid == cancel_txid && value.to_sat() == SWAP_L2_AMOUNT
+    });
+    anyhow::ensure!(
+        refunded,
+        "Cancel transaction {} did not return the locked value",
+        cancel_txid
+    );
+
+    // A cancelled swap cannot be cancelled again
+    let _err = sidechain
+        .rpc_client
+        .cancel_swap(swap_id)
+        .await
+        .expect_err("cancel_swap for a cancelled swap should fail");
+
+    // A swap without an expiry can be asked to cancel. The request brings
+    // the expiry forward, and leaves the swap claimable until then.
+    let (request_swap_id, _request_swap_txid) = sidechain
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz".to_string(),
+            SWAP_L1_AMOUNT,
+            Some(sidechain.rpc_client.get_new_address().await?),
+            SWAP_L2_AMOUNT,
+            Some(1),
+            None,
+            false,
+            false,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    let _request_txid =
+        sidechain.rpc_client.cancel_swap(request_swap_id).await?;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    let request_height = sidechain.rpc_client.getblockcount().await? - 1;
+    let status = sidechain
+        .rpc_client
+        .get_swap_status(request_swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap not found"))?;
+    anyhow::ensure!(
+        matches!(status.state, SwapState::Pending),
+        "Swap should stay Pending after a cancellation request: {:?}",
+        status.state
+    );
+    anyhow::ensure!(
+        status.expires_at_height
+            == Some(request_height + Swap::CANCEL_GRACE_BLOCKS),
+        "Cancellation request at height {} set expiry {:?}",
+        request_height,
+        status.expires_at_height
+    );
+    anyhow::ensure!(
+        locked_to_swap(
+            &sidechain.rpc_client.list_utxos().await?,
+            request_swap_id
+        ),
+        "Swap {} should stay locked after a cancellation request",
+        request_swap_id
+    );
+
+    // A second request would not bring the expiry forward
+    let _err = sidechain
+        .rpc_client
+        .cancel_swap(request_swap_id)
+        .await
+        .expect_err("repeated cancellation request should fail");
+
+    tracing::info!("Swap cancel test passed");
+    crate::swap_creation::cleanup_swapper(sidechain, enforcer_post_setup).await
+}
+
+pub fn swap_cancel_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_cancel",
+        async move {
+            let (res_tx, mut res_rx) = mpsc::unbounded();
+            let _task: AbortOnDrop<()> = tokio::task::spawn({
+                let res_tx = res_tx.clone();
+                async move {
+                    let res = swap_cancel_task(bin_paths, res_tx.clone()).await;
+                    drop(res_tx.unbounded_send(res));
+                }
+                .in_current_span()
+            })
+            .into();
+            res_rx.next().await.ok_or_else(|| {
+                anyhow::anyhow!("Unexpected end of test task result stream")
+            })?
+        }
+        .boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -0,0 +1,244 @@
+//! Test claims of a swap by a counterparty on another node, whose wallet
+//! does not hold the swap creator's signing keys.
+//!
+//! L1 payments reported to a node (here via `update_swap_l1_txid`) only
+//! change that node's local view of the swap. The claimer pays on Regtest,
+//! and proves the payment with an SPV proof built from its L1 node, so the
+//! claim must be accepted by the creator's node too.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::{PostSetup as EnforcerPostSetup, Sidechain as _},
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{OutputContent, ParentChainType, SwapId, SwapState};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{
+    regtest_l1::{fund_l1_wallet, new_l1_address, pay_l1, report_l1_payment},
+    setup::{Init, PostSetup},
+    swap_cancel::locked_to_swap,
+    util::BinPaths,
+};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+const SYNC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Set up the enforcer, and separate creator and claimer nodes
+pub async fn setup(
+    bin_paths: &BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<(EnforcerPostSetup, PostSetup, PostSetup)> {
+    let enforcer_post_setup =
+        crate::swap_creation::setup(bin_paths, res_tx.clone()).await?;
+    let creator = PostSetup::setup(
+        Init {
+            coinshift_app: bin_paths.coinshift_app.clone(),
+            data_dir_suffix: Some("creator".to_owned()),
+        },
+        &enforcer_post_setup,
+        res_tx.clone(),
+    )
+    .await?;
+    tracing::info!("Setup creator's node successfully");
+    let claimer = PostSetup::setup(
+        Init {
+            coinshift_app: bin_paths.coinshift_app.clone(),
+            data_dir_suffix: Some("claimer".to_owned()),
+        },
+        &enforcer_post_setup,
+        res_tx,
+    )
+    .await?;
+    tracing::info!("Setup claimer's node successfully");
+    Ok((enforcer_post_setup, creator, claimer))
+}
+
+/// Wait for the claimer to reach the same block count as the creator
+pub async fn wait_for_sync(
+    claimer: &PostSetup,
+    creator: &PostSetup,
+) -> anyhow::Result<()> {
+    let deadline = tokio::time::Instant::now() + SYNC_TIMEOUT;
+    loop {
+        let claimer_block_count = claimer.rpc_client.getblockcount().await?;
+        let creator_block_count = creator.rpc_client.getblockcount().await?;
+        if claimer_block_count == creator_block_count {
+            return Ok(());
+        }
+        anyhow::ensure!(
+            tokio::time::Instant::now() < deadline,
+            "Claimer failed to sync from creator within timeout. Claimer: {}, Creator: {}",
+            claimer_block_count,
+            creator_block_count
+        );
+        sleep(std::time::Duration::from_millis(500)).await;
+    }
+}
+
+pub async fn swap_state(
+    node: &PostSetup,
+    swap_id: SwapId,
+) -> anyhow::Result<SwapState> {
+    let swap = node
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+    Ok(swap.state)
+}
+
+async fn swap_claim_two_node_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut enforcer_post_setup, mut creator, claimer) =
+        setup(&bin_paths, res_tx).await?;
+
+    let deposit_address = creator.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut creator,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+    tracing::info!("Deposited to creator successfully");
+    let () = fund_l1_wallet(&mut enforcer_post_setup).await?;
+    // Only the claimer has an L1 node to prove its payment with
+    let () = claimer.set_regtest_l1_rpc(&enforcer_post_setup)?;
+
+    // The claimer pulls blocks from the creator, and relays its claim
+    claimer
+        .rpc_client
+        .connect_peer(creator.net_addr().into())
+        .await?;
+    sleep(std::time::Duration::from_secs(2)).await;
+
+    // The creator offers L2 coins to an address in the claimer's wallet
+    let l1_recipient = new_l1_address(&enforcer_post_setup).await?;
+    let claimer_address = claimer.rpc_client.get_new_address().await?;
+    let (swap_id, _swap_txid) = creator
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            l1_recipient.clone(),
+            SWAP_L1_AMOUNT,
+            Some(claimer_address),
+            SWAP_L2_AMOUNT,
+            Some(1),
+            None,
+            false,
+            false,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    creator.bmm_single(&mut enforcer_post_setup).await?;
+    wait_for_sync(&claimer, &creator).await?;
+
+    let claimer_utxos = claimer.rpc_client.list_utxos().await?;
+    anyhow::ensure!(
+        locked_to_swap(&claimer_utxos, swap_id),
+        "Claimer does not see the locked output of swap {}",
+        swap_id
+    );
+    let claimer_wallet_addresses =
+        claimer.rpc_client.get_wallet_addresses().await?;
+    anyhow::ensure!(
+        !claimer_utxos.iter().any(|utxo| {
+            matches!(utxo.output.content, OutputContent::SwapPending { .. })
+                && claimer_wallet_addresses.contains(&utxo.output.address)
+        }),
+        "Swap {} output should not belong to the claimer's wallet",
+        swap_id
+    );
+
+    // The claimer pays on L1, and reports the payment to its own node only
+    let l1_txid = pay_l1(
+        &mut enforcer_post_setup,
+        &l1_recipient,
+        bitcoin::Amount::from_sat(SWAP_L1_AMOUNT),
+        1,
+    )
+    .await?;
+    tracing::info!(%swap_id, %l1_txid, "Paid swap on L1");
+    let () = report_l1_payment(&claimer, swap_id, l1_txid, 1).await?;
+    let state = swap_state(&creator, swap_id).await?;
+    anyhow::ensure!(
+        matches!(state, SwapState::Pending),
+        "The claimer's L1 observation should not reach the creator: {:?}",
+        state
+    );
+
+    // The claim carries an SPV proof, so the creator's node accepts it
+    let claim_txid = claimer.rpc_client.claim_swap(swap_id, None).await?;
+    tracing::info!(%swap_id, %claim_txid, "Claimed swap");
+    sleep(std::time::Duration::from_secs(1)).await;
+    creator.bmm_single(&mut enforcer_post_setup).await?;
+    wait_for_sync(&claimer, &creator).await?;
+
+    for node in [&creator, &claimer] {
+        let state = swap_state(node, swap_id).await?;
+        anyhow::ensure!(
+            matches!(state, SwapState::Completed),
+            "Swap should be Completed after the claim: {:?}",
+            state
+        );
+        anyhow::ensure!(
+            !locked_to_swap(&node.rpc_client.list_utxos().await?, swap_id),
+            "Swap {} should not stay locked after the claim",
+            swap_id
+        );
+    }
+    let balance = claimer.rpc_client.balance().await?;
+    anyhow::ensure!(
+        balance.total == bitcoin::Amount::from_sat(SWAP_L2_AMOUNT),
+        "Claimer should receive the swap's L2 amount: {}",
+        balance.total
+    );
+
+    tracing::info!("Two-node swap claim test passed");
+    drop(claimer);
+    crate::swap_creation::cleanup_swapper(creator, enforcer_post_setup).await
+}
+
+pub fn swap_claim_two_node_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_claim_two_node",
+        async move {
+            let (res_tx, mut res_rx) = mpsc::unbounded();
+            let _task: AbortOnDrop<()> = tokio::task::spawn({
+                let res_tx = res_tx.clone();
+                async move {
+                    let res =
+                        swap_claim_two_node_task(bin_paths, res_tx.clone())
+                            .await;
+                    drop(res_tx.unbounded_send(res));
+                }
+                .in_current_span()
+            })
+            .into();
+            res_rx.next().await.ok_or_else(|| {
+                anyhow::anyhow!("Unexpected end of test task result stream")
+            })?
+        }
+        .boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
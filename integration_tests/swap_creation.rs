@@ -24,7 +24,7 @@ use crate::{
 };
 
 /// Initial setup for the test
-async fn setup(
+pub async fn setup(
     bin_paths: &BinPaths,
     res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
 ) -> anyhow::Result<EnforcerPostSetup> {
@@ -267,7 +267,8 @@ async fn swap_creation_fixed_task(
     let l2_recipient_address = sidechain.rpc_client.get_new_address().await?;
 
     // Generate a regtest address for L1 recipient
-    let l1_recipient_address = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    let l1_recipient_address =
+        "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
 
     // Create a pre-specified swap (with l2_recipient)
     tracing::info!("Creating pre-specified swap");
@@ -280,6 +281,10 @@ async fn swap_creation_fixed_task(
             Some(l2_recipient_address),
             SWAP_L2_AMOUNT,
             Some(1), // required_confirmations
+            None,    // expires_in_blocks
+            false,   // require_l1_commitment
+            false,   // allow_partial_fills
+            None,    // l1_match_policy
             SWAP_FEE,
         )
         .await?;
@@ -355,7 +360,8 @@ async fn swap_creation_open_task(
     .await?;
     tracing::info!("Deposited to sidechain successfully");
 
-    let l1_recipient_address = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    let l1_recipient_address =
+        "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
 
     // Create an open swap (without l2_recipient)
     tracing::info!("Creating open swap");
@@ -368,6 +374,10 @@ async fn swap_creation_open_task(
             None, // None = open swap
             SWAP_L2_AMOUNT,
             Some(1),
+            None,
+            false,
+            false,
+            None,
             SWAP_FEE,
         )
         .await?;
@@ -446,7 +456,8 @@ async fn swap_creation_open_fill_task(
     .await?;
     tracing::info!("Deposited to sidechain successfully");
 
-    let l1_recipient_address = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    let l1_recipient_address =
+        "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
 
     // Create an open swap (without l2_recipient)
     tracing::info!("Creating open swap to later fill");
@@ -459,6 +470,10 @@ async fn swap_creation_open_fill_task(
             None, // open swap
             SWAP_L2_AMOUNT,
             Some(1),
+            None,
+            false,
+            false,
+            None,
             SWAP_FEE,
         )
         .await?;
@@ -528,63 +543,49 @@ async fn swap_creation_open_fill_task(
         status_ready.l2_claimer_address
     );
 
-    // Claim the swap: recipient is taken from stored l2_claimer_address (can pass None)
-    let claim_txid = sidechain.rpc_client.claim_swap(swap_id, None).await?;
-    tracing::info!(swap_id = %swap_id, claim_txid = %claim_txid, "Claimed swap");
+    // Claim the swap: there is no L1 payment behind the fake txid, so no SPV
+    // proof can be built and the claim must be refused
+    let claim_err = sidechain
+        .rpc_client
+        .claim_swap(swap_id, None)
+        .await
+        .expect_err("claim without an SPV proof of the L1 payment should fail");
+    tracing::info!(
+        swap_id = %swap_id,
+        error = %claim_err,
+        "Claim without L1 payment proof rejected"
+    );
 
-    // Mine the claim transaction into a block
+    // Mine a block; the swap must stay claimable with its outputs locked
     sidechain.bmm_single(&mut enforcer_post_setup).await?;
     sleep(std::time::Duration::from_millis(500)).await;
-
-    // Verify completion
-    // Wait until the swap is marked completed
-    const MAX_STATUS_RETRIES: usize = 10;
-    const STATUS_DELAY_MS: u64 = 200;
-    let mut completed = None;
-    for _ in 0..MAX_STATUS_RETRIES {
-        let status = sidechain.rpc_client.get_swap_status(swap_id).await?;
-        if let Some(s) = status
-            && matches!(s.state, SwapState::Completed)
-        {
-            completed = Some(s);
-            break;
-        }
-        sleep(std::time::Duration::from_millis(STATUS_DELAY_MS)).await;
-    }
-    let completed_swap = completed.ok_or_else(|| {
-        anyhow::anyhow!("Swap not marked Completed after claim")
-    })?;
-
-    // Locked outputs should be released
-    let utxos_after = sidechain.rpc_client.list_utxos().await?;
-    let still_locked = utxos_after.iter().any(|utxo| {
-        matches!(
-            utxo.output.content,
-            coinshift::types::OutputContent::SwapPending { swap_id: locked, .. }
-                if locked == swap_id.0
-        )
-    });
+    let status_after = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap not found after claim attempt"))?;
     anyhow::ensure!(
-        !still_locked,
-        "Expected no locked outputs after swap completion"
+        matches!(status_after.state, SwapState::ReadyToClaim),
+        "Swap should stay ReadyToClaim without a valid claim: {:?}",
+        status_after.state
     );
+    wait_for_locked_utxos(&sidechain.rpc_client, swap_id, SWAP_L2_AMOUNT)
+        .await?;
 
     // Final report
     tracing::info!(
         swap_id = %swap_id,
         swap_create_txid = %swap_txid,
         fake_l1_txid_hex = %fake_l1_txid_hex,
-        claim_txid = %claim_txid,
         l1_recipient = l1_recipient_address,
         l1_amount_sats = SWAP_L1_AMOUNT,
         l2_amount_sats = SWAP_L2_AMOUNT,
         claimer_address = %claimer_address,
-        final_state = ?completed_swap.state,
-        utxos_total = utxos_after.len(),
-        "Open swap fill report: swap completed and locks released"
+        final_state = ?status_after.state,
+        "Open swap fill report: claim without L1 payment proof rejected"
     );
 
-    tracing::info!("Open swap fill and claim test passed");
+    tracing::info!("Open swap fill test passed");
 
     cleanup_swapper(sidechain, enforcer_post_setup).await
 }
@@ -0,0 +1,173 @@
+//! Test that a swap created with an expiry is cancelled by consensus once the
+//! expiry height is reached, releasing its locked SwapPending output back to
+//! the creator without a cancel transaction.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::Sidechain as _,
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{ParentChainType, Swap, SwapState};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{swap_cancel::locked_to_swap, util::BinPaths};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+const EXPIRES_IN_BLOCKS: u32 = 2;
+const L1_RECIPIENT: &str = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
+
+async fn swap_expiry_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut sidechain, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "swap-expiry",
+        )
+        .await?;
+
+    let deposit_address = sidechain.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut sidechain,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+
+    // Expiries outside 1..=MAX_EXPIRY_BLOCKS are rejected
+    for expires_in_blocks in [0, Swap::MAX_EXPIRY_BLOCKS + 1] {
+        let _err = sidechain
+            .rpc_client
+            .create_swap(
+                ParentChainType::Regtest,
+                L1_RECIPIENT.to_string(),
+                SWAP_L1_AMOUNT,
+                None,
+                SWAP_L2_AMOUNT,
+                Some(1),
+                Some(expires_in_blocks),
+                false,
+                false,
+                None,
+                SWAP_FEE,
+            )
+            .await
+            .expect_err("create_swap with an out-of-range expiry should fail");
+    }
+
+    let (swap_id, _swap_txid) = sidechain
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            L1_RECIPIENT.to_string(),
+            SWAP_L1_AMOUNT,
+            None,
+            SWAP_L2_AMOUNT,
+            Some(1),
+            Some(EXPIRES_IN_BLOCKS),
+            false,
+            false,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    let status = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap not found"))?;
+    anyhow::ensure!(
+        status.expires_at_height.is_some(),
+        "Swap {} was created without an expiry height",
+        swap_id
+    );
+    anyhow::ensure!(
+        locked_to_swap(&sidechain.rpc_client.list_utxos().await?, swap_id),
+        "Swap {} has no locked output after creation",
+        swap_id
+    );
+
+    // Still open one block before the expiry height
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    let status = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap not found"))?;
+    anyhow::ensure!(
+        matches!(status.state, SwapState::Pending),
+        "Swap should still be Pending before it expires: {:?}",
+        status.state
+    );
+
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    let status = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap not found"))?;
+    anyhow::ensure!(
+        matches!(status.state, SwapState::Cancelled),
+        "Swap should be Cancelled once it expires: {:?}",
+        status.state
+    );
+
+    // The unlocked output can now be swept back to the creator
+    let _cancel_txid = sidechain.rpc_client.cancel_swap(swap_id).await?;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+    anyhow::ensure!(
+        !locked_to_swap(&sidechain.rpc_client.list_utxos().await?, swap_id),
+        "Swap {} output was not refunded after expiry",
+        swap_id
+    );
+
+    tracing::info!("Swap expiry test passed");
+    crate::swap_creation::cleanup_swapper(sidechain, enforcer_post_setup).await
+}
+
+pub fn swap_expiry_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_expiry",
+        async move {
+            let (res_tx, mut res_rx) = mpsc::unbounded();
+            let _task: AbortOnDrop<()> = tokio::task::spawn({
+                let res_tx = res_tx.clone();
+                async move {
+                    let res = swap_expiry_task(bin_paths, res_tx.clone()).await;
+                    drop(res_tx.unbounded_send(res));
+                }
+                .in_current_span()
+            })
+            .into();
+            res_rx.next().await.ok_or_else(|| {
+                anyhow::anyhow!("Unexpected end of test task result stream")
+            })?
+        }
+        .boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
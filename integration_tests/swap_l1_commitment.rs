@@ -0,0 +1,151 @@
+//! Test swaps that require an OP_RETURN commitment in the L1 payment.
+//!
+//! The filler's L2 address is declared by the commitment, so it cannot be
+//! set with update_swap_l1_txid. The commitment returned by
+//! get_swap_commitment must name the swap and the filler's L2 address.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::Sidechain as _,
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{ParentChainType, SwapCommitment, SwapState};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::util::BinPaths;
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+const L1_RECIPIENT: &str = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wpqfppz";
+
+async fn swap_l1_commitment_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut sidechain, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "swap-l1-commitment",
+        )
+        .await?;
+
+    let deposit_address = sidechain.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut sidechain,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+
+    // Open swap, so that the filler's L2 address comes from the commitment
+    let (swap_id, _txid) = sidechain
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            L1_RECIPIENT.to_string(),
+            SWAP_L1_AMOUNT,
+            None,
+            SWAP_L2_AMOUNT,
+            Some(1),
+            None,
+            true,
+            false,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    let swap = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+    anyhow::ensure!(
+        swap.l1_commitment_required,
+        "Swap {} should require an L1 commitment",
+        swap_id
+    );
+
+    // The commitment names the swap and the filler's L2 address
+    let filler_address = sidechain.rpc_client.get_new_address().await?;
+    let commitment_hex = sidechain
+        .rpc_client
+        .get_swap_commitment(swap_id, filler_address)
+        .await?;
+    let expected = SwapCommitment {
+        swap_id,
+        l2_claimer_address: filler_address,
+    };
+    anyhow::ensure!(
+        commitment_hex == hex::encode(expected.to_data()),
+        "Unexpected swap commitment: {}",
+        commitment_hex
+    );
+
+    // The filler's L2 address cannot be declared without the L1 payment
+    let _err = sidechain
+        .rpc_client
+        .update_swap_l1_txid(swap_id, "dd".repeat(32), 1, Some(filler_address))
+        .await
+        .expect_err(
+            "update_swap_l1_txid should fail for a swap requiring a commitment",
+        );
+    let swap = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+    anyhow::ensure!(
+        matches!(swap.state, SwapState::Pending)
+            && swap.l2_claimer_address.is_none(),
+        "Swap should stay Pending without a claimer: {:?} {:?}",
+        swap.state,
+        swap.l2_claimer_address
+    );
+
+    tracing::info!("L1 commitment swap test passed");
+    crate::swap_creation::cleanup_swapper(sidechain, enforcer_post_setup).await
+}
+
+pub fn swap_l1_commitment_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_l1_commitment",
+        async move {
+            let (res_tx, mut res_rx) = mpsc::unbounded();
+            let _task: AbortOnDrop<()> = tokio::task::spawn({
+                let res_tx = res_tx.clone();
+                async move {
+                    let res =
+                        swap_l1_commitment_task(bin_paths, res_tx.clone())
+                            .await;
+                    drop(res_tx.unbounded_send(res));
+                }
+                .in_current_span()
+            })
+            .into();
+            res_rx.next().await.ok_or_else(|| {
+                anyhow::anyhow!("Unexpected end of test task result stream")
+            })?
+        }
+        .boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -0,0 +1,220 @@
+//! Test the L1 → L2 swap flow across two nodes.
+//!
+//! The offerer creates an L1 → L2 swap, offering an L1 payment for L2 coins.
+//! An L2 holder on another node accepts it by locking the L2 amount with a
+//! SwapLock transaction, naming their L1 address.
+//!
+//! The offerer then pays the holder on Regtest, and claims the L2 coins
+//! with an SPV proof of the payment.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::Sidechain as _,
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{ParentChainType, SwapDirection, SwapState};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{
+    regtest_l1::{fund_l1_wallet, new_l1_address, pay_l1, report_l1_payment},
+    swap_cancel::locked_to_swap,
+    swap_claim_two_node::{setup, swap_state, wait_for_sync},
+    util::BinPaths,
+};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const OFFERER_FUNDS: u64 = 1_000_000;
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+
+async fn swap_l1_to_l2_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut enforcer_post_setup, mut holder, offerer) =
+        setup(&bin_paths, res_tx).await?;
+
+    let deposit_address = holder.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut holder,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+    tracing::info!("Deposited to L2 holder successfully");
+    let () = fund_l1_wallet(&mut enforcer_post_setup).await?;
+    // The offerer proves its L1 payment with its own L1 node
+    let () = offerer.set_regtest_l1_rpc(&enforcer_post_setup)?;
+
+    // The offerer pulls blocks from the holder, and relays its transactions
+    offerer
+        .rpc_client
+        .connect_peer(holder.net_addr().into())
+        .await?;
+    sleep(std::time::Duration::from_secs(2)).await;
+
+    // The offerer needs a coin to pay the SwapCreate fee
+    let offerer_funding_address = offerer.rpc_client.get_new_address().await?;
+    let _: coinshift::types::Txid = holder
+        .rpc_client
+        .transfer(offerer_funding_address, OFFERER_FUNDS, SWAP_FEE)
+        .await?;
+    holder.bmm_single(&mut enforcer_post_setup).await?;
+    wait_for_sync(&offerer, &holder).await?;
+
+    // The offerer offers an L1 payment for L2 coins
+    let l2_recipient = offerer.rpc_client.get_new_address().await?;
+    let (swap_id, _swap_txid) = offerer
+        .rpc_client
+        .create_l1_to_l2_swap(
+            ParentChainType::Regtest,
+            SWAP_L1_AMOUNT,
+            Some(l2_recipient),
+            SWAP_L2_AMOUNT,
+            Some(1),
+            None,
+            false,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    sleep(std::time::Duration::from_secs(1)).await;
+    holder.bmm_single(&mut enforcer_post_setup).await?;
+    wait_for_sync(&offerer, &holder).await?;
+
+    for node in [&holder, &offerer] {
+        let swap = node
+            .rpc_client
+            .get_swap_status(swap_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+        anyhow::ensure!(
+            swap.direction == SwapDirection::L1ToL2
+                && matches!(swap.state, SwapState::AwaitingLock),
+            "Swap should be an L1 → L2 swap awaiting a lock: {:?} {:?}",
+            swap.direction,
+            swap.state
+        );
+        anyhow::ensure!(
+            !locked_to_swap(&node.rpc_client.list_utxos().await?, swap_id),
+            "Swap {} should not lock any outputs before SwapLock",
+            swap_id
+        );
+    }
+
+    // The holder accepts the offer, locking the L2 amount
+    let l1_recipient = new_l1_address(&enforcer_post_setup).await?;
+    let lock_txid = holder
+        .rpc_client
+        .lock_swap(swap_id, l1_recipient.clone(), SWAP_FEE)
+        .await?;
+    tracing::info!(%swap_id, %lock_txid, "Holder locked swap");
+    holder.bmm_single(&mut enforcer_post_setup).await?;
+    wait_for_sync(&offerer, &holder).await?;
+
+    for node in [&holder, &offerer] {
+        let swap = node
+            .rpc_client
+            .get_swap_status(swap_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+        anyhow::ensure!(
+            matches!(swap.state, SwapState::Pending),
+            "Swap should be Pending once locked: {:?}",
+            swap.state
+        );
+        anyhow::ensure!(
+            swap.l1_recipient_address.as_ref() == Some(&l1_recipient),
+            "Swap should pay the holder's L1 address: {:?}",
+            swap.l1_recipient_address
+        );
+        anyhow::ensure!(
+            locked_to_swap(&node.rpc_client.list_utxos().await?, swap_id),
+            "Swap {} has no locked output after SwapLock",
+            swap_id
+        );
+    }
+
+    // The offerer pays the holder on L1, and claims the L2 coins
+    let l1_txid = pay_l1(
+        &mut enforcer_post_setup,
+        &l1_recipient,
+        bitcoin::Amount::from_sat(SWAP_L1_AMOUNT),
+        1,
+    )
+    .await?;
+    tracing::info!(%swap_id, %l1_txid, "Offerer paid swap on L1");
+    let () = report_l1_payment(&offerer, swap_id, l1_txid, 1).await?;
+    let offerer_balance = offerer.rpc_client.balance().await?.total;
+    let claim_txid = offerer.rpc_client.claim_swap(swap_id, None).await?;
+    tracing::info!(%swap_id, %claim_txid, "Offerer claimed swap");
+    sleep(std::time::Duration::from_secs(1)).await;
+    holder.bmm_single(&mut enforcer_post_setup).await?;
+    wait_for_sync(&offerer, &holder).await?;
+
+    for node in [&holder, &offerer] {
+        let state = swap_state(node, swap_id).await?;
+        anyhow::ensure!(
+            matches!(state, SwapState::Completed),
+            "Swap should be Completed after the claim: {:?}",
+            state
+        );
+        anyhow::ensure!(
+            !locked_to_swap(&node.rpc_client.list_utxos().await?, swap_id),
+            "Swap {} should not stay locked after the claim",
+            swap_id
+        );
+    }
+    let expected_balance = offerer_balance
+        .checked_add(bitcoin::Amount::from_sat(SWAP_L2_AMOUNT))
+        .ok_or_else(|| anyhow::anyhow!("Offerer balance overflow"))?;
+    let offerer_balance = offerer.rpc_client.balance().await?.total;
+    anyhow::ensure!(
+        offerer_balance == expected_balance,
+        "Offerer should receive the swap's L2 amount: {} != {}",
+        offerer_balance,
+        expected_balance
+    );
+
+    tracing::info!("L1 → L2 swap test passed");
+    drop(offerer);
+    crate::swap_creation::cleanup_swapper(holder, enforcer_post_setup).await
+}
+
+pub fn swap_l1_to_l2_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_l1_to_l2",
+        async move {
+            let (res_tx, mut res_rx) = mpsc::unbounded();
+            let _task: AbortOnDrop<()> = tokio::task::spawn({
+                let res_tx = res_tx.clone();
+                async move {
+                    let res =
+                        swap_l1_to_l2_task(bin_paths, res_tx.clone()).await;
+                    drop(res_tx.unbounded_send(res));
+                }
+                .in_current_span()
+            })
+            .into();
+            res_rx.next().await.ok_or_else(|| {
+                anyhow::anyhow!("Unexpected end of test task result stream")
+            })?
+        }
+        .boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -0,0 +1,278 @@
+//! Test swaps that accept partial fills.
+//!
+//! A swap created with allow_partial_fills starts with nothing filled, and
+//! cannot be claimed until an L1 payment is known. Each L1 payment then
+//! releases its share of the L2 amount, and the remainder is locked back to
+//! the swap until a later payment fills it.
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::deposit,
+    setup::Sidechain as _,
+    util::{AbortOnDrop, AsyncTrial, TestFailureCollector, TestFileRegistry},
+};
+use coinshift::types::{
+    Address, OutputContent, ParentChainType, PointedOutput, SwapId, SwapState,
+    SwapTxId,
+};
+use coinshift_app_rpc_api::RpcClient as _;
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{
+    regtest_l1::{fund_l1_wallet, new_l1_address, pay_l1, report_l1_payment},
+    util::BinPaths,
+};
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const SWAP_L2_AMOUNT: u64 = 10_000_000;
+const SWAP_L1_AMOUNT: u64 = 5_000_000;
+const SWAP_FEE: u64 = 1_000;
+/// The first L1 payment fills 40% of the swap
+const FIRST_FILL_L1_AMOUNT: u64 = 2_000_000;
+const FIRST_FILL_L2_AMOUNT: u64 = 4_000_000;
+
+/// Total value locked to a swap
+fn locked_value(utxos: &[PointedOutput], swap_id: SwapId) -> bitcoin::Amount {
+    utxos
+        .iter()
+        .filter_map(|utxo| match utxo.output.content {
+            OutputContent::SwapPending {
+                value,
+                swap_id: locked_swap_id,
+            } if locked_swap_id == swap_id.0 => Some(value),
+            _ => None,
+        })
+        .sum()
+}
+
+/// Total spendable value paid to `address`
+fn value_at(utxos: &[PointedOutput], address: Address) -> bitcoin::Amount {
+    utxos
+        .iter()
+        .filter_map(|utxo| match utxo.output.content {
+            OutputContent::Value(value) if utxo.output.address == address => {
+                Some(value)
+            }
+            _ => None,
+        })
+        .sum()
+}
+
+async fn swap_partial_fills_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut sidechain, mut enforcer_post_setup) =
+        crate::swap_creation::setup_swapper(
+            &bin_paths,
+            res_tx.clone(),
+            "swap-partial-fills",
+        )
+        .await?;
+
+    let deposit_address = sidechain.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut sidechain,
+        &deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+    let () = fund_l1_wallet(&mut enforcer_post_setup).await?;
+    let () = sidechain.set_regtest_l1_rpc(&enforcer_post_setup)?;
+
+    let l1_recipient = new_l1_address(&enforcer_post_setup).await?;
+    let (swap_id, _txid) = sidechain
+        .rpc_client
+        .create_swap(
+            ParentChainType::Regtest,
+            l1_recipient.clone(),
+            SWAP_L1_AMOUNT,
+            None,
+            SWAP_L2_AMOUNT,
+            Some(1),
+            None,
+            false,
+            true,
+            None,
+            SWAP_FEE,
+        )
+        .await?;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    let swap = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+    anyhow::ensure!(
+        swap.partial_fills,
+        "Swap {} should accept partial fills",
+        swap_id
+    );
+    anyhow::ensure!(
+        swap.l1_amount_filled == bitcoin::Amount::ZERO
+            && swap.filled_l1_txids.is_empty(),
+        "Swap should start unfilled: {} filled by {:?}",
+        swap.l1_amount_filled,
+        swap.filled_l1_txids
+    );
+
+    // Without an L1 payment, there is nothing to release
+    let filler_address = sidechain.rpc_client.get_new_address().await?;
+    let _err = sidechain
+        .rpc_client
+        .claim_swap(swap_id, Some(filler_address))
+        .await
+        .expect_err("claim_swap should fail before any L1 payment");
+    let swap = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+    anyhow::ensure!(
+        matches!(swap.state, SwapState::Pending),
+        "Swap should stay Pending: {:?}",
+        swap.state
+    );
+
+    // The first L1 payment fills part of the swap, and releases its share
+    let first_l1_txid = pay_l1(
+        &mut enforcer_post_setup,
+        &l1_recipient,
+        bitcoin::Amount::from_sat(FIRST_FILL_L1_AMOUNT),
+        1,
+    )
+    .await?;
+    let () = report_l1_payment(&sidechain, swap_id, first_l1_txid, 1).await?;
+    let _: coinshift::types::Txid = sidechain
+        .rpc_client
+        .claim_swap(swap_id, Some(filler_address))
+        .await?;
+    sleep(std::time::Duration::from_secs(1)).await;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    let swap = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+    anyhow::ensure!(
+        matches!(swap.state, SwapState::Pending),
+        "Partly filled swap should wait for the next payment: {:?}",
+        swap.state
+    );
+    anyhow::ensure!(
+        swap.l1_amount_filled
+            == bitcoin::Amount::from_sat(FIRST_FILL_L1_AMOUNT)
+            && swap.filled_l1_txids
+                == [SwapTxId::from_bitcoin_txid(&first_l1_txid)],
+        "Swap should record the first fill: {} filled by {:?}",
+        swap.l1_amount_filled,
+        swap.filled_l1_txids
+    );
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    anyhow::ensure!(
+        value_at(&utxos, filler_address)
+            == bitcoin::Amount::from_sat(FIRST_FILL_L2_AMOUNT),
+        "First fill should release its share of the L2 amount: {}",
+        value_at(&utxos, filler_address)
+    );
+    anyhow::ensure!(
+        locked_value(&utxos, swap_id)
+            == bitcoin::Amount::from_sat(SWAP_L2_AMOUNT - FIRST_FILL_L2_AMOUNT),
+        "Remainder should be locked back to the swap: {}",
+        locked_value(&utxos, swap_id)
+    );
+
+    // The second L1 payment fills the rest, and completes the swap
+    let second_l1_txid = pay_l1(
+        &mut enforcer_post_setup,
+        &l1_recipient,
+        bitcoin::Amount::from_sat(SWAP_L1_AMOUNT - FIRST_FILL_L1_AMOUNT),
+        1,
+    )
+    .await?;
+    let () = report_l1_payment(&sidechain, swap_id, second_l1_txid, 1).await?;
+    let _: coinshift::types::Txid = sidechain
+        .rpc_client
+        .claim_swap(swap_id, Some(filler_address))
+        .await?;
+    sleep(std::time::Duration::from_secs(1)).await;
+    sidechain.bmm_single(&mut enforcer_post_setup).await?;
+    sleep(std::time::Duration::from_millis(500)).await;
+
+    let swap = sidechain
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Swap {swap_id} not found"))?;
+    anyhow::ensure!(
+        matches!(swap.state, SwapState::Completed),
+        "Second fill should complete the swap: {:?}",
+        swap.state
+    );
+    anyhow::ensure!(
+        swap.l1_amount_filled == bitcoin::Amount::from_sat(SWAP_L1_AMOUNT)
+            && swap.filled_l1_txids
+                == [
+                    SwapTxId::from_bitcoin_txid(&first_l1_txid),
+                    SwapTxId::from_bitcoin_txid(&second_l1_txid),
+                ],
+        "Swap should record both fills: {} filled by {:?}",
+        swap.l1_amount_filled,
+        swap.filled_l1_txids
+    );
+    let utxos = sidechain.rpc_client.list_utxos().await?;
+    anyhow::ensure!(
+        value_at(&utxos, filler_address)
+            == bitcoin::Amount::from_sat(SWAP_L2_AMOUNT),
+        "Both fills should release the whole L2 amount: {}",
+        value_at(&utxos, filler_address)
+    );
+    anyhow::ensure!(
+        locked_value(&utxos, swap_id) == bitcoin::Amount::ZERO,
+        "Nothing should stay locked to a completed swap: {}",
+        locked_value(&utxos, swap_id)
+    );
+
+    tracing::info!("Partial fills swap test passed");
+    crate::swap_creation::cleanup_swapper(sidechain, enforcer_post_setup).await
+}
+
+pub fn swap_partial_fills_trial(
+    bin_paths: BinPaths,
+    file_registry: TestFileRegistry,
+    failure_collector: TestFailureCollector,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new(
+        "swap_partial_fills",
+        async move {
+            let (res_tx, mut res_rx) = mpsc::unbounded();
+            let _task: AbortOnDrop<()> = tokio::task::spawn({
+                let res_tx = res_tx.clone();
+                async move {
+                    let res =
+                        swap_partial_fills_task(bin_paths, res_tx.clone())
+                            .await;
+                    drop(res_tx.unbounded_send(res));
+                }
+                .in_current_span()
+            })
+            .into();
+            res_rx.next().await.ok_or_else(|| {
+                anyhow::anyhow!("Unexpected end of test task result stream")
+            })?
+        }
+        .boxed(),
+        file_registry,
+        failure_collector,
+    )
+}
@@ -7,16 +7,20 @@ use std::{
 use bitcoin::{self, hashes::Hash as _};
 use fallible_iterator::{FallibleIterator, IteratorExt};
 use heed::types::SerdeBincode;
+use serde::{Deserialize, Serialize};
 use sneed::{
     DatabaseUnique, EnvError, RoTxn, RwTxn, UnitKey,
     db::error::Error as DbError, rwtxn::Error as RwTxnError,
 };
 
 use crate::types::{
-    Accumulator, BlockHash, BmmResult, Body, Header, Tip, VERSION, Version,
-    proto::mainchain,
+    Accumulator, BlockHash, BmmResult, Body, Header, ParentChainType, Tip,
+    VERSION, Version, proto::mainchain,
 };
 
+/// Number of blocks between difficulty adjustments on Bitcoin and signet
+const L1_DIFFICULTY_ADJUSTMENT_INTERVAL: u32 = 2016;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -35,6 +39,29 @@ pub enum Error {
     InvalidMerkleRoot,
     #[error("invalid previous side hash")]
     InvalidPrevSideHash,
+    #[error("{0:?} headers cannot be validated")]
+    L1HeadersUnsupported(ParentChainType),
+    #[error("invalid proof of work for {parent_chain:?} header {block_hash}")]
+    L1InvalidPow {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
+    #[error(
+        "{parent_chain:?} header {block_hash} has a target easier than the chain's limit"
+    )]
+    L1TargetTooEasy {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
+    #[error(
+        "{parent_chain:?} header {block_hash} has difficulty bits {actual:?}, expected {expected:?}"
+    )]
+    L1UnexpectedBits {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+        expected: bitcoin::CompactTarget,
+        actual: bitcoin::CompactTarget,
+    },
     #[error("no accumulator for block {0}")]
     NoAccumulator(BlockHash),
     #[error("no ancestor with depth {depth} for block {block_hash}")]
@@ -56,6 +83,11 @@ pub enum Error {
     NoHeader(BlockHash),
     #[error("no height info for block hash {0}")]
     NoHeight(BlockHash),
+    #[error("unknown {parent_chain:?} header {block_hash}")]
+    NoL1Header {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
     #[error("unknown mainchain block hash: {0}")]
     NoMainBlockHash(bitcoin::BlockHash),
     #[error("no mainchain block info for block hash {0}")]
@@ -66,6 +98,16 @@ pub enum Error {
     NoMainHeight(bitcoin::BlockHash),
 }
 
+/// Header of a block on a swap target chain, tracked by
+/// [`Archive::put_l1_headers`]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct L1HeaderInfo {
+    pub header: bitcoin::block::Header,
+    pub height: u32,
+    /// Total work of the header and its tracked ancestors
+    pub total_work: bitcoin::Work,
+}
+
 #[derive(Clone)]
 pub struct Archive {
     accumulators:
@@ -102,6 +144,23 @@ pub struct Archive {
     >,
     /// Sidechain headers. All ancestors of any header should always be present.
     headers: DatabaseUnique<SerdeBincode<BlockHash>, SerdeBincode<Header>>,
+    /// Best tracked chain of each swap target chain, by height. Heights
+    /// below the first tracked header are absent.
+    l1_best_chain: DatabaseUnique<
+        SerdeBincode<(ParentChainType, u32)>,
+        SerdeBincode<bitcoin::BlockHash>,
+    >,
+    /// Headers of swap target chains. All ancestors of any header should be
+    /// present, back to the first header tracked for the chain.
+    l1_header_infos: DatabaseUnique<
+        SerdeBincode<(ParentChainType, bitcoin::BlockHash)>,
+        SerdeBincode<L1HeaderInfo>,
+    >,
+    /// Tip of the best tracked chain of each swap target chain
+    l1_tips: DatabaseUnique<
+        SerdeBincode<ParentChainType>,
+        SerdeBincode<bitcoin::BlockHash>,
+    >,
     main_block_hash_to_height:
         DatabaseUnique<SerdeBincode<bitcoin::BlockHash>, SerdeBincode<u32>>,
     /// Mainchain block infos.
@@ -140,7 +199,7 @@ pub struct Archive {
 }
 
 impl Archive {
-    pub const NUM_DBS: u32 = 14;
+    pub const NUM_DBS: u32 = 17;
 
     pub fn new(env: &sneed::Env) -> Result<Self, Error> {
         tracing::debug!("Archive::new: Acquiring write transaction");
@@ -239,6 +298,16 @@ impl Archive {
         let headers = DatabaseUnique::create(env, &mut rwtxn, "headers")
             .map_err(EnvError::from)?;
         tracing::debug!("Archive::new: headers database created");
+        tracing::debug!("Archive::new: Creating L1 header databases");
+        let l1_best_chain =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_best_chain")
+                .map_err(EnvError::from)?;
+        let l1_header_infos =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_header_infos")
+                .map_err(EnvError::from)?;
+        let l1_tips = DatabaseUnique::create(env, &mut rwtxn, "l1_tips")
+            .map_err(EnvError::from)?;
+        tracing::debug!("Archive::new: L1 header databases created");
         tracing::debug!("Archive::new: Creating main_hash_to_height database");
         let main_block_hash_to_height =
             DatabaseUnique::create(env, &mut rwtxn, "main_hash_to_height")
@@ -306,6 +375,9 @@ impl Archive {
             exponential_ancestors,
             exponential_main_ancestors,
             headers,
+            l1_best_chain,
+            l1_header_infos,
+            l1_tips,
             main_block_infos,
             main_block_hash_to_height,
             main_header_infos,
@@ -431,6 +503,85 @@ impl Archive {
             .ok_or(Error::NoHeader(block_hash))
     }
 
+    pub fn try_get_l1_header_info(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    ) -> Result<Option<L1HeaderInfo>, Error> {
+        let header_info = self
+            .l1_header_infos
+            .try_get(rotxn, &(parent_chain, block_hash))
+            .map_err(DbError::from)?;
+        Ok(header_info)
+    }
+
+    /// Tip of the best tracked chain, or `None` if no headers are tracked
+    /// for the chain
+    pub fn try_get_l1_tip(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+    ) -> Result<Option<L1HeaderInfo>, Error> {
+        let Some(block_hash) = self
+            .l1_tips
+            .try_get(rotxn, &parent_chain)
+            .map_err(DbError::from)?
+        else {
+            return Ok(None);
+        };
+        let header_info = self
+            .try_get_l1_header_info(rotxn, parent_chain, block_hash)?
+            .ok_or(Error::NoL1Header {
+                parent_chain,
+                block_hash,
+            })?;
+        Ok(Some(header_info))
+    }
+
+    /// Block at a height in the best tracked chain
+    pub fn try_get_l1_best_block_hash(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        height: u32,
+    ) -> Result<Option<bitcoin::BlockHash>, Error> {
+        let block_hash = self
+            .l1_best_chain
+            .try_get(rotxn, &(parent_chain, height))
+            .map_err(DbError::from)?;
+        Ok(block_hash)
+    }
+
+    /// Confirmations of an L1 block in the best tracked chain, or `None` if
+    /// no headers are tracked for the chain. Blocks that are unknown or not
+    /// in the best tracked chain have no confirmations.
+    pub fn l1_confirmations(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    ) -> Result<Option<u32>, Error> {
+        let Some(tip) = self.try_get_l1_tip(rotxn, parent_chain)? else {
+            return Ok(None);
+        };
+        let Some(header_info) =
+            self.try_get_l1_header_info(rotxn, parent_chain, block_hash)?
+        else {
+            return Ok(Some(0));
+        };
+        let best_block_hash = self.try_get_l1_best_block_hash(
+            rotxn,
+            parent_chain,
+            header_info.height,
+        )?;
+        if best_block_hash == Some(block_hash) {
+            Ok(Some(tip.height - header_info.height + 1))
+        } else {
+            Ok(Some(0))
+        }
+    }
+
     pub fn try_get_main_block_info(
         &self,
         rotxn: &RoTxn,
@@ -1035,6 +1186,184 @@ impl Archive {
         Ok(())
     }
 
+    /// Difficulty bits required for a header at `height` on top of `prev`,
+    /// or `None` if they cannot be determined from the tracked headers
+    fn expected_l1_bits(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+        prev: &L1HeaderInfo,
+        height: u32,
+    ) -> Result<Option<bitcoin::CompactTarget>, Error> {
+        let network = match parent_chain {
+            ParentChainType::BTC => bitcoin::Network::Bitcoin,
+            ParentChainType::Signet => bitcoin::Network::Signet,
+            // Regtest never retargets
+            ParentChainType::Regtest => return Ok(Some(prev.header.bits)),
+            // Bitcoin Cash retargets every block (ASERT), which is not
+            // checked. Headers are still bounded by the chain's target
+            // limit.
+            ParentChainType::BCH => return Ok(None),
+            _ => return Err(Error::L1HeadersUnsupported(parent_chain)),
+        };
+        if height % L1_DIFFICULTY_ADJUSTMENT_INTERVAL != 0 {
+            return Ok(Some(prev.header.bits));
+        }
+        // Find the first block of the epoch that `prev` ends
+        let epoch_start_height = height - L1_DIFFICULTY_ADJUSTMENT_INTERVAL;
+        let mut epoch_start = *prev;
+        while epoch_start.height > epoch_start_height {
+            match self.try_get_l1_header_info(
+                rotxn,
+                parent_chain,
+                epoch_start.header.prev_blockhash,
+            )? {
+                Some(header_info) => epoch_start = header_info,
+                // Tracking started within the epoch
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(
+            bitcoin::CompactTarget::from_header_difficulty_adjustment(
+                epoch_start.header,
+                prev.header,
+                network,
+            ),
+        ))
+    }
+
+    /// Make `tip` the tip of the best tracked chain, re-indexing the best
+    /// chain back to its fork point with the previous best chain
+    fn set_l1_tip(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: ParentChainType,
+        tip: &L1HeaderInfo,
+    ) -> Result<(), Error> {
+        // The new best chain may be shorter, if it has more work
+        if let Some(prev_tip) = self.try_get_l1_tip(rwtxn, parent_chain)? {
+            for height in tip.height + 1..=prev_tip.height {
+                self.l1_best_chain
+                    .delete(rwtxn, &(parent_chain, height))
+                    .map_err(DbError::from)?;
+            }
+        }
+        let mut header_info = *tip;
+        loop {
+            let block_hash = header_info.header.block_hash();
+            let key = (parent_chain, header_info.height);
+            if self
+                .l1_best_chain
+                .try_get(rwtxn, &key)
+                .map_err(DbError::from)?
+                == Some(block_hash)
+            {
+                break;
+            }
+            self.l1_best_chain
+                .put(rwtxn, &key, &block_hash)
+                .map_err(DbError::from)?;
+            match self.try_get_l1_header_info(
+                rwtxn,
+                parent_chain,
+                header_info.header.prev_blockhash,
+            )? {
+                Some(prev) => header_info = prev,
+                None => break,
+            }
+        }
+        self.l1_tips
+            .put(rwtxn, &parent_chain, &tip.header.block_hash())
+            .map_err(DbError::from)?;
+        Ok(())
+    }
+
+    /// Track headers of a swap target chain, in order. Each header must
+    /// extend a tracked header, except for the first header tracked for the
+    /// chain, which is trusted as an anchor at `anchor_height`.
+    ///
+    /// Headers are checked for proof of work against the chain's target
+    /// limit and for the expected difficulty. The chain with the most work
+    /// becomes the best tracked chain.
+    pub fn put_l1_headers(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: ParentChainType,
+        anchor_height: u32,
+        headers: &[bitcoin::block::Header],
+    ) -> Result<(), Error> {
+        let max_target = parent_chain
+            .spv_max_target()
+            .ok_or(Error::L1HeadersUnsupported(parent_chain))?;
+        let mut tip = self.try_get_l1_tip(rwtxn, parent_chain)?;
+        for header in headers {
+            let block_hash = header.block_hash();
+            if self
+                .try_get_l1_header_info(rwtxn, parent_chain, block_hash)?
+                .is_some()
+            {
+                continue;
+            }
+            let prev = self.try_get_l1_header_info(
+                rwtxn,
+                parent_chain,
+                header.prev_blockhash,
+            )?;
+            let height = match (&prev, &tip) {
+                (Some(prev), _) => prev.height + 1,
+                (None, None) => anchor_height,
+                (None, Some(_)) => {
+                    return Err(Error::NoL1Header {
+                        parent_chain,
+                        block_hash: header.prev_blockhash,
+                    });
+                }
+            };
+            let target = header.target();
+            if target > max_target {
+                return Err(Error::L1TargetTooEasy {
+                    parent_chain,
+                    block_hash,
+                });
+            }
+            header
+                .validate_pow(target)
+                .map_err(|_| Error::L1InvalidPow {
+                    parent_chain,
+                    block_hash,
+                })?;
+            if let Some(prev) = &prev
+                && let Some(expected) =
+                    self.expected_l1_bits(rwtxn, parent_chain, prev, height)?
+                && header.bits != expected
+            {
+                return Err(Error::L1UnexpectedBits {
+                    parent_chain,
+                    block_hash,
+                    expected,
+                    actual: header.bits,
+                });
+            }
+            let total_work = match &prev {
+                Some(prev) => prev.total_work + header.work(),
+                None => header.work(),
+            };
+            let header_info = L1HeaderInfo {
+                header: *header,
+                height,
+                total_work,
+            };
+            self.l1_header_infos
+                .put(rwtxn, &(parent_chain, block_hash), &header_info)
+                .map_err(DbError::from)?;
+            if tip.is_none_or(|tip| total_work > tip.total_work) {
+                self.set_l1_tip(rwtxn, parent_chain, &header_info)?;
+                tip = Some(header_info);
+            }
+        }
+        Ok(())
+    }
+
     /// Return a fallible iterator over headers for ancestors of a block,
     /// starting with the specified block's header
     pub fn ancestor_headers<'a, 'rotxn>(
@@ -1641,3 +1970,147 @@ impl FallibleIterator for AncestorsRev<'_, '_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{CompactTarget, TxMerkleNode, block, hashes::Hash as _};
+    use sneed::Env;
+
+    use super::{Archive, Error};
+    use crate::types::ParentChainType;
+
+    const REGTEST_BITS: u32 = 0x207fffff;
+
+    fn open_archive(dir: &tempfile::TempDir) -> (Env, Archive) {
+        let mut env_open_opts = heed::EnvOpenOptions::new();
+        env_open_opts
+            .map_size(16 * 1024 * 1024)
+            .max_dbs(Archive::NUM_DBS);
+        let env = unsafe { Env::open(&env_open_opts, dir.path()) }.unwrap();
+        let archive = Archive::new(&env).unwrap();
+        (env, archive)
+    }
+
+    /// Mine `len` regtest headers on top of `prev_blockhash`. `salt`
+    /// distinguishes forks.
+    fn mine_chain(
+        prev_blockhash: bitcoin::BlockHash,
+        len: usize,
+        salt: u8,
+    ) -> Vec<block::Header> {
+        let mut headers: Vec<block::Header> = Vec::with_capacity(len);
+        let mut prev_blockhash = prev_blockhash;
+        for _ in 0..len {
+            let mut header = block::Header {
+                version: block::Version::TWO,
+                prev_blockhash,
+                merkle_root: TxMerkleNode::from_byte_array([salt; 32]),
+                time: 1_700_000_000,
+                bits: CompactTarget::from_consensus(REGTEST_BITS),
+                nonce: 0,
+            };
+            while header.validate_pow(header.target()).is_err() {
+                header.nonce += 1;
+            }
+            prev_blockhash = header.block_hash();
+            headers.push(header);
+        }
+        headers
+    }
+
+    #[test]
+    fn heavier_fork_becomes_best_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, archive) = open_archive(&dir);
+        let chain = ParentChainType::Regtest;
+        let mut rwtxn = env.write_txn().unwrap();
+        let main = mine_chain(bitcoin::BlockHash::all_zeros(), 4, 0);
+        archive
+            .put_l1_headers(&mut rwtxn, chain, 100, &main)
+            .unwrap();
+        let tip = archive.try_get_l1_tip(&rwtxn, chain).unwrap().unwrap();
+        assert_eq!(tip.height, 103);
+        let confirmations = |rwtxn: &sneed::RwTxn, header: &block::Header| {
+            archive
+                .l1_confirmations(rwtxn, chain, header.block_hash())
+                .unwrap()
+        };
+        assert_eq!(confirmations(&rwtxn, &main[1]), Some(3));
+        assert_eq!(confirmations(&rwtxn, &main[3]), Some(1));
+
+        // A fork from the second header, one block longer than the best
+        // chain
+        let fork = mine_chain(main[1].block_hash(), 3, 1);
+        archive.put_l1_headers(&mut rwtxn, chain, 0, &fork).unwrap();
+        let tip = archive.try_get_l1_tip(&rwtxn, chain).unwrap().unwrap();
+        assert_eq!(tip.header, fork[2]);
+        assert_eq!(tip.height, 104);
+        assert_eq!(confirmations(&rwtxn, &main[1]), Some(4));
+        assert_eq!(confirmations(&rwtxn, &main[2]), Some(0));
+        assert_eq!(confirmations(&rwtxn, &fork[0]), Some(3));
+        assert_eq!(
+            archive
+                .try_get_l1_best_block_hash(&rwtxn, chain, 102)
+                .unwrap(),
+            Some(fork[0].block_hash())
+        );
+        // Untracked chains have no header-derived confirmations
+        assert_eq!(
+            archive
+                .l1_confirmations(
+                    &rwtxn,
+                    ParentChainType::Signet,
+                    main[1].block_hash()
+                )
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, archive) = open_archive(&dir);
+        let chain = ParentChainType::Regtest;
+        let mut rwtxn = env.write_txn().unwrap();
+        let main = mine_chain(bitcoin::BlockHash::all_zeros(), 2, 0);
+        archive
+            .put_l1_headers(&mut rwtxn, chain, 0, &main[..1])
+            .unwrap();
+
+        // Does not extend a tracked header
+        let detached =
+            mine_chain(bitcoin::BlockHash::from_byte_array([1; 32]), 1, 0);
+        assert!(matches!(
+            archive.put_l1_headers(&mut rwtxn, chain, 0, &detached),
+            Err(Error::NoL1Header { .. })
+        ));
+
+        // Proof of work does not meet the header's target
+        let mut invalid_pow = main[1];
+        while invalid_pow.validate_pow(invalid_pow.target()).is_ok() {
+            invalid_pow.nonce += 1;
+        }
+        assert!(matches!(
+            archive.put_l1_headers(&mut rwtxn, chain, 0, &[invalid_pow]),
+            Err(Error::L1InvalidPow { .. })
+        ));
+
+        // Regtest does not retarget, so the bits must not change
+        let mut retargeted = main[1];
+        retargeted.bits = CompactTarget::from_consensus(0x2000ffff);
+        while retargeted.validate_pow(retargeted.target()).is_err() {
+            retargeted.nonce += 1;
+        }
+        assert!(matches!(
+            archive.put_l1_headers(&mut rwtxn, chain, 0, &[retargeted]),
+            Err(Error::L1UnexpectedBits { .. })
+        ));
+
+        // Litecoin uses scrypt proof of work
+        assert!(matches!(
+            archive.put_l1_headers(&mut rwtxn, ParentChainType::LTC, 0, &main),
+            Err(Error::L1HeadersUnsupported(ParentChainType::LTC))
+        ));
+    }
+}
@@ -125,6 +125,16 @@ impl MemPool {
         Ok(())
     }
 
+    pub fn try_get(
+        &self,
+        rotxn: &RoTxn,
+        txid: Txid,
+    ) -> Result<Option<AuthorizedTransaction>, Error> {
+        self.transactions
+            .try_get(rotxn, &txid)
+            .map_err(|err| DbError::from(err).into())
+    }
+
     pub fn take(
         &self,
         rotxn: &RoTxn,
@@ -0,0 +1,272 @@
+//! Address manager for peer discovery.
+//!
+//! Tracks addresses learned from bootstrap seeds, the `known_peers` DB,
+//! inbound connections and peer gossip, and scores them by how reachable
+//! they have been, so that discovery connects to the best candidates first.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// Where an address was learned from
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum AddrSource {
+    /// Learned from a peer's `Peers` response
+    Gossip,
+    /// Stored in the `known_peers` DB, connected to manually, or an inbound
+    /// connection
+    Known,
+    /// Bootstrap seed, either built in or from the config
+    Seed,
+}
+
+#[derive(Clone, Debug)]
+struct AddrInfo {
+    source: AddrSource,
+    /// Consecutive failed connections
+    failures: u32,
+    last_attempt: Option<Instant>,
+    last_success: Option<Instant>,
+}
+
+impl AddrInfo {
+    fn new(source: AddrSource) -> Self {
+        Self {
+            source,
+            failures: 0,
+            last_attempt: None,
+            last_success: None,
+        }
+    }
+
+    /// Reachability score. Higher is better.
+    fn score(&self) -> i64 {
+        let source_score = match self.source {
+            AddrSource::Gossip => 0,
+            AddrSource::Known => 10,
+            AddrSource::Seed => 20,
+        };
+        let success_score = if self.last_success.is_some() { 50 } else { 0 };
+        source_score + success_score - 20 * i64::from(self.failures)
+    }
+
+    /// Earliest time at which a connection should be attempted again
+    fn retry_at(&self) -> Option<Instant> {
+        let last_attempt = self.last_attempt?;
+        let backoff = AddrManager::BASE_RETRY_DELAY
+            .saturating_mul(1 << self.failures.min(7))
+            .min(AddrManager::MAX_RETRY_DELAY);
+        Some(last_attempt + backoff)
+    }
+}
+
+/// Addresses that can never be connected to
+fn is_connectable(addr: &SocketAddr) -> bool {
+    let ip = addr.ip();
+    !ip.is_unspecified() && !ip.is_multicast() && addr.port() != 0
+}
+
+#[derive(Debug, Default)]
+pub struct AddrManager {
+    addrs: HashMap<SocketAddr, AddrInfo>,
+}
+
+impl AddrManager {
+    /// Maximum number of addresses to track
+    pub const MAX_ADDRS: usize = 2048;
+
+    /// Delay before retrying an address after a connection attempt,
+    /// doubled for each consecutive failure
+    pub const BASE_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+    pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+    /// Gossiped addresses that have never been reached are dropped after
+    /// this many consecutive failures
+    pub const MAX_GOSSIP_FAILURES: u32 = 3;
+
+    pub fn len(&self) -> usize {
+        self.addrs.len()
+    }
+
+    /// Add an address, or upgrade the source of a tracked address.
+    /// Returns `true` if the address was not tracked before.
+    pub fn add(&mut self, addr: SocketAddr, source: AddrSource) -> bool {
+        if !is_connectable(&addr) {
+            return false;
+        }
+        if let Some(info) = self.addrs.get_mut(&addr) {
+            info.source = info.source.max(source);
+            return false;
+        }
+        let info = AddrInfo::new(source);
+        if self.addrs.len() >= Self::MAX_ADDRS {
+            // Evict the worst address, if it is worse than the new one
+            let Some((worst_addr, worst_score)) = self
+                .addrs
+                .iter()
+                .map(|(addr, info)| (*addr, info.score()))
+                .min_by_key(|(_, score)| *score)
+            else {
+                return false;
+            };
+            if worst_score >= info.score() {
+                return false;
+            }
+            self.addrs.remove(&worst_addr);
+        }
+        self.addrs.insert(addr, info);
+        true
+    }
+
+    pub fn mark_attempt(&mut self, addr: SocketAddr, now: Instant) {
+        if let Some(info) = self.addrs.get_mut(&addr) {
+            info.last_attempt = Some(now);
+        }
+    }
+
+    /// Record that a message was received from the address
+    pub fn mark_success(&mut self, addr: SocketAddr, now: Instant) {
+        if let Some(info) = self.addrs.get_mut(&addr) {
+            info.failures = 0;
+            info.last_success = Some(now);
+        }
+    }
+
+    /// Record that a connection to the address closed without receiving
+    /// any messages
+    pub fn mark_failure(&mut self, addr: SocketAddr) {
+        let Some(info) = self.addrs.get_mut(&addr) else {
+            return;
+        };
+        info.failures = info.failures.saturating_add(1);
+        if info.source == AddrSource::Gossip
+            && info.last_success.is_none()
+            && info.failures >= Self::MAX_GOSSIP_FAILURES
+        {
+            self.addrs.remove(&addr);
+        }
+    }
+
+    /// Best addresses to connect to, excluding those that are still backing
+    /// off from a previous attempt
+    pub fn select(
+        &self,
+        count: usize,
+        exclude: &HashSet<SocketAddr>,
+        now: Instant,
+    ) -> Vec<SocketAddr> {
+        let mut candidates: Vec<_> = self
+            .addrs
+            .iter()
+            .filter(|(addr, info)| {
+                !exclude.contains(addr)
+                    && info.retry_at().is_none_or(|retry_at| retry_at <= now)
+            })
+            .collect();
+        candidates.sort_by_key(|(addr, info)| (-info.score(), **addr));
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(addr, _)| *addr)
+            .collect()
+    }
+
+    /// Addresses to share with a peer, excluding the peer itself and
+    /// addresses that failed their last connection
+    pub fn sample(&self, count: usize, peer: SocketAddr) -> Vec<SocketAddr> {
+        let mut addrs: Vec<_> = self
+            .addrs
+            .iter()
+            .filter(|(addr, info)| **addr != peer && info.failures == 0)
+            .collect();
+        addrs.sort_by_key(|(addr, info)| (-info.score(), **addr));
+        addrs
+            .into_iter()
+            .take(count)
+            .map(|(addr, _)| *addr)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn rejects_unconnectable_addrs() {
+        let mut addr_manager = AddrManager::default();
+        assert!(
+            !addr_manager.add(
+                SocketAddr::from(([0, 0, 0, 0], 4000)),
+                AddrSource::Gossip
+            )
+        );
+        assert!(!addr_manager.add(addr(0), AddrSource::Gossip));
+        assert!(addr_manager.add(addr(4000), AddrSource::Gossip));
+        assert!(!addr_manager.add(addr(4000), AddrSource::Seed));
+        assert_eq!(addr_manager.len(), 1);
+    }
+
+    #[test]
+    fn selects_reachable_addrs_first() {
+        let now = Instant::now();
+        let mut addr_manager = AddrManager::default();
+        let _: bool = addr_manager.add(addr(1), AddrSource::Gossip);
+        let _: bool = addr_manager.add(addr(2), AddrSource::Gossip);
+        let _: bool = addr_manager.add(addr(3), AddrSource::Seed);
+        addr_manager.mark_success(addr(2), now);
+        assert_eq!(
+            addr_manager.select(2, &HashSet::new(), now),
+            vec![addr(2), addr(3)]
+        );
+        assert_eq!(
+            addr_manager.select(3, &HashSet::from([addr(2)]), now),
+            vec![addr(3), addr(1)]
+        );
+    }
+
+    #[test]
+    fn backs_off_after_failures() {
+        let now = Instant::now();
+        let mut addr_manager = AddrManager::default();
+        let _: bool = addr_manager.add(addr(1), AddrSource::Known);
+        addr_manager.mark_attempt(addr(1), now);
+        addr_manager.mark_failure(addr(1));
+        assert!(addr_manager.select(1, &HashSet::new(), now).is_empty());
+        let retry_at = now + 2 * AddrManager::BASE_RETRY_DELAY;
+        assert_eq!(
+            addr_manager.select(1, &HashSet::new(), retry_at),
+            vec![addr(1)]
+        );
+        // Failed addresses are not shared
+        assert!(addr_manager.sample(10, addr(2)).is_empty());
+    }
+
+    #[test]
+    fn drops_unreachable_gossip() {
+        let mut addr_manager = AddrManager::default();
+        let _: bool = addr_manager.add(addr(1), AddrSource::Gossip);
+        let _: bool = addr_manager.add(addr(2), AddrSource::Known);
+        for _ in 0..AddrManager::MAX_GOSSIP_FAILURES {
+            addr_manager.mark_failure(addr(1));
+            addr_manager.mark_failure(addr(2));
+        }
+        assert_eq!(addr_manager.sample(10, addr(3)), Vec::new());
+        assert_eq!(addr_manager.len(), 1);
+    }
+
+    #[test]
+    fn sample_excludes_requesting_peer() {
+        let mut addr_manager = AddrManager::default();
+        let _: bool = addr_manager.add(addr(1), AddrSource::Known);
+        let _: bool = addr_manager.add(addr(2), AddrSource::Gossip);
+        assert_eq!(addr_manager.sample(10, addr(1)), vec![addr(2)]);
+    }
+}
@@ -41,6 +41,9 @@ pub enum AcceptConnection {
 
 #[allow(clippy::duplicated_attributes)]
 #[derive(Debug, Error, Transitive)]
+#[transitive(from(db::error::Delete, db::Error))]
+#[transitive(from(db::error::IterInit, db::Error))]
+#[transitive(from(db::error::IterItem, db::Error))]
 #[transitive(from(db::error::Put, db::Error))]
 #[transitive(from(db::error::TryGet, db::Error))]
 #[transitive(from(env::error::CreateDb, env::Error))]
@@ -54,6 +57,8 @@ pub enum Error {
     AcceptError,
     #[error(transparent)]
     AlreadyConnected(#[from] AlreadyConnected),
+    #[error("peer at {0} is banned")]
+    Banned(IpAddr),
     #[error("bincode error")]
     Bincode(#[from] bincode::Error),
     #[error("connect error")]
@@ -64,6 +69,8 @@ pub enum Error {
     DbEnv(#[from] env::Error),
     #[error("Database write error")]
     DbWrite(#[from] rwtxn::Error),
+    #[error("node identity error")]
+    Identity(#[from] crate::net::identity::Error),
     #[error("quinn error")]
     Io(#[from] std::io::Error),
     #[error("peer connection not found for {0}")]
@@ -0,0 +1,195 @@
+//! Persistent node identity.
+//!
+//! A node is identified by its self-signed TLS certificate, which is stored
+//! in the data directory so that it stays the same across restarts. Peers
+//! refer to a node by the [`NodeId`] fingerprint of its certificate.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use hex::FromHex;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use thiserror::Error;
+
+/// Fingerprint of a node's certificate
+#[derive(
+    Clone,
+    Copy,
+    DeserializeFromStr,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    SerializeDisplay,
+)]
+pub struct NodeId(pub [u8; 32]);
+
+impl NodeId {
+    /// Fingerprint of a DER-encoded certificate
+    pub fn from_cert(cert: &CertificateDer<'_>) -> Self {
+        Self(*blake3::hash(cert).as_bytes())
+    }
+}
+
+impl std::fmt::Debug for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl std::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for NodeId {
+    type Err = hex::FromHexError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <[u8; 32]>::from_hex(s).map(Self)
+    }
+}
+
+impl utoipa::PartialSchema for NodeId {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        let obj =
+            utoipa::openapi::Object::with_type(utoipa::openapi::Type::String);
+        utoipa::openapi::RefOr::T(utoipa::openapi::Schema::Object(obj))
+    }
+}
+
+impl utoipa::ToSchema for NodeId {
+    fn name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("NodeId")
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to generate node certificate")]
+    Generate(#[from] rcgen::Error),
+    #[error("failed to read node identity from `{}`", .path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to write node identity to `{}`", .path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// Certificate and private key of this node
+pub struct NodeIdentity {
+    cert: CertificateDer<'static>,
+    key: PrivatePkcs8KeyDer<'static>,
+    id: NodeId,
+}
+
+impl NodeIdentity {
+    pub const CERT_FILE_NAME: &str = "node_cert.der";
+
+    pub const KEY_FILE_NAME: &str = "node_key.der";
+
+    fn new(
+        cert: CertificateDer<'static>,
+        key: PrivatePkcs8KeyDer<'static>,
+    ) -> Self {
+        let id = NodeId::from_cert(&cert);
+        Self { cert, key, id }
+    }
+
+    /// Generate a new identity, without storing it
+    pub fn generate() -> Result<Self, rcgen::Error> {
+        let cert_key =
+            rcgen::generate_simple_self_signed(vec!["localhost".into()])?;
+        let key = PrivatePkcs8KeyDer::from(cert_key.key_pair.serialize_der());
+        Ok(Self::new(cert_key.cert.der().clone(), key))
+    }
+
+    /// Load the identity stored in `dir`, or generate and store a new one if
+    /// there is none
+    pub fn load_or_generate(dir: &Path) -> Result<Self, Error> {
+        let cert_path = dir.join(Self::CERT_FILE_NAME);
+        let key_path = dir.join(Self::KEY_FILE_NAME);
+        if cert_path.exists() && key_path.exists() {
+            let read = |path: &Path| {
+                std::fs::read(path).map_err(|source| Error::Read {
+                    path: path.to_owned(),
+                    source,
+                })
+            };
+            let cert = CertificateDer::from(read(&cert_path)?);
+            let key = PrivatePkcs8KeyDer::from(read(&key_path)?);
+            return Ok(Self::new(cert, key));
+        }
+        let identity = Self::generate()?;
+        let () = write_private(&key_path, identity.key.secret_pkcs8_der())
+            .map_err(|source| Error::Write {
+                path: key_path,
+                source,
+            })?;
+        let () =
+            std::fs::write(&cert_path, &identity.cert).map_err(|source| {
+                Error::Write {
+                    path: cert_path,
+                    source,
+                }
+            })?;
+        Ok(identity)
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn cert(&self) -> &CertificateDer<'static> {
+        &self.cert
+    }
+
+    pub fn key(&self) -> PrivateKeyDer<'static> {
+        PrivateKeyDer::Pkcs8(self.key.clone_key())
+    }
+}
+
+/// Write a file that only the owner can read
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write as _;
+    let mut open_opts = std::fs::OpenOptions::new();
+    open_opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        open_opts.mode(0o600);
+    }
+    open_opts.open(path)?.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_persists_across_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let identity = NodeIdentity::load_or_generate(dir.path()).unwrap();
+        let reloaded = NodeIdentity::load_or_generate(dir.path()).unwrap();
+        assert_eq!(identity.id(), reloaded.id());
+        assert_ne!(identity.id(), NodeIdentity::generate().unwrap().id());
+    }
+
+    #[test]
+    fn node_id_roundtrips_through_hex() {
+        let id = NodeIdentity::generate().unwrap().id();
+        assert_eq!(id.to_string().parse::<NodeId>().unwrap(), id);
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!("\"{id}\""));
+        assert_eq!(serde_json::from_str::<NodeId>(&json).unwrap(), id);
+        assert!("not hex".parse::<NodeId>().is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Misbehavior scoring and peer bans.
+//!
+//! Each kind of misbehavior adds to a score for the peer's IP address.
+//! Scores decay over time, so that occasional mistakes by honest peers do
+//! not add up. A peer is disconnected once its score reaches
+//! [`DISCONNECT_THRESHOLD`], and banned for [`DEFAULT_BAN_DURATION`] once it
+//! reaches [`BAN_THRESHOLD`].
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::schema;
+
+/// Peers are disconnected once their score reaches this threshold
+pub const DISCONNECT_THRESHOLD: u32 = 50;
+
+/// Peers are banned once their score reaches this threshold
+pub const BAN_THRESHOLD: u32 = 100;
+
+/// Duration of bans caused by misbehavior
+pub const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Scores decay by one point per interval
+pub const SCORE_DECAY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Misbehavior {
+    /// Sent a transaction that is invalid in any state, e.g. with a bad
+    /// signature. Transactions that only conflict with this node's state
+    /// are rejected without scoring the peer.
+    InvalidTransaction,
+    /// Sent a response that does not match the request
+    InvalidResponse,
+    /// Sent a tip that failed BMM verification, or with incorrect total
+    /// work
+    InvalidTip,
+    /// Sent a message with incorrect magic bytes, or that could not be
+    /// decoded
+    MalformedMessage,
+    /// Sent a message larger than the limit for its kind
+    OversizedMessage,
+    /// Kept sending requests over quota, beyond the tolerance for bursts
+    RequestFlood,
+    /// Sent a block other than the one that was requested
+    UnrequestedBlock,
+}
+
+impl Misbehavior {
+    pub const fn score(self) -> u32 {
+        match self {
+            Self::InvalidTransaction => 10,
+            Self::RequestFlood => 25,
+            Self::InvalidResponse
+            | Self::MalformedMessage
+            | Self::OversizedMessage
+            | Self::UnrequestedBlock => DISCONNECT_THRESHOLD,
+            Self::InvalidTip => BAN_THRESHOLD,
+        }
+    }
+}
+
+impl std::fmt::Display for Misbehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::InvalidTransaction => "invalid transaction",
+            Self::InvalidResponse => "invalid response",
+            Self::InvalidTip => "invalid tip",
+            Self::MalformedMessage => "malformed message",
+            Self::OversizedMessage => "oversized message",
+            Self::RequestFlood => "request flood",
+            Self::UnrequestedBlock => "unrequested block",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Action to take against a peer after recording misbehavior
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Penalty {
+    None,
+    Disconnect,
+    Ban,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Score {
+    points: u32,
+    /// Time from which the points decay
+    updated_at: Instant,
+}
+
+impl Score {
+    /// Apply decay up to `now`
+    fn decay(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        let intervals = (elapsed.as_secs() / SCORE_DECAY_INTERVAL.as_secs())
+            .min(u64::from(u32::MAX)) as u32;
+        self.points = self.points.saturating_sub(intervals);
+        self.updated_at += SCORE_DECAY_INTERVAL * intervals;
+    }
+}
+
+/// Misbehavior scores of peer IP addresses
+#[derive(Debug, Default)]
+pub struct MisbehaviorScores {
+    scores: HashMap<IpAddr, Score>,
+}
+
+impl MisbehaviorScores {
+    /// Current score for an IP address
+    pub fn get(&self, ip: IpAddr, now: Instant) -> u32 {
+        self.scores.get(&ip).map_or(0, |score| {
+            let mut score = *score;
+            score.decay(now);
+            score.points
+        })
+    }
+
+    /// Add misbehavior to the score for an IP address, returning the
+    /// penalty for the peer. The score is reset if the peer is banned.
+    pub fn record(
+        &mut self,
+        ip: IpAddr,
+        misbehavior: Misbehavior,
+        now: Instant,
+    ) -> Penalty {
+        self.scores.retain(|_, score| {
+            score.decay(now);
+            score.points > 0
+        });
+        let score = self.scores.entry(ip).or_insert(Score {
+            points: 0,
+            updated_at: now,
+        });
+        score.points = score.points.saturating_add(misbehavior.score());
+        if score.points >= BAN_THRESHOLD {
+            self.scores.remove(&ip);
+            Penalty::Ban
+        } else if score.points >= DISCONNECT_THRESHOLD {
+            Penalty::Disconnect
+        } else {
+            Penalty::None
+        }
+    }
+}
+
+/// Unix time, in seconds
+pub(in crate::net) fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Ban stored in the `banned_peers` DB
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(in crate::net) struct Ban {
+    /// Unix time at which the ban expires, in seconds
+    pub banned_until: u64,
+    pub reason: String,
+}
+
+impl Ban {
+    pub fn new(duration: Duration, reason: String, now: u64) -> Self {
+        Self {
+            banned_until: now.saturating_add(duration.as_secs()),
+            reason,
+        }
+    }
+
+    pub fn is_active(&self, now: u64) -> bool {
+        now < self.banned_until
+    }
+}
+
+// RPC output representation for a banned peer
+#[derive(Clone, Debug, Deserialize, Serialize, utoipa::ToSchema)]
+pub struct BannedPeer {
+    #[schema(value_type = schema::IpAddr)]
+    pub ip: IpAddr,
+    /// Unix time at which the ban expires, in seconds
+    pub banned_until: u64,
+    pub reason: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    #[test]
+    fn escalates_to_disconnect_and_ban() {
+        let now = Instant::now();
+        let mut scores = MisbehaviorScores::default();
+        for _ in 0..4 {
+            assert_eq!(
+                scores.record(IP, Misbehavior::InvalidTransaction, now),
+                Penalty::None
+            );
+        }
+        assert_eq!(
+            scores.record(IP, Misbehavior::InvalidTransaction, now),
+            Penalty::Disconnect
+        );
+        assert_eq!(
+            scores.record(IP, Misbehavior::UnrequestedBlock, now),
+            Penalty::Ban
+        );
+        // Score is reset after a ban
+        assert_eq!(scores.get(IP, now), 0);
+        assert_eq!(
+            scores.record(IP, Misbehavior::InvalidTip, now),
+            Penalty::Ban
+        );
+    }
+
+    #[test]
+    fn scores_decay() {
+        let now = Instant::now();
+        let mut scores = MisbehaviorScores::default();
+        assert_eq!(
+            scores.record(IP, Misbehavior::RequestFlood, now),
+            Penalty::None
+        );
+        let later = now + SCORE_DECAY_INTERVAL * 10;
+        assert_eq!(scores.get(IP, later), 15);
+        assert_eq!(
+            scores.record(IP, Misbehavior::RequestFlood, later),
+            Penalty::None
+        );
+        assert_eq!(scores.get(IP, later + SCORE_DECAY_INTERVAL * 40), 0);
+    }
+
+    #[test]
+    fn bans_expire() {
+        let ban = Ban::new(Duration::from_secs(60), "test".to_owned(), 1000);
+        assert!(ban.is_active(1000));
+        assert!(ban.is_active(1059));
+        assert!(!ban.is_active(1060));
+    }
+}
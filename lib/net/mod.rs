@@ -1,31 +1,43 @@
 use std::{
     collections::{HashMap, HashSet, hash_map},
-    net::SocketAddr,
-    sync::Arc,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        Arc,
+        atomic::{self, AtomicU64},
+    },
+    time::{Duration, Instant},
 };
 
 use fallible_iterator::FallibleIterator;
 use futures::{StreamExt, channel::mpsc};
 use heed::types::{SerdeBincode, Unit};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use quinn::{ClientConfig, Endpoint, ServerConfig};
 use sneed::{
-    DatabaseUnique, EnvError, RwTxn, RwTxnError, UnitKey,
-    db::error::Error as DbError,
+    DatabaseUnique, EnvError, RoTxn, RwTxn, RwTxnError, UnitKey,
+    db::{self, error::Error as DbError},
 };
 use tokio_stream::StreamNotifyClose;
 use tracing::instrument;
 
 use crate::{
     archive::Archive,
+    mempool::MemPool,
     state::State,
-    types::{AuthorizedTransaction, Network, THIS_SIDECHAIN, VERSION, Version},
+    types::{Network, THIS_SIDECHAIN, Txid, VERSION, Version},
 };
 
+mod addr_manager;
 pub mod error;
+pub mod identity;
+pub mod misbehavior;
 mod peer;
 
+use addr_manager::{AddrManager, AddrSource};
 pub use error::Error;
+pub use identity::{NodeId, NodeIdentity};
+use misbehavior::Ban;
+pub use misbehavior::{BannedPeer, Misbehavior};
 pub(crate) use peer::error::mailbox::Error as PeerConnectionMailboxError;
 use peer::{
     Connection, ConnectionContext as PeerConnectionCtxt,
@@ -34,35 +46,41 @@ use peer::{
 pub use peer::{
     ConnectionError as PeerConnectionError, Info as PeerConnectionInfo,
     InternalMessage as PeerConnectionMessage, Peer, PeerConnectionStatus,
-    PeerStateId, Request as PeerRequest, ResponseMessage as PeerResponse,
-    message as peer_message,
+    PeerStateId, Request as PeerRequest, RequestQuotas,
+    ResponseMessage as PeerResponse, message as peer_message,
 };
 
-/// Dummy certificate verifier that treats any certificate as valid.
-/// NOTE, such verification is vulnerable to MITM attacks, but convenient for testing.
+/// Certificate verifier for peer connections, in both directions.
+/// Node certificates are self-signed, so there is no chain to verify.
+/// If no peers are pinned, any certificate is accepted, which is vulnerable
+/// to MITM attacks. Otherwise, the certificate must be that of a pinned
+/// node.
 #[derive(Debug)]
-struct SkipServerVerification;
+struct PeerCertVerifier {
+    pinned_peers: HashSet<NodeId>,
+}
 
-impl SkipServerVerification {
-    fn new() -> Arc<Self> {
-        Arc::new(Self)
+impl PeerCertVerifier {
+    fn new(pinned_peers: HashSet<NodeId>) -> Arc<Self> {
+        Arc::new(Self { pinned_peers })
     }
-}
 
-impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
-    fn verify_server_cert(
+    fn verify_pinned(
         &self,
-        _end_entity: &rustls::pki_types::CertificateDer,
-        _intermediates: &[rustls::pki_types::CertificateDer],
-        _server_name: &rustls::pki_types::ServerName,
-        _ocsp_response: &[u8],
-        _now: rustls::pki_types::UnixTime,
-    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
-        Ok(rustls::client::danger::ServerCertVerified::assertion())
+        end_entity: &rustls::pki_types::CertificateDer,
+    ) -> Result<(), rustls::Error> {
+        if self.pinned_peers.is_empty()
+            || self.pinned_peers.contains(&NodeId::from_cert(end_entity))
+        {
+            Ok(())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ))
+        }
     }
 
     fn verify_tls12_signature(
-        &self,
         message: &[u8],
         cert: &rustls::pki_types::CertificateDer<'_>,
         dss: &rustls::DigitallySignedStruct,
@@ -78,7 +96,6 @@ impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
     }
 
     fn verify_tls13_signature(
-        &self,
         message: &[u8],
         cert: &rustls::pki_types::CertificateDer<'_>,
         dss: &rustls::DigitallySignedStruct,
@@ -93,59 +110,146 @@ impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
         )
     }
 
-    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+    fn supported_verify_schemes() -> Vec<rustls::SignatureScheme> {
         rustls::crypto::ring::default_provider()
             .signature_verification_algorithms
             .supported_schemes()
     }
 }
 
-fn configure_client()
--> Result<ClientConfig, quinn::crypto::rustls::NoInitialCipherSuite> {
+impl rustls::client::danger::ServerCertVerifier for PeerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer,
+        _intermediates: &[rustls::pki_types::CertificateDer],
+        _server_name: &rustls::pki_types::ServerName,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        let () = self.verify_pinned(end_entity)?;
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error>
+    {
+        Self::verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error>
+    {
+        Self::verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        Self::supported_verify_schemes()
+    }
+}
+
+impl rustls::server::danger::ClientCertVerifier for PeerCertVerifier {
+    /// Peers that do not present a certificate are only accepted if no
+    /// peers are pinned
+    fn client_auth_mandatory(&self) -> bool {
+        !self.pinned_peers.is_empty()
+    }
+
+    fn root_hint_subjects(&self) -> &[rustls::DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::server::danger::ClientCertVerified, rustls::Error> {
+        let () = self.verify_pinned(end_entity)?;
+        Ok(rustls::server::danger::ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error>
+    {
+        Self::verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error>
+    {
+        Self::verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        Self::supported_verify_schemes()
+    }
+}
+
+/// Client configuration, presenting the node certificate
+fn configure_client(
+    identity: &NodeIdentity,
+    verifier: Arc<PeerCertVerifier>,
+) -> Result<ClientConfig, Error> {
     let crypto = rustls::ClientConfig::builder()
         .dangerous()
-        .with_custom_certificate_verifier(SkipServerVerification::new())
-        .with_no_client_auth();
+        .with_custom_certificate_verifier(verifier)
+        .with_client_auth_cert(vec![identity.cert().clone()], identity.key())?;
     let client_config =
         quinn::crypto::rustls::QuicClientConfig::try_from(crypto)?;
     Ok(ClientConfig::new(Arc::new(client_config)))
 }
 
-/// Returns default server configuration along with its certificate.
-fn configure_server() -> Result<(ServerConfig, Vec<u8>), Error> {
-    let cert_key =
-        rcgen::generate_simple_self_signed(vec!["localhost".into()])?;
-    let keypair_der = cert_key.key_pair.serialize_der();
-    let priv_key = rustls::pki_types::PrivateKeyDer::Pkcs8(keypair_der.into());
-    let cert_der = cert_key.cert.der().to_vec();
-    let cert_chain = vec![cert_key.cert.into()];
-
-    let mut server_config =
-        ServerConfig::with_single_cert(cert_chain, priv_key)?;
+/// Server configuration, presenting the node certificate
+fn configure_server(
+    identity: &NodeIdentity,
+    verifier: Arc<PeerCertVerifier>,
+) -> Result<ServerConfig, Error> {
+    let crypto = rustls::ServerConfig::builder()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(vec![identity.cert().clone()], identity.key())?;
+    let server_config =
+        quinn::crypto::rustls::QuicServerConfig::try_from(crypto)?;
+    let mut server_config = ServerConfig::with_crypto(Arc::new(server_config));
     let transport_config = Arc::get_mut(&mut server_config.transport).unwrap();
     transport_config.max_concurrent_uni_streams(1_u8.into());
 
-    Ok((server_config, cert_der))
+    Ok(server_config)
 }
 
 /// Constructs a QUIC endpoint configured to listen for incoming connections on a certain address
-/// and port.
-///
-/// ## Returns
-///
-/// - a stream of incoming QUIC connections
-/// - server certificate serialized into DER format
+/// and port, and to connect to peers, using the node identity.
+/// If `pinned_peers` is not empty, connections are only made with pinned
+/// nodes, in either direction.
 pub fn make_server_endpoint(
     bind_addr: SocketAddr,
-) -> Result<(Endpoint, Vec<u8>), Error> {
-    let (server_config, server_cert) = configure_server()?;
+    identity: &NodeIdentity,
+    pinned_peers: HashSet<NodeId>,
+) -> Result<Endpoint, Error> {
+    let verifier = PeerCertVerifier::new(pinned_peers);
+    let server_config = configure_server(identity, verifier.clone())?;
 
     tracing::info!("creating server endpoint: binding to {bind_addr}",);
 
     let mut endpoint = Endpoint::server(server_config, bind_addr)?;
-    let client_cfg = configure_client()?;
+    let client_cfg = configure_client(identity, verifier)?;
     endpoint.set_default_client_config(client_cfg);
-    Ok((endpoint, server_cert))
+    Ok(endpoint)
 }
 
 // None indicates that the stream has ended
@@ -186,18 +290,35 @@ const fn seed_node_addrs(network: Network) -> &'static [SocketAddr] {
 pub struct Net {
     pub server: Endpoint,
     archive: Archive,
+    mempool: MemPool,
     network: Network,
     state: State,
     active_peers: Arc<RwLock<HashMap<SocketAddr, PeerConnectionHandle>>>,
+    addr_manager: Arc<RwLock<AddrManager>>,
+    /// Quotas for requests received from each peer
+    request_quotas: RequestQuotas,
+    /// Distinguishes repeated `GetPeers` requests
+    get_peers_nonce: Arc<AtomicU64>,
+    /// Txids of announced transactions that are being fetched from peers
+    in_flight_txids: Arc<Mutex<peer::InFlightTxids>>,
     // None indicates that the stream has ended
     peer_info_tx:
         mpsc::UnboundedSender<(SocketAddr, Option<PeerConnectionInfo>)>,
     known_peers: DatabaseUnique<SerdeBincode<SocketAddr>, Unit>,
+    /// Banned peer IP addresses. Expired bans are removed on startup.
+    banned_peers: DatabaseUnique<SerdeBincode<IpAddr>, SerdeBincode<Ban>>,
+    node_id: NodeId,
     _version: DatabaseUnique<UnitKey, SerdeBincode<Version>>,
 }
 
 impl Net {
-    pub const NUM_DBS: u32 = 2;
+    pub const NUM_DBS: u32 = 3;
+
+    /// Interval at which to discover new peers
+    pub const DISCOVER_PEERS_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Discovery connects to new peers while there are fewer active peers
+    pub const TARGET_PEERS: usize = 8;
 
     fn add_active_peer(
         &self,
@@ -222,6 +343,12 @@ impl Net {
         tracing::trace!(%addr, "remove active peer: starting");
         let mut active_peers_write = self.active_peers.write();
         if let Some(peer_connection) = active_peers_write.remove(&addr) {
+            let mut addr_manager_write = self.addr_manager.write();
+            if peer_connection.received_msg_successfully() {
+                addr_manager_write.mark_success(addr, Instant::now());
+            } else {
+                addr_manager_write.mark_failure(addr);
+            }
             drop(peer_connection);
             tracing::info!(%addr, "remove active peer: disconnected");
         }
@@ -241,6 +368,11 @@ impl Net {
         active_peers_read.get(&addr).map(f)
     }
 
+    /// Fingerprint of this node's certificate, by which peers can pin it
+    pub fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
     // TODO: This should have more context.
     // Last received message, connection state, etc.
     pub fn get_active_peers(&self) -> Vec<Peer> {
@@ -259,6 +391,17 @@ impl Net {
         &self,
         env: sneed::Env,
         addr: SocketAddr,
+    ) -> Result<(), Error> {
+        self.connect_peer_(env, addr, true)
+    }
+
+    /// Connect to a peer, storing it in the known_peers DB if `persist` is
+    /// set. Discovered peers are only stored once they have been reached.
+    fn connect_peer_(
+        &self,
+        env: sneed::Env,
+        addr: SocketAddr,
+        persist: bool,
     ) -> Result<(), Error> {
         if self.active_peers.read().contains_key(&addr) {
             tracing::error!("connect peer: already connected");
@@ -272,17 +415,36 @@ impl Net {
         if addr.ip().is_unspecified() {
             return Err(Error::UnspecfiedPeerIP(addr.ip()));
         }
+        {
+            let rotxn = env.read_txn().map_err(EnvError::from)?;
+            if self.is_banned(&rotxn, addr.ip()).map_err(DbError::from)? {
+                return Err(Error::Banned(addr.ip()));
+            }
+        }
         let connecting = self.server.connect(addr, "localhost")?;
-        let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
-        self.known_peers
-            .put(&mut rwtxn, &addr, &())
-            .map_err(DbError::from)?;
-        rwtxn.commit().map_err(RwTxnError::from)?;
+        if persist {
+            let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+            self.known_peers
+                .put(&mut rwtxn, &addr, &())
+                .map_err(DbError::from)?;
+            rwtxn.commit().map_err(RwTxnError::from)?;
+        }
+        {
+            let mut addr_manager_write = self.addr_manager.write();
+            if persist {
+                let _: bool = addr_manager_write.add(addr, AddrSource::Known);
+            }
+            addr_manager_write.mark_attempt(addr, Instant::now());
+        }
         let connection_ctxt = PeerConnectionCtxt {
             env,
             archive: self.archive.clone(),
+            mempool: self.mempool.clone(),
             network: self.network,
             state: self.state.clone(),
+            addr_manager: self.addr_manager.clone(),
+            request_quotas: self.request_quotas,
+            in_flight_txids: self.in_flight_txids.clone(),
         };
 
         let (connection_handle, info_rx) =
@@ -317,6 +479,90 @@ impl Net {
             .map_err(|err| DbError::from(err).into())
     }
 
+    fn is_banned(
+        &self,
+        rotxn: &RoTxn,
+        ip: IpAddr,
+    ) -> Result<bool, db::error::TryGet> {
+        let ban = self.banned_peers.try_get(rotxn, &ip)?;
+        Ok(ban.is_some_and(|ban| ban.is_active(misbehavior::unix_time())))
+    }
+
+    /// Ban a peer IP address for the specified duration, and disconnect
+    /// from any peers at that address.
+    /// An existing ban for the address is replaced.
+    pub fn ban_peer(
+        &self,
+        env: &sneed::Env,
+        ip: IpAddr,
+        duration: Duration,
+        reason: String,
+    ) -> Result<(), Error> {
+        let ban = Ban::new(duration, reason, misbehavior::unix_time());
+        let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+        self.banned_peers
+            .put(&mut rwtxn, &ip, &ban)
+            .map_err(DbError::from)?;
+        rwtxn.commit().map_err(RwTxnError::from)?;
+        tracing::warn!(
+            %ip,
+            banned_until = ban.banned_until,
+            reason = %ban.reason,
+            "banned peer"
+        );
+        let addrs: Vec<SocketAddr> = self
+            .active_peers
+            .read()
+            .keys()
+            .filter(|addr| addr.ip() == ip)
+            .copied()
+            .collect();
+        for addr in addrs {
+            let () = self.remove_active_peer(addr);
+        }
+        Ok(())
+    }
+
+    /// Remove the ban for a peer IP address.
+    /// Returns `true` if the address was banned.
+    pub fn unban_peer(
+        &self,
+        env: &sneed::Env,
+        ip: IpAddr,
+    ) -> Result<bool, Error> {
+        let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+        let was_banned = self
+            .banned_peers
+            .delete(&mut rwtxn, &ip)
+            .map_err(DbError::from)?;
+        rwtxn.commit().map_err(RwTxnError::from)?;
+        Ok(was_banned)
+    }
+
+    /// Peer IP addresses that are currently banned
+    pub fn list_banned(
+        &self,
+        env: &sneed::Env,
+    ) -> Result<Vec<BannedPeer>, Error> {
+        let now = misbehavior::unix_time();
+        let rotxn = env.read_txn().map_err(EnvError::from)?;
+        let banned: Vec<BannedPeer> = self
+            .banned_peers
+            .iter(&rotxn)
+            .map_err(DbError::from)?
+            .filter(|(_, ban)| Ok(ban.is_active(now)))
+            .map(|(ip, ban)| {
+                Ok(BannedPeer {
+                    ip,
+                    banned_until: ban.banned_until,
+                    reason: ban.reason,
+                })
+            })
+            .collect()
+            .map_err(DbError::from)?;
+        Ok(banned)
+    }
+
     // TODO setup coinshift.bip300.xyz as the seed node
     pub const SEED_NODE_ADDRS: &[SocketAddr] = {
         const SIGNET_MINING_SERVER: SocketAddr = SocketAddr::new(
@@ -331,16 +577,26 @@ impl Net {
         &[SIGNET_MINING_SERVER, BIP300_XYZ]
     };
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         env: &sneed::Env,
         archive: Archive,
+        mempool: MemPool,
         network: Network,
         state: State,
         bind_addr: SocketAddr,
+        seed_peers: &[SocketAddr],
+        identity: &NodeIdentity,
+        pinned_peers: HashSet<NodeId>,
+        request_quotas: RequestQuotas,
     ) -> Result<(Self, PeerInfoRx), Error> {
         tracing::debug!(bind_addr = %bind_addr, "Net::new: Starting initialization");
-        tracing::debug!("Net::new: Creating server endpoint");
-        let (server, _) = make_server_endpoint(bind_addr)?;
+        tracing::info!(
+            node_id = %identity.id(),
+            pinned_peers = pinned_peers.len(),
+            "Net::new: Creating server endpoint"
+        );
+        let server = make_server_endpoint(bind_addr, identity, pinned_peers)?;
         tracing::debug!("Net::new: Server endpoint created");
         let active_peers = Arc::new(RwLock::new(HashMap::new()));
         tracing::debug!("Net::new: Opening database transaction");
@@ -370,6 +626,20 @@ impl Net {
         };
         tracing::debug!("Net::new: Creating net_version database");
         let version = DatabaseUnique::create(env, &mut rwtxn, "net_version")?;
+        tracing::debug!("Net::new: Creating banned_peers database");
+        let banned_peers =
+            DatabaseUnique::create(env, &mut rwtxn, "banned_peers")?;
+        {
+            let now = misbehavior::unix_time();
+            let expired: Vec<IpAddr> = banned_peers
+                .iter(&rwtxn)?
+                .filter(|(_, ban)| Ok(!ban.is_active(now)))
+                .map(|(ip, _)| Ok(ip))
+                .collect()?;
+            for ip in &expired {
+                let _: bool = banned_peers.delete(&mut rwtxn, ip)?;
+            }
+        }
         if version.try_get(&rwtxn, &())?.is_none() {
             version.put(&mut rwtxn, &(), &*VERSION)?;
         }
@@ -380,11 +650,18 @@ impl Net {
         let net = Net {
             server,
             archive,
+            mempool,
             network,
             state,
             active_peers,
+            addr_manager: Arc::default(),
+            request_quotas,
+            get_peers_nonce: Arc::default(),
+            in_flight_txids: Arc::default(),
             peer_info_tx,
             known_peers,
+            banned_peers,
+            node_id: identity.id(),
             _version: version,
         };
         tracing::debug!("Net::new: Reading known peers from database");
@@ -399,6 +676,17 @@ impl Net {
                 .map_err(DbError::from)?;
             known_peers
         };
+        {
+            let mut addr_manager_write = net.addr_manager.write();
+            for (peer_addr, ()) in &known_peers {
+                let _: bool =
+                    addr_manager_write.add(*peer_addr, AddrSource::Known);
+            }
+            for seed_addr in seed_node_addrs(network).iter().chain(seed_peers) {
+                let _: bool =
+                    addr_manager_write.add(*seed_addr, AddrSource::Seed);
+            }
+        }
         tracing::info!(
             peer_count = known_peers.len(),
             "Net::new: Connecting to known peers"
@@ -439,6 +727,20 @@ impl Net {
                 }
             }
         }
+        // Bootstrap seeds from the config are only stored once reached
+        for seed_addr in seed_peers {
+            if net.active_peers.read().contains_key(seed_addr) {
+                continue;
+            }
+            if let Err(err) = net.connect_peer_(env.clone(), *seed_addr, false)
+            {
+                tracing::debug!(
+                    seed_addr = %seed_addr,
+                    error = %err,
+                    "Net::new: Failed to connect to seed peer"
+                );
+            }
+        }
         let connection_elapsed = connection_start.elapsed();
         tracing::info!(
             elapsed_secs = connection_elapsed.as_secs_f64(),
@@ -482,7 +784,16 @@ impl Net {
         let addr = connection.addr();
 
         tracing::trace!(%addr, "accepted incoming connection");
-        if self.active_peers.read().contains_key(&addr) {
+        let banned = {
+            let rotxn = env.read_txn().map_err(EnvError::from)?;
+            self.is_banned(&rotxn, addr.ip()).map_err(DbError::from)?
+        };
+        if banned {
+            tracing::info!(%addr, "incoming connection: peer is banned");
+            connection
+                .inner
+                .close(quinn::VarInt::from_u32(2), b"banned");
+        } else if self.active_peers.read().contains_key(&addr) {
             tracing::info!(
                 %addr, "incoming connection: already peered, refusing duplicate",
             );
@@ -499,13 +810,18 @@ impl Net {
             .put(&mut rwtxn, &addr, &())
             .map_err(DbError::from)?;
         rwtxn.commit().map_err(RwTxnError::from)?;
+        let _: bool = self.addr_manager.write().add(addr, AddrSource::Known);
 
         tracing::trace!(%addr, "wrote peer to database");
         let connection_ctxt = PeerConnectionCtxt {
             env,
             archive: self.archive.clone(),
+            mempool: self.mempool.clone(),
             network: self.network,
             state: self.state.clone(),
+            addr_manager: self.addr_manager.clone(),
+            request_quotas: self.request_quotas,
+            in_flight_txids: self.in_flight_txids.clone(),
         };
         let (connection_handle, info_rx) =
             peer::handle(connection_ctxt, connection);
@@ -552,12 +868,12 @@ impl Net {
         true
     }
 
-    /// Push a tx to all active peers, except those in the provided set
-    pub fn push_tx(
-        &self,
-        exclude: HashSet<SocketAddr>,
-        tx: AuthorizedTransaction,
-    ) {
+    /// Announce a txid to all active peers, except those in the provided
+    /// set and those that already know of the tx. Peers request the tx if
+    /// they do not have it.
+    /// Each peer connection batches the txids that it announces, see
+    /// [`PeerConnectionMessage::AnnounceTransaction`].
+    pub fn announce_tx(&self, exclude: HashSet<SocketAddr>, txid: Txid) {
         self.active_peers
             .read()
             .iter()
@@ -570,16 +886,101 @@ impl Net {
                     }
                     PeerConnectionStatus::Connected => {}
                 }
-                let request: PeerRequest = peer::message::PushTransactionRequest {
-                    transaction: tx.clone(),
-                }.into();
+                if !peer_connection_handle.known_txids.lock().insert(txid) {
+                    tracing::trace!(%addr, %txid, "skipping peer that already knows of tx");
+                    return;
+                }
                 if let Err(_send_err) = peer_connection_handle
                     .internal_message_tx
-                    .unbounded_send(request.into())
+                    .unbounded_send(PeerConnectionMessage::AnnounceTransaction(
+                        txid,
+                    ))
                 {
-                    let txid = tx.transaction.txid();
-                    tracing::warn!("Failed to push tx {txid} to peer at {addr}")
+                    tracing::warn!("Failed to announce tx {txid} to peer at {addr}")
                 }
             })
     }
+
+    /// Track addresses learned from a peer.
+    /// Returns the number of new addresses.
+    pub fn add_peer_addrs<I>(&self, addrs: I) -> usize
+    where
+        I: IntoIterator<Item = SocketAddr>,
+    {
+        let mut addr_manager_write = self.addr_manager.write();
+        addrs
+            .into_iter()
+            .filter(|addr| addr_manager_write.add(*addr, AddrSource::Gossip))
+            .count()
+    }
+
+    /// Ask connected peers for addresses, store peers that have been
+    /// reached in the known_peers DB, and connect to the best known
+    /// addresses while there are fewer than [`Self::TARGET_PEERS`] active
+    /// peers
+    pub fn discover_peers(&self, env: &sneed::Env) {
+        let now = Instant::now();
+        let mut reached = Vec::new();
+        let active: HashSet<SocketAddr> = {
+            let active_peers_read = self.active_peers.read();
+            let mut addr_manager_write = self.addr_manager.write();
+            for (addr, conn_handle) in active_peers_read.iter() {
+                if conn_handle.received_msg_successfully() {
+                    addr_manager_write.mark_success(*addr, now);
+                    reached.push(*addr);
+                }
+                if conn_handle.connection_status()
+                    != PeerConnectionStatus::Connected
+                {
+                    continue;
+                }
+                let request: PeerRequest = peer::message::GetPeersRequest {
+                    nonce: self
+                        .get_peers_nonce
+                        .fetch_add(1, atomic::Ordering::SeqCst),
+                }
+                .into();
+                if let Err(_send_err) = conn_handle
+                    .internal_message_tx
+                    .unbounded_send(request.into())
+                {
+                    tracing::warn!(%addr, "Failed to request peers");
+                }
+            }
+            active_peers_read.keys().copied().collect()
+        };
+        if !reached.is_empty()
+            && let Err(err) = (|| -> Result<(), Error> {
+                let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+                for addr in &reached {
+                    self.known_peers
+                        .put(&mut rwtxn, addr, &())
+                        .map_err(DbError::from)?;
+                }
+                rwtxn.commit().map_err(RwTxnError::from)?;
+                Ok(())
+            })()
+        {
+            let err = anyhow::Error::from(err);
+            tracing::warn!("Failed to store reached peers: {err:#}");
+        }
+        let wanted = Self::TARGET_PEERS.saturating_sub(active.len());
+        if wanted == 0 {
+            return;
+        }
+        let candidates = self.addr_manager.read().select(wanted, &active, now);
+        tracing::debug!(
+            active_peers = active.len(),
+            known_addrs = self.addr_manager.read().len(),
+            candidates = candidates.len(),
+            "discover peers: connecting to candidates"
+        );
+        for addr in candidates {
+            if let Err(err) = self.connect_peer_(env.clone(), addr, false) {
+                let err = anyhow::Error::from(err);
+                tracing::debug!(%addr, "discover peers: failed to connect: {err:#}");
+                self.addr_manager.write().mark_failure(addr);
+            }
+        }
+    }
 }
@@ -1,10 +1,12 @@
 use thiserror::Error;
 
-use crate::net::peer::PeerStateId;
+use crate::net::{misbehavior::Misbehavior, peer::PeerStateId};
 
 pub(in crate::net::peer) mod connection {
     use thiserror::Error;
 
+    use crate::net::misbehavior::Misbehavior;
+
     #[derive(Debug, Error)]
     pub enum Send {
         #[error("bincode error")]
@@ -83,6 +85,22 @@ pub(in crate::net::peer) mod connection {
         ReadToEnd(#[from] quinn::ReadToEndError),
     }
 
+    impl Receive {
+        pub fn misbehavior(&self) -> Option<Misbehavior> {
+            match self {
+                Self::BadMagic(_) | Self::Bincode(_) => {
+                    Some(Misbehavior::MalformedMessage)
+                }
+                Self::ReadToEnd(quinn::ReadToEndError::TooLong) => {
+                    Some(Misbehavior::OversizedMessage)
+                }
+                Self::Connection(_)
+                | Self::ReadMagic(_)
+                | Self::ReadToEnd(_) => None,
+            }
+        }
+    }
+
     #[derive(Debug, Error)]
     #[error("Failed to receive request from peer")]
     #[repr(transparent)]
@@ -97,6 +115,12 @@ pub(in crate::net::peer) mod connection {
         }
     }
 
+    impl ReceiveRequest {
+        pub fn misbehavior(&self) -> Option<Misbehavior> {
+            self.0.misbehavior()
+        }
+    }
+
     #[derive(Debug, Error)]
     #[error("Failed to receive response from peer")]
     #[repr(transparent)]
@@ -110,6 +134,12 @@ pub(in crate::net::peer) mod connection {
             Self(err.into())
         }
     }
+
+    impl ReceiveResponse {
+        pub fn misbehavior(&self) -> Option<Misbehavior> {
+            self.0.misbehavior()
+        }
+    }
 }
 
 pub(in crate::net::peer) mod channel_pool {
@@ -287,6 +317,8 @@ pub enum Error {
     DbEnv(#[from] sneed::env::Error),
     #[error(transparent)]
     Mailbox(#[from] mailbox::Error),
+    #[error("mempool error")]
+    MemPool(#[from] crate::mempool::Error),
     #[error("missing peer state for id {0}")]
     MissingPeerState(PeerStateId),
     #[error(transparent)]
@@ -304,3 +336,21 @@ pub enum Error {
     #[error("state error")]
     State(#[from] crate::state::Error),
 }
+
+impl Error {
+    /// Misbehavior by the peer that caused the error, if any
+    pub fn misbehavior(&self) -> Option<Misbehavior> {
+        match self {
+            Self::Mailbox(mailbox::Error::BlockingTask(
+                blocking_task::Error::Task(err),
+            )) if matches!(**err, blocking_task::TaskError::PeerBan(_)) => {
+                Some(Misbehavior::InvalidTip)
+            }
+            Self::Mailbox(mailbox::Error::ReceiveRequest(err)) => {
+                err.misbehavior()
+            }
+            Self::ReceiveResponse(err) => err.misbehavior(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Transaction inventory known to a peer, and transactions being fetched
+//! from peers
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use hashlink::LinkedHashSet;
+
+use crate::types::Txid;
+
+/// Txids that a peer is known to have, because the peer announced or sent
+/// them, or because they were announced or sent to the peer.
+/// Transactions are not announced to peers that already know of them.
+/// The oldest txids are evicted once [`Self::MAX_LEN`] is reached.
+#[derive(Debug, Default)]
+pub struct KnownTxids(LinkedHashSet<Txid>);
+
+impl KnownTxids {
+    pub const MAX_LEN: usize = 50_000;
+
+    /// Returns `true` if the txid was not known before
+    pub fn insert(&mut self, txid: Txid) -> bool {
+        if !self.0.insert(txid) {
+            return false;
+        }
+        if self.0.len() > Self::MAX_LEN {
+            let _: Option<Txid> = self.0.pop_front();
+        }
+        true
+    }
+}
+
+/// Txids of announced transactions that are being fetched from a peer.
+/// Shared by all peer connections, so that a transaction announced by
+/// several peers is only fetched from one of them at a time.
+/// A fetch may be retried once [`Self::TIMEOUT`] has passed without a
+/// response.
+#[derive(Debug, Default)]
+pub struct InFlightTxids(HashMap<Txid, Instant>);
+
+impl InFlightTxids {
+    pub const MAX_LEN: usize = 50_000;
+
+    pub const TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// Returns `true` if the txid was not in flight, in which case the
+    /// caller should fetch it
+    pub fn insert(&mut self, txid: Txid, now: Instant) -> bool {
+        let is_pending = |requested_at: &Instant| {
+            now.duration_since(*requested_at) < Self::TIMEOUT
+        };
+        if self.0.get(&txid).is_some_and(is_pending) {
+            return false;
+        }
+        if self.0.len() >= Self::MAX_LEN {
+            self.0.retain(|_, requested_at| is_pending(requested_at));
+            if self.0.len() >= Self::MAX_LEN {
+                return false;
+            }
+        }
+        let _: Option<Instant> = self.0.insert(txid, now);
+        true
+    }
+
+    /// Mark a fetch as complete, whether or not it succeeded
+    pub fn remove(&mut self, txid: &Txid) {
+        let _: Option<Instant> = self.0.remove(txid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txid(n: usize) -> Txid {
+        let mut bytes = [0; 32];
+        bytes[..8].copy_from_slice(&(n as u64).to_le_bytes());
+        Txid(bytes)
+    }
+
+    #[test]
+    fn evicts_oldest_txids() {
+        let mut known_txids = KnownTxids::default();
+        for n in 0..KnownTxids::MAX_LEN {
+            assert!(known_txids.insert(txid(n)));
+        }
+        assert!(!known_txids.insert(txid(0)));
+        assert!(known_txids.insert(txid(KnownTxids::MAX_LEN)));
+        // The oldest txid was evicted
+        assert!(known_txids.insert(txid(0)));
+        assert!(!known_txids.insert(txid(KnownTxids::MAX_LEN)));
+    }
+
+    #[test]
+    fn fetches_in_flight_txids_once() {
+        let mut in_flight = InFlightTxids::default();
+        let now = Instant::now();
+        assert!(in_flight.insert(txid(0), now));
+        assert!(!in_flight.insert(txid(0), now));
+        assert!(in_flight.insert(txid(1), now));
+        // A fetch that was not answered may be retried
+        assert!(in_flight.insert(txid(0), now + InFlightTxids::TIMEOUT));
+        // A completed fetch may be repeated, e.g. if the transaction was
+        // not served
+        in_flight.remove(&txid(1));
+        assert!(in_flight.insert(txid(1), now));
+    }
+}
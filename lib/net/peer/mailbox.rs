@@ -24,13 +24,16 @@ use crate::{
         message::{Request, RequestMessage},
         request_queue,
     },
-    types::proto::mainchain,
+    types::{Txid, proto::mainchain},
     util::join_set,
 };
 
 /// Message received from the connection task / net task / node
 #[derive(Debug)]
 pub enum InternalMessage {
+    /// Announce a txid to the peer. Txids are batched, and announced with
+    /// the next heartbeat.
+    AnnounceTransaction(Txid),
     /// Indicates if a BMM verification request completed.
     /// Does not indicate that BMM was verified successfully.
     BmmVerification {
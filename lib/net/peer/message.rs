@@ -1,12 +1,12 @@
 //! P2P message types
 
-use std::{collections::HashSet, num::NonZeroUsize};
+use std::{collections::HashSet, net::SocketAddr, num::NonZeroUsize};
 
 use borsh::BorshSerialize;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    net::peer::{PeerState, PeerStateId},
+    net::peer::{Connection, PeerState, PeerStateId},
     types::{
         AuthorizedTransaction, BlockHash, Body, Header, Network, Tip, Txid,
     },
@@ -110,11 +110,79 @@ impl PushTransactionRequest {
     }
 }
 
+/// Maximum number of addresses in a [`ResponseMessage::Peers`]
+pub const MAX_PEERS_PER_MESSAGE: usize = 250;
+
+/// Request addresses of other peers known to the peer
+#[derive(BorshSerialize, Clone, Debug, Deserialize, Serialize)]
+pub struct GetPeersRequest {
+    /// Distinguishes repeated requests, which would otherwise be
+    /// deduplicated. Only relevant for the requester, so serialization is
+    /// skipped
+    #[serde(skip)]
+    pub nonce: u64,
+}
+
+impl GetPeersRequest {
+    /// Limit bytes to read in a response to a request
+    pub const fn read_response_limit(&self) -> NonZeroUsize {
+        // 64B limit per address
+        NonZeroUsize::new(MAX_PEERS_PER_MESSAGE * 64 + 64).unwrap()
+    }
+}
+
+/// Maximum number of txids in an [`AnnounceTransactionsRequest`].
+/// This is the same as [`MAX_TRANSACTIONS_PER_MESSAGE`], so that the
+/// announced transactions can be fetched with one [`GetTransactionsRequest`].
+pub const MAX_TXIDS_PER_MESSAGE: usize = MAX_TRANSACTIONS_PER_MESSAGE;
+
+/// Announce txids of transactions that are available to request with
+/// [`GetTransactionsRequest`]
+#[derive(BorshSerialize, Clone, Debug, Deserialize, Serialize)]
+pub struct AnnounceTransactionsRequest {
+    /// At most [`MAX_TXIDS_PER_MESSAGE`] txids
+    pub txids: Vec<Txid>,
+}
+
+impl AnnounceTransactionsRequest {
+    /// Limit bytes to read in a response to a request
+    pub const fn read_response_limit(&self) -> NonZeroUsize {
+        // Response is an ack
+        NonZeroUsize::new(64).unwrap()
+    }
+}
+
+/// Maximum number of transactions in a [`GetTransactionsRequest`]
+pub const MAX_TRANSACTIONS_PER_MESSAGE: usize = 100;
+
+/// Request announced transactions
+#[derive(BorshSerialize, Clone, Debug, Deserialize, Serialize)]
+pub struct GetTransactionsRequest {
+    /// At most [`MAX_TRANSACTIONS_PER_MESSAGE`] txids
+    pub txids: Vec<Txid>,
+}
+
+impl GetTransactionsRequest {
+    /// Limit bytes to read in a response to a request
+    pub const fn read_response_limit(&self) -> NonZeroUsize {
+        // Same limit per tx as for a pushed tx
+        NonZeroUsize::new(
+            self.txids.len() * Connection::READ_REQUEST_LIMIT + 64,
+        )
+        .unwrap()
+    }
+}
+
 #[derive(BorshSerialize, Clone, Debug)]
 pub enum Request {
     GetBlock(GetBlockRequest),
     GetHeaders(GetHeadersRequest),
+    /// Transactions are relayed by announcing txids instead. Pushed
+    /// transactions are still accepted from peers.
     PushTransaction(PushTransactionRequest),
+    GetPeers(GetPeersRequest),
+    AnnounceTransactions(AnnounceTransactionsRequest),
+    GetTransactions(GetTransactionsRequest),
 }
 
 impl Request {
@@ -125,6 +193,11 @@ impl Request {
             Self::GetBlock(request) => request.read_response_limit(),
             Self::GetHeaders(request) => request.read_response_limit(),
             Self::PushTransaction(request) => request.read_response_limit(),
+            Self::GetPeers(request) => request.read_response_limit(),
+            Self::AnnounceTransactions(request) => {
+                request.read_response_limit()
+            }
+            Self::GetTransactions(request) => request.read_response_limit(),
         }
     }
 }
@@ -147,6 +220,24 @@ impl From<PushTransactionRequest> for Request {
     }
 }
 
+impl From<GetPeersRequest> for Request {
+    fn from(request: GetPeersRequest) -> Self {
+        Self::GetPeers(request)
+    }
+}
+
+impl From<AnnounceTransactionsRequest> for Request {
+    fn from(request: AnnounceTransactionsRequest) -> Self {
+        Self::AnnounceTransactions(request)
+    }
+}
+
+impl From<GetTransactionsRequest> for Request {
+    fn from(request: GetTransactionsRequest) -> Self {
+        Self::GetTransactions(request)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum RequestMessageRef<'a> {
     Heartbeat(&'a Heartbeat),
@@ -188,6 +279,9 @@ impl<'a> Serialize for RequestMessageRef<'a> {
             GetBlock(&'b GetBlockRequest),
             GetHeaders(&'b GetHeadersRequest),
             PushTransaction(&'b PushTransactionRequest),
+            GetPeers(&'b GetPeersRequest),
+            AnnounceTransactions(&'b AnnounceTransactionsRequest),
+            GetTransactions(&'b GetTransactionsRequest),
         }
 
         let repr = match self {
@@ -200,6 +294,13 @@ impl<'a> Serialize for RequestMessageRef<'a> {
                 Request::PushTransaction(request) => {
                     Repr::PushTransaction(request)
                 }
+                Request::GetPeers(request) => Repr::GetPeers(request),
+                Request::AnnounceTransactions(request) => {
+                    Repr::AnnounceTransactions(request)
+                }
+                Request::GetTransactions(request) => {
+                    Repr::GetTransactions(request)
+                }
             },
         };
         repr.serialize(serializer)
@@ -211,7 +312,10 @@ impl<'a> Serialize for RequestMessageRef<'a> {
 #[transitive(
     from(GetBlockRequest, Request),
     from(GetHeadersRequest, Request),
-    from(PushTransactionRequest, Request)
+    from(PushTransactionRequest, Request),
+    from(GetPeersRequest, Request),
+    from(AnnounceTransactionsRequest, Request),
+    from(GetTransactionsRequest, Request)
 )]
 pub enum RequestMessage {
     Heartbeat(Heartbeat),
@@ -258,12 +362,18 @@ impl<'de> Deserialize<'de> for RequestMessage {
             GetBlock(GetBlockRequest),
             GetHeaders(GetHeadersRequest),
             PushTransaction(PushTransactionRequest),
+            GetPeers(GetPeersRequest),
+            AnnounceTransactions(AnnounceTransactionsRequest),
+            GetTransactions(GetTransactionsRequest),
         }
         let res = match Repr::deserialize(deserializer)? {
             Repr::Heartbeat(heartbeat) => heartbeat.into(),
             Repr::GetBlock(request) => request.into(),
             Repr::GetHeaders(request) => request.into(),
             Repr::PushTransaction(request) => request.into(),
+            Repr::GetPeers(request) => request.into(),
+            Repr::AnnounceTransactions(request) => request.into(),
+            Repr::GetTransactions(request) => request.into(),
         };
         Ok(res)
     }
@@ -286,6 +396,13 @@ pub enum ResponseMessage {
     },
     TransactionAccepted(Txid),
     TransactionRejected(Txid),
+    /// Addresses of other peers, at most [`MAX_PEERS_PER_MESSAGE`]
+    Peers(Vec<SocketAddr>),
+    /// Acknowledges an announcement
+    Ack,
+    /// Requested transactions that are available. Transactions that are not
+    /// available are omitted.
+    Transactions(Vec<AuthorizedTransaction>),
 }
 
 impl ResponseMessage {
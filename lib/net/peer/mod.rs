@@ -10,6 +10,7 @@ use std::{
 use bitcoin::Work;
 use borsh::BorshSerialize;
 use futures::channel::mpsc;
+use parking_lot::{Mutex, RwLock};
 use quinn::{RecvStream, SendStream};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -17,21 +18,27 @@ use tokio::{spawn, task::JoinHandle, time::Duration};
 
 use crate::{
     archive::Archive,
+    mempool::MemPool,
+    net::{addr_manager::AddrManager, misbehavior::Misbehavior},
     state::State,
     types::{AuthorizedTransaction, Hash, Network, Tip, Version, hash, schema},
 };
 
 mod channel_pool;
 pub(crate) mod error;
+mod inventory;
 pub(crate) mod mailbox;
 pub mod message;
+mod rate_limit;
 mod request_queue;
 mod task;
 
 pub use error::Error as ConnectionError;
+pub(in crate::net) use inventory::{InFlightTxids, KnownTxids};
 pub use mailbox::InternalMessage;
 use message::{Heartbeat, RequestMessage, RequestMessageRef};
 pub use message::{Request, ResponseMessage};
+pub use rate_limit::RequestQuotas;
 use task::ConnectionTask;
 
 #[derive(Debug, Error)]
@@ -115,6 +122,8 @@ pub enum Info {
     /// New tip ready (body and header exist in archive, BMM verified)
     NewTipReady(Tip),
     NewTransaction(AuthorizedTransaction),
+    /// Peer misbehaved, without the connection failing
+    Misbehavior(Misbehavior),
     Response(Box<(ResponseMessage, Request)>),
 }
 
@@ -333,8 +342,16 @@ impl Connection {
 pub struct ConnectionContext {
     pub env: sneed::Env,
     pub archive: Archive,
+    pub mempool: MemPool,
     pub network: Network,
     pub state: State,
+    /// Shared with the net, to answer `GetPeers` requests
+    pub addr_manager: Arc<RwLock<AddrManager>>,
+    /// Quotas for requests received from the peer
+    pub request_quotas: RequestQuotas,
+    /// Shared with the net, so that announced transactions are only
+    /// fetched from one peer at a time
+    pub in_flight_txids: Arc<Mutex<InFlightTxids>>,
 }
 
 #[derive(
@@ -381,6 +398,8 @@ pub struct ConnectionHandle {
     pub(in crate::net) received_msg_successfully: Arc<AtomicBool>,
     /// Representation of [`PeerConnectionStatus`]
     pub(in crate::net) status_repr: Arc<AtomicBool>,
+    /// Txids that the peer is known to have
+    pub(in crate::net) known_txids: Arc<Mutex<KnownTxids>>,
     /// Push messages from connection task / net task / node
     pub internal_message_tx: mpsc::UnboundedSender<InternalMessage>,
 }
@@ -416,14 +435,17 @@ pub fn handle(
     let (mailbox_tx, mailbox_rx) = mailbox::new();
     let internal_message_tx = mailbox_tx.internal_message_tx.clone();
     let received_msg_successfully = Arc::new(AtomicBool::new(false));
+    let known_txids = Arc::<Mutex<KnownTxids>>::default();
     let connection_task = {
         let info_tx = info_tx.clone();
         let received_msg_successfully = received_msg_successfully.clone();
+        let known_txids = known_txids.clone();
         move || async move {
             let connection_task = ConnectionTask {
                 connection,
                 ctxt,
                 info_tx,
+                known_txids,
                 mailbox_rx,
                 mailbox_tx,
                 received_msg_successfully,
@@ -447,6 +469,7 @@ pub fn handle(
         task,
         received_msg_successfully,
         status_repr: Arc::new(AtomicBool::new(status.as_repr())),
+        known_txids,
         internal_message_tx,
     };
     (connection_handle, info_rx)
@@ -459,6 +482,7 @@ pub fn connect(
     let connection_status = PeerConnectionStatus::Connecting;
     let status_repr = Arc::new(AtomicBool::new(connection_status.as_repr()));
     let received_msg_successfully = Arc::new(AtomicBool::new(false));
+    let known_txids = Arc::<Mutex<KnownTxids>>::default();
     let (info_tx, info_rx) = mpsc::unbounded();
     let (mailbox_tx, mailbox_rx) = mailbox::new();
     let internal_message_tx = mailbox_tx.internal_message_tx.clone();
@@ -466,6 +490,7 @@ pub fn connect(
         let received_msg_successfully = received_msg_successfully.clone();
         let status_repr = status_repr.clone();
         let info_tx = info_tx.clone();
+        let known_txids = known_txids.clone();
         move || async move {
             let connection =
                 Connection::from_connecting(connecting, ctxt.network).await?;
@@ -478,6 +503,7 @@ pub fn connect(
                 connection,
                 ctxt,
                 info_tx,
+                known_txids,
                 mailbox_rx,
                 mailbox_tx,
                 received_msg_successfully,
@@ -497,6 +523,7 @@ pub fn connect(
         task,
         received_msg_successfully,
         status_repr,
+        known_txids,
         internal_message_tx,
     };
     (connection_handle, info_rx)
@@ -0,0 +1,187 @@
+//! Rate limiting of requests sent to and received from a peer.
+//!
+//! Both directions are derived from one table, [`RequestQuotas::OUTBOUND`].
+//! Requests sent to a peer are paced to it, and the default quotas for
+//! requests received from a peer allow [`RequestQuotas::INBOUND_HEADROOM`]
+//! times as many, so that a peer running the defaults never exceeds them.
+
+use std::num::NonZeroU32;
+
+use governor::{DefaultDirectRateLimiter, Quota};
+
+use crate::net::peer::message::Request;
+
+/// Maximum number of requests of each type that a peer may send per second.
+/// Peers may also send a burst of up to this many requests at once.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RequestQuotas {
+    pub get_block: NonZeroU32,
+    pub get_headers: NonZeroU32,
+    pub push_transaction: NonZeroU32,
+    pub get_peers: NonZeroU32,
+    pub announce_transactions: NonZeroU32,
+    pub get_transactions: NonZeroU32,
+}
+
+impl RequestQuotas {
+    /// Requests of each type sent to each peer per second, at most
+    pub const OUTBOUND: Self = Self {
+        get_block: NonZeroU32::new(50).unwrap(),
+        get_headers: NonZeroU32::new(5).unwrap(),
+        push_transaction: NonZeroU32::new(500).unwrap(),
+        get_peers: NonZeroU32::new(1).unwrap(),
+        announce_transactions: NonZeroU32::new(10).unwrap(),
+        get_transactions: NonZeroU32::new(50).unwrap(),
+    };
+
+    /// Factor by which the default quotas for requests received from a peer
+    /// exceed [`Self::OUTBOUND`], to allow for network jitter
+    pub const INBOUND_HEADROOM: NonZeroU32 = NonZeroU32::new(2).unwrap();
+}
+
+impl Default for RequestQuotas {
+    fn default() -> Self {
+        let inbound =
+            |quota: NonZeroU32| quota.saturating_mul(Self::INBOUND_HEADROOM);
+        let outbound = Self::OUTBOUND;
+        Self {
+            get_block: inbound(outbound.get_block),
+            get_headers: inbound(outbound.get_headers),
+            push_transaction: inbound(outbound.push_transaction),
+            get_peers: inbound(outbound.get_peers),
+            announce_transactions: inbound(outbound.announce_transactions),
+            get_transactions: inbound(outbound.get_transactions),
+        }
+    }
+}
+
+/// Result of checking a request received from a peer against its quota
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(in crate::net::peer) enum QuotaCheck {
+    /// The request is within its quota
+    Allowed,
+    /// The request exceeds its quota, and is not served
+    Exceeded,
+    /// The request exceeds its quota, and the peer has exceeded its
+    /// quotas more than [`RequestLimiter::OVER_QUOTA_TOLERANCE`] times per
+    /// second. The request is not served, and counts as a request flood.
+    Flood,
+}
+
+/// Token buckets for each type of request sent to or received from a peer
+pub(in crate::net::peer) struct RequestLimiter {
+    get_block: DefaultDirectRateLimiter,
+    get_headers: DefaultDirectRateLimiter,
+    push_transaction: DefaultDirectRateLimiter,
+    get_peers: DefaultDirectRateLimiter,
+    announce_transactions: DefaultDirectRateLimiter,
+    get_transactions: DefaultDirectRateLimiter,
+    /// Requests over quota that are tolerated before they count as a flood
+    over_quota: DefaultDirectRateLimiter,
+}
+
+impl RequestLimiter {
+    /// Requests over quota that are tolerated per second, with bursts of up
+    /// to the same number, before they count as a flood
+    pub const OVER_QUOTA_TOLERANCE: NonZeroU32 = NonZeroU32::new(10).unwrap();
+
+    pub fn new(quotas: &RequestQuotas) -> Self {
+        let limiter =
+            |quota| DefaultDirectRateLimiter::direct(Quota::per_second(quota));
+        Self {
+            get_block: limiter(quotas.get_block),
+            get_headers: limiter(quotas.get_headers),
+            push_transaction: limiter(quotas.push_transaction),
+            get_peers: limiter(quotas.get_peers),
+            announce_transactions: limiter(quotas.announce_transactions),
+            get_transactions: limiter(quotas.get_transactions),
+            over_quota: limiter(Self::OVER_QUOTA_TOLERANCE),
+        }
+    }
+
+    /// Limiter for requests sent to a peer
+    pub fn outbound() -> Self {
+        Self::new(&RequestQuotas::OUTBOUND)
+    }
+
+    fn limiter(&self, request: &Request) -> &DefaultDirectRateLimiter {
+        match request {
+            Request::GetBlock(_) => &self.get_block,
+            Request::GetHeaders(_) => &self.get_headers,
+            Request::PushTransaction(_) => &self.push_transaction,
+            Request::GetPeers(_) => &self.get_peers,
+            Request::AnnounceTransactions(_) => &self.announce_transactions,
+            Request::GetTransactions(_) => &self.get_transactions,
+        }
+    }
+
+    /// Check a request received from a peer, consuming a token if it is
+    /// within its quota
+    pub fn check(&self, request: &Request) -> QuotaCheck {
+        if self.limiter(request).check().is_ok() {
+            QuotaCheck::Allowed
+        } else if self.over_quota.check().is_ok() {
+            QuotaCheck::Exceeded
+        } else {
+            QuotaCheck::Flood
+        }
+    }
+
+    /// Wait until a request can be sent to a peer, consuming a token
+    pub async fn until_ready(&self, request: &Request) {
+        self.limiter(request).until_ready().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::peer::message::GetPeersRequest;
+
+    #[test]
+    fn limits_each_request_type() {
+        let quotas = RequestQuotas {
+            get_peers: NonZeroU32::new(2).unwrap(),
+            ..RequestQuotas::default()
+        };
+        let limiter = RequestLimiter::new(&quotas);
+        let get_peers = Request::GetPeers(GetPeersRequest { nonce: 0 });
+        assert_eq!(limiter.check(&get_peers), QuotaCheck::Allowed);
+        assert_eq!(limiter.check(&get_peers), QuotaCheck::Allowed);
+        assert_eq!(limiter.check(&get_peers), QuotaCheck::Exceeded);
+        // Other request types have separate quotas
+        assert!(limiter.get_headers.check().is_ok());
+    }
+
+    #[test]
+    fn only_sustained_floods_count() {
+        let limiter = RequestLimiter::new(&RequestQuotas::default());
+        let get_peers = Request::GetPeers(GetPeersRequest { nonce: 0 });
+        for _ in 0..RequestQuotas::default().get_peers.get() {
+            assert_eq!(limiter.check(&get_peers), QuotaCheck::Allowed);
+        }
+        for _ in 0..RequestLimiter::OVER_QUOTA_TOLERANCE.get() {
+            assert_eq!(limiter.check(&get_peers), QuotaCheck::Exceeded);
+        }
+        assert_eq!(limiter.check(&get_peers), QuotaCheck::Flood);
+    }
+
+    #[test]
+    fn inbound_quotas_exceed_outbound() {
+        let inbound = RequestQuotas::default();
+        let outbound = RequestQuotas::OUTBOUND;
+        for (inbound, outbound) in [
+            (inbound.get_block, outbound.get_block),
+            (inbound.get_headers, outbound.get_headers),
+            (inbound.push_transaction, outbound.push_transaction),
+            (inbound.get_peers, outbound.get_peers),
+            (
+                inbound.announce_transactions,
+                outbound.announce_transactions,
+            ),
+            (inbound.get_transactions, outbound.get_transactions),
+        ] {
+            assert!(inbound > outbound);
+        }
+    }
+}
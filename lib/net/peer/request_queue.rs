@@ -1,9 +1,8 @@
 //! Request queue that handles rate limiting and deduplication
 
-use std::{collections::HashSet, num::NonZeroU32, sync::Arc};
+use std::{collections::HashSet, sync::Arc};
 
 use futures::{Stream, StreamExt, channel::mpsc, stream};
-use governor::{DefaultDirectRateLimiter, Quota};
 use parking_lot::Mutex;
 
 use crate::{
@@ -12,22 +11,11 @@ use crate::{
         channel_pool::{self, ChannelPool},
         error,
         message::{Heartbeat, Request},
+        rate_limit::RequestLimiter,
     },
     types::{Hash, hash},
 };
 
-const REQUEST_QUOTA: Quota =
-    Quota::per_second(NonZeroU32::new(50_000).unwrap());
-
-/// The rate limiter token cost for a request
-const fn request_cost(req: &Request) -> NonZeroU32 {
-    match req {
-        Request::GetBlock { .. } => NonZeroU32::new(1000).unwrap(),
-        Request::GetHeaders { .. } => NonZeroU32::new(10_000).unwrap(),
-        Request::PushTransaction { .. } => NonZeroU32::new(10).unwrap(),
-    }
-}
-
 /// Receiver for errors when sending messages.
 /// If dropped, sending new messages will fail, and existing tasks to send
 /// messages and receive responses will be aborted.
@@ -35,7 +23,8 @@ const fn request_cost(req: &Request) -> NonZeroU32 {
 pub struct ErrorRx {
     heartbeat_rx: mpsc::UnboundedReceiver<Heartbeat>,
     request_rx: mpsc::UnboundedReceiver<Request>,
-    rate_limiter: Arc<DefaultDirectRateLimiter>,
+    /// Paces requests to [`crate::net::RequestQuotas::OUTBOUND`]
+    rate_limiter: Arc<RequestLimiter>,
 }
 
 impl ErrorRx {
@@ -86,10 +75,7 @@ impl ErrorRx {
                     let rate_limiter = self.rate_limiter.clone();
                     async move {
                         let guard = limiter.acquire().await;
-                        rate_limiter
-                            .until_n_ready(request_cost(&request))
-                            .await
-                            .unwrap();
+                        rate_limiter.until_ready(&request).await;
                         SourceItem::Request(request, guard)
                     }
                 }
@@ -175,11 +161,10 @@ pub fn new() -> (Sender, ErrorRx) {
         request_tx,
         request_hashes: Arc::new(Mutex::new(HashSet::new())),
     };
-    let rate_limiter = DefaultDirectRateLimiter::direct(REQUEST_QUOTA);
     let error_rx = ErrorRx {
         heartbeat_rx,
         request_rx,
-        rate_limiter: Arc::new(rate_limiter),
+        rate_limiter: Arc::new(RequestLimiter::outbound()),
     };
     (sender, error_rx)
 }
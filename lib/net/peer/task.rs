@@ -3,28 +3,36 @@
 use std::{
     cmp::Ordering,
     collections::{HashMap, HashSet},
+    net::SocketAddr,
     sync::{Arc, atomic::AtomicBool},
+    time::Instant,
 };
 
 use fallible_iterator::FallibleIterator;
 use futures::{StreamExt as _, channel::mpsc};
+use parking_lot::Mutex;
 use quinn::SendStream;
 use sneed::EnvError;
 
 use crate::{
-    net::peer::{
-        BanReason, Connection, ConnectionContext, Info, PeerState, PeerStateId,
-        Request, TipInfo,
-        error::{Error, blocking_task},
-        mailbox::{
-            self, BlockingTaskFn, ForwardResponseItem, ForwardResponseResult,
-            InternalMessage, MailboxItem,
+    net::{
+        misbehavior::Misbehavior,
+        peer::{
+            BanReason, Connection, ConnectionContext, Info, PeerState,
+            PeerStateId, Request, TipInfo,
+            error::{Error, blocking_task},
+            inventory::KnownTxids,
+            mailbox::{
+                self, BlockingTaskFn, ForwardResponseItem,
+                ForwardResponseResult, InternalMessage, MailboxItem,
+            },
+            message::{self, Heartbeat, RequestMessage, ResponseMessage},
+            rate_limit::{QuotaCheck, RequestLimiter},
+            request_queue,
         },
-        message::{self, Heartbeat, RequestMessage, ResponseMessage},
-        request_queue,
     },
     types::{
-        AuthorizedTransaction, BlockHash, BmmResult, Header, Tip, VERSION,
+        AuthorizedTransaction, BlockHash, BmmResult, Header, Tip, Txid, VERSION,
     },
     util::join_set,
 };
@@ -33,6 +41,8 @@ pub(in crate::net::peer) struct ConnectionTask {
     pub connection: Connection,
     pub ctxt: ConnectionContext,
     pub info_tx: mpsc::UnboundedSender<Info>,
+    /// Txids known to the peer
+    pub known_txids: Arc<Mutex<KnownTxids>>,
     /// Sender for the task's mailbox
     pub mailbox_rx: mailbox::Receiver,
     /// Receiver for the task's mailbox
@@ -42,6 +52,15 @@ pub(in crate::net::peer) struct ConnectionTask {
 }
 
 impl ConnectionTask {
+    /// Error code used to reset the response stream for requests that
+    /// exceed their quota
+    const RATE_LIMITED_ERROR_CODE: quinn::VarInt = quinn::VarInt::from_u32(1);
+
+    /// Error code used to reset the response stream for requests that
+    /// exceed the size limit for their kind
+    const OVERSIZED_REQUEST_ERROR_CODE: quinn::VarInt =
+        quinn::VarInt::from_u32(2);
+
     /// Check if peer tip is better, requesting headers if necessary.
     /// Returns `Some(true)` if the peer tip is better and headers are available,
     /// `Some(false)` if the peer tip is better and headers were requested,
@@ -651,23 +670,38 @@ impl ConnectionTask {
     async fn handle_push_tx(
         ctxt: &ConnectionContext,
         info_tx: &mpsc::UnboundedSender<Info>,
+        known_txids: &Mutex<KnownTxids>,
         response_tx: SendStream,
         tx: AuthorizedTransaction,
     ) -> Result<(), Error> {
         let txid = tx.transaction.txid();
+        let _: bool = known_txids.lock().insert(txid);
         let validate_tx_result = {
             let rotxn = ctxt.env.read_txn().map_err(EnvError::from)?;
             ctxt.state.validate_transaction(&rotxn, &tx)
         };
         match validate_tx_result {
             Err(err) => {
+                // Transactions that conflict with this node's state may be
+                // valid for the peer, e.g. if it has not yet seen a
+                // conflicting transaction
+                let is_invalid = err.is_state_independent();
+                let err = anyhow::Error::from(err);
+                tracing::debug!(%txid, "Rejected transaction from peer: {err:#}");
                 Connection::send_response(
                     ctxt.network,
                     response_tx,
                     ResponseMessage::TransactionRejected(txid),
                 )
                 .await?;
-                Err(Error::from(err))
+                if is_invalid {
+                    info_tx
+                        .unbounded_send(Info::Misbehavior(
+                            Misbehavior::InvalidTransaction,
+                        ))
+                        .map_err(|_| Error::SendInfo)?;
+                }
+                Ok(())
             }
             Ok(_) => {
                 Connection::send_response(
@@ -684,14 +718,126 @@ impl ConnectionTask {
         }
     }
 
+    /// Reset the response stream for a request that exceeds the size limit
+    /// for its kind, and report the peer
+    fn reject_oversized_request(
+        info_tx: &mpsc::UnboundedSender<Info>,
+        mut response_tx: SendStream,
+    ) -> Result<(), Error> {
+        let _: Result<(), quinn::ClosedStream> =
+            response_tx.reset(Self::OVERSIZED_REQUEST_ERROR_CODE);
+        info_tx
+            .unbounded_send(Info::Misbehavior(Misbehavior::OversizedMessage))
+            .map_err(|_| Error::SendInfo)
+    }
+
+    /// Acknowledge announced txids, and request the transactions that are
+    /// neither in the mempool nor already being fetched from another peer,
+    /// with one request
+    async fn handle_announce_txs(
+        ctxt: &ConnectionContext,
+        info_tx: &mpsc::UnboundedSender<Info>,
+        request_queue: &request_queue::Sender,
+        known_txids: &Mutex<KnownTxids>,
+        response_tx: SendStream,
+        txids: Vec<Txid>,
+    ) -> Result<(), Error> {
+        if txids.len() > message::MAX_TXIDS_PER_MESSAGE {
+            return Self::reject_oversized_request(info_tx, response_tx);
+        }
+        let missing_txids = {
+            let rotxn = ctxt.env.read_txn().map_err(EnvError::from)?;
+            let mut known_txids = known_txids.lock();
+            let mut missing_txids = Vec::new();
+            for txid in txids {
+                let _: bool = known_txids.insert(txid);
+                if ctxt.mempool.try_get(&rotxn, txid)?.is_none() {
+                    missing_txids.push(txid);
+                }
+            }
+            let now = Instant::now();
+            let mut in_flight_txids = ctxt.in_flight_txids.lock();
+            missing_txids.retain(|txid| in_flight_txids.insert(*txid, now));
+            missing_txids
+        };
+        let () = Connection::send_response(
+            ctxt.network,
+            response_tx,
+            ResponseMessage::Ack,
+        )
+        .await?;
+        if !missing_txids.is_empty() {
+            let request = message::GetTransactionsRequest {
+                txids: missing_txids,
+            };
+            let _: bool = request_queue.send_request(request.into())?;
+        }
+        Ok(())
+    }
+
+    /// Respond with the requested transactions that are in the mempool
+    async fn handle_get_txs(
+        ctxt: &ConnectionContext,
+        info_tx: &mpsc::UnboundedSender<Info>,
+        known_txids: &Mutex<KnownTxids>,
+        response_tx: SendStream,
+        txids: Vec<Txid>,
+    ) -> Result<(), Error> {
+        if txids.len() > message::MAX_TRANSACTIONS_PER_MESSAGE {
+            return Self::reject_oversized_request(info_tx, response_tx);
+        }
+        let transactions = {
+            let rotxn = ctxt.env.read_txn().map_err(EnvError::from)?;
+            let mut transactions = Vec::new();
+            for txid in txids {
+                if let Some(tx) = ctxt.mempool.try_get(&rotxn, txid)? {
+                    transactions.push(tx);
+                }
+            }
+            transactions
+        };
+        {
+            let mut known_txids = known_txids.lock();
+            for tx in &transactions {
+                let _: bool = known_txids.insert(tx.transaction.txid());
+            }
+        }
+        Connection::send_response(
+            ctxt.network,
+            response_tx,
+            ResponseMessage::Transactions(transactions),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn handle_get_peers(
+        ctxt: &ConnectionContext,
+        response_tx: SendStream,
+        addr: SocketAddr,
+    ) -> Result<(), Error> {
+        let peers = ctxt
+            .addr_manager
+            .read()
+            .sample(message::MAX_PEERS_PER_MESSAGE, addr);
+        let resp = ResponseMessage::Peers(peers);
+        let () =
+            Connection::send_response(ctxt.network, response_tx, resp).await?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn handle_peer_request(
         ctxt: &Arc<ConnectionContext>,
         info_tx: &mpsc::UnboundedSender<Info>,
         mailbox_sender: &mailbox::Sender,
+        known_txids: &Mutex<KnownTxids>,
         peer_state: &mut Option<PeerStateId>,
         // Map associating peer state hashes to peer state
         peer_states: &mut HashMap<PeerStateId, PeerState>,
         response_tx: SendStream,
+        // Address of the peer that sent the request
+        addr: SocketAddr,
         request_msg: RequestMessage,
     ) -> Result<(), Error> {
         match request_msg {
@@ -746,10 +892,61 @@ impl ConnectionTask {
             RequestMessage::Request(Request::PushTransaction(
                 message::PushTransactionRequest { transaction },
             )) => {
-                Self::handle_push_tx(ctxt, info_tx, response_tx, transaction)
-                    .await
+                Self::handle_push_tx(
+                    ctxt,
+                    info_tx,
+                    known_txids,
+                    response_tx,
+                    transaction,
+                )
+                .await
+            }
+            RequestMessage::Request(Request::GetPeers(
+                message::GetPeersRequest { nonce: _ },
+            )) => Self::handle_get_peers(ctxt, response_tx, addr).await,
+            RequestMessage::Request(Request::AnnounceTransactions(
+                message::AnnounceTransactionsRequest { txids },
+            )) => {
+                Self::handle_announce_txs(
+                    ctxt,
+                    info_tx,
+                    &mailbox_sender.request_tx,
+                    known_txids,
+                    response_tx,
+                    txids,
+                )
+                .await
             }
+            RequestMessage::Request(Request::GetTransactions(
+                message::GetTransactionsRequest { txids },
+            )) => {
+                Self::handle_get_txs(
+                    ctxt,
+                    info_tx,
+                    known_txids,
+                    response_tx,
+                    txids,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Announce the pending txids, in as few requests as possible
+    fn flush_announcements(
+        request_queue: &request_queue::Sender,
+        pending_announcements: &mut Vec<Txid>,
+    ) -> Result<(), Error> {
+        while !pending_announcements.is_empty() {
+            let len = pending_announcements
+                .len()
+                .min(message::MAX_TXIDS_PER_MESSAGE);
+            let request = message::AnnounceTransactionsRequest {
+                txids: pending_announcements.drain(..len).collect(),
+            };
+            let _: bool = request_queue.send_request(request.into())?;
         }
+        Ok(())
     }
 
     fn handle_internal_message(
@@ -759,9 +956,21 @@ impl ConnectionTask {
         blocking_task_queue_tx: &mpsc::UnboundedSender<BlockingTaskFn>,
         // known peer states
         peer_states: &HashMap<PeerStateId, PeerState>,
+        // txids to announce with the next heartbeat
+        pending_announcements: &mut Vec<Txid>,
         msg: InternalMessage,
     ) -> Result<(), Error> {
         match msg {
+            InternalMessage::AnnounceTransaction(txid) => {
+                pending_announcements.push(txid);
+                if pending_announcements.len() >= message::MAX_TXIDS_PER_MESSAGE
+                {
+                    let () = Self::flush_announcements(
+                        request_queue,
+                        pending_announcements,
+                    )?;
+                }
+            }
             InternalMessage::ForwardRequest(request) => {
                 let _: bool = request_queue.send_request(request)?;
             }
@@ -823,10 +1032,14 @@ impl ConnectionTask {
 
     pub async fn run(self) -> Result<(), Error> {
         let ctxt = Arc::new(self.ctxt);
+        let addr = self.connection.addr();
         // current peer state
         let mut peer_state = Option::<PeerStateId>::None;
         // known peer states
         let mut peer_states = HashMap::<PeerStateId, PeerState>::new();
+        // txids to announce with the next heartbeat
+        let mut pending_announcements = Vec::<Txid>::new();
+        let request_limiter = RequestLimiter::new(&ctxt.request_quotas);
         let mut mailbox_stream = self
             .mailbox_rx
             .into_stream(self.connection, &self.received_msg_successfully);
@@ -840,6 +1053,7 @@ impl ConnectionTask {
                         &self.mailbox_tx.request_tx,
                         &self.mailbox_tx.blocking_task_queue_tx,
                         &peer_states,
+                        &mut pending_announcements,
                         msg,
                     )?;
                 }
@@ -858,6 +1072,10 @@ impl ConnectionTask {
                     });
                 }
                 MailboxItem::Heartbeat => {
+                    let () = Self::flush_announcements(
+                        &self.mailbox_tx.request_tx,
+                        &mut pending_announcements,
+                    )?;
                     let tip_info = 'tip_info: {
                         let rotxn =
                             ctxt.env.read_txn().map_err(EnvError::from)?;
@@ -890,19 +1108,57 @@ impl ConnectionTask {
                     });
                     self.mailbox_tx.request_tx.send_heartbeat(heartbeat_msg)?;
                 }
-                MailboxItem::PeerRequest((request, response_tx)) => {
+                MailboxItem::PeerRequest((request, mut response_tx)) => {
+                    // Requests over quota are not served. Only sustained
+                    // floods are scored, as occasional bursts over quota
+                    // may be caused by network jitter.
+                    if let RequestMessage::Request(request) = &request {
+                        let quota_check = request_limiter.check(request);
+                        if quota_check != QuotaCheck::Allowed {
+                            tracing::debug!(
+                                %addr,
+                                ?request,
+                                ?quota_check,
+                                "Peer exceeded request quota"
+                            );
+                            let _: Result<(), quinn::ClosedStream> =
+                                response_tx
+                                    .reset(Self::RATE_LIMITED_ERROR_CODE);
+                            if quota_check == QuotaCheck::Flood {
+                                self.info_tx
+                                    .unbounded_send(Info::Misbehavior(
+                                        Misbehavior::RequestFlood,
+                                    ))
+                                    .map_err(|_| Error::SendInfo)?;
+                            }
+                            continue;
+                        }
+                    }
                     let () = Self::handle_peer_request(
                         &ctxt,
                         &self.info_tx,
                         &self.mailbox_tx,
+                        &self.known_txids,
                         &mut peer_state,
                         &mut peer_states,
                         response_tx,
+                        addr,
                         request,
                     )
                     .await?;
                 }
                 MailboxItem::PeerResponse(peer_response) => {
+                    // Transactions that were not served may be fetched
+                    // from other peers
+                    if let Request::GetTransactions(
+                        message::GetTransactionsRequest { txids },
+                    ) = &peer_response.request
+                    {
+                        let mut in_flight_txids = ctxt.in_flight_txids.lock();
+                        for txid in txids {
+                            in_flight_txids.remove(txid);
+                        }
+                    }
                     let info = peer_response
                         .response
                         .map(|resp| {
@@ -0,0 +1,600 @@
+//! Header chains of swap target chains
+//!
+//! Headers are fetched from the configured L1 endpoints and validated before
+//! they are tracked in the archive, so that swap confirmations are derived
+//! from proof of work instead of being trusted from an endpoint. A
+//! transaction's confirmations are only counted once a merkle proof shows
+//! that it is included in a tracked block.
+
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use sneed::{EnvError, RwTxnError};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+
+use super::L1ChangeWatcher;
+use crate::{
+    archive::{self, Archive},
+    parent_chain_rpc::{
+        self, L1Notification, L1Status, ParentChainBackend, TransactionInfo,
+        load_l1_endpoints_from_path, load_rpc_configs_from_path,
+    },
+    types::ParentChainType,
+};
+
+/// Interval between header syncs, for chains whose new blocks are not
+/// pushed by their endpoints
+const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Depth below the endpoint's tip from which headers are tracked, when a
+/// chain is first synced
+const INITIAL_SYNC_DEPTH: u32 = 144;
+
+/// Most headers fetched for a chain in one sync
+const MAX_HEADERS_PER_SYNC: u32 = 2016;
+
+/// Deepest L1 reorg that is followed
+const MAX_REORG_DEPTH: u32 = 100;
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("Archive error")]
+    Archive(#[from] archive::Error),
+    #[error("Database env error")]
+    DbEnv(#[from] EnvError),
+    #[error("Database write error")]
+    DbWrite(#[from] RwTxnError),
+    #[error(
+        "no common ancestor with the tracked {parent_chain:?} headers within {MAX_REORG_DEPTH} blocks of height {height}"
+    )]
+    NoCommonAncestor {
+        parent_chain: ParentChainType,
+        height: u32,
+    },
+    #[error("L1 endpoint error")]
+    ParentChainRpc(#[from] parent_chain_rpc::Error),
+}
+
+/// Backend whose confirmations are derived from the tracked header chain of
+/// its parent chain. Until headers are tracked for the parent chain, or if
+/// they cannot be validated (e.g. Litecoin), the endpoints' confirmations
+/// are used.
+struct HeaderChainBackend {
+    inner: Box<dyn ParentChainBackend>,
+    parent_chain: ParentChainType,
+    env: sneed::Env,
+    archive: Archive,
+}
+
+impl HeaderChainBackend {
+    /// Confirmations of a transaction in the tracked header chain, or
+    /// `None` if no headers are tracked
+    fn tracked_confirmations(
+        &self,
+        tx: &TransactionInfo,
+    ) -> Result<Option<u32>, archive::Error> {
+        let rotxn = self.env.read_txn().map_err(EnvError::from)?;
+        match tx.blockhash {
+            Some(block_hash) => self.archive.l1_confirmations(
+                &rotxn,
+                self.parent_chain,
+                block_hash,
+            ),
+            // Unconfirmed, or the endpoint did not report the block
+            None => Ok(self
+                .archive
+                .try_get_l1_tip(&rotxn, self.parent_chain)?
+                .map(|_| 0)),
+        }
+    }
+
+    /// Returns `true` if the endpoint's merkle proof shows that the
+    /// transaction is included in the block. The block is in the tracked
+    /// header chain, and its hash commits to the merkle root.
+    async fn is_included(
+        &self,
+        txid: &str,
+        block_hash: bitcoin::BlockHash,
+    ) -> bool {
+        let merkle_block = match self.inner.get_tx_merkle_proof(txid).await {
+            Ok(merkle_block) => merkle_block,
+            Err(err) => {
+                tracing::warn!(
+                    parent_chain = ?self.parent_chain,
+                    %txid,
+                    endpoint = %self.inner.endpoint(),
+                    error = %err,
+                    "Failed to get merkle proof for L1 transaction"
+                );
+                return false;
+            }
+        };
+        let Ok(txid) = txid.parse::<bitcoin::Txid>() else {
+            return false;
+        };
+        let mut matches = Vec::new();
+        let mut indexes = Vec::new();
+        merkle_block.header.block_hash() == block_hash
+            && merkle_block
+                .txn
+                .extract_matches(&mut matches, &mut indexes)
+                .is_ok_and(|merkle_root| {
+                    merkle_root == merkle_block.header.merkle_root
+                })
+            && matches.contains(&txid)
+    }
+
+    /// Replace the endpoint's confirmations with those in the tracked
+    /// header chain. Confirmations are only counted once the transaction's
+    /// inclusion in a tracked block is proven by a merkle proof.
+    async fn confirm(
+        &self,
+        tx: TransactionInfo,
+    ) -> Result<TransactionInfo, parent_chain_rpc::Error> {
+        let tracked = self.tracked_confirmations(&tx).map_err(|err| {
+            parent_chain_rpc::Error::TrackedHeaders(Box::new(err))
+        })?;
+        let Some(mut confirmations) = tracked else {
+            return Ok(tx);
+        };
+        if confirmations > 0
+            && let Some(block_hash) = tx.blockhash
+            && !self.is_included(&tx.txid, block_hash).await
+        {
+            tracing::debug!(
+                parent_chain = ?self.parent_chain,
+                txid = %tx.txid,
+                %block_hash,
+                "L1 transaction is not proven to be in its block"
+            );
+            confirmations = 0;
+        }
+        if confirmations != tx.confirmations {
+            tracing::debug!(
+                parent_chain = ?self.parent_chain,
+                txid = %tx.txid,
+                endpoint_confirmations = tx.confirmations,
+                confirmations,
+                "Using confirmations from tracked L1 headers"
+            );
+        }
+        Ok(TransactionInfo {
+            confirmations,
+            ..tx
+        })
+    }
+}
+
+#[async_trait]
+impl ParentChainBackend for HeaderChainBackend {
+    fn endpoint(&self) -> &str {
+        self.inner.endpoint()
+    }
+
+    async fn get_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<TransactionInfo, parent_chain_rpc::Error> {
+        let tx = self.inner.get_transaction(txid).await?;
+        self.confirm(tx).await
+    }
+
+    async fn get_address_history(
+        &self,
+        address: &str,
+    ) -> Result<Vec<TransactionInfo>, parent_chain_rpc::Error> {
+        let history = self.inner.get_address_history(address).await?;
+        let mut confirmed = Vec::with_capacity(history.len());
+        for tx in history {
+            confirmed.push(self.confirm(tx).await?);
+        }
+        Ok(confirmed)
+    }
+
+    async fn get_block_height(&self) -> Result<u32, parent_chain_rpc::Error> {
+        self.inner.get_block_height().await
+    }
+
+    async fn get_header_at_height(
+        &self,
+        height: u32,
+    ) -> Result<bitcoin::block::Header, parent_chain_rpc::Error> {
+        self.inner.get_header_at_height(height).await
+    }
+
+    async fn get_tx_merkle_proof(
+        &self,
+        txid: &str,
+    ) -> Result<bitcoin::MerkleBlock, parent_chain_rpc::Error> {
+        self.inner.get_tx_merkle_proof(txid).await
+    }
+
+    async fn get_headers(
+        &self,
+        heights: Range<u32>,
+    ) -> Result<Vec<bitcoin::block::Header>, parent_chain_rpc::Error> {
+        self.inner.get_headers(heights).await
+    }
+}
+
+/// Backend for the L1 endpoints configured for a parent chain, with
+/// confirmations derived from its tracked header chain
+pub(super) fn load_backend(
+    rpc_config_path: &Path,
+    parent_chain: ParentChainType,
+    l1_status: &Arc<L1Status>,
+    env: &sneed::Env,
+    archive: &Archive,
+) -> Option<Box<dyn ParentChainBackend>> {
+    let inner = load_l1_endpoints_from_path(rpc_config_path, parent_chain)?
+        .build_backend(parent_chain, l1_status.clone())?;
+    Some(Box::new(HeaderChainBackend {
+        inner,
+        parent_chain,
+        env: env.clone(),
+        archive: archive.clone(),
+    }))
+}
+
+/// Highest height, at most `height`, at which the endpoint's best chain
+/// matches the best tracked chain
+async fn find_fork_height(
+    env: &sneed::Env,
+    archive: &Archive,
+    parent_chain: ParentChainType,
+    backend: &dyn ParentChainBackend,
+    height: u32,
+) -> Result<u32, Error> {
+    let min_height = height.saturating_sub(MAX_REORG_DEPTH);
+    // Read the tracked hashes first, so that no read transaction is held
+    // during endpoint requests
+    let tracked = {
+        let rotxn = env.read_txn().map_err(EnvError::from)?;
+        (min_height..=height)
+            .rev()
+            .map(|height| {
+                archive
+                    .try_get_l1_best_block_hash(&rotxn, parent_chain, height)
+                    .map(|block_hash| (height, block_hash))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    for (height, tracked_block_hash) in tracked {
+        // Below the first tracked header
+        let Some(tracked_block_hash) = tracked_block_hash else {
+            break;
+        };
+        let block_hash =
+            backend.get_header_at_height(height).await?.block_hash();
+        if block_hash == tracked_block_hash {
+            return Ok(height);
+        }
+        tracing::debug!(
+            ?parent_chain,
+            height,
+            %block_hash,
+            %tracked_block_hash,
+            "L1 endpoint is on a different branch"
+        );
+    }
+    Err(Error::NoCommonAncestor {
+        parent_chain,
+        height,
+    })
+}
+
+/// Fetch and track new headers from the endpoint's best chain. Returns the
+/// number of headers fetched.
+async fn sync_chain(
+    env: &sneed::Env,
+    archive: &Archive,
+    parent_chain: ParentChainType,
+    backend: &dyn ParentChainBackend,
+) -> Result<u32, Error> {
+    let tip = {
+        let rotxn = env.read_txn().map_err(EnvError::from)?;
+        archive.try_get_l1_tip(&rotxn, parent_chain)?
+    };
+    let endpoint_height = backend.get_block_height().await?;
+    let start_height = match tip {
+        Some(tip) => {
+            find_fork_height(
+                env,
+                archive,
+                parent_chain,
+                backend,
+                tip.height.min(endpoint_height),
+            )
+            .await?
+                + 1
+        }
+        None => endpoint_height.saturating_sub(INITIAL_SYNC_DEPTH),
+    };
+    if start_height > endpoint_height {
+        return Ok(0);
+    }
+    let end_height = endpoint_height
+        .min(start_height.saturating_add(MAX_HEADERS_PER_SYNC - 1));
+    let headers = backend.get_headers(start_height..end_height + 1).await?;
+    let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+    archive.put_l1_headers(&mut rwtxn, parent_chain, start_height, &headers)?;
+    rwtxn.commit().map_err(RwTxnError::from)?;
+    Ok(end_height - start_height + 1)
+}
+
+struct L1HeaderTask {
+    env: sneed::Env,
+    archive: Archive,
+    rpc_config_path: PathBuf,
+    l1_status: Arc<L1Status>,
+    l1_changes: L1ChangeWatcher,
+}
+
+impl L1HeaderTask {
+    /// Sync each configured parent chain whose headers can be validated.
+    /// Returns the chains that were synced.
+    async fn sync(&self) -> Vec<ParentChainType> {
+        let Some(configs) = load_rpc_configs_from_path(&self.rpc_config_path)
+        else {
+            return Vec::new();
+        };
+        let mut synced = Vec::new();
+        for (parent_chain, endpoints) in configs {
+            if parent_chain.spv_max_target().is_none() {
+                continue;
+            }
+            synced.push(parent_chain);
+            let Some(backend) =
+                endpoints.build_backend(parent_chain, self.l1_status.clone())
+            else {
+                continue;
+            };
+            let res = sync_chain(
+                &self.env,
+                &self.archive,
+                parent_chain,
+                backend.as_ref(),
+            )
+            .await;
+            match res {
+                Ok(0) => (),
+                Ok(fetched) => {
+                    tracing::debug!(
+                        ?parent_chain,
+                        fetched,
+                        "Synced L1 headers"
+                    );
+                }
+                Err(err) => {
+                    let err = anyhow::Error::from(err);
+                    tracing::warn!(
+                        ?parent_chain,
+                        "Failed to sync L1 headers: {err:#}"
+                    );
+                }
+            }
+        }
+        synced
+    }
+
+    /// Sync whenever a synced chain announces a new block, or polling is
+    /// due
+    async fn run(mut self) {
+        loop {
+            let synced = self.sync().await;
+            let _: Option<_> = self
+                .l1_changes
+                .wait(synced.iter().copied(), SYNC_INTERVAL, |change| {
+                    matches!(change.notification, L1Notification::Block(_))
+                        && synced.contains(&change.parent_chain)
+                })
+                .await;
+        }
+    }
+}
+
+/// Handle to the task that syncs L1 headers. Aborts the task when the last
+/// handle is dropped.
+#[derive(Clone)]
+pub(super) struct L1HeaderTaskHandle {
+    task: Arc<JoinHandle<()>>,
+}
+
+impl L1HeaderTaskHandle {
+    pub fn new(
+        runtime: &tokio::runtime::Runtime,
+        env: sneed::Env,
+        archive: Archive,
+        rpc_config_path: PathBuf,
+        l1_status: Arc<L1Status>,
+        l1_changes: L1ChangeWatcher,
+    ) -> Self {
+        let task = L1HeaderTask {
+            env,
+            archive,
+            rpc_config_path,
+            l1_status,
+            l1_changes,
+        };
+        let task = runtime.spawn(task.run());
+        Self {
+            task: Arc::new(task),
+        }
+    }
+}
+
+impl Drop for L1HeaderTaskHandle {
+    // If only one reference exists (ie. within self), abort the task.
+    fn drop(&mut self) {
+        // use `Arc::get_mut` since `Arc::into_inner` requires ownership of the
+        // Arc, and cloning would increase the reference count
+        if let Some(task) = Arc::get_mut(&mut self.task) {
+            task.abort()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{CompactTarget, TxMerkleNode, block, hashes::Hash as _};
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::parent_chain_rpc::{MockBackend, ScriptPubKey, Vout};
+
+    fn open_archive(dir: &tempfile::TempDir) -> (sneed::Env, Archive) {
+        let mut env_open_opts = heed::EnvOpenOptions::new();
+        env_open_opts
+            .map_size(16 * 1024 * 1024)
+            .max_dbs(Archive::NUM_DBS);
+        let env =
+            unsafe { sneed::Env::open(&env_open_opts, dir.path()) }.unwrap();
+        let archive = Archive::new(&env).unwrap();
+        (env, archive)
+    }
+
+    /// Serve `len` regtest headers from height 0, in `backend`. The block
+    /// at `tx_height` contains only `txid`.
+    fn mine_headers(
+        backend: &MockBackend,
+        len: u32,
+        (tx_height, txid): (u32, bitcoin::Txid),
+    ) -> Vec<block::Header> {
+        let mut headers: Vec<block::Header> = Vec::new();
+        let mut prev_blockhash = bitcoin::BlockHash::all_zeros();
+        for height in 0..len {
+            let merkle_root = if height == tx_height {
+                TxMerkleNode::from_raw_hash(txid.to_raw_hash())
+            } else {
+                TxMerkleNode::all_zeros()
+            };
+            let mut header = block::Header {
+                version: block::Version::TWO,
+                prev_blockhash,
+                merkle_root,
+                time: 1_700_000_000 + height,
+                bits: CompactTarget::from_consensus(0x207fffff),
+                nonce: 0,
+            };
+            while header.validate_pow(header.target()).is_err() {
+                header.nonce += 1;
+            }
+            backend.insert_header(height, header);
+            prev_blockhash = header.block_hash();
+            headers.push(header);
+        }
+        backend.set_tip_height(len - 1);
+        headers
+    }
+
+    /// Transaction paying 0.1 in the block at height 5
+    fn tx_at_height_5(
+        txid: &str,
+        headers: &[block::Header],
+    ) -> TransactionInfo {
+        TransactionInfo {
+            txid: txid.to_owned(),
+            confirmations: 0,
+            blockheight: Some(5),
+            blockhash: Some(headers[5].block_hash()),
+            vout: vec![Vout {
+                value: 0.1,
+                script_pub_key: ScriptPubKey {
+                    address: None,
+                    addresses: None,
+                    hex: None,
+                },
+            }],
+            vin: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn confirmations_follow_tracked_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, archive) = open_archive(&dir);
+        let parent_chain = ParentChainType::Regtest;
+        let mock = MockBackend::new();
+        let txid = "ab".repeat(32);
+        let headers = mine_headers(&mock, 10, (5, txid.parse().unwrap()));
+        mock.insert_transaction(tx_at_height_5(&txid, &headers));
+        let merkle_proof = |txid: &str| {
+            bitcoin::MerkleBlock::from_header_txids_with_predicate(
+                &headers[5],
+                &[txid.parse().unwrap()],
+                |_| true,
+            )
+        };
+        mock.insert_merkle_proof(&txid, merkle_proof(&txid));
+        // The endpoint claims that these are in the block at height 5, but
+        // serves no proof, or a proof that does not match the block
+        let unproven_txid = "cd".repeat(32);
+        mock.insert_transaction(tx_at_height_5(&unproven_txid, &headers));
+        let mismatched_txid = "ef".repeat(32);
+        mock.insert_transaction(tx_at_height_5(&mismatched_txid, &headers));
+        mock.insert_merkle_proof(
+            &mismatched_txid,
+            merkle_proof(&mismatched_txid),
+        );
+        let backend = HeaderChainBackend {
+            inner: Box::new(mock),
+            parent_chain,
+            env: env.clone(),
+            archive: archive.clone(),
+        };
+        // Endpoint confirmations are used until headers are tracked
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations(&txid)).unwrap(),
+            5
+        );
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations(&unproven_txid))
+                .unwrap(),
+            5
+        );
+
+        // The mock chain is shallower than the initial sync depth, so all
+        // of it is synced
+        assert_eq!(
+            block_on(sync_chain(
+                &env,
+                &archive,
+                parent_chain,
+                backend.inner.as_ref()
+            ))
+            .unwrap(),
+            10
+        );
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations(&txid)).unwrap(),
+            5
+        );
+        for txid in [&unproven_txid, &mismatched_txid] {
+            assert_eq!(
+                block_on(backend.get_transaction_confirmations(txid)).unwrap(),
+                0
+            );
+        }
+        assert_eq!(
+            block_on(sync_chain(
+                &env,
+                &archive,
+                parent_chain,
+                backend.inner.as_ref()
+            ))
+            .unwrap(),
+            0
+        );
+
+        // The endpoint claims a block that is not in the tracked chain
+        let mut tx = block_on(backend.inner.get_transaction(&txid)).unwrap();
+        tx.blockhash = Some(bitcoin::BlockHash::from_byte_array([7; 32]));
+        assert_eq!(block_on(backend.confirm(tx)).unwrap().confirmations, 0);
+    }
+}
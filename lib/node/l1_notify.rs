@@ -0,0 +1,367 @@
+//! Push-based monitoring of swap target chains
+//!
+//! L1 endpoints with a ZMQ publisher announce new blocks and transactions,
+//! so that tasks watching a parent chain can react when something changes
+//! instead of polling. Polling remains the fallback for chains without a
+//! live subscription, and for notifications that are missed.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task::{AbortHandle, JoinHandle, JoinSet},
+};
+
+use crate::{
+    parent_chain_rpc::{
+        self, L1Notification, ZmqSubscription, load_rpc_configs_from_path,
+    },
+    types::ParentChainType,
+};
+
+/// Interval at which the configured publishers are reloaded, and failed
+/// subscriptions are retried
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Changes buffered for each watcher. Watchers that fall further behind
+/// poll instead.
+const CHANGES_CAPACITY: usize = 1024;
+
+/// Interval at which chains with a live subscription are polled, in case a
+/// notification is missed
+pub const SUBSCRIBED_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Change announced by an L1 endpoint of a parent chain
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct L1Change {
+    pub parent_chain: ParentChainType,
+    pub notification: L1Notification,
+}
+
+/// Changes announced by the L1 endpoints of each parent chain
+#[derive(Clone)]
+pub struct L1Changes {
+    sender: broadcast::Sender<L1Change>,
+    /// Number of live subscriptions for each parent chain
+    subscriptions: Arc<Mutex<HashMap<ParentChainType, usize>>>,
+}
+
+impl Default for L1Changes {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANGES_CAPACITY);
+        Self {
+            sender,
+            subscriptions: Arc::default(),
+        }
+    }
+}
+
+impl L1Changes {
+    /// Returns `true` if changes to the parent chain are pushed by at least
+    /// one of its endpoints
+    pub fn is_subscribed(&self, parent_chain: ParentChainType) -> bool {
+        self.subscriptions.lock().contains_key(&parent_chain)
+    }
+
+    /// Watch for changes announced from now on
+    pub fn watch(&self) -> L1ChangeWatcher {
+        L1ChangeWatcher {
+            receiver: self.sender.subscribe(),
+            changes: self.clone(),
+        }
+    }
+
+    fn publish(&self, change: L1Change) {
+        // Fails if nothing is watching
+        let _: Result<usize, _> = self.sender.send(change);
+    }
+
+    /// Count a live subscription for the parent chain, until the returned
+    /// guard is dropped
+    fn live_subscription(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> LiveSubscription {
+        *self.subscriptions.lock().entry(parent_chain).or_default() += 1;
+        LiveSubscription {
+            changes: self.clone(),
+            parent_chain,
+        }
+    }
+}
+
+struct LiveSubscription {
+    changes: L1Changes,
+    parent_chain: ParentChainType,
+}
+
+impl Drop for LiveSubscription {
+    fn drop(&mut self) {
+        let mut subscriptions = self.changes.subscriptions.lock();
+        if let Some(count) = subscriptions.get_mut(&self.parent_chain) {
+            *count -= 1;
+            if *count == 0 {
+                subscriptions.remove(&self.parent_chain);
+            }
+        }
+    }
+}
+
+/// Receiver of [`L1Change`]s, for a task that would otherwise poll L1
+pub struct L1ChangeWatcher {
+    receiver: broadcast::Receiver<L1Change>,
+    changes: L1Changes,
+}
+
+impl L1ChangeWatcher {
+    /// Wait for a change for which `is_relevant` returns `true`, or until
+    /// it is time to poll `chains`: after `poll_interval` if any of them
+    /// has no live subscription, or after [`SUBSCRIBED_POLL_INTERVAL`]
+    /// otherwise. Returns the relevant change, or `None` if it is time to
+    /// poll.
+    pub async fn wait<I, F>(
+        &mut self,
+        chains: I,
+        poll_interval: Duration,
+        mut is_relevant: F,
+    ) -> Option<L1Change>
+    where
+        I: IntoIterator<Item = ParentChainType>,
+        F: FnMut(&L1Change) -> bool,
+    {
+        let mut chains = chains.into_iter().peekable();
+        let all_subscribed = chains.peek().is_some()
+            && chains.all(|chain| self.changes.is_subscribed(chain));
+        let interval = if all_subscribed {
+            SUBSCRIBED_POLL_INTERVAL.max(poll_interval)
+        } else {
+            poll_interval
+        };
+        let deadline = tokio::time::Instant::now() + interval;
+        loop {
+            match tokio::time::timeout_at(deadline, self.receiver.recv()).await
+            {
+                Ok(Ok(change)) => {
+                    if is_relevant(&change) {
+                        return Some(change);
+                    }
+                }
+                // Missed changes may have been relevant
+                Ok(Err(RecvError::Lagged(_))) => return None,
+                Ok(Err(RecvError::Closed)) => {
+                    tokio::time::sleep_until(deadline).await;
+                    return None;
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+/// Forward the notifications of a ZMQ publisher until the subscription
+/// fails
+async fn subscribe(
+    parent_chain: ParentChainType,
+    url: String,
+    changes: L1Changes,
+) {
+    let mut subscription = match ZmqSubscription::connect(&url).await {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            let err = anyhow::Error::from(err);
+            tracing::warn!(
+                ?parent_chain,
+                %url,
+                "Failed to subscribe to L1 notifications; polling instead: {err:#}"
+            );
+            return;
+        }
+    };
+    tracing::info!(?parent_chain, %url, "Subscribed to L1 notifications");
+    let _live = changes.live_subscription(parent_chain);
+    loop {
+        match subscription.recv().await {
+            Ok(notification) => {
+                tracing::trace!(
+                    ?parent_chain,
+                    ?notification,
+                    "Received L1 notification"
+                );
+                changes.publish(L1Change {
+                    parent_chain,
+                    notification,
+                });
+            }
+            Err(
+                err @ parent_chain_rpc::Error::InvalidZmqNotification { .. },
+            ) => {
+                tracing::debug!(
+                    ?parent_chain,
+                    url = %subscription.url(),
+                    "Ignoring L1 notification: {err}"
+                );
+            }
+            Err(err) => {
+                let err = anyhow::Error::from(err);
+                tracing::warn!(
+                    ?parent_chain,
+                    url = %subscription.url(),
+                    "L1 notification subscription failed; polling instead: {err:#}"
+                );
+                return;
+            }
+        }
+    }
+}
+
+struct L1NotifyTask {
+    rpc_config_path: PathBuf,
+    changes: L1Changes,
+}
+
+impl L1NotifyTask {
+    /// ZMQ publishers in the L1 config
+    fn configured_publishers(&self) -> HashSet<(ParentChainType, String)> {
+        let Some(configs) = load_rpc_configs_from_path(&self.rpc_config_path)
+        else {
+            return HashSet::new();
+        };
+        configs
+            .iter()
+            .flat_map(|(parent_chain, endpoints)| {
+                endpoints
+                    .zmq_urls()
+                    .map(|url| (*parent_chain, url.to_owned()))
+            })
+            .collect()
+    }
+
+    async fn run(self) {
+        let mut tasks = JoinSet::new();
+        let mut subscriptions =
+            HashMap::<(ParentChainType, String), AbortHandle>::new();
+        let mut interval = tokio::time::interval(RECONNECT_INTERVAL);
+        loop {
+            interval.tick().await;
+            while tasks.try_join_next().is_some() {}
+            let publishers = self.configured_publishers();
+            subscriptions.retain(|publisher, task| {
+                let keep =
+                    publishers.contains(publisher) && !task.is_finished();
+                if !keep {
+                    task.abort();
+                }
+                keep
+            });
+            for publisher in publishers {
+                if subscriptions.contains_key(&publisher) {
+                    continue;
+                }
+                let (parent_chain, url) = publisher.clone();
+                let task = tasks.spawn(subscribe(
+                    parent_chain,
+                    url,
+                    self.changes.clone(),
+                ));
+                subscriptions.insert(publisher, task);
+            }
+        }
+    }
+}
+
+/// Handle to the task that subscribes to the L1 notifications of the
+/// configured endpoints. Aborts the task when the last handle is dropped.
+#[derive(Clone)]
+pub(super) struct L1NotifyTaskHandle {
+    task: Arc<JoinHandle<()>>,
+}
+
+impl L1NotifyTaskHandle {
+    pub fn new(
+        runtime: &tokio::runtime::Runtime,
+        rpc_config_path: PathBuf,
+        changes: L1Changes,
+    ) -> Self {
+        let task = L1NotifyTask {
+            rpc_config_path,
+            changes,
+        };
+        let task = runtime.spawn(task.run());
+        Self {
+            task: Arc::new(task),
+        }
+    }
+}
+
+impl Drop for L1NotifyTaskHandle {
+    // If only one reference exists (ie. within self), abort the task.
+    fn drop(&mut self) {
+        // use `Arc::get_mut` since `Arc::into_inner` requires ownership of the
+        // Arc, and cloning would increase the reference count
+        if let Some(task) = Arc::get_mut(&mut self.task) {
+            task.abort()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash as _;
+
+    use super::*;
+    use crate::parent_chain_rpc::ZmqPublisher;
+
+    #[test]
+    fn changes_are_pushed_from_configured_publishers() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let rpc_config_path = dir.path().join("l1_rpc_configs.json");
+        let mut publisher = runtime
+            .block_on(ZmqPublisher::bind("tcp://127.0.0.1:0"))
+            .unwrap();
+        let configs = serde_json::json!({
+            "Regtest": {
+                "url": "http://127.0.0.1:18443",
+                "zmq_url": publisher.url(),
+            }
+        });
+        std::fs::write(&rpc_config_path, configs.to_string()).unwrap();
+        let changes = L1Changes::default();
+        let mut watcher = changes.watch();
+        let _task =
+            L1NotifyTaskHandle::new(&runtime, rpc_config_path, changes.clone());
+        let block_hash = bitcoin::BlockHash::from_byte_array([5; 32]);
+        let change = runtime.block_on(async {
+            // Notifications are dropped until the subscription is
+            // established
+            loop {
+                publisher.publish_block(block_hash).await.unwrap();
+                if let Some(change) = watcher
+                    .wait(
+                        [ParentChainType::Regtest],
+                        Duration::from_millis(100),
+                        |_| true,
+                    )
+                    .await
+                {
+                    break change;
+                }
+            }
+        });
+        assert_eq!(
+            change,
+            L1Change {
+                parent_chain: ParentChainType::Regtest,
+                notification: L1Notification::Block(block_hash),
+            }
+        );
+        assert!(changes.is_subscribed(ParentChainType::Regtest));
+        assert!(!changes.is_subscribed(ParentChainType::Signet));
+    }
+}
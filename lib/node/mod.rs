@@ -1,8 +1,9 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::Debug,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     sync::{Arc, Mutex as StdMutex},
+    time::Duration,
 };
 
 use bitcoin::amount::CheckedSum;
@@ -15,24 +16,34 @@ use tonic::transport::Channel;
 use crate::{
     archive::{self, Archive},
     mempool::{self, MemPool},
-    net::{self, Net, Peer},
+    net::{self, BannedPeer, Net, NodeId, NodeIdentity, Peer, RequestQuotas},
+    parent_chain_rpc::{L1Status, ParentChainBackend},
     state::{self, State},
     types::{
         Accumulator, Address, AmountOverflowError, AmountUnderflowError,
         Authorized, AuthorizedTransaction, BlockHash, BmmResult, Body,
         FilledTransaction, GetValue, Header, Network, OutPoint, OutPointKey,
-        Output, SpentOutput, SwapId, Tip, Transaction, Txid, WithdrawalBundle,
+        Output, ParentChainType, SpentOutput, SwapId, Tip, Transaction, TxData,
+        Txid, WithdrawalBundle,
         proto::{self, mainchain},
     },
     util::Watchable,
 };
 
+mod l1_headers;
+mod l1_notify;
 mod mainchain_task;
 mod net_task;
 
+use l1_headers::L1HeaderTaskHandle;
+use l1_notify::L1NotifyTaskHandle;
 use mainchain_task::MainchainTaskHandle;
 
 use self::net_task::NetTaskHandle;
+pub use self::{
+    l1_notify::{L1Change, L1ChangeWatcher, L1Changes},
+    net_task::WriteTxnStats,
+};
 
 #[derive(Debug, thiserror::Error, transitive::Transitive)]
 #[transitive(from(env::error::ReadTxn, EnvError))]
@@ -102,6 +113,12 @@ impl From<state::Error> for Error {
 pub struct NodeConfig<MainchainTransport = Channel> {
     pub datadir: std::path::PathBuf,
     pub bind_addr: SocketAddr,
+    /// Bootstrap peers, connected to at startup to discover the network
+    pub seed_peers: Vec<SocketAddr>,
+    /// If not empty, only peers with these node IDs are connected to
+    pub pinned_peers: HashSet<NodeId>,
+    /// Quotas for requests received from each peer
+    pub request_quotas: RequestQuotas,
     pub cusf_mainchain: mainchain::ValidatorClient<MainchainTransport>,
     pub cusf_mainchain_wallet:
         Option<mainchain::WalletClient<MainchainTransport>>,
@@ -119,6 +136,19 @@ pub struct Node<MainchainTransport = Channel> {
     /// Swap IDs we created that are still pending (mempool). Only creator can cancel those.
     created_pending_swap_ids: Arc<StdMutex<HashSet<SwapId>>>,
     env: sneed::Env,
+    /// Syncs the header chains of swap target chains, if L1 endpoints are
+    /// configured. Held so that the task runs as long as the node.
+    #[allow(dead_code)]
+    l1_header_task: Option<L1HeaderTaskHandle>,
+    /// Changes pushed by the L1 endpoints of swap target chains
+    l1_changes: L1Changes,
+    /// Subscribes to the notifications of L1 endpoints with a ZMQ
+    /// publisher. Held so that the task runs as long as the node.
+    #[allow(dead_code)]
+    l1_notify_task: Option<L1NotifyTaskHandle>,
+    l1_rpc_config_path: Option<std::path::PathBuf>,
+    /// Disagreements between redundant L1 endpoints
+    l1_status: Arc<L1Status>,
     mainchain_task: MainchainTaskHandle,
     mempool: MemPool,
     net: Net,
@@ -210,17 +240,25 @@ where
                 config.cusf_mainchain.clone(),
             );
         tracing::info!("Node::new: MainchainTaskHandle created");
+        let identity = NodeIdentity::load_or_generate(&config.datadir)
+            .map_err(net::Error::from)?;
         tracing::info!(bind_addr = %config.bind_addr, "Node::new: Creating Net");
         let (net, peer_info_rx) = Net::new(
             &env,
             archive.clone(),
+            mempool.clone(),
             config.network,
             state.clone(),
             config.bind_addr,
+            &config.seed_peers,
+            &identity,
+            config.pinned_peers.clone(),
+            config.request_quotas,
         )?;
         tracing::info!("Node::new: Net created");
         tracing::info!("Node::new: Creating NetTaskHandle");
         let wallet_clone = config.wallet.clone();
+        let l1_status = Arc::<L1Status>::default();
         let net_task = NetTaskHandle::new(
             runtime,
             env.clone(),
@@ -232,8 +270,29 @@ where
             peer_info_rx,
             state.clone(),
             wallet_clone,
-            config.l1_rpc_config_path,
+            config.l1_rpc_config_path.clone(),
+            l1_status.clone(),
         );
+        let l1_changes = L1Changes::default();
+        let l1_notify_task =
+            config.l1_rpc_config_path.clone().map(|rpc_config_path| {
+                L1NotifyTaskHandle::new(
+                    runtime,
+                    rpc_config_path,
+                    l1_changes.clone(),
+                )
+            });
+        let l1_header_task =
+            config.l1_rpc_config_path.clone().map(|rpc_config_path| {
+                L1HeaderTaskHandle::new(
+                    runtime,
+                    env.clone(),
+                    archive.clone(),
+                    rpc_config_path,
+                    l1_status.clone(),
+                    l1_changes.watch(),
+                )
+            });
         tracing::info!("Node::new: NetTaskHandle created");
         let cusf_mainchain_wallet = config
             .cusf_mainchain_wallet
@@ -290,6 +349,11 @@ where
             cusf_mainchain_wallet,
             created_pending_swap_ids: Arc::new(StdMutex::new(HashSet::new())),
             env,
+            l1_header_task,
+            l1_changes,
+            l1_notify_task,
+            l1_rpc_config_path: config.l1_rpc_config_path,
+            l1_status,
             mainchain_task,
             mempool,
             net,
@@ -325,6 +389,40 @@ where
         &self.env
     }
 
+    /// Shared L1 clients, with the health of their endpoints, and
+    /// disagreements between redundant L1 endpoints, recorded by the
+    /// backends built with it
+    pub fn l1_status(&self) -> &Arc<L1Status> {
+        &self.l1_status
+    }
+
+    /// Changes pushed by the L1 endpoints of swap target chains, for tasks
+    /// that would otherwise poll them
+    pub fn l1_changes(&self) -> &L1Changes {
+        &self.l1_changes
+    }
+
+    /// Backend for the L1 endpoints configured for a parent chain, with
+    /// confirmations derived from the tracked L1 headers where available.
+    /// Returns `None` if no endpoint is configured for the parent chain.
+    pub fn l1_backend(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> Option<Box<dyn ParentChainBackend>> {
+        l1_headers::load_backend(
+            self.l1_rpc_config_path.as_deref()?,
+            parent_chain,
+            &self.l1_status,
+            &self.env,
+            &self.archive,
+        )
+    }
+
+    /// Time for which block connection has held the write transaction
+    pub fn write_txn_stats(&self) -> WriteTxnStats {
+        self.net_task.write_txn_stats()
+    }
+
     pub fn archive(&self) -> &Archive {
         &self.archive
     }
@@ -414,7 +512,8 @@ where
                 }
             }
         }
-        self.net.push_tx(Default::default(), transaction);
+        self.net
+            .announce_tx(Default::default(), transaction.transaction.txid());
         Ok(())
     }
 
@@ -605,6 +704,8 @@ where
         let mut fee = bitcoin::Amount::ZERO;
         let mut returned_transactions = vec![];
         let mut spent_utxos = HashSet::new();
+        let mut locked_swaps = HashSet::new();
+        let mut tracked_l1_chains = HashSet::new();
         for transaction in transactions {
             let inputs: HashSet<_> =
                 transaction.transaction.inputs.iter().copied().collect();
@@ -623,6 +724,22 @@ where
                     .delete(&mut rwtxn, transaction.transaction.txid())?;
                 continue;
             }
+            // Only one SwapLock per swap can be included; the others become
+            // invalid once it is connected
+            if let TxData::SwapLock { swap_id, .. } =
+                &transaction.transaction.data
+                && !locked_swaps.insert(*swap_id)
+            {
+                continue;
+            }
+            // Likewise, only one TrackL1Headers per parent chain, since the
+            // others no longer advance the tracked L1 headers
+            if let TxData::TrackL1Headers { parent_chain, .. } =
+                &transaction.transaction.data
+                && !tracked_l1_chains.insert(*parent_chain)
+            {
+                continue;
+            }
             let filled_transaction = self
                 .state
                 .fill_authorized_transaction(&rwtxn, transaction)?;
@@ -697,6 +814,31 @@ where
         self.net.get_active_peers()
     }
 
+    /// Ban a peer IP address, disconnecting from any peers at that address
+    pub fn ban_peer(
+        &self,
+        ip: IpAddr,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        self.net
+            .ban_peer(&self.env, ip, duration, "banned manually".to_owned())
+            .map_err(Error::from)
+    }
+
+    /// Returns `true` if the address was banned
+    pub fn unban_peer(&self, ip: IpAddr) -> Result<bool, Error> {
+        self.net.unban_peer(&self.env, ip).map_err(Error::from)
+    }
+
+    pub fn list_banned(&self) -> Result<Vec<BannedPeer>, Error> {
+        self.net.list_banned(&self.env).map_err(Error::from)
+    }
+
+    /// Fingerprint of this node's certificate, by which peers can pin it
+    pub fn node_id(&self) -> NodeId {
+        self.net.node_id()
+    }
+
     pub async fn request_mainchain_ancestor_infos(
         &self,
         block_hash: bitcoin::BlockHash,
@@ -5,8 +5,11 @@ use std::{
     collections::{HashMap, HashSet},
     net::SocketAddr,
     path::PathBuf,
-    sync::Arc,
-    time::Duration,
+    sync::{
+        Arc,
+        atomic::{self, AtomicU64},
+    },
+    time::{Duration, Instant},
 };
 
 use fallible_iterator::{FallibleIterator, IteratorExt};
@@ -19,24 +22,31 @@ use futures::{
     stream,
 };
 use nonempty::NonEmpty;
+use serde::{Deserialize, Serialize};
 use sneed::{DbError, EnvError, RwTxn, RwTxnError, db};
 use thiserror::Error;
 use tokio::task::{self, JoinHandle};
 use tokio_stream::StreamNotifyClose;
 
-use super::mainchain_task::{self, MainchainTaskHandle};
+use super::{
+    l1_headers,
+    mainchain_task::{self, MainchainTaskHandle},
+};
 use crate::{
     archive::{self, Archive},
     mempool::{self, MemPool},
     net::{
-        self, Net, PeerConnectionError, PeerConnectionInfo,
+        self, Misbehavior, Net, PeerConnectionError, PeerConnectionInfo,
         PeerConnectionMailboxError, PeerConnectionMessage, PeerInfoRx,
-        PeerRequest, PeerResponse, PeerStateId, peer_message,
+        PeerRequest, PeerResponse, PeerStateId,
+        misbehavior::{self, MisbehaviorScores, Penalty},
+        peer_message,
     },
-    parent_chain_rpc::RpcConfig,
-    state::{self, State},
+    parent_chain_rpc::{L1Status, ParentChainBackend},
+    state::{self, L1Observations, L1Query, State},
     types::{
-        BmmResult, Body, Header, MerkleRoot, ParentChainType, Tip,
+        AuthorizedTransaction, BmmResult, Body, Header, MerkleRoot,
+        ParentChainType, Tip,
         proto::{self, mainchain},
     },
     util::join_set,
@@ -94,7 +104,7 @@ fn connect_tip_(
     header: &Header,
     body: &Body,
     two_way_peg_data: &mainchain::TwoWayPegData,
-    rpc_config_getter: Option<&dyn Fn(ParentChainType) -> Option<RpcConfig>>,
+    l1_observations: &L1Observations,
     wallet: Option<&crate::wallet::Wallet>,
 ) -> Result<(), Error> {
     let block_hash = header.hash();
@@ -119,7 +129,7 @@ fn connect_tip_(
     let () = state.connect_two_way_peg_data(
         rwtxn,
         two_way_peg_data,
-        rpc_config_getter,
+        l1_observations,
         wallet,
     )?;
     let accumulator = state.get_accumulator(rwtxn)?;
@@ -278,10 +288,12 @@ fn reorg_to_tip(
     mempool: &MemPool,
     state: &State,
     new_tip: Tip,
-    rpc_config_path: Option<&PathBuf>,
+    l1_observations: &L1Observations,
     wallet: Option<&crate::wallet::Wallet>,
+    write_txn_metrics: &WriteTxnMetrics,
 ) -> Result<bool, Error> {
     let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
+    let mut rwtxn_opened = Instant::now();
     let tip_height = state.try_get_height(&rwtxn)?;
     let tip = state
         .try_get_tip(&rwtxn)?
@@ -415,17 +427,6 @@ fn reorg_to_tip(
             }
             two_way_peg_data
         };
-        let rpc_config_getter: Option<
-            Box<dyn Fn(ParentChainType) -> Option<RpcConfig>>,
-        > = rpc_config_path.map(|path| {
-            let p = path.clone();
-            Box::new(move |chain: ParentChainType| {
-                crate::parent_chain_rpc::load_rpc_config_from_path(&p, chain)
-            }) as Box<dyn Fn(ParentChainType) -> Option<RpcConfig>>
-        });
-        let rpc_config_getter: Option<
-            &dyn Fn(ParentChainType) -> Option<RpcConfig>,
-        > = rpc_config_getter.as_ref().map(|b| b.as_ref());
         let () = connect_tip_(
             &mut rwtxn,
             archive,
@@ -434,7 +435,7 @@ fn reorg_to_tip(
             &header,
             &body,
             &two_way_peg_data,
-            rpc_config_getter,
+            l1_observations,
             wallet,
         )?;
         let new_tip_hash = state.try_get_tip(&rwtxn)?.unwrap();
@@ -450,16 +451,163 @@ fn reorg_to_tip(
             continue;
         }
         rwtxn.commit().map_err(RwTxnError::from)?;
+        write_txn_metrics.record(rwtxn_opened.elapsed());
         tracing::info!("synced to tip: {}", new_tip.block_hash);
         rwtxn = env.write_txn().map_err(EnvError::from)?;
+        rwtxn_opened = Instant::now();
     }
     let tip = state.try_get_tip(&rwtxn)?;
     assert_eq!(tip, Some(new_tip.block_hash));
     rwtxn.commit().map_err(RwTxnError::from)?;
+    write_txn_metrics.record(rwtxn_opened.elapsed());
     tracing::info!("synced to tip: {}", new_tip.block_hash);
     Ok(true)
 }
 
+/// Queries for the swaps that may be checked against L1 when reorging to
+/// `new_tip`
+fn l1_queries_for_tip(
+    env: &sneed::Env,
+    archive: &Archive,
+    state: &State,
+    new_tip: Tip,
+) -> Result<Vec<L1Query>, Error> {
+    let rotxn = env.read_txn().map_err(EnvError::from)?;
+    let common_ancestor = match state.try_get_tip(&rotxn)? {
+        Some(tip_hash) => archive.last_common_ancestor(
+            &rotxn,
+            tip_hash,
+            new_tip.block_hash,
+        )?,
+        None => None,
+    };
+    // Bodies to connect, oldest first
+    let mut bodies: Vec<Body> = archive
+        .ancestors(&rotxn, new_tip.block_hash)
+        .take_while(|block_hash| {
+            Ok(common_ancestor
+                .is_none_or(|common_ancestor| *block_hash != common_ancestor))
+        })
+        .map(|block_hash| archive.get_body(&rotxn, block_hash))
+        .collect()?;
+    bodies.reverse();
+    Ok(state.l1_queries(&rotxn, &bodies)?)
+}
+
+/// Fetch L1 observations for the swaps that may be checked when reorging to
+/// `new_tip`. This must be done before the write transaction for the reorg
+/// is opened, since backend requests can be slow.
+async fn fetch_l1_observations(
+    ctxt: &NetTaskContext,
+    new_tip: Tip,
+) -> L1Observations {
+    let Some(rpc_config_path) = ctxt.rpc_config_path.as_ref() else {
+        return L1Observations::default();
+    };
+    let queries = match task::block_in_place(|| {
+        l1_queries_for_tip(&ctxt.env, &ctxt.archive, &ctxt.state, new_tip)
+    }) {
+        Ok(queries) => queries,
+        Err(err) => {
+            let err = anyhow::Error::from(err);
+            tracing::warn!(
+                ?new_tip,
+                "Failed to collect swaps to observe on L1: {err:#}"
+            );
+            return L1Observations::default();
+        }
+    };
+    // One backend per parent chain, shared by its queries
+    let mut backends =
+        HashMap::<ParentChainType, Option<Arc<dyn ParentChainBackend>>>::new();
+    let queries: Vec<_> = queries
+        .into_iter()
+        .filter_map(|query| {
+            let backend = backends
+                .entry(query.parent_chain)
+                .or_insert_with(|| {
+                    l1_headers::load_backend(
+                        rpc_config_path,
+                        query.parent_chain,
+                        &ctxt.l1_status,
+                        &ctxt.env,
+                        &ctxt.archive,
+                    )
+                    .map(Arc::from)
+                })
+                .clone()?;
+            Some((query, backend))
+        })
+        .collect();
+    if queries.is_empty() {
+        return L1Observations::default();
+    }
+    let fetch_start = Instant::now();
+    let l1_observations = L1Observations::fetch(queries).await;
+    tracing::debug!(
+        observed_swaps = l1_observations.len(),
+        elapsed_ms = fetch_start.elapsed().as_millis(),
+        "Fetched L1 observations for swaps"
+    );
+    l1_observations
+}
+
+/// Time for which block connection holds the write transaction, over all
+/// committed reorgs
+#[derive(Debug, Default)]
+pub(super) struct WriteTxnMetrics {
+    count: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+    last_micros: AtomicU64,
+}
+
+/// Snapshot of the time for which block connection held the write
+/// transaction, in microseconds
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    utoipa::ToSchema,
+)]
+pub struct WriteTxnStats {
+    /// Number of committed write transactions
+    pub count: u64,
+    pub last_micros: u64,
+    pub max_micros: u64,
+    pub total_micros: u64,
+}
+
+impl WriteTxnMetrics {
+    fn record(&self, held: Duration) {
+        let micros = u64::try_from(held.as_micros()).unwrap_or(u64::MAX);
+        self.count.fetch_add(1, atomic::Ordering::Relaxed);
+        self.total_micros
+            .fetch_add(micros, atomic::Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, atomic::Ordering::Relaxed);
+        self.last_micros.store(micros, atomic::Ordering::Relaxed);
+        tracing::debug!(
+            held_ms = held.as_millis(),
+            "Committed block connection write transaction"
+        );
+    }
+
+    fn stats(&self) -> WriteTxnStats {
+        let load = |micros: &AtomicU64| micros.load(atomic::Ordering::Relaxed);
+        WriteTxnStats {
+            count: load(&self.count),
+            last_micros: load(&self.last_micros),
+            max_micros: load(&self.max_micros),
+            total_micros: load(&self.total_micros),
+        }
+    }
+}
+
 #[derive(Clone)]
 struct NetTaskContext {
     env: sneed::Env,
@@ -470,8 +618,11 @@ struct NetTaskContext {
     state: State,
     wallet: Option<Arc<crate::wallet::Wallet>>,
     /// Path to L1 RPC config JSON (e.g. l1_rpc_configs.json). When set, Coinshift
-    /// will query the swap target chain on each block connect to update swap state.
+    /// will query the swap target chain before each reorg to update swap state.
     rpc_config_path: Option<PathBuf>,
+    /// Disagreements between redundant L1 endpoints
+    l1_status: Arc<L1Status>,
+    write_txn_metrics: Arc<WriteTxnMetrics>,
 }
 
 /// Message indicating a tip that is ready to reorg to, with the address of the
@@ -514,8 +665,54 @@ struct NetTask {
 }
 
 impl NetTask {
+    /// Record misbehavior by a peer, disconnecting or banning it if its
+    /// score reaches a threshold
+    fn handle_misbehavior(
+        ctxt: &NetTaskContext,
+        misbehavior_scores: &mut MisbehaviorScores,
+        addr: SocketAddr,
+        misbehavior: Misbehavior,
+    ) -> Result<(), Error> {
+        let penalty =
+            misbehavior_scores.record(addr.ip(), misbehavior, Instant::now());
+        tracing::warn!(%addr, %misbehavior, ?penalty, "peer misbehaved");
+        match penalty {
+            Penalty::None => (),
+            Penalty::Disconnect => {
+                let () = ctxt.net.remove_active_peer(addr);
+            }
+            Penalty::Ban => {
+                let () = ctxt.net.ban_peer(
+                    &ctxt.env,
+                    addr.ip(),
+                    misbehavior::DEFAULT_BAN_DURATION,
+                    misbehavior.to_string(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a validated tx received from a peer to the mempool, and announce
+    /// it to all other peers
+    fn add_peer_tx(
+        ctxt: &NetTaskContext,
+        addr: SocketAddr,
+        mut tx: AuthorizedTransaction,
+    ) -> Result<(), Error> {
+        let mut rwtxn = ctxt.env.write_txn().map_err(EnvError::from)?;
+        let () = ctxt.state.regenerate_proof(&rwtxn, &mut tx.transaction)?;
+        ctxt.mempool.put(&mut rwtxn, &tx)?;
+        rwtxn.commit().map_err(RwTxnError::from)?;
+        let () = ctxt
+            .net
+            .announce_tx(HashSet::from_iter([addr]), tx.transaction.txid());
+        Ok(())
+    }
+
     fn handle_response(
         ctxt: &NetTaskContext,
+        misbehavior_scores: &mut MisbehaviorScores,
         // Attempt to switch to a descendant tip once a body has been
         // stored, if all other ancestor bodies are available.
         // Each descendant tip maps to the peers that sent that tip.
@@ -547,8 +744,12 @@ impl NetTask {
                 if header.hash() != block_hash {
                     // Invalid response
                     tracing::warn!(%addr, ?req, ?resp,"Invalid response from peer; unexpected block hash");
-                    let () = ctxt.net.remove_active_peer(addr);
-                    return Ok::<_, Error>(());
+                    return Self::handle_misbehavior(
+                        ctxt,
+                        misbehavior_scores,
+                        addr,
+                        Misbehavior::UnrequestedBlock,
+                    );
                 }
                 {
                     let mut rwtxn =
@@ -781,14 +982,22 @@ impl NetTask {
                 // check that the end header is as requested
                 let Some(end_header) = headers.last() else {
                     tracing::warn!(%addr, ?req, "Invalid response from peer; missing end header");
-                    let () = ctxt.net.remove_active_peer(addr);
-                    return Ok(());
+                    return Self::handle_misbehavior(
+                        ctxt,
+                        misbehavior_scores,
+                        addr,
+                        Misbehavior::InvalidResponse,
+                    );
                 };
                 let end_header_hash = end_header.hash();
                 if end_header_hash != end {
                     tracing::warn!(%addr, ?req, ?end_header,"Invalid response from peer; unexpected end header");
-                    let () = ctxt.net.remove_active_peer(addr);
-                    return Ok(());
+                    return Self::handle_misbehavior(
+                        ctxt,
+                        misbehavior_scores,
+                        addr,
+                        Misbehavior::InvalidResponse,
+                    );
                 }
                 // Must be at least one header due to previous check
                 let start_hash = headers.first().unwrap().prev_side_hash;
@@ -797,8 +1006,12 @@ impl NetTask {
                     && !start.contains(&start_hash)
                 {
                     tracing::warn!(%addr, ?req, %start_hash, "Invalid response from peer; invalid start hash");
-                    let () = ctxt.net.remove_active_peer(addr);
-                    return Ok(());
+                    return Self::handle_misbehavior(
+                        ctxt,
+                        misbehavior_scores,
+                        addr,
+                        Misbehavior::InvalidResponse,
+                    );
                 }
                 // check that the end header height is as expected
                 {
@@ -816,8 +1029,12 @@ impl NetTask {
                     };
                     if end_height != height {
                         tracing::warn!(%addr, ?req, ?start_hash, "Invalid response from peer; invalid end height");
-                        let () = ctxt.net.remove_active_peer(addr);
-                        return Ok(());
+                        return Self::handle_misbehavior(
+                            ctxt,
+                            misbehavior_scores,
+                            addr,
+                            Misbehavior::InvalidResponse,
+                        );
                     }
                 }
                 // check that headers are sequential based on prev_side_hash
@@ -825,8 +1042,12 @@ impl NetTask {
                 for header in &headers {
                     if header.prev_side_hash != prev_side_hash {
                         tracing::warn!(%addr, ?req, ?headers,"Invalid response from peer; non-sequential headers");
-                        let () = ctxt.net.remove_active_peer(addr);
-                        return Ok(());
+                        return Self::handle_misbehavior(
+                            ctxt,
+                            misbehavior_scores,
+                            addr,
+                            Misbehavior::InvalidResponse,
+                        );
                     }
                     prev_side_hash = Some(header.hash());
                 }
@@ -882,16 +1103,101 @@ impl NetTask {
                 ),
                 PeerResponse::TransactionRejected(_),
             ) => Ok(()),
+            (
+                PeerRequest::GetPeers(peer_message::GetPeersRequest {
+                    nonce: _,
+                }),
+                PeerResponse::Peers(peers),
+            ) => {
+                if peers.len() > peer_message::MAX_PEERS_PER_MESSAGE {
+                    // Invalid response
+                    tracing::warn!(%addr, count = peers.len(), "Invalid response from peer; too many addresses");
+                    return Self::handle_misbehavior(
+                        ctxt,
+                        misbehavior_scores,
+                        addr,
+                        Misbehavior::OversizedMessage,
+                    );
+                }
+                let new_addrs = ctxt.net.add_peer_addrs(peers);
+                tracing::debug!(%addr, new_addrs, "learned peer addresses");
+                Ok(())
+            }
+            (
+                PeerRequest::AnnounceTransactions(
+                    peer_message::AnnounceTransactionsRequest { txids: _ },
+                ),
+                PeerResponse::Ack,
+            ) => Ok(()),
+            (
+                PeerRequest::GetTransactions(
+                    peer_message::GetTransactionsRequest { txids },
+                ),
+                PeerResponse::Transactions(transactions),
+            ) => {
+                let requested: HashSet<_> = txids.iter().copied().collect();
+                if transactions.len() > txids.len()
+                    || !transactions
+                        .iter()
+                        .all(|tx| requested.contains(&tx.transaction.txid()))
+                {
+                    // Invalid response
+                    tracing::warn!(%addr, "Invalid response from peer; unrequested transactions");
+                    return Self::handle_misbehavior(
+                        ctxt,
+                        misbehavior_scores,
+                        addr,
+                        Misbehavior::InvalidResponse,
+                    );
+                }
+                for tx in transactions {
+                    let txid = tx.transaction.txid();
+                    let validate_tx_result = {
+                        let rotxn =
+                            ctxt.env.read_txn().map_err(EnvError::from)?;
+                        if ctxt.mempool.try_get(&rotxn, txid)?.is_some() {
+                            continue;
+                        }
+                        ctxt.state.validate_transaction(&rotxn, &tx)
+                    };
+                    if let Err(err) = validate_tx_result {
+                        let is_invalid = err.is_state_independent();
+                        let err = anyhow::Error::from(err);
+                        tracing::debug!(%addr, %txid, "Rejected transaction from peer: {err:#}");
+                        if is_invalid {
+                            let () = Self::handle_misbehavior(
+                                ctxt,
+                                misbehavior_scores,
+                                addr,
+                                Misbehavior::InvalidTransaction,
+                            )?;
+                        }
+                        continue;
+                    }
+                    if let Err(err) = Self::add_peer_tx(ctxt, addr, tx) {
+                        let err = anyhow::Error::from(err);
+                        tracing::warn!(%addr, %txid, "Failed to add transaction from peer: {err:#}");
+                    }
+                }
+                Ok(())
+            }
             (
                 req @ (PeerRequest::GetBlock { .. }
                 | PeerRequest::GetHeaders { .. }
-                | PeerRequest::PushTransaction { .. }),
+                | PeerRequest::PushTransaction { .. }
+                | PeerRequest::GetPeers { .. }
+                | PeerRequest::AnnounceTransactions { .. }
+                | PeerRequest::GetTransactions { .. }),
                 resp,
             ) => {
                 // Invalid response
                 tracing::warn!(%addr, ?req, ?resp,"Invalid response from peer");
-                let () = ctxt.net.remove_active_peer(addr);
-                Ok(())
+                Self::handle_misbehavior(
+                    ctxt,
+                    misbehavior_scores,
+                    addr,
+                    Misbehavior::InvalidResponse,
+                )
             }
         }
     }
@@ -906,6 +1212,8 @@ impl NetTask {
                     <net::error::AcceptConnection as fatality::Split>::Fatal,
                 >,
             ),
+            // Signal to request peer addresses and connect to new peers
+            DiscoverPeers,
             // Forward a mainchain task request, along with the peer that
             // caused the request, and the peer state ID of the request
             ForwardMainchainTaskRequest(
@@ -973,11 +1281,16 @@ impl NetTask {
             });
         let peer_info_stream = StreamNotifyClose::new(self.peer_info_rx)
             .map(MailboxItem::PeerInfo);
+        let discover_peers_stream = stream::unfold((), |()| async {
+            tokio::time::sleep(Net::DISCOVER_PEERS_INTERVAL).await;
+            Some((MailboxItem::DiscoverPeers, ()))
+        });
         let (reconnect_peer_spawner, reconnect_peer_rx) = join_set::new();
         let reconnect_peer_stream = reconnect_peer_rx
             .map(|addr| MailboxItem::ReconnectPeer(addr.unwrap()));
         let mut mailbox_stream = stream::select_all([
             accept_connections.boxed(),
+            discover_peers_stream.boxed(),
             forward_request_stream.boxed(),
             mainchain_task_response_stream.boxed(),
             new_tip_ready_stream.boxed(),
@@ -997,6 +1310,7 @@ impl NetTask {
             mainchain_task::Request,
             HashSet<(SocketAddr, PeerStateId)>,
         >::new();
+        let mut misbehavior_scores = MisbehaviorScores::default();
         while let Some(mailbox_item) = mailbox_stream.next().await {
             tracing::trace!(?mailbox_item, "received new mailbox item");
             match mailbox_item {
@@ -1019,6 +1333,9 @@ impl NetTask {
                         );
                     }
                 },
+                MailboxItem::DiscoverPeers => {
+                    let () = self.ctxt.net.discover_peers(&self.ctxt.env);
+                }
                 MailboxItem::ForwardMainchainTaskRequest(
                     request,
                     peer,
@@ -1094,6 +1411,8 @@ impl NetTask {
                     }
                     let mut guard = OneshotGuard::new(resp_tx);
 
+                    let l1_observations =
+                        fetch_l1_observations(&self.ctxt, new_tip).await;
                     let reorg_result = task::block_in_place(|| {
                         reorg_to_tip(
                             &self.ctxt.env,
@@ -1101,8 +1420,9 @@ impl NetTask {
                             &self.ctxt.mempool,
                             &self.ctxt.state,
                             new_tip,
-                            self.ctxt.rpc_config_path.as_ref(),
+                            &l1_observations,
                             self.ctxt.wallet.as_deref(),
+                            &self.ctxt.write_txn_metrics,
                         )
                     });
                     let reorg_applied = match reorg_result {
@@ -1178,9 +1498,32 @@ impl NetTask {
                             });
                         }
                         PeerConnectionInfo::Error(err) => {
+                            let misbehavior = err.misbehavior();
                             let err = anyhow::anyhow!(err);
                             tracing::error!(%addr, err = format!("{err:#}"), "Peer connection error");
                             let () = self.ctxt.net.remove_active_peer(addr);
+                            if let Some(misbehavior) = misbehavior
+                                && let Err(err) = Self::handle_misbehavior(
+                                    &self.ctxt,
+                                    &mut misbehavior_scores,
+                                    addr,
+                                    misbehavior,
+                                )
+                            {
+                                let err = anyhow::Error::from(err);
+                                tracing::error!(%addr, "Failed to handle peer misbehavior: {err:#}");
+                            }
+                        }
+                        PeerConnectionInfo::Misbehavior(misbehavior) => {
+                            if let Err(err) = Self::handle_misbehavior(
+                                &self.ctxt,
+                                &mut misbehavior_scores,
+                                addr,
+                                misbehavior,
+                            ) {
+                                let err = anyhow::Error::from(err);
+                                tracing::error!(%addr, "Failed to handle peer misbehavior: {err:#}");
+                            }
                         }
                         PeerConnectionInfo::NeedMainchainAncestors {
                             main_hash,
@@ -1217,27 +1560,11 @@ impl NetTask {
                                 );
                             }
                         }
-                        PeerConnectionInfo::NewTransaction(mut new_tx) => {
+                        PeerConnectionInfo::NewTransaction(new_tx) => {
                             // Handle transaction errors gracefully - log but don't crash the net task
-                            if let Err(err) = (|| -> Result<(), Error> {
-                                let mut rwtxn = self
-                                    .ctxt
-                                    .env
-                                    .write_txn()
-                                    .map_err(EnvError::from)?;
-                                let () = self.ctxt.state.regenerate_proof(
-                                    &rwtxn,
-                                    &mut new_tx.transaction,
-                                )?;
-                                self.ctxt.mempool.put(&mut rwtxn, &new_tx)?;
-                                rwtxn.commit().map_err(RwTxnError::from)?;
-                                // broadcast
-                                let () = self.ctxt.net.push_tx(
-                                    HashSet::from_iter([addr]),
-                                    new_tx,
-                                );
-                                Ok(())
-                            })() {
+                            if let Err(err) =
+                                Self::add_peer_tx(&self.ctxt, addr, new_tx)
+                            {
                                 let err = anyhow::Error::from(err);
                                 tracing::error!(
                                     %addr,
@@ -1259,6 +1586,7 @@ impl NetTask {
                                 tokio::task::block_in_place(|| {
                                     Self::handle_response(
                                         &self.ctxt,
+                                        &mut misbehavior_scores,
                                         &mut descendant_tips,
                                         &self.new_tip_ready_tx,
                                         addr,
@@ -1312,6 +1640,7 @@ pub(super) struct NetTaskHandle {
     /// An optional oneshot sender can be used receive the result of attempting
     /// to reorg to the new tip, on the corresponding oneshot receiver.
     new_tip_ready_tx: UnboundedSender<NewTipReadyMessage>,
+    write_txn_metrics: Arc<WriteTxnMetrics>,
 }
 
 impl NetTaskHandle {
@@ -1328,6 +1657,7 @@ impl NetTaskHandle {
         state: State,
         wallet: Option<Arc<crate::wallet::Wallet>>,
         rpc_config_path: Option<PathBuf>,
+        l1_status: Arc<L1Status>,
     ) -> Self {
         let ctxt = NetTaskContext {
             env,
@@ -1338,7 +1668,10 @@ impl NetTaskHandle {
             state,
             wallet,
             rpc_config_path,
+            l1_status,
+            write_txn_metrics: Arc::default(),
         };
+        let write_txn_metrics = ctxt.write_txn_metrics.clone();
         let (
             forward_mainchain_task_request_tx,
             forward_mainchain_task_request_rx,
@@ -1362,9 +1695,15 @@ impl NetTaskHandle {
         NetTaskHandle {
             task: Arc::new(task),
             new_tip_ready_tx,
+            write_txn_metrics,
         }
     }
 
+    /// Time for which block connection has held the write transaction
+    pub fn write_txn_stats(&self) -> WriteTxnStats {
+        self.write_txn_metrics.stats()
+    }
+
     /// Push a tip that is ready to reorg to, and await successful application.
     /// A result of Ok(true) indicates that the tip was applied and reorged
     /// to successfully.
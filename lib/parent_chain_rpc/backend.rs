@@ -0,0 +1,236 @@
+//! Parent chain backends: the L1 data sources that swaps are checked against
+
+use std::ops::Range;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{Error, ParentChainRpcClient, TransactionInfo};
+use crate::types::L1MatchPolicy;
+
+/// API served by an L1 endpoint
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    /// Bitcoin Core compatible JSON-RPC
+    #[default]
+    BitcoinCore,
+    /// Esplora REST API
+    Esplora,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unknown L1 backend `{0}`: expected `bitcoin_core` or `esplora`")]
+pub struct ParseBackendKindError(String);
+
+impl std::str::FromStr for BackendKind {
+    type Err = ParseBackendKindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bitcoin_core" => Ok(Self::BitcoinCore),
+            "esplora" => Ok(Self::Esplora),
+            _ => Err(ParseBackendKindError(s.to_owned())),
+        }
+    }
+}
+
+/// L1 queries needed to detect and confirm swap payments.
+///
+/// Txids are hex strings in RPC byte order, as returned by the L1 node.
+#[async_trait]
+pub trait ParentChainBackend: Send + Sync {
+    /// Endpoint queried by the backend, for logging
+    fn endpoint(&self) -> &str;
+
+    /// Get transaction by ID
+    async fn get_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<TransactionInfo, Error>;
+
+    /// Get confirmations for a transaction by ID
+    async fn get_transaction_confirmations(
+        &self,
+        txid: &str,
+    ) -> Result<u32, Error> {
+        Ok(self.get_transaction(txid).await?.confirmations)
+    }
+
+    /// Get transactions paying to an address, including unconfirmed ones
+    async fn get_address_history(
+        &self,
+        address: &str,
+    ) -> Result<Vec<TransactionInfo>, Error>;
+
+    /// Get current block height
+    async fn get_block_height(&self) -> Result<u32, Error>;
+
+    /// Get the header of the block at a height in the endpoint's best chain
+    async fn get_header_at_height(
+        &self,
+        height: u32,
+    ) -> Result<bitcoin::block::Header, Error>;
+
+    /// Get a merkle proof that a confirmed transaction is included in its
+    /// block, as returned by `gettxoutproof`
+    async fn get_tx_merkle_proof(
+        &self,
+        txid: &str,
+    ) -> Result<bitcoin::MerkleBlock, Error>;
+
+    /// Get the headers at a range of heights in the endpoint's best chain
+    async fn get_headers(
+        &self,
+        heights: Range<u32>,
+    ) -> Result<Vec<bitcoin::block::Header>, Error> {
+        let mut headers = Vec::with_capacity(heights.len());
+        for height in heights {
+            headers.push(self.get_header_at_height(height).await?);
+        }
+        Ok(headers)
+    }
+
+    /// Find transactions to an address that pay an amount under `policy`.
+    /// Returns (sender_address, tx_info).
+    async fn find_transactions_by_address_and_amount(
+        &self,
+        address: &str,
+        amount_sats: u64,
+        policy: L1MatchPolicy,
+    ) -> Result<Vec<(String, TransactionInfo)>, Error> {
+        find_transactions_by_address(self, address, |tx| {
+            tx.pays_amount_to(address, amount_sats, policy)
+        })
+        .await
+    }
+
+    /// Find transactions paying more than zero and at most `max_sats` in
+    /// total to an address.
+    /// Returns (sender_address, tx_info).
+    async fn find_transactions_by_address_up_to_amount(
+        &self,
+        address: &str,
+        max_sats: u64,
+    ) -> Result<Vec<(String, TransactionInfo)>, Error> {
+        find_transactions_by_address(self, address, |tx| {
+            (1..=max_sats).contains(&tx.value_to(address))
+        })
+        .await
+    }
+}
+
+/// Find transactions to an address for which `is_match` returns `true`.
+/// Returns (sender_address, tx_info).
+async fn find_transactions_by_address<B, F>(
+    backend: &B,
+    address: &str,
+    is_match: F,
+) -> Result<Vec<(String, TransactionInfo)>, Error>
+where
+    B: ParentChainBackend + ?Sized,
+    F: Fn(&TransactionInfo) -> bool,
+{
+    let txs: Vec<TransactionInfo> = backend
+        .get_address_history(address)
+        .await?
+        .into_iter()
+        .filter(|tx| is_match(tx))
+        .collect();
+    let mut matches = Vec::with_capacity(txs.len());
+    for tx in txs {
+        let sender = sender_address(backend, &tx)
+            .await
+            .unwrap_or_else(|| "unknown".to_string());
+        matches.push((sender, tx));
+    }
+    Ok(matches)
+}
+
+/// Address spent by the first input of a transaction, if it can be found
+async fn sender_address<B>(backend: &B, tx: &TransactionInfo) -> Option<String>
+where
+    B: ParentChainBackend + ?Sized,
+{
+    let vin = tx.vin.first()?;
+    let (Some(input_txid), Some(input_vout)) = (&vin.txid, vin.vout) else {
+        return None;
+    };
+    let input_tx = backend.get_transaction(input_txid).await.ok()?;
+    let script_pub_key =
+        &input_tx.vout.get(input_vout as usize)?.script_pub_key;
+    script_pub_key.address.clone().or_else(|| {
+        script_pub_key
+            .addresses
+            .as_ref()
+            .and_then(|addrs| addrs.first().cloned())
+    })
+}
+
+#[async_trait]
+impl ParentChainBackend for ParentChainRpcClient {
+    fn endpoint(&self) -> &str {
+        self.endpoint.url()
+    }
+
+    async fn get_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<TransactionInfo, Error> {
+        ParentChainRpcClient::get_transaction(self, txid).await
+    }
+
+    /// Relies on the node's wallet, via `listunspent`. The transactions are
+    /// fetched in one batch.
+    async fn get_address_history(
+        &self,
+        address: &str,
+    ) -> Result<Vec<TransactionInfo>, Error> {
+        let txids = self.list_transactions(address).await?;
+        let mut history = Vec::new();
+        for (txid, res) in
+            txids.iter().zip(self.get_transactions(&txids).await?)
+        {
+            match res {
+                Ok(tx) => history.push(tx),
+                Err(Error::TransactionNotFound) => {
+                    // Transaction might have been spent, skip it
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("Error getting transaction {}: {}", txid, e);
+                    continue;
+                }
+            }
+        }
+        Ok(history)
+    }
+
+    async fn get_block_height(&self) -> Result<u32, Error> {
+        ParentChainRpcClient::get_block_height(self).await
+    }
+
+    async fn get_header_at_height(
+        &self,
+        height: u32,
+    ) -> Result<bitcoin::block::Header, Error> {
+        self.get_block_header(&self.get_block_hash(height).await?)
+            .await
+    }
+
+    async fn get_tx_merkle_proof(
+        &self,
+        txid: &str,
+    ) -> Result<bitcoin::MerkleBlock, Error> {
+        self.get_tx_out_proof(txid).await
+    }
+
+    async fn get_headers(
+        &self,
+        heights: Range<u32>,
+    ) -> Result<Vec<bitcoin::block::Header>, Error> {
+        self.get_block_headers(heights).await
+    }
+}
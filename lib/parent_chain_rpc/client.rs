@@ -0,0 +1,383 @@
+//! Shared HTTP clients for L1 endpoints
+//!
+//! The backends built for a parent chain share one connection pool. Requests
+//! that fail in transit are retried with exponential backoff, and each
+//! endpoint has a circuit breaker: after repeated failures the endpoint is
+//! marked unhealthy, and requests to it fail fast until it is probed again.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use parking_lot::Mutex;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use super::Error;
+
+/// Timeout for a single HTTP request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Attempts made for a request, including the first one
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry, doubled for each further retry
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+const MAX_BACKOFF: Duration = Duration::from_secs(4);
+
+/// Consecutive failed requests after which an endpoint is marked unhealthy
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// Time for which requests to an unhealthy endpoint fail fast, before a
+/// request is let through to probe it
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+
+/// State of the circuit breaker of an L1 endpoint
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests are sent
+    Closed,
+    /// The endpoint is unhealthy, and requests fail fast
+    Open,
+    /// The endpoint was unhealthy, and the next request probes it
+    HalfOpen,
+}
+
+/// Health of an L1 endpoint
+#[derive(Clone, Debug, Deserialize, Serialize, utoipa::ToSchema)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub healthy: bool,
+    pub circuit: CircuitState,
+    pub consecutive_failures: u32,
+    /// Unix time of the last successful request, in seconds
+    pub last_success: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    /// Set while the circuit is open
+    opened_at: Option<Instant>,
+    /// Set while a request probes the endpoint. A probe that has not
+    /// finished after [`OPEN_DURATION`] is presumed abandoned.
+    probing_since: Option<Instant>,
+    last_success: Option<u64>,
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Returns an error if requests to the endpoint should fail fast
+    fn check(&self, url: &str) -> Result<(), Error> {
+        let mut state = self.state.lock();
+        let Some(opened_at) = state.opened_at else {
+            return Ok(());
+        };
+        let open_for = opened_at.elapsed();
+        let can_probe = open_for >= OPEN_DURATION
+            && state
+                .probing_since
+                .is_none_or(|since| since.elapsed() >= OPEN_DURATION);
+        if can_probe {
+            state.probing_since = Some(Instant::now());
+            return Ok(());
+        }
+        Err(Error::EndpointUnhealthy {
+            url: url.to_owned(),
+            retry_in: OPEN_DURATION.saturating_sub(open_for),
+        })
+    }
+
+    fn record_success(&self, url: &str) {
+        let mut state = self.state.lock();
+        if state.opened_at.is_some() {
+            tracing::info!(%url, "L1 endpoint is healthy again");
+        }
+        state.consecutive_failures = 0;
+        state.opened_at = None;
+        state.probing_since = None;
+        state.last_success = Some(unix_time());
+    }
+
+    fn record_failure(&self, url: &str, err: &Error) {
+        let mut state = self.state.lock();
+        state.consecutive_failures += 1;
+        state.last_error = Some(err.to_string());
+        if state.probing_since.take().is_some() {
+            state.opened_at = Some(Instant::now());
+        } else if state.opened_at.is_none()
+            && state.consecutive_failures >= FAILURE_THRESHOLD
+        {
+            tracing::warn!(
+                %url,
+                consecutive_failures = state.consecutive_failures,
+                "L1 endpoint marked unhealthy: {err}"
+            );
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    fn health(&self, url: &str) -> EndpointHealth {
+        let state = self.state.lock();
+        let circuit = match state.opened_at {
+            None => CircuitState::Closed,
+            Some(_) if state.probing_since.is_some() => CircuitState::HalfOpen,
+            Some(opened_at) if opened_at.elapsed() >= OPEN_DURATION => {
+                CircuitState::HalfOpen
+            }
+            Some(_) => CircuitState::Open,
+        };
+        EndpointHealth {
+            url: url.to_owned(),
+            healthy: circuit == CircuitState::Closed,
+            circuit,
+            consecutive_failures: state.consecutive_failures,
+            last_success: state.last_success,
+            last_error: state.last_error.clone(),
+        }
+    }
+}
+
+/// Returns `true` if a failed request may succeed if it is retried
+fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::Http(err) => !err.is_builder(),
+        Error::HttpStatus { status, .. } => {
+            status.is_server_error()
+                || *status == StatusCode::REQUEST_TIMEOUT
+                || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Default)]
+struct L1ClientInner {
+    /// Built on first use, so that failing to build it is reported as a
+    /// request error
+    http: Mutex<Option<reqwest::Client>>,
+    breakers: Mutex<HashMap<String, Arc<CircuitBreaker>>>,
+}
+
+/// HTTP client shared by the backends of a parent chain, with the health of
+/// each endpoint that it has been used for. Cloning is cheap.
+#[derive(Clone, Debug, Default)]
+pub struct L1Client {
+    inner: Arc<L1ClientInner>,
+}
+
+impl L1Client {
+    fn http(&self) -> Result<reqwest::Client, Error> {
+        let mut http = self.inner.http.lock();
+        if let Some(http) = &*http {
+            return Ok(http.clone());
+        }
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        *http = Some(client.clone());
+        Ok(client)
+    }
+
+    fn breaker(&self, url: &str) -> Arc<CircuitBreaker> {
+        self.inner
+            .breakers
+            .lock()
+            .entry(url.to_owned())
+            .or_default()
+            .clone()
+    }
+
+    /// Requests to the endpoint at `url`
+    pub(super) fn endpoint(&self, url: &str) -> Endpoint {
+        Endpoint {
+            client: self.clone(),
+            url: url.to_owned(),
+            breaker: self.breaker(url),
+        }
+    }
+
+    /// Health of the endpoint at `url`. Endpoints that have not been
+    /// requested yet are healthy.
+    pub fn health(&self, url: &str) -> EndpointHealth {
+        self.breaker(url).health(url)
+    }
+}
+
+/// Requests to an L1 endpoint, through the client of its parent chain
+pub(super) struct Endpoint {
+    client: L1Client,
+    url: String,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl Endpoint {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn attempt<F, A>(
+        http: &reqwest::Client,
+        request: &F,
+        answered: &A,
+    ) -> Result<(StatusCode, String), Error>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+        A: Fn(StatusCode, &str) -> bool,
+    {
+        let response = request(http).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if answered(status, &body) {
+            Ok((status, body))
+        } else {
+            Err(Error::HttpStatus { status, body })
+        }
+    }
+
+    /// Send the request built by `request`, retrying transient failures.
+    /// `answered` returns `true` if a response was served by the endpoint
+    /// as intended, even if it reports an error (e.g. an unknown
+    /// transaction). Other responses count as failures of the endpoint.
+    pub async fn send<F, A>(
+        &self,
+        request: F,
+        answered: A,
+    ) -> Result<(StatusCode, String), Error>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+        A: Fn(StatusCode, &str) -> bool,
+    {
+        let () = self.breaker.check(&self.url)?;
+        let http = self.client.http()?;
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 1;
+        let res = loop {
+            match Self::attempt(&http, &request, &answered).await {
+                Err(err) if attempt < MAX_ATTEMPTS && is_transient(&err) => {
+                    tracing::debug!(
+                        url = %self.url,
+                        attempt,
+                        "L1 request failed, retrying in {backoff:?}: {err}"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    attempt += 1;
+                }
+                res => break res,
+            }
+        };
+        match &res {
+            Ok(_) => self.breaker.record_success(&self.url),
+            Err(err) => self.breaker.record_failure(&self.url, err),
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_error() -> Error {
+        Error::HttpStatus {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn breaker_opens_after_repeated_failures() {
+        let url = "http://127.0.0.1:18443";
+        let breaker = CircuitBreaker::default();
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            breaker.check(url).unwrap();
+            breaker.record_failure(url, &server_error());
+        }
+        assert!(breaker.health(url).healthy);
+        breaker.record_failure(url, &server_error());
+        let health = breaker.health(url);
+        assert_eq!(health.circuit, CircuitState::Open);
+        assert_eq!(health.consecutive_failures, FAILURE_THRESHOLD);
+        assert!(matches!(
+            breaker.check(url),
+            Err(Error::EndpointUnhealthy { .. })
+        ));
+    }
+
+    #[test]
+    fn breaker_closes_after_successful_probe() {
+        let url = "http://127.0.0.1:18443";
+        let breaker = CircuitBreaker::default();
+        for _ in 0..FAILURE_THRESHOLD {
+            breaker.record_failure(url, &server_error());
+        }
+        // Open for long enough that a probe is let through
+        breaker.state.lock().opened_at =
+            Instant::now().checked_sub(OPEN_DURATION);
+        assert_eq!(breaker.health(url).circuit, CircuitState::HalfOpen);
+        breaker.check(url).unwrap();
+        // Only one probe at a time
+        assert!(breaker.check(url).is_err());
+        breaker.record_failure(url, &server_error());
+        assert_eq!(breaker.health(url).circuit, CircuitState::Open);
+        breaker.state.lock().opened_at =
+            Instant::now().checked_sub(OPEN_DURATION);
+        breaker.check(url).unwrap();
+        breaker.record_success(url);
+        let health = breaker.health(url);
+        assert!(health.healthy);
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(health.last_success.is_some());
+    }
+
+    #[test]
+    fn only_transient_failures_are_retried() {
+        assert!(is_transient(&server_error()));
+        assert!(!is_transient(&Error::HttpStatus {
+            status: StatusCode::UNAUTHORIZED,
+            body: String::new(),
+        }));
+        assert!(!is_transient(&Error::TransactionNotFound));
+    }
+
+    #[test]
+    fn failed_request_counts_once() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        // Nothing listens on port 9 (discard)
+        let url = "http://127.0.0.1:9";
+        let client = L1Client::default();
+        let res = runtime.block_on(
+            client
+                .endpoint(url)
+                .send(|http| http.get(url), |status, _| status.is_success()),
+        );
+        assert!(matches!(res, Err(Error::Http(_))));
+        let health = client.health(url);
+        assert!(health.healthy);
+        assert_eq!(health.consecutive_failures, 1);
+        assert!(health.last_error.is_some());
+        assert_eq!(
+            client.health("http://127.0.0.1:18443").consecutive_failures,
+            0
+        );
+    }
+}
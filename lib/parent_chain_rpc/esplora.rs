@@ -0,0 +1,273 @@
+//! Esplora REST API backend
+//!
+//! Unlike the Bitcoin Core backend, address history comes from the Esplora
+//! index, so it does not depend on the L1 node's wallet.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{
+    Endpoint, Error, L1Client, ParentChainBackend, ScriptPubKey,
+    TransactionInfo, Vin, Vout,
+};
+
+/// Number of confirmed transactions per page of address history
+const CHAIN_TXS_PAGE_SIZE: usize = 25;
+
+#[derive(Debug, Deserialize)]
+struct EsploraTx {
+    txid: String,
+    vin: Vec<EsploraVin>,
+    vout: Vec<EsploraVout>,
+    status: EsploraTxStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraVin {
+    txid: String,
+    vout: u32,
+    #[serde(default)]
+    is_coinbase: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraVout {
+    scriptpubkey: String,
+    scriptpubkey_address: Option<String>,
+    value: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraTxStatus {
+    confirmed: bool,
+    block_height: Option<u32>,
+    block_hash: Option<bitcoin::BlockHash>,
+}
+
+impl EsploraTx {
+    fn into_transaction_info(self, tip_height: u32) -> TransactionInfo {
+        let (blockheight, blockhash) = if self.status.confirmed {
+            (self.status.block_height, self.status.block_hash)
+        } else {
+            (None, None)
+        };
+        let confirmations = blockheight
+            .filter(|height| *height <= tip_height)
+            .map_or(0, |height| tip_height - height + 1);
+        let vin = self
+            .vin
+            .into_iter()
+            .map(|vin| {
+                if vin.is_coinbase {
+                    Vin {
+                        txid: None,
+                        vout: None,
+                    }
+                } else {
+                    Vin {
+                        txid: Some(vin.txid),
+                        vout: Some(vin.vout),
+                    }
+                }
+            })
+            .collect();
+        let vout = self
+            .vout
+            .into_iter()
+            .map(|vout| Vout {
+                value: bitcoin::Amount::from_sat(vout.value).to_btc(),
+                script_pub_key: ScriptPubKey {
+                    address: vout.scriptpubkey_address,
+                    addresses: None,
+                    hex: Some(vout.scriptpubkey),
+                },
+            })
+            .collect();
+        TransactionInfo {
+            txid: self.txid,
+            confirmations,
+            blockheight,
+            blockhash,
+            vout,
+            vin,
+        }
+    }
+}
+
+/// Client for an Esplora REST API (e.g. `https://blockstream.info/api`)
+pub struct EsploraClient {
+    base_url: String,
+    endpoint: Endpoint,
+}
+
+impl EsploraClient {
+    /// Client for the API at `base_url`, sending requests through `client`
+    pub fn new(base_url: String, client: &L1Client) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let endpoint = client.endpoint(&base_url);
+        Self { base_url, endpoint }
+    }
+
+    /// GET a path relative to the base URL. A 404 response is reported as
+    /// [`Error::TransactionNotFound`], since the only lookups by key are
+    /// transaction lookups.
+    async fn get_text(&self, path: &str) -> Result<String, Error> {
+        let url = format!("{}{path}", self.base_url);
+        tracing::debug!(url = %url, "Making Esplora request");
+        let (status, body) = self
+            .endpoint
+            .send(
+                |http| http.get(&url),
+                // Client errors are caused by the request, e.g. an unknown
+                // transaction
+                |status, _| {
+                    !status.is_server_error()
+                        && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+                },
+            )
+            .await?;
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::TransactionNotFound);
+        }
+        if !status.is_success() {
+            tracing::error!(
+                url = %url,
+                status = %status,
+                response_body = %body,
+                "Esplora returned error"
+            );
+            return Err(Error::Rpc(format!("{status}: {body}")));
+        }
+        Ok(body)
+    }
+
+    async fn get_json<T>(&self, path: &str) -> Result<T, Error>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        Ok(serde_json::from_str(&self.get_text(path).await?)?)
+    }
+
+    /// Get the hash of the block at a height in the best chain
+    pub async fn get_block_hash(
+        &self,
+        height: u32,
+    ) -> Result<bitcoin::BlockHash, Error> {
+        self.get_text(&format!("/block-height/{height}"))
+            .await?
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidResponse)
+    }
+}
+
+#[async_trait]
+impl ParentChainBackend for EsploraClient {
+    fn endpoint(&self) -> &str {
+        &self.base_url
+    }
+
+    async fn get_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<TransactionInfo, Error> {
+        let tx: EsploraTx = self.get_json(&format!("/tx/{txid}")).await?;
+        let tip_height = self.get_block_height().await?;
+        Ok(tx.into_transaction_info(tip_height))
+    }
+
+    /// Pages through the address's confirmed history, after the first page
+    /// which also includes mempool transactions
+    async fn get_address_history(
+        &self,
+        address: &str,
+    ) -> Result<Vec<TransactionInfo>, Error> {
+        let tip_height = self.get_block_height().await?;
+        let mut history = Vec::new();
+        let mut page: Vec<EsploraTx> =
+            self.get_json(&format!("/address/{address}/txs")).await?;
+        loop {
+            let confirmed: Vec<&EsploraTx> =
+                page.iter().filter(|tx| tx.status.confirmed).collect();
+            let last_seen = match confirmed.last() {
+                Some(last) if confirmed.len() >= CHAIN_TXS_PAGE_SIZE => {
+                    Some(last.txid.clone())
+                }
+                _ => None,
+            };
+            history.extend(
+                page.into_iter()
+                    .map(|tx| tx.into_transaction_info(tip_height)),
+            );
+            let Some(last_seen) = last_seen else {
+                break;
+            };
+            page = self
+                .get_json(&format!("/address/{address}/txs/chain/{last_seen}"))
+                .await?;
+        }
+        Ok(history)
+    }
+
+    async fn get_block_height(&self) -> Result<u32, Error> {
+        self.get_text("/blocks/tip/height")
+            .await?
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidResponse)
+    }
+
+    async fn get_header_at_height(
+        &self,
+        height: u32,
+    ) -> Result<bitcoin::block::Header, Error> {
+        let block_hash = self.get_block_hash(height).await?;
+        let header_hex = self
+            .get_text(&format!("/block/{block_hash}/header"))
+            .await?;
+        Ok(bitcoin::consensus::encode::deserialize_hex(
+            header_hex.trim(),
+        )?)
+    }
+
+    async fn get_tx_merkle_proof(
+        &self,
+        txid: &str,
+    ) -> Result<bitcoin::MerkleBlock, Error> {
+        let merkle_block_hex = self
+            .get_text(&format!("/tx/{txid}/merkleblock-proof"))
+            .await?;
+        Ok(bitcoin::consensus::encode::deserialize_hex(
+            merkle_block_hex.trim(),
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn esplora_tx_converts_to_transaction_info() {
+        let tx: EsploraTx = serde_json::from_value(serde_json::json!({
+            "txid": "aa".repeat(32),
+            "vin": [{
+                "txid": "bb".repeat(32),
+                "vout": 1,
+                "is_coinbase": false
+            }],
+            "vout": [{
+                "scriptpubkey": "0014".to_string() + &"cc".repeat(20),
+                "scriptpubkey_address": "tb1qexample",
+                "value": 29_000_000
+            }],
+            "status": { "confirmed": true, "block_height": 100 }
+        }))
+        .unwrap();
+        let info = tx.into_transaction_info(105);
+        assert_eq!(info.confirmations, 6);
+        assert_eq!(info.blockheight, Some(100));
+        assert_eq!(info.vin[0].vout, Some(1));
+        assert_eq!(info.value_to("tb1qexample"), 29_000_000);
+    }
+}
@@ -0,0 +1,317 @@
+//! In-memory parent chain backend, for tests
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+use super::{Error, ParentChainBackend, TransactionInfo};
+
+#[derive(Debug, Default)]
+struct MockChain {
+    tip_height: u32,
+    headers: HashMap<u32, bitcoin::block::Header>,
+    transactions: HashMap<String, TransactionInfo>,
+    merkle_proofs: HashMap<String, bitcoin::MerkleBlock>,
+}
+
+/// Parent chain backend serving transactions from memory.
+///
+/// Confirmations are derived from each transaction's `blockheight` and the
+/// tip height, so that the chain can be advanced with
+/// [`MockBackend::set_tip_height`].
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    chain: Mutex<MockChain>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_tip_height(&self, height: u32) {
+        self.chain.lock().tip_height = height;
+    }
+
+    /// Insert a transaction, replacing any with the same txid. It is
+    /// unconfirmed if `blockheight` is `None`.
+    pub fn insert_transaction(&self, tx: TransactionInfo) {
+        self.chain.lock().transactions.insert(tx.txid.clone(), tx);
+    }
+
+    pub fn remove_transaction(&self, txid: &str) -> Option<TransactionInfo> {
+        self.chain.lock().transactions.remove(txid)
+    }
+
+    /// Set the header at a height, replacing any existing header
+    pub fn insert_header(&self, height: u32, header: bitcoin::block::Header) {
+        self.chain.lock().headers.insert(height, header);
+    }
+
+    /// Set the merkle proof served for a transaction
+    pub fn insert_merkle_proof(
+        &self,
+        txid: &str,
+        merkle_block: bitcoin::MerkleBlock,
+    ) {
+        self.chain
+            .lock()
+            .merkle_proofs
+            .insert(txid.to_owned(), merkle_block);
+    }
+}
+
+impl MockChain {
+    fn transaction_info(&self, tx: &TransactionInfo) -> TransactionInfo {
+        let confirmations = tx
+            .blockheight
+            .filter(|height| *height <= self.tip_height)
+            .map_or(0, |height| self.tip_height - height + 1);
+        TransactionInfo {
+            confirmations,
+            ..tx.clone()
+        }
+    }
+}
+
+#[async_trait]
+impl ParentChainBackend for MockBackend {
+    fn endpoint(&self) -> &str {
+        "mock"
+    }
+
+    async fn get_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<TransactionInfo, Error> {
+        let chain = self.chain.lock();
+        let tx = chain
+            .transactions
+            .get(txid)
+            .ok_or(Error::TransactionNotFound)?;
+        Ok(chain.transaction_info(tx))
+    }
+
+    async fn get_address_history(
+        &self,
+        address: &str,
+    ) -> Result<Vec<TransactionInfo>, Error> {
+        let chain = self.chain.lock();
+        let history = chain
+            .transactions
+            .values()
+            .filter(|tx| tx.vout.iter().any(|vout| vout.pays_to(address)))
+            .map(|tx| chain.transaction_info(tx))
+            .collect();
+        Ok(history)
+    }
+
+    async fn get_block_height(&self) -> Result<u32, Error> {
+        Ok(self.chain.lock().tip_height)
+    }
+
+    async fn get_header_at_height(
+        &self,
+        height: u32,
+    ) -> Result<bitcoin::block::Header, Error> {
+        self.chain
+            .lock()
+            .headers
+            .get(&height)
+            .copied()
+            .ok_or(Error::InvalidResponse)
+    }
+
+    async fn get_tx_merkle_proof(
+        &self,
+        txid: &str,
+    ) -> Result<bitcoin::MerkleBlock, Error> {
+        self.chain
+            .lock()
+            .merkle_proofs
+            .get(txid)
+            .cloned()
+            .ok_or(Error::TransactionNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::{
+        parent_chain_rpc::{ScriptPubKey, Vin, Vout},
+        types::L1MatchPolicy,
+    };
+
+    const RECIPIENT: &str = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+    const SENDER: &str = "bcrt1qsender";
+
+    fn tx(
+        txid: &str,
+        vin: Vec<Vin>,
+        outputs: &[(&str, u64)],
+        blockheight: Option<u32>,
+    ) -> TransactionInfo {
+        let vout = outputs
+            .iter()
+            .map(|(address, sats)| Vout {
+                value: bitcoin::Amount::from_sat(*sats).to_btc(),
+                script_pub_key: ScriptPubKey {
+                    address: Some(address.to_string()),
+                    addresses: None,
+                    hex: None,
+                },
+            })
+            .collect();
+        TransactionInfo {
+            txid: txid.to_string(),
+            confirmations: 0,
+            blockheight,
+            blockhash: None,
+            vout,
+            vin,
+        }
+    }
+
+    fn spend(txid: &str, vout: u32) -> Vec<Vin> {
+        vec![Vin {
+            txid: Some(txid.to_string()),
+            vout: Some(vout),
+        }]
+    }
+
+    #[test]
+    fn confirmations_follow_tip_height() {
+        let backend = MockBackend::new();
+        backend.set_tip_height(10);
+        backend.insert_transaction(tx("a", vec![], &[(RECIPIENT, 1)], None));
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations("a")).unwrap(),
+            0
+        );
+        backend.insert_transaction(tx(
+            "a",
+            vec![],
+            &[(RECIPIENT, 1)],
+            Some(10),
+        ));
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations("a")).unwrap(),
+            1
+        );
+        backend.set_tip_height(12);
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations("a")).unwrap(),
+            3
+        );
+        backend.remove_transaction("a");
+        assert!(matches!(
+            block_on(backend.get_transaction("a")),
+            Err(Error::TransactionNotFound)
+        ));
+    }
+
+    #[test]
+    fn find_by_amount_reports_sender() {
+        let backend = MockBackend::new();
+        backend.set_tip_height(5);
+        backend.insert_transaction(tx(
+            "funding",
+            vec![],
+            &[(SENDER, 60_000)],
+            Some(1),
+        ));
+        backend.insert_transaction(tx(
+            "payment",
+            spend("funding", 0),
+            &[(RECIPIENT, 29_000_000), (SENDER, 1)],
+            Some(2),
+        ));
+        backend.insert_transaction(tx(
+            "other",
+            vec![],
+            &[(RECIPIENT, 1_000)],
+            Some(3),
+        ));
+        let matches =
+            block_on(backend.find_transactions_by_address_and_amount(
+                RECIPIENT,
+                29_000_000,
+                L1MatchPolicy::Exact,
+            ))
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        let (sender, tx_info) = &matches[0];
+        assert_eq!(sender, SENDER);
+        assert_eq!(tx_info.txid, "payment");
+        assert_eq!(tx_info.confirmations, 4);
+    }
+
+    #[test]
+    fn find_by_amount_applies_match_policy() {
+        let backend = MockBackend::new();
+        backend.insert_transaction(tx(
+            "exact",
+            vec![],
+            &[(RECIPIENT, 1_000)],
+            None,
+        ));
+        backend.insert_transaction(tx(
+            "overpaid",
+            vec![],
+            &[(RECIPIENT, 1_001)],
+            None,
+        ));
+        backend.insert_transaction(tx(
+            "split",
+            vec![],
+            &[(RECIPIENT, 600), (SENDER, 5_000), (RECIPIENT, 400)],
+            None,
+        ));
+        let matching = |policy| {
+            let matches =
+                block_on(backend.find_transactions_by_address_and_amount(
+                    RECIPIENT, 1_000, policy,
+                ))
+                .unwrap();
+            let mut txids: Vec<_> =
+                matches.into_iter().map(|(_, tx)| tx.txid).collect();
+            txids.sort();
+            txids
+        };
+        assert_eq!(matching(L1MatchPolicy::Exact), ["exact"]);
+        assert_eq!(matching(L1MatchPolicy::AtLeast), ["exact", "overpaid"]);
+        assert_eq!(
+            matching(L1MatchPolicy::SumOfOutputs),
+            ["exact", "overpaid", "split"]
+        );
+    }
+
+    #[test]
+    fn find_up_to_amount_sums_outputs() {
+        let backend = MockBackend::new();
+        backend.insert_transaction(tx(
+            "split",
+            vec![],
+            &[(RECIPIENT, 600), (RECIPIENT, 400)],
+            None,
+        ));
+        backend.insert_transaction(tx(
+            "too_much",
+            vec![],
+            &[(RECIPIENT, 1_001)],
+            None,
+        ));
+        let matches = block_on(
+            backend.find_transactions_by_address_up_to_amount(RECIPIENT, 1_000),
+        )
+        .unwrap();
+        let txids: Vec<_> =
+            matches.iter().map(|(_, tx)| tx.txid.as_str()).collect();
+        assert_eq!(txids, ["split"]);
+        assert_eq!(matches[0].0, "unknown");
+    }
+}
@@ -0,0 +1,892 @@
+//! Parent Chain RPC client for querying L1 blockchain transactions
+//!
+//! This module provides a generic RPC client that works with any Bitcoin-compatible
+//! blockchain (Bitcoin, Bitcoin Cash, Litecoin, etc.) that implements the standard
+//! Bitcoin Core JSON-RPC interface.
+//!
+//! Swap monitoring goes through the [`ParentChainBackend`] trait, which is
+//! implemented by the JSON-RPC client, an Esplora REST client and an
+//! in-memory mock.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::types::{
+    L1MatchPolicy, MAX_SPV_HEADERS, ParentChainType, SpvProof, SwapCommitment,
+    SwapId,
+};
+
+mod backend;
+mod client;
+mod esplora;
+mod mock;
+mod network;
+mod quorum;
+mod zmq;
+
+pub use backend::{BackendKind, ParentChainBackend, ParseBackendKindError};
+use client::Endpoint;
+pub use client::{CircuitState, EndpointHealth, L1Client};
+pub use esplora::EsploraClient;
+pub use mock::MockBackend;
+pub use network::{DetectedChain, detect_chain, detect_chain_type};
+pub use quorum::{
+    L1ChainStatus, L1Endpoints, L1Status, L1Warning, QuorumPolicy,
+};
+pub use zmq::{L1Notification, ZmqPublisher, ZmqSubscription};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP request error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("HTTP error {status}: {body}")]
+    HttpStatus {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    #[error(
+        "L1 endpoint {url} is unhealthy; retrying in {}s",
+        retry_in.as_secs()
+    )]
+    EndpointUnhealthy { url: String, retry_in: Duration },
+    #[error("JSON parsing error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("RPC error: {0}")]
+    Rpc(String),
+    #[error("Invalid response format")]
+    InvalidResponse,
+    #[error("Transaction not found")]
+    TransactionNotFound,
+    #[error("Failed to decode L1 data from RPC response: {0}")]
+    ConsensusDecode(#[from] bitcoin::consensus::encode::FromHexError),
+    /// Node's network is not one known for the expected chain type
+    #[error(
+        "Node chain mismatch: expected {expected:?}, node is on {detected}"
+    )]
+    ChainMismatch {
+        expected: ParentChainType,
+        detected: DetectedChain,
+    },
+    #[error("Node is on an unknown network: {0}")]
+    UnknownChain(DetectedChain),
+    #[error(
+        "Node is on {detected}, which may be any of {candidates:?}; specify the parent chain"
+    )]
+    AmbiguousChain {
+        detected: DetectedChain,
+        candidates: Vec<ParentChainType>,
+    },
+    #[error("Failed to read RPC cookie file `{}`", path.display())]
+    ReadCookieFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Invalid RPC cookie file `{}`: expected `user:password`", .0.display())]
+    InvalidCookieFile(PathBuf),
+    #[error(
+        "Only {responded} of {endpoints} L1 endpoints responded, too few for the {policy:?} quorum policy"
+    )]
+    NoQuorum {
+        policy: QuorumPolicy,
+        responded: usize,
+        endpoints: usize,
+    },
+    #[error("Failed to read tracked L1 headers")]
+    TrackedHeaders(#[source] Box<crate::archive::Error>),
+    #[error("ZMQ error")]
+    Zmq(#[from] zeromq::ZmqError),
+    #[error("Invalid ZMQ `{topic}` notification")]
+    InvalidZmqNotification { topic: String },
+    #[error(
+        "SPV proof would need {count} L1 headers, more than the limit of {MAX_SPV_HEADERS}; track L1 headers first"
+    )]
+    SpvProofTooLarge { count: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcConfig {
+    pub url: String,
+    #[serde(default)]
+    pub user: String,
+    #[serde(default)]
+    pub password: String,
+    /// Bitcoin Core `.cookie` file. If set, credentials are read from it for
+    /// each request instead of using `user` and `password`, since the cookie
+    /// changes whenever the node restarts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookie_file: Option<PathBuf>,
+    /// API served at `url`
+    #[serde(default)]
+    pub backend: BackendKind,
+    /// ZMQ publisher of the node, e.g. `tcp://127.0.0.1:28332`, for
+    /// `hashblock` and `rawtx` notifications. If set, L1 changes are pushed
+    /// to the tasks that would otherwise poll the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zmq_url: Option<String>,
+}
+
+impl RpcConfig {
+    /// Credentials for HTTP basic auth, if any
+    fn credentials(&self) -> Result<Option<(String, String)>, Error> {
+        if let Some(path) = &self.cookie_file {
+            let cookie = std::fs::read_to_string(path).map_err(|source| {
+                Error::ReadCookieFile {
+                    path: path.clone(),
+                    source,
+                }
+            })?;
+            let (user, password) = cookie
+                .trim_end()
+                .split_once(':')
+                .ok_or_else(|| Error::InvalidCookieFile(path.clone()))?;
+            Ok(Some((user.to_owned(), password.to_owned())))
+        } else if !self.user.is_empty() {
+            Ok(Some((self.user.clone(), self.password.clone())))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Build a client for the configured backend, sending requests through
+    /// `client`
+    pub fn build_backend(
+        &self,
+        client: &L1Client,
+    ) -> Box<dyn ParentChainBackend> {
+        match self.backend {
+            BackendKind::BitcoinCore => {
+                Box::new(ParentChainRpcClient::new(self.clone(), client))
+            }
+            BackendKind::Esplora => {
+                Box::new(EsploraClient::new(self.url.clone(), client))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RpcError {
+    code: i32,
+    message: String,
+}
+
+/// Bitcoin Core RPC error code for an unknown transaction, among others
+const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionInfo {
+    pub txid: String,
+    pub confirmations: u32,
+    pub blockheight: Option<u32>,
+    /// Block that includes the transaction, if it is confirmed
+    #[serde(default)]
+    pub blockhash: Option<bitcoin::BlockHash>,
+    pub vout: Vec<Vout>,
+    pub vin: Vec<Vin>,
+}
+
+impl TransactionInfo {
+    /// Find the OP_RETURN commitment to `swap_id` in the outputs
+    pub fn swap_commitment(&self, swap_id: &SwapId) -> Option<SwapCommitment> {
+        let scripts: Vec<bitcoin::ScriptBuf> = self
+            .vout
+            .iter()
+            .filter_map(|vout| vout.script_pub_key.hex.as_deref())
+            .filter_map(|hex| bitcoin::ScriptBuf::from_hex(hex).ok())
+            .collect();
+        SwapCommitment::find(
+            scripts.iter().map(|script| script.as_script()),
+            swap_id,
+        )
+    }
+
+    /// Total paid to an address by the outputs, in sats
+    pub fn value_to(&self, address: &str) -> u64 {
+        self.vout
+            .iter()
+            .filter(|vout| vout.pays_to(address))
+            .map(Vout::value_sats)
+            .sum()
+    }
+
+    /// Returns `true` if the outputs pay `amount_sats` to an address under
+    /// `policy`
+    pub fn pays_amount_to(
+        &self,
+        address: &str,
+        amount_sats: u64,
+        policy: L1MatchPolicy,
+    ) -> bool {
+        policy.matches(
+            self.vout
+                .iter()
+                .filter(|vout| vout.pays_to(address))
+                .map(|vout| bitcoin::Amount::from_sat(vout.value_sats())),
+            bitcoin::Amount::from_sat(amount_sats),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vout {
+    pub value: f64,
+    #[serde(rename = "scriptPubKey")]
+    pub script_pub_key: ScriptPubKey,
+}
+
+impl Vout {
+    pub fn value_sats(&self) -> u64 {
+        (self.value * 100_000_000.0).round() as u64
+    }
+
+    /// Returns `true` if the output pays to `address`
+    pub fn pays_to(&self, address: &str) -> bool {
+        self.script_pub_key.address.as_deref() == Some(address)
+            || self
+                .script_pub_key
+                .addresses
+                .as_ref()
+                .is_some_and(|addrs| addrs.iter().any(|addr| addr == address))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptPubKey {
+    pub address: Option<String>,
+    pub addresses: Option<Vec<String>>,
+    /// Hex-encoded output script
+    #[serde(default)]
+    pub hex: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vin {
+    pub txid: Option<String>,
+    pub vout: Option<u32>,
+}
+
+/// Most calls sent in one batch request
+const MAX_BATCH_SIZE: usize = 500;
+
+/// Response to one call of a batch request
+#[derive(Debug, Deserialize)]
+struct RpcBatchResponse {
+    id: usize,
+    result: Option<serde_json::Value>,
+    error: Option<RpcError>,
+}
+
+/// Result of an RPC call, from its response
+fn rpc_result<T>(method: &str, response: RpcResponse<T>) -> Result<T, Error> {
+    if let Some(error) = response.error {
+        if method == "getrawtransaction"
+            && error.code == RPC_INVALID_ADDRESS_OR_KEY
+        {
+            return Err(Error::TransactionNotFound);
+        }
+        tracing::error!(
+            method = %method,
+            rpc_error_code = %error.code,
+            rpc_error_message = %error.message,
+            "RPC returned error"
+        );
+        return Err(Error::Rpc(format!("{}: {}", error.code, error.message)));
+    }
+    response.result.ok_or(Error::InvalidResponse)
+}
+
+/// RPC client for communicating with parent chain nodes (Bitcoin, Bitcoin Cash, Litecoin, etc.)
+///
+/// This client uses the standard Bitcoin Core JSON-RPC interface, which is compatible
+/// with most Bitcoin-derivative blockchains.
+pub struct ParentChainRpcClient {
+    config: RpcConfig,
+    endpoint: Endpoint,
+}
+
+impl ParentChainRpcClient {
+    /// Client for the node at `config`, sending requests through `client`
+    pub fn new(config: RpcConfig, client: &L1Client) -> Self {
+        let endpoint = client.endpoint(&config.url);
+        Self { config, endpoint }
+    }
+
+    /// POST a JSON-RPC request, or batch of requests, returning the
+    /// response body
+    async fn post(&self, request: &serde_json::Value) -> Result<String, Error> {
+        let credentials = self.config.credentials()?;
+        let (status, body) = self
+            .endpoint
+            .send(
+                |http| {
+                    let request = http.post(&self.config.url).json(request);
+                    match &credentials {
+                        Some((user, password)) => {
+                            request.basic_auth(user, Some(password))
+                        }
+                        None => request,
+                    }
+                },
+                // Errors are reported with an HTTP error status, and a JSON
+                // body
+                |status, body| {
+                    status.is_success()
+                        || body.trim_start().starts_with(['{', '['])
+                },
+            )
+            .await?;
+        tracing::trace!(
+            url = %self.config.url,
+            status = %status,
+            response_body = %body,
+            "Raw RPC response body"
+        );
+        Ok(body)
+    }
+
+    async fn call<T>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, Error>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        // Use jsonrpc "1.0" for compatibility with nodes that accept curl-style requests (e.g. BCH)
+        let request = json!({
+            "jsonrpc": "1.0",
+            "id": "coinshift",
+            "method": method,
+            "params": params
+        });
+
+        tracing::debug!(
+            url = %self.config.url,
+            method = %method,
+            params = %params,
+            "Making RPC call"
+        );
+
+        let body = self.post(&request).await?;
+        let response: RpcResponse<T> = match serde_json::from_str(&body) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                tracing::error!(
+                    url = %self.config.url,
+                    method = %method,
+                    response_body = %body,
+                    error = %e,
+                    "Failed to parse response as JSON"
+                );
+                return Err(Error::Json(e));
+            }
+        };
+        rpc_result(method, response)
+    }
+
+    /// Call `method` once for each of `params`, in batch requests. Returns
+    /// the result of each call, in order.
+    async fn batch<T>(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<Vec<Result<T, Error>>, Error>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        tracing::debug!(
+            url = %self.config.url,
+            method = %method,
+            calls = params.len(),
+            "Making batched RPC calls"
+        );
+        let mut results = Vec::with_capacity(params.len());
+        for chunk in params.chunks(MAX_BATCH_SIZE) {
+            let requests = chunk
+                .iter()
+                .enumerate()
+                .map(|(id, params)| {
+                    json!({
+                        "jsonrpc": "1.0",
+                        "id": id,
+                        "method": method,
+                        "params": params
+                    })
+                })
+                .collect();
+            let body = self.post(&serde_json::Value::Array(requests)).await?;
+            // Responses may be in any order
+            let mut responses: Vec<RpcBatchResponse> =
+                serde_json::from_str(&body)?;
+            responses.sort_unstable_by_key(|response| response.id);
+            if responses.len() != chunk.len()
+                || responses
+                    .iter()
+                    .enumerate()
+                    .any(|(id, response)| response.id != id)
+            {
+                return Err(Error::InvalidResponse);
+            }
+            results.extend(responses.into_iter().map(
+                |response| -> Result<T, Error> {
+                    let value = rpc_result(
+                        method,
+                        RpcResponse {
+                            result: response.result,
+                            error: response.error,
+                        },
+                    )?;
+                    Ok(serde_json::from_value(value)?)
+                },
+            ));
+        }
+        Ok(results)
+    }
+
+    /// Get transaction by ID
+    pub async fn get_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<TransactionInfo, Error> {
+        tracing::debug!(
+            txid = %txid,
+            "Fetching transaction from RPC"
+        );
+        let result = self
+            .call::<TransactionInfo>("getrawtransaction", json!([txid, true]))
+            .await;
+        match &result {
+            Ok(tx_info) => {
+                tracing::debug!(
+                    txid = %txid,
+                    confirmations = %tx_info.confirmations,
+                    blockheight = ?tx_info.blockheight,
+                    "Successfully fetched transaction"
+                );
+            }
+            Err(Error::TransactionNotFound) => {
+                tracing::debug!(txid = %txid, "Transaction not found");
+            }
+            Err(e) => {
+                tracing::error!(
+                    txid = %txid,
+                    error = %e,
+                    error_debug = ?e,
+                    "Failed to fetch transaction"
+                );
+            }
+        }
+        result
+    }
+
+    /// Get transactions by ID, in one batch. Returns the result of each
+    /// lookup, in order.
+    pub async fn get_transactions(
+        &self,
+        txids: &[String],
+    ) -> Result<Vec<Result<TransactionInfo, Error>>, Error> {
+        let params = txids.iter().map(|txid| json!([txid, true])).collect();
+        self.batch("getrawtransaction", params).await
+    }
+
+    /// Get confirmations for a transaction by ID
+    pub async fn get_transaction_confirmations(
+        &self,
+        txid: &str,
+    ) -> Result<u32, Error> {
+        let tx = self.get_transaction(txid).await?;
+        Ok(tx.confirmations)
+    }
+
+    /// Get transactions for an address
+    /// Returns list of transaction IDs
+    pub async fn list_transactions(
+        &self,
+        address: &str,
+    ) -> Result<Vec<String>, Error> {
+        // Use listunspent to find transactions (works for most cases)
+        // For more comprehensive results, we'd need to use a block explorer API
+        // or maintain our own index
+        let unspent: Vec<serde_json::Value> = self
+            .call("listunspent", json!([0, 999999, [address]]))
+            .await?;
+
+        let mut txids = std::collections::HashSet::new();
+        for utxo in unspent {
+            if let Some(txid) = utxo.get("txid").and_then(|v| v.as_str()) {
+                txids.insert(txid.to_string());
+            }
+        }
+
+        // Also try to get transactions from getreceivedbyaddress (if available)
+        // This is a fallback, but not all nodes support it
+        // Note: We don't use the result, but calling it may help populate the node's internal index
+        let _result: Result<f64, _> =
+            self.call("getreceivedbyaddress", json!([address, 0])).await;
+
+        Ok(txids.into_iter().collect())
+    }
+
+    /// Get current block height
+    pub async fn get_block_height(&self) -> Result<u32, Error> {
+        let info: serde_json::Value =
+            self.call("getblockchaininfo", json!([])).await?;
+        let blocks = info
+            .get("blocks")
+            .and_then(|v| v.as_u64())
+            .ok_or(Error::InvalidResponse)?;
+        Ok(blocks as u32)
+    }
+
+    /// Get the hash of the block at a height in the node's best chain
+    pub async fn get_block_hash(
+        &self,
+        height: u32,
+    ) -> Result<bitcoin::BlockHash, Error> {
+        self.call("getblockhash", json!([height])).await
+    }
+
+    /// Get a block header
+    pub async fn get_block_header(
+        &self,
+        block_hash: &bitcoin::BlockHash,
+    ) -> Result<bitcoin::block::Header, Error> {
+        let header_hex: String = self
+            .call("getblockheader", json!([block_hash, false]))
+            .await?;
+        Ok(bitcoin::consensus::encode::deserialize_hex(&header_hex)?)
+    }
+
+    /// Get the headers at a range of heights in the node's best chain, in
+    /// two batches
+    pub async fn get_block_headers(
+        &self,
+        heights: Range<u32>,
+    ) -> Result<Vec<bitcoin::block::Header>, Error> {
+        if heights.is_empty() {
+            return Ok(Vec::new());
+        }
+        let block_hashes = self
+            .batch::<bitcoin::BlockHash>(
+                "getblockhash",
+                heights.map(|height| json!([height])).collect(),
+            )
+            .await?
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        self.batch::<String>(
+            "getblockheader",
+            block_hashes
+                .iter()
+                .map(|block_hash| json!([block_hash, false]))
+                .collect(),
+        )
+        .await?
+        .into_iter()
+        .map(|header_hex| -> Result<bitcoin::block::Header, Error> {
+            Ok(bitcoin::consensus::encode::deserialize_hex(&header_hex?)?)
+        })
+        .collect()
+    }
+
+    /// Get the height of a block in the node's best chain
+    pub async fn get_block_header_height(
+        &self,
+        block_hash: &bitcoin::BlockHash,
+    ) -> Result<u32, Error> {
+        let header: serde_json::Value = self
+            .call("getblockheader", json!([block_hash, true]))
+            .await?;
+        let height = header
+            .get("height")
+            .and_then(|v| v.as_u64())
+            .ok_or(Error::InvalidResponse)?;
+        Ok(height as u32)
+    }
+
+    /// Get a merkle proof that a confirmed transaction is included in its
+    /// block
+    pub async fn get_tx_out_proof(
+        &self,
+        txid: &str,
+    ) -> Result<bitcoin::MerkleBlock, Error> {
+        let merkle_block_hex: String =
+            self.call("gettxoutproof", json!([[txid]])).await?;
+        Ok(bitcoin::consensus::encode::deserialize_hex(
+            &merkle_block_hex,
+        )?)
+    }
+
+    /// Build an SPV proof for a confirmed transaction, with
+    /// `confirmations` headers from the including block onwards.
+    ///
+    /// If `anchor` is set, the proof is extended with headers so that it
+    /// connects to the anchor block, which must be in the node's best chain.
+    pub async fn build_spv_proof(
+        &self,
+        txid: &str,
+        confirmations: u32,
+        anchor: Option<bitcoin::BlockHash>,
+    ) -> Result<SpvProof, Error> {
+        let tx_info = self.get_transaction(txid).await?;
+        let block_height =
+            tx_info.blockheight.ok_or(Error::TransactionNotFound)?;
+        let merkle_block = self.get_tx_out_proof(txid).await?;
+        let transaction_hex: String =
+            self.call("getrawtransaction", json!([txid, false])).await?;
+        let transaction: bitcoin::Transaction =
+            bitcoin::consensus::encode::deserialize_hex(&transaction_hex)?;
+        let mut last_height = block_height + confirmations.saturating_sub(1);
+        let mut first_height = block_height;
+        if let Some(anchor) = anchor {
+            let anchor_height = self.get_block_header_height(&anchor).await?;
+            last_height = last_height.max(anchor_height);
+            first_height = first_height.min(anchor_height + 1);
+        }
+        let count = last_height + 1 - first_height;
+        if count as usize > MAX_SPV_HEADERS {
+            return Err(Error::SpvProofTooLarge { count });
+        }
+        let ancestors =
+            self.get_block_headers(first_height..block_height).await?;
+        let headers = self
+            .get_block_headers(block_height + 1..last_height + 1)
+            .await?;
+        Ok(SpvProof {
+            ancestors,
+            merkle_block,
+            transaction,
+            headers,
+        })
+    }
+
+    /// Get the headers that follow `anchor` in the node's best chain, up to
+    /// the tip and at most [`MAX_SPV_HEADERS`], to extend the tracked L1
+    /// headers
+    pub async fn get_headers_after(
+        &self,
+        anchor: &bitcoin::BlockHash,
+    ) -> Result<Vec<bitcoin::block::Header>, Error> {
+        let anchor_height = self.get_block_header_height(anchor).await?;
+        let tip_height = self.get_block_height().await?;
+        let last_height =
+            tip_height.min(anchor_height + MAX_SPV_HEADERS as u32);
+        self.get_block_headers(anchor_height + 1..last_height + 1)
+            .await
+    }
+
+    /// Get the chain name from getblockchaininfo, lowercase (e.g. "signet",
+    /// "main", "testnet4", "test4").
+    pub async fn get_blockchain_chain_name(&self) -> Result<String, Error> {
+        let info: serde_json::Value =
+            self.call("getblockchaininfo", json!([])).await?;
+        let chain = info
+            .get("chain")
+            .and_then(|v| v.as_str())
+            .ok_or(Error::InvalidResponse)?;
+        Ok(chain.to_lowercase())
+    }
+}
+
+/// Load the L1 endpoints for all parent chains from a JSON file.
+///
+/// The file format is `{ "<ParentChainType>": { "url": "...", "user": "...", "password": "..." }, ... }`
+/// (e.g. the same format written by the GUI to `l1_rpc_configs.json`).
+/// `user` and `password` may be omitted, and `"cookie_file"` may be set
+/// instead. An optional `"backend"` field selects `"bitcoin_core"` (the
+/// default) or `"esplora"`.
+///
+/// A parent chain may instead have several endpoints, as
+/// `{ "endpoints": [{ "url": "..." }, ...], "quorum": "majority" }`, where
+/// `"quorum"` is one of `"first_success"` (the default), `"majority"` or
+/// `"all_agree"`.
+pub fn load_rpc_configs_from_path(
+    path: &Path,
+) -> Option<HashMap<ParentChainType, L1Endpoints>> {
+    let file_content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&file_content).ok()
+}
+
+/// Load the L1 endpoints for a parent chain from a JSON file, in the format
+/// described in [`load_rpc_configs_from_path`].
+pub fn load_l1_endpoints_from_path(
+    path: &Path,
+    parent_chain: ParentChainType,
+) -> Option<L1Endpoints> {
+    load_rpc_configs_from_path(path)?.remove(&parent_chain)
+}
+
+/// Load the primary RPC config for a parent chain from a JSON file, in the
+/// format described in [`load_rpc_configs_from_path`].
+pub fn load_rpc_config_from_path(
+    path: &Path,
+    parent_chain: ParentChainType,
+) -> Option<RpcConfig> {
+    load_l1_endpoints_from_path(path, parent_chain)?
+        .endpoints
+        .into_iter()
+        .next()
+}
+
+/// Set the primary RPC config for a parent chain in a JSON file, keeping
+/// its other endpoints and the configs for other parent chains. Creates the
+/// file and its parent directory if needed.
+pub fn save_rpc_config_to_path(
+    path: &Path,
+    parent_chain: ParentChainType,
+    config: RpcConfig,
+) -> std::io::Result<()> {
+    let mut configs = load_rpc_configs_from_path(path).unwrap_or_default();
+    configs
+        .entry(parent_chain)
+        .and_modify(|endpoints| endpoints.set_primary(config.clone()))
+        .or_insert_with(|| config.into());
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&configs)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::write(path, json)
+}
+
+/// Validate the L1 config file: each node must be on a network known for
+/// the parent chain that it is configured for. Call before app start.
+pub async fn validate_l1_config_file(path: &Path) -> Result<(), Error> {
+    // No file, or invalid JSON that will be overwritten when the user saves
+    let Some(configs) = load_rpc_configs_from_path(path) else {
+        return Ok(());
+    };
+    for (parent_chain, endpoints) in configs {
+        for config in &endpoints.endpoints {
+            let _: ParentChainType =
+                detect_chain_type(config, Some(parent_chain)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Get RPC config for a parent chain
+/// This is a placeholder - in practice, this should access the GUI's stored config
+pub fn get_rpc_config(_parent_chain: ParentChainType) -> Option<RpcConfig> {
+    // TODO: Access stored RPC config from GUI/app state
+    // For now, return None to indicate no config available
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_rpc_config_from_path_missing_file_returns_none() {
+        let path = Path::new("/nonexistent/l1_rpc_configs.json");
+        assert!(
+            load_rpc_config_from_path(path, ParentChainType::Regtest).is_none()
+        );
+    }
+
+    #[test]
+    fn load_rpc_config_from_path_valid_file_returns_config() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("coinshift_l1_rpc_test.json");
+        let configs = serde_json::json!({
+            "Regtest": { "url": "http://127.0.0.1:18443", "user": "u", "password": "p" }
+        });
+        std::fs::write(&path, configs.to_string()).unwrap();
+        let cfg = load_rpc_config_from_path(&path, ParentChainType::Regtest);
+        drop(std::fs::remove_file(&path)); // best-effort cleanup
+        assert!(cfg.is_some());
+        let cfg = cfg.unwrap();
+        assert_eq!(cfg.url, "http://127.0.0.1:18443");
+        assert_eq!(cfg.user, "u");
+        assert_eq!(cfg.password, "p");
+    }
+
+    #[test]
+    fn load_rpc_config_from_path_wrong_chain_returns_none() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("coinshift_l1_rpc_test2.json");
+        let configs = serde_json::json!({
+            "Signet": { "url": "http://127.0.0.1:38332", "user": "u", "password": "p" }
+        });
+        std::fs::write(&path, configs.to_string()).unwrap();
+        let cfg = load_rpc_config_from_path(&path, ParentChainType::Regtest);
+        drop(std::fs::remove_file(&path)); // best-effort cleanup
+        assert!(cfg.is_none());
+    }
+
+    #[test]
+    fn save_rpc_config_to_path_keeps_other_chains() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("coinshift_l1_rpc_test_save.json");
+        let configs = serde_json::json!({
+            "Signet": { "url": "http://127.0.0.1:38332", "user": "u", "password": "p" }
+        });
+        std::fs::write(&path, configs.to_string()).unwrap();
+        let regtest = RpcConfig {
+            url: "http://127.0.0.1:18443".to_string(),
+            user: String::new(),
+            password: String::new(),
+            cookie_file: Some(PathBuf::from("/regtest/.cookie")),
+            backend: BackendKind::BitcoinCore,
+            zmq_url: None,
+        };
+        save_rpc_config_to_path(&path, ParentChainType::Regtest, regtest)
+            .unwrap();
+        let configs = load_rpc_configs_from_path(&path);
+        drop(std::fs::remove_file(&path)); // best-effort cleanup
+        let configs = configs.unwrap();
+        let primary = |parent_chain| configs[&parent_chain].primary().unwrap();
+        assert_eq!(primary(ParentChainType::Signet).user, "u");
+        assert_eq!(
+            primary(ParentChainType::Regtest).cookie_file.as_deref(),
+            Some(Path::new("/regtest/.cookie"))
+        );
+    }
+
+    #[test]
+    fn cookie_file_credentials() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("coinshift_l1_rpc_test.cookie");
+        std::fs::write(&path, "__cookie__:secret\n").unwrap();
+        let config = RpcConfig {
+            url: "http://127.0.0.1:18443".to_string(),
+            user: "ignored".to_string(),
+            password: "ignored".to_string(),
+            cookie_file: Some(path.clone()),
+            backend: BackendKind::BitcoinCore,
+            zmq_url: None,
+        };
+        let credentials = config.credentials();
+        drop(std::fs::remove_file(&path)); // best-effort cleanup
+        assert_eq!(
+            credentials.unwrap(),
+            Some(("__cookie__".to_string(), "secret".to_string()))
+        );
+        assert!(matches!(
+            config.credentials(),
+            Err(Error::ReadCookieFile { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_l1_config_file_empty_or_missing_ok() {
+        let path = Path::new("/nonexistent/l1_rpc_configs.json");
+        assert!(
+            futures::executor::block_on(validate_l1_config_file(path)).is_ok()
+        );
+    }
+}
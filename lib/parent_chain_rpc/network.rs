@@ -0,0 +1,165 @@
+//! Detection of the network that an L1 endpoint serves
+//!
+//! Endpoints are user-defined, so before an endpoint is used for a parent
+//! chain, the chain name reported by the node and its genesis block hash are
+//! checked against the networks registered for that parent chain.
+
+use std::fmt;
+
+use super::{
+    BackendKind, Error, EsploraClient, L1Client, ParentChainRpcClient,
+    RpcConfig,
+};
+use crate::types::{ParentChainType, parent_chain_registry};
+
+/// Network reported by an L1 endpoint
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DetectedChain {
+    /// Chain name reported by `getblockchaininfo`, lowercase. Not available
+    /// from Esplora endpoints.
+    pub chain: Option<String>,
+    pub genesis_hash: bitcoin::BlockHash,
+}
+
+impl DetectedChain {
+    /// Parent chains that the endpoint may serve. More than one is possible,
+    /// e.g. Bitcoin and Bitcoin Cash share a genesis block and chain name.
+    pub fn candidates(&self) -> Vec<ParentChainType> {
+        parent_chain_registry()
+            .chains()
+            .iter()
+            .filter(|params| {
+                params.networks.iter().any(|network| {
+                    network.genesis_hash == self.genesis_hash
+                        && self.chain.as_deref().is_none_or(|chain| {
+                            network.chain_names.iter().any(|name| name == chain)
+                        })
+                })
+            })
+            .map(|params| ParentChainType::from_id(params.id))
+            .collect()
+    }
+}
+
+impl fmt::Display for DetectedChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(chain) = &self.chain {
+            write!(f, "chain \"{chain}\" ")?;
+        }
+        write!(f, "with genesis block {}", self.genesis_hash)
+    }
+}
+
+/// Query the network served by the endpoint at `config`. The endpoint is
+/// not known to serve any parent chain yet, so a client of its own is used.
+pub async fn detect_chain(config: &RpcConfig) -> Result<DetectedChain, Error> {
+    let l1_client = L1Client::default();
+    match config.backend {
+        BackendKind::BitcoinCore => {
+            let client = ParentChainRpcClient::new(config.clone(), &l1_client);
+            Ok(DetectedChain {
+                chain: Some(client.get_blockchain_chain_name().await?),
+                genesis_hash: client.get_block_hash(0).await?,
+            })
+        }
+        BackendKind::Esplora => {
+            let client = EsploraClient::new(config.url.clone(), &l1_client);
+            Ok(DetectedChain {
+                chain: None,
+                genesis_hash: client.get_block_hash(0).await?,
+            })
+        }
+    }
+}
+
+/// Detect the parent chain served by the endpoint at `config`.
+///
+/// If `expected` is set, the endpoint must serve a network known for that
+/// parent chain. Otherwise, the endpoint must serve a network known for
+/// exactly one parent chain.
+pub async fn detect_chain_type(
+    config: &RpcConfig,
+    expected: Option<ParentChainType>,
+) -> Result<ParentChainType, Error> {
+    let detected = detect_chain(config).await?;
+    let candidates = detected.candidates();
+    match (expected, candidates.as_slice()) {
+        (Some(expected), candidates) if candidates.contains(&expected) => {
+            Ok(expected)
+        }
+        (Some(expected), _) => Err(Error::ChainMismatch { expected, detected }),
+        (None, [parent_chain]) => Ok(*parent_chain),
+        (None, []) => Err(Error::UnknownChain(detected)),
+        (None, _) => Err(Error::AmbiguousChain {
+            detected,
+            candidates,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BITCOIN_GENESIS_HASH: &str =
+        "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+    const SIGNET_GENESIS_HASH: &str =
+        "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6";
+
+    const REGTEST_GENESIS_HASH: &str =
+        "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206";
+
+    fn detected(chain: Option<&str>, genesis_hash: &str) -> DetectedChain {
+        DetectedChain {
+            chain: chain.map(str::to_owned),
+            genesis_hash: genesis_hash.parse().unwrap(),
+        }
+    }
+
+    /// Registered genesis hashes of the chains that are Bitcoin networks
+    #[test]
+    fn genesis_hashes_match_rust_bitcoin() {
+        for params in parent_chain_registry().chains() {
+            let Some(network) = params.bitcoin_network else {
+                continue;
+            };
+            let genesis_hash =
+                bitcoin::constants::genesis_block(network).block_hash();
+            assert!(
+                params
+                    .networks
+                    .iter()
+                    .any(|known| known.genesis_hash == genesis_hash),
+                "{}",
+                params.name
+            );
+        }
+    }
+
+    #[test]
+    fn candidates_require_chain_name_and_genesis_hash() {
+        let signet = detected(Some("signet"), SIGNET_GENESIS_HASH);
+        assert_eq!(signet.candidates(), [ParentChainType::Signet]);
+        let wrong_name = detected(Some("regtest"), SIGNET_GENESIS_HASH);
+        assert!(wrong_name.candidates().is_empty());
+        // Bitcoin testnet4 reports the same chain name as BCH testnet4
+        let btc_testnet4 = detected(
+            Some("testnet4"),
+            "00000000da84f2bafbbc53dee25a72ae507ff4914b867c565be350b0da8bf043",
+        );
+        assert!(btc_testnet4.candidates().is_empty());
+    }
+
+    #[test]
+    fn mainnet_genesis_is_ambiguous() {
+        let main = detected(Some("main"), BITCOIN_GENESIS_HASH);
+        assert_eq!(
+            main.candidates(),
+            [ParentChainType::BTC, ParentChainType::BCH]
+        );
+        // Esplora endpoints do not report a chain name
+        let esplora = detected(None, REGTEST_GENESIS_HASH);
+        assert_eq!(esplora.candidates(), [ParentChainType::Regtest]);
+    }
+}
@@ -0,0 +1,694 @@
+//! Redundant L1 endpoints for a parent chain.
+//!
+//! A single lagging or dishonest L1 node could report a swap payment that
+//! does not exist, or hide one that does. With several endpoints, queries
+//! are answered according to a [`QuorumPolicy`], and disagreements between
+//! endpoints are recorded in [`L1Status`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::Range,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use futures::{FutureExt as _, future::BoxFuture};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    EndpointHealth, Error, L1Client, ParentChainBackend, RpcConfig,
+    TransactionInfo,
+};
+use crate::types::ParentChainType;
+
+/// Number of recent warnings kept by [`L1Status`]
+const MAX_WARNINGS: usize = 100;
+
+/// Endpoints may lag each other by this many blocks without a warning
+const LAG_TOLERANCE: u32 = 1;
+
+/// How the answers of redundant endpoints are combined
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum QuorumPolicy {
+    /// Use the first endpoint that responds, in config order
+    #[default]
+    FirstSuccess,
+    /// A transaction is present if more than half of the endpoints see it,
+    /// with the most confirmations that more than half of them report
+    Majority,
+    /// Every endpoint must respond. A transaction is present if all of them
+    /// see it, with the fewest confirmations that any of them report.
+    AllAgree,
+}
+
+impl QuorumPolicy {
+    /// Combine the observations of a value (e.g. confirmations of a tx) by
+    /// `endpoints` endpoints. Each response is `None` if the endpoint does
+    /// not see the value. Returns `None` if too few endpoints responded.
+    fn combine(
+        self,
+        endpoints: usize,
+        responses: &[Option<u32>],
+    ) -> Option<Option<u32>> {
+        match self {
+            Self::FirstSuccess => responses.first().copied(),
+            Self::Majority => {
+                let quorum = endpoints / 2 + 1;
+                if responses.len() < quorum {
+                    return None;
+                }
+                let mut seen: Vec<u32> =
+                    responses.iter().flatten().copied().collect();
+                seen.sort_unstable_by(|a, b| b.cmp(a));
+                Some(seen.get(quorum - 1).copied())
+            }
+            Self::AllAgree => {
+                if responses.len() < endpoints {
+                    return None;
+                }
+                let seen: Option<Vec<u32>> =
+                    responses.iter().copied().collect();
+                Some(seen.and_then(|seen| seen.into_iter().min()))
+            }
+        }
+    }
+}
+
+/// Endpoint configs on disk: either a single endpoint, or several with a
+/// quorum policy
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum L1EndpointsRepr {
+    Redundant {
+        endpoints: Vec<RpcConfig>,
+        #[serde(default)]
+        quorum: QuorumPolicy,
+    },
+    Single(RpcConfig),
+}
+
+/// L1 endpoints configured for a parent chain
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(from = "L1EndpointsRepr", into = "L1EndpointsRepr")]
+pub struct L1Endpoints {
+    /// The first endpoint is the primary endpoint, used where a single node
+    /// is needed, e.g. to build SPV proofs
+    pub endpoints: Vec<RpcConfig>,
+    pub quorum: QuorumPolicy,
+}
+
+impl From<L1EndpointsRepr> for L1Endpoints {
+    fn from(repr: L1EndpointsRepr) -> Self {
+        match repr {
+            L1EndpointsRepr::Redundant { endpoints, quorum } => {
+                Self { endpoints, quorum }
+            }
+            L1EndpointsRepr::Single(config) => config.into(),
+        }
+    }
+}
+
+impl From<L1Endpoints> for L1EndpointsRepr {
+    fn from(endpoints: L1Endpoints) -> Self {
+        match <[RpcConfig; 1]>::try_from(endpoints.endpoints) {
+            Ok([config]) if endpoints.quorum == QuorumPolicy::default() => {
+                Self::Single(config)
+            }
+            Ok(configs) => Self::Redundant {
+                endpoints: configs.into(),
+                quorum: endpoints.quorum,
+            },
+            Err(configs) => Self::Redundant {
+                endpoints: configs,
+                quorum: endpoints.quorum,
+            },
+        }
+    }
+}
+
+impl From<RpcConfig> for L1Endpoints {
+    fn from(config: RpcConfig) -> Self {
+        Self {
+            endpoints: vec![config],
+            quorum: QuorumPolicy::default(),
+        }
+    }
+}
+
+impl L1Endpoints {
+    pub fn primary(&self) -> Option<&RpcConfig> {
+        self.endpoints.first()
+    }
+
+    /// Replace the primary endpoint, or add it if there are no endpoints
+    pub fn set_primary(&mut self, config: RpcConfig) {
+        match self.endpoints.first_mut() {
+            Some(primary) => *primary = config,
+            None => self.endpoints.push(config),
+        }
+    }
+
+    /// ZMQ publishers of the endpoints
+    pub fn zmq_urls(&self) -> impl Iterator<Item = &str> {
+        self.endpoints
+            .iter()
+            .filter_map(|config| config.zmq_url.as_deref())
+    }
+
+    /// Build a backend that queries the endpoints according to the quorum
+    /// policy, through the shared client of the parent chain in `status`,
+    /// recording disagreements in `status`. Returns `None` if no endpoints
+    /// are configured.
+    pub fn build_backend(
+        &self,
+        parent_chain: ParentChainType,
+        status: Arc<L1Status>,
+    ) -> Option<Box<dyn ParentChainBackend>> {
+        let client = status.client(parent_chain);
+        match self.endpoints.as_slice() {
+            [] => None,
+            [config] => Some(config.build_backend(&client)),
+            configs => {
+                let members: Vec<_> = configs
+                    .iter()
+                    .map(|config| config.build_backend(&client))
+                    .collect();
+                Some(Box::new(QuorumBackend::new(
+                    parent_chain,
+                    members,
+                    self.quorum,
+                    status,
+                )))
+            }
+        }
+    }
+}
+
+/// Disagreement between the endpoints of a parent chain
+#[derive(Clone, Debug, Deserialize, Serialize, utoipa::ToSchema)]
+pub struct L1Warning {
+    pub parent_chain: ParentChainType,
+    /// Unix time, in seconds
+    pub timestamp: u64,
+    pub message: String,
+}
+
+/// Shared clients of the L1 endpoints of each parent chain, and recent
+/// disagreements between endpoints
+#[derive(Debug, Default)]
+pub struct L1Status {
+    clients: Mutex<HashMap<ParentChainType, L1Client>>,
+    warnings: Mutex<VecDeque<L1Warning>>,
+}
+
+impl L1Status {
+    /// Client shared by the backends of a parent chain
+    pub fn client(&self, parent_chain: ParentChainType) -> L1Client {
+        self.clients.lock().entry(parent_chain).or_default().clone()
+    }
+
+    fn record(&self, parent_chain: ParentChainType, message: String) {
+        tracing::warn!(?parent_chain, "L1 endpoints disagree: {message}");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let mut warnings = self.warnings.lock();
+        if warnings.len() >= MAX_WARNINGS {
+            warnings.pop_front();
+        }
+        warnings.push_back(L1Warning {
+            parent_chain,
+            timestamp,
+            message,
+        });
+    }
+
+    /// Recent warnings, oldest first
+    pub fn warnings(&self) -> Vec<L1Warning> {
+        self.warnings.lock().iter().cloned().collect()
+    }
+}
+
+/// Configured endpoints, their health and recent warnings for a parent
+/// chain, without credentials
+#[derive(Clone, Debug, Deserialize, Serialize, utoipa::ToSchema)]
+pub struct L1ChainStatus {
+    pub parent_chain: ParentChainType,
+    pub endpoints: Vec<String>,
+    /// Health of each endpoint, in the order of `endpoints`
+    pub health: Vec<EndpointHealth>,
+    pub quorum: QuorumPolicy,
+    pub warnings: Vec<L1Warning>,
+}
+
+impl L1Status {
+    /// Status of each configured parent chain
+    pub fn report<I>(&self, configs: I) -> Vec<L1ChainStatus>
+    where
+        I: IntoIterator<Item = (ParentChainType, L1Endpoints)>,
+    {
+        let warnings = self.warnings();
+        configs
+            .into_iter()
+            .map(|(parent_chain, endpoints)| {
+                let client = self.client(parent_chain);
+                let urls: Vec<String> = endpoints
+                    .endpoints
+                    .into_iter()
+                    .map(|config| config.url)
+                    .collect();
+                L1ChainStatus {
+                    parent_chain,
+                    health: urls.iter().map(|url| client.health(url)).collect(),
+                    endpoints: urls,
+                    quorum: endpoints.quorum,
+                    warnings: warnings
+                        .iter()
+                        .filter(|warning| warning.parent_chain == parent_chain)
+                        .cloned()
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Backend that queries redundant endpoints, combining their answers
+/// according to a quorum policy
+struct QuorumBackend {
+    parent_chain: ParentChainType,
+    members: Vec<Box<dyn ParentChainBackend>>,
+    policy: QuorumPolicy,
+    /// Endpoints of all members, for logging
+    endpoint: String,
+    status: Arc<L1Status>,
+}
+
+impl QuorumBackend {
+    fn new(
+        parent_chain: ParentChainType,
+        members: Vec<Box<dyn ParentChainBackend>>,
+        policy: QuorumPolicy,
+        status: Arc<L1Status>,
+    ) -> Self {
+        let endpoint = members
+            .iter()
+            .map(|member| member.endpoint())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            parent_chain,
+            members,
+            policy,
+            endpoint,
+            status,
+        }
+    }
+
+    /// Query the members. With [`QuorumPolicy::FirstSuccess`], members are
+    /// queried in order until one succeeds. Otherwise, all members are
+    /// queried concurrently. Returns the successful responses, with the
+    /// endpoint of each.
+    async fn query<'a, T, F>(&'a self, f: F) -> Vec<(&'a str, T)>
+    where
+        F: Fn(&'a dyn ParentChainBackend) -> BoxFuture<'a, Result<T, Error>>,
+    {
+        let log_err = |member: &dyn ParentChainBackend, err: &Error| {
+            tracing::warn!(
+                parent_chain = ?self.parent_chain,
+                endpoint = %member.endpoint(),
+                error = %err,
+                "L1 endpoint request failed"
+            );
+        };
+        if self.policy == QuorumPolicy::FirstSuccess {
+            for member in &self.members {
+                match f(member.as_ref()).await {
+                    Ok(res) => return vec![(member.endpoint(), res)],
+                    Err(err) => log_err(member.as_ref(), &err),
+                }
+            }
+            return Vec::new();
+        }
+        let responses = futures::future::join_all(
+            self.members.iter().map(|member| f(member.as_ref())),
+        )
+        .await;
+        self.members
+            .iter()
+            .zip(responses)
+            .filter_map(|(member, res)| match res {
+                Ok(res) => Some((member.endpoint(), res)),
+                Err(err) => {
+                    log_err(member.as_ref(), &err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn no_quorum(&self, responded: usize) -> Error {
+        Error::NoQuorum {
+            policy: self.policy,
+            responded,
+            endpoints: self.members.len(),
+        }
+    }
+
+    /// Combine the members' observations of a value, warning if they
+    /// disagree by more than `tolerance`
+    fn combine(
+        &self,
+        what: &str,
+        responses: &[(&str, Option<u32>)],
+        tolerance: u32,
+    ) -> Result<Option<u32>, Error> {
+        let values: Vec<Option<u32>> =
+            responses.iter().map(|(_, value)| *value).collect();
+        let seen: Vec<u32> = values.iter().flatten().copied().collect();
+        let presence_differs = !seen.is_empty() && seen.len() != values.len();
+        let spread_exceeded = seen
+            .iter()
+            .max()
+            .zip(seen.iter().min())
+            .is_some_and(|(max, min)| max - min > tolerance);
+        if presence_differs || spread_exceeded {
+            let reports = responses
+                .iter()
+                .map(|(endpoint, value)| match value {
+                    Some(value) => format!("{endpoint}: {value}"),
+                    None => format!("{endpoint}: not found"),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.status
+                .record(self.parent_chain, format!("{what}: {reports}"));
+        }
+        self.policy
+            .combine(self.members.len(), &values)
+            .ok_or_else(|| self.no_quorum(responses.len()))
+    }
+}
+
+#[async_trait]
+impl ParentChainBackend for QuorumBackend {
+    fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    async fn get_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<TransactionInfo, Error> {
+        let responses = self
+            .query(|member| {
+                async move {
+                    match member.get_transaction(txid).await {
+                        Ok(tx) => Ok(Some(tx)),
+                        Err(Error::TransactionNotFound) => Ok(None),
+                        Err(err) => Err(err),
+                    }
+                }
+                .boxed()
+            })
+            .await;
+        let confirmations: Vec<_> = responses
+            .iter()
+            .map(|(endpoint, tx)| {
+                (*endpoint, tx.as_ref().map(|tx| tx.confirmations))
+            })
+            .collect();
+        let confirmations = self
+            .combine(
+                &format!("confirmations of {txid}"),
+                &confirmations,
+                LAG_TOLERANCE,
+            )?
+            .ok_or(Error::TransactionNotFound)?;
+        let tx = responses
+            .into_iter()
+            .find_map(|(_, tx)| tx)
+            .ok_or(Error::TransactionNotFound)?;
+        Ok(TransactionInfo {
+            confirmations,
+            ..tx
+        })
+    }
+
+    async fn get_address_history(
+        &self,
+        address: &str,
+    ) -> Result<Vec<TransactionInfo>, Error> {
+        let responses = self
+            .query(|member| member.get_address_history(address))
+            .await;
+        let no_txs = vec![None; responses.len()];
+        if self.policy.combine(self.members.len(), &no_txs).is_none() {
+            return Err(self.no_quorum(responses.len()));
+        }
+        let mut history: Vec<TransactionInfo> = Vec::new();
+        for (_, txs) in &responses {
+            for tx in txs {
+                if !history.iter().any(|seen| seen.txid == tx.txid) {
+                    history.push(tx.clone());
+                }
+            }
+        }
+        let mut agreed = Vec::new();
+        for tx in history {
+            let confirmations: Vec<_> = responses
+                .iter()
+                .map(|(endpoint, txs)| {
+                    let confirmations = txs
+                        .iter()
+                        .find(|member_tx| member_tx.txid == tx.txid)
+                        .map(|member_tx| member_tx.confirmations);
+                    (*endpoint, confirmations)
+                })
+                .collect();
+            let what =
+                format!("confirmations of {} paying to {address}", tx.txid);
+            if let Some(confirmations) =
+                self.combine(&what, &confirmations, LAG_TOLERANCE)?
+            {
+                agreed.push(TransactionInfo {
+                    confirmations,
+                    ..tx
+                });
+            }
+        }
+        Ok(agreed)
+    }
+
+    async fn get_block_height(&self) -> Result<u32, Error> {
+        let responses = self.query(|member| member.get_block_height()).await;
+        let heights: Vec<_> = responses
+            .iter()
+            .map(|(endpoint, height)| (*endpoint, Some(*height)))
+            .collect();
+        self.combine("block height", &heights, LAG_TOLERANCE)?
+            .ok_or(Error::InvalidResponse)
+    }
+
+    /// Headers carry their own proof of work, which is checked before they
+    /// are tracked, so the first member to answer is used
+    async fn get_header_at_height(
+        &self,
+        height: u32,
+    ) -> Result<bitcoin::block::Header, Error> {
+        let mut last_err = None;
+        for member in &self.members {
+            match member.get_header_at_height(height).await {
+                Ok(header) => return Ok(header),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| self.no_quorum(0)))
+    }
+
+    /// Merkle proofs are checked against the tracked headers, so as for
+    /// [`Self::get_header_at_height`], the first member to answer is used
+    async fn get_tx_merkle_proof(
+        &self,
+        txid: &str,
+    ) -> Result<bitcoin::MerkleBlock, Error> {
+        let mut last_err = None;
+        for member in &self.members {
+            match member.get_tx_merkle_proof(txid).await {
+                Ok(merkle_block) => return Ok(merkle_block),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| self.no_quorum(0)))
+    }
+
+    /// As for [`Self::get_header_at_height`], the first member to answer
+    /// is used
+    async fn get_headers(
+        &self,
+        heights: Range<u32>,
+    ) -> Result<Vec<bitcoin::block::Header>, Error> {
+        let mut last_err = None;
+        for member in &self.members {
+            match member.get_headers(heights.clone()).await {
+                Ok(headers) => return Ok(headers),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| self.no_quorum(0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::parent_chain_rpc::{MockBackend, ScriptPubKey, Vout};
+
+    const RECIPIENT: &str = "bcrt1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+
+    fn payment(txid: &str, blockheight: u32) -> TransactionInfo {
+        TransactionInfo {
+            txid: txid.to_string(),
+            confirmations: 0,
+            blockheight: Some(blockheight),
+            blockhash: None,
+            vout: vec![Vout {
+                value: 0.1,
+                script_pub_key: ScriptPubKey {
+                    address: Some(RECIPIENT.to_string()),
+                    addresses: None,
+                    hex: None,
+                },
+            }],
+            vin: Vec::new(),
+        }
+    }
+
+    fn mock(tip_height: u32, txs: &[TransactionInfo]) -> Box<MockBackend> {
+        let backend = MockBackend::new();
+        backend.set_tip_height(tip_height);
+        for tx in txs {
+            backend.insert_transaction(tx.clone());
+        }
+        Box::new(backend)
+    }
+
+    fn quorum(
+        policy: QuorumPolicy,
+        members: Vec<Box<MockBackend>>,
+    ) -> (QuorumBackend, Arc<L1Status>) {
+        let status = Arc::new(L1Status::default());
+        let members = members
+            .into_iter()
+            .map(|member| member as Box<dyn ParentChainBackend>)
+            .collect();
+        let backend = QuorumBackend::new(
+            ParentChainType::Regtest,
+            members,
+            policy,
+            status.clone(),
+        );
+        (backend, status)
+    }
+
+    #[test]
+    fn combine_policies() {
+        let responses = [Some(6), None, Some(2)];
+        assert_eq!(
+            QuorumPolicy::FirstSuccess.combine(3, &responses),
+            Some(Some(6))
+        );
+        assert_eq!(
+            QuorumPolicy::Majority.combine(3, &responses),
+            Some(Some(2))
+        );
+        assert_eq!(QuorumPolicy::AllAgree.combine(3, &responses), Some(None));
+        assert_eq!(
+            QuorumPolicy::AllAgree.combine(3, &[Some(6), Some(2), Some(3)]),
+            Some(Some(2))
+        );
+        // Too few responses
+        assert_eq!(QuorumPolicy::Majority.combine(3, &[Some(6)]), None);
+        assert_eq!(
+            QuorumPolicy::AllAgree.combine(3, &[Some(6), Some(6)]),
+            None
+        );
+    }
+
+    #[test]
+    fn lying_endpoint_is_outvoted() {
+        let tx = payment("a", 100);
+        let (backend, status) = quorum(
+            QuorumPolicy::Majority,
+            vec![mock(105, &[]), mock(105, &[]), mock(105, &[tx])],
+        );
+        assert!(matches!(
+            block_on(backend.get_transaction("a")),
+            Err(Error::TransactionNotFound)
+        ));
+        assert!(
+            block_on(backend.get_address_history(RECIPIENT))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(status.warnings().len(), 2);
+    }
+
+    #[test]
+    fn lagging_endpoint_limits_confirmations() {
+        let tx = payment("a", 100);
+        let (backend, status) = quorum(
+            QuorumPolicy::AllAgree,
+            vec![mock(110, &[tx.clone()]), mock(102, &[tx])],
+        );
+        assert_eq!(
+            block_on(backend.get_transaction_confirmations("a")).unwrap(),
+            3
+        );
+        assert_eq!(block_on(backend.get_block_height()).unwrap(), 102);
+        assert_eq!(status.warnings().len(), 2);
+    }
+
+    #[test]
+    fn endpoints_config_formats() {
+        let single: L1Endpoints = serde_json::from_value(serde_json::json!({
+            "url": "http://127.0.0.1:18443", "user": "u", "password": "p"
+        }))
+        .unwrap();
+        assert_eq!(single.endpoints.len(), 1);
+        assert_eq!(single.quorum, QuorumPolicy::FirstSuccess);
+        assert!(serde_json::to_value(&single).unwrap().get("url").is_some());
+        let redundant: L1Endpoints =
+            serde_json::from_value(serde_json::json!({
+                "endpoints": [
+                    { "url": "http://127.0.0.1:18443" },
+                    { "url": "https://esplora.example/api", "backend": "esplora" }
+                ],
+                "quorum": "all_agree"
+            }))
+            .unwrap();
+        assert_eq!(redundant.endpoints.len(), 2);
+        assert_eq!(redundant.quorum, QuorumPolicy::AllAgree);
+        assert!(
+            serde_json::to_value(&redundant)
+                .unwrap()
+                .get("endpoints")
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! ZMQ notifications published by bitcoind-style L1 nodes.
+//!
+//! Nodes started with `-zmqpubhashblock=<url>` and `-zmqpubrawtx=<url>`
+//! publish multipart messages of a topic, a body and a little-endian
+//! sequence number. Subscribing to them lets tasks react to new L1 blocks
+//! and transactions instead of polling.
+
+use bitcoin::hashes::Hash as _;
+use bytes::Bytes;
+use zeromq::{Socket as _, SocketRecv as _, SocketSend as _, ZmqMessage};
+
+use super::Error;
+
+/// Topic of the block hash notifications
+pub const HASHBLOCK_TOPIC: &str = "hashblock";
+
+/// Topic of the raw transaction notifications
+pub const RAWTX_TOPIC: &str = "rawtx";
+
+/// Notification published by an L1 node
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum L1Notification {
+    /// A block was connected to the node's best chain
+    Block(bitcoin::BlockHash),
+    /// A transaction was accepted to the node's mempool, or was included in
+    /// a connected block
+    Tx(bitcoin::Txid),
+}
+
+impl L1Notification {
+    /// Parse the topic and body of a notification. Returns `None` for
+    /// topics that are not subscribed to.
+    pub fn parse(topic: &[u8], body: &[u8]) -> Result<Option<Self>, Error> {
+        let invalid = || Error::InvalidZmqNotification {
+            topic: String::from_utf8_lossy(topic).into_owned(),
+        };
+        if topic == HASHBLOCK_TOPIC.as_bytes() {
+            // Block hashes are published in RPC byte order
+            let mut hash: [u8; 32] = body.try_into().map_err(|_| invalid())?;
+            hash.reverse();
+            Ok(Some(Self::Block(bitcoin::BlockHash::from_byte_array(hash))))
+        } else if topic == RAWTX_TOPIC.as_bytes() {
+            let tx: bitcoin::Transaction =
+                bitcoin::consensus::deserialize(body).map_err(|_| invalid())?;
+            Ok(Some(Self::Tx(tx.compute_txid())))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Subscription to the block and transaction notifications of an L1 node
+pub struct ZmqSubscription {
+    socket: zeromq::SubSocket,
+    url: String,
+}
+
+impl ZmqSubscription {
+    /// Connect to the publisher at `url`, e.g. `tcp://127.0.0.1:28332`
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        let mut socket = zeromq::SubSocket::new();
+        socket.connect(url).await?;
+        socket.subscribe(HASHBLOCK_TOPIC).await?;
+        socket.subscribe(RAWTX_TOPIC).await?;
+        Ok(Self {
+            socket,
+            url: url.to_owned(),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Wait for the next notification
+    pub async fn recv(&mut self) -> Result<L1Notification, Error> {
+        loop {
+            let message = self.socket.recv().await?;
+            let (Some(topic), Some(body)) = (message.get(0), message.get(1))
+            else {
+                continue;
+            };
+            if let Some(notification) = L1Notification::parse(topic, body)? {
+                return Ok(notification);
+            }
+        }
+    }
+}
+
+/// Publisher of bitcoind-style notifications, to stand in for an L1 node
+/// in tests
+pub struct ZmqPublisher {
+    socket: zeromq::PubSocket,
+    url: String,
+    hashblock_sequence: u32,
+    rawtx_sequence: u32,
+}
+
+impl ZmqPublisher {
+    /// Bind to `url`, e.g. `tcp://127.0.0.1:0` for any free port
+    pub async fn bind(url: &str) -> Result<Self, Error> {
+        let mut socket = zeromq::PubSocket::new();
+        let endpoint = socket.bind(url).await?;
+        Ok(Self {
+            socket,
+            url: endpoint.to_string(),
+            hashblock_sequence: 0,
+            rawtx_sequence: 0,
+        })
+    }
+
+    /// URL that subscribers can connect to
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn publish(
+        &mut self,
+        topic: &'static str,
+        body: Vec<u8>,
+        sequence: u32,
+    ) -> Result<(), Error> {
+        let mut message = ZmqMessage::from(topic);
+        message.push_back(Bytes::from(body));
+        message.push_back(Bytes::copy_from_slice(&sequence.to_le_bytes()));
+        self.socket.send(message).await?;
+        Ok(())
+    }
+
+    pub async fn publish_block(
+        &mut self,
+        block_hash: bitcoin::BlockHash,
+    ) -> Result<(), Error> {
+        let mut body = block_hash.to_byte_array();
+        body.reverse();
+        let sequence = self.hashblock_sequence;
+        self.hashblock_sequence = sequence.wrapping_add(1);
+        self.publish(HASHBLOCK_TOPIC, body.to_vec(), sequence).await
+    }
+
+    pub async fn publish_tx(
+        &mut self,
+        tx: &bitcoin::Transaction,
+    ) -> Result<(), Error> {
+        let sequence = self.rawtx_sequence;
+        self.rawtx_sequence = sequence.wrapping_add(1);
+        self.publish(RAWTX_TOPIC, bitcoin::consensus::serialize(tx), sequence)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn test_tx() -> bitcoin::Transaction {
+        bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(50_000),
+                script_pubkey: bitcoin::ScriptBuf::new(),
+            }],
+        }
+    }
+
+    #[test]
+    fn parse_hashblock_in_rpc_byte_order() {
+        let mut body = [0; 32];
+        body[31] = 1;
+        let mut hash = [0; 32];
+        hash[0] = 1;
+        assert_eq!(
+            L1Notification::parse(b"hashblock", &body).unwrap(),
+            Some(L1Notification::Block(bitcoin::BlockHash::from_byte_array(
+                hash
+            )))
+        );
+        assert!(L1Notification::parse(b"hashblock", &body[1..]).is_err());
+        assert_eq!(L1Notification::parse(b"sequence", &body).unwrap(), None);
+    }
+
+    #[test]
+    fn subscription_receives_published_notifications() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let mut publisher =
+                ZmqPublisher::bind("tcp://127.0.0.1:0").await.unwrap();
+            let mut subscription =
+                ZmqSubscription::connect(publisher.url()).await.unwrap();
+            let block_hash = bitcoin::BlockHash::from_byte_array([3; 32]);
+            let tx = test_tx();
+            // Messages published before the subscription is established
+            // are dropped, so publish until one arrives
+            let notification = loop {
+                publisher.publish_block(block_hash).await.unwrap();
+                if let Ok(res) = tokio::time::timeout(
+                    Duration::from_millis(100),
+                    subscription.recv(),
+                )
+                .await
+                {
+                    break res.unwrap();
+                }
+            };
+            assert_eq!(notification, L1Notification::Block(block_hash));
+            publisher.publish_tx(&tx).await.unwrap();
+            let notification = loop {
+                match subscription.recv().await.unwrap() {
+                    L1Notification::Block(_) => continue,
+                    notification => break notification,
+                }
+            };
+            assert_eq!(notification, L1Notification::Tx(tx.compute_txid()));
+        });
+    }
+}
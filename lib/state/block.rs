@@ -5,12 +5,16 @@ use sneed::{RoTxn, RwTxn, db::error::Error as DbError};
 
 use crate::{
     authorization::Authorization,
-    state::{Error, PrevalidatedBlock, State, error},
+    state::{
+        Error, PrevalidatedBlock, State, SwapUpdateSource, error, l1_headers,
+        swap,
+    },
     types::{
-        AccumulatorDiff, AmountOverflowError, Body, FilledTransaction,
-        GetAddress as _, GetValue as _, Header, InPoint, MerkleRoot, OutPoint,
-        OutPointKey, PointedOutput, SpentOutput, Swap, SwapId, SwapState,
-        SwapTxId, TxData, Verify as _,
+        AccumulatorDiff, AmountOverflowError, BlockHash, Body,
+        FilledTransaction, GetAddress as _, GetValue as _, Header, InPoint,
+        MerkleRoot, OutPoint, OutPointKey, PointedOutput, SpentOutput,
+        SpvProof, Swap, SwapId, SwapState, SwapTxId, Transaction, TxData,
+        Verify as _, decode_l1_headers,
     },
 };
 
@@ -146,13 +150,24 @@ pub fn prevalidate(
     if coinbase_value > total_fees {
         return Err(Error::NotEnoughFees);
     }
-    let spent_utxos = filled_transactions
-        .iter()
-        .flat_map(|t| t.spent_utxos.iter());
-    for (authorization, spent_utxo) in
+    let spent_utxos = filled_transactions.iter().flat_map(|filled_tx| {
+        let transaction = &filled_tx.transaction;
+        filled_tx
+            .spent_utxos
+            .iter()
+            .map(move |utxo| (transaction, utxo))
+    });
+    for (authorization, (transaction, spent_utxo)) in
         body.authorizations.iter().zip(spent_utxos)
     {
-        if authorization.get_address() != spent_utxo.address {
+        if authorization.get_address() != spent_utxo.address
+            && !swap::is_swap_claim_input(
+                state,
+                rotxn,
+                transaction,
+                spent_utxo,
+            )?
+        {
             return Err(Error::WrongPubKeyForAddress);
         }
     }
@@ -250,160 +265,13 @@ pub fn connect_prevalidated(
                 .map_err(DbError::from)?;
         }
 
-        // Process swap transactions
-        match &filled.transaction.data {
-            TxData::SwapCreate {
-                swap_id,
-                parent_chain,
-                l1_txid_bytes,
-                required_confirmations,
-                l2_recipient,
-                l2_amount,
-                l1_recipient_address,
-                l1_amount,
-            } => {
-                let swap_id = SwapId(*swap_id);
-                let current_height = pre.next_height;
-
-                // Reconstruct L1 txid
-                let l1_txid = SwapTxId::from_bytes(l1_txid_bytes);
-
-                // Check if swap already exists (might be from mempool or previous block)
-                // If it exists but is corrupted, delete it first to avoid issues
-                match state.get_swap(rwtxn, &swap_id) {
-                    Ok(Some(ref existing)) => {
-                        tracing::warn!(
-                            swap_id = %swap_id,
-                            existing_state = ?existing.state,
-                            "Swap already exists in database, will overwrite during block connection"
-                        );
-                    }
-                    Ok(None) => {
-                        // Swap doesn't exist, that's fine
-                    }
-                    Err(_) => {
-                        // Swap exists but is corrupted - delete it first
-                        tracing::warn!(
-                            swap_id = %swap_id,
-                            "Existing swap is corrupted, deleting before saving new one"
-                        );
-                        // Try to delete the corrupted swap
-                        drop(state.swaps.delete(rwtxn, &swap_id));
-                    }
-                }
-
-                // L2 creator = first input's address (only they may cancel/delete)
-                let l2_creator_address =
-                    filled.spent_utxos.first().map(|o| o.address);
-
-                // Reconstruct swap object
-                let swap = Swap::new(
-                    swap_id,
-                    crate::types::SwapDirection::L2ToL1,
-                    *parent_chain,
-                    l1_txid,
-                    Some(*required_confirmations),
-                    *l2_recipient, // Now optional
-                    bitcoin::Amount::from_sat(*l2_amount),
-                    l1_recipient_address.clone(),
-                    l1_amount.map(bitcoin::Amount::from_sat),
-                    current_height,
-                    None, // TODO: Add expiration support
-                    l2_creator_address,
-                );
-
-                // Verify swap ID matches
-                if swap.id.0 != swap_id.0 {
-                    return Err(Error::InvalidTransaction(
-                        "Swap ID mismatch in SwapCreate".to_owned(),
-                    ));
-                }
-
-                tracing::debug!(
-                    swap_id = %swap_id,
-                    l2_recipient = ?swap.l2_recipient,
-                    l2_amount = %swap.l2_amount,
-                    l1_amount = ?swap.l1_amount,
-                    state = ?swap.state,
-                    "Reconstructed swap from SwapCreate transaction, about to save"
-                );
-
-                // Lock outputs for L2 → L1 swaps
-                // Only lock outputs with SwapPending content, not change outputs
-                if l1_recipient_address.is_some() {
-                    for (vout, output) in
-                        filled.transaction.outputs.iter().enumerate()
-                    {
-                        // Only lock SwapPending outputs, not regular Value outputs (change)
-                        if matches!(
-                            output.content,
-                            crate::types::OutputContent::SwapPending { .. }
-                        ) {
-                            let outpoint = OutPoint::Regular {
-                                txid,
-                                vout: vout as u32,
-                            };
-                            state.lock_output_to_swap(
-                                rwtxn, &outpoint, &swap_id,
-                            )?;
-                        }
-                    }
-                }
-
-                // Save swap - this is where corruption might happen
-                tracing::debug!(
-                    swap_id = %swap_id,
-                    "About to save swap during block connection"
-                );
-                state.save_swap(rwtxn, &swap)?;
-                tracing::debug!(
-                    swap_id = %swap_id,
-                    "Swap saved during block connection"
-                );
-            }
-            TxData::SwapClaim {
-                swap_id,
-                l2_claimer_address,
-                ..
-            } => {
-                let swap_id = SwapId(*swap_id);
-
-                // Get swap
-                let mut swap = state
-                    .get_swap(rwtxn, &swap_id)?
-                    .ok_or_else(|| Error::SwapNotFound { swap_id })?;
-
-                // Verify state
-                if !matches!(swap.state, SwapState::ReadyToClaim) {
-                    return Err(Error::InvalidTransaction(format!(
-                        "Swap {} is not ready to claim",
-                        swap_id
-                    )));
-                }
-
-                // For open swaps, verify claimer address is provided
-                if swap.l2_recipient.is_none() && l2_claimer_address.is_none() {
-                    return Err(Error::InvalidTransaction(
-                        "Open swap claim requires l2_claimer_address"
-                            .to_string(),
-                    ));
-                }
-
-                // Unlock outputs
-                for (outpoint, _) in &filled.transaction.inputs {
-                    if state.is_output_locked_to_swap(rwtxn, outpoint)?
-                        == Some(swap_id)
-                    {
-                        state.unlock_output_from_swap(rwtxn, outpoint)?;
-                    }
-                }
-
-                // Mark swap as completed
-                swap.mark_completed();
-                state.save_swap(rwtxn, &swap)?;
-            }
-            TxData::Regular => {}
-        }
+        let () = connect_swap_tx(
+            state,
+            rwtxn,
+            filled,
+            header.hash(),
+            pre.next_height,
+        )?;
     }
 
     // Update tip/height
@@ -432,6 +300,303 @@ pub fn connect_prevalidated(
     Ok(pre.computed_merkle_root)
 }
 
+/// Validate a transaction of a block being connected against the swap
+/// state, and apply its swap state changes
+fn connect_swap_tx(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled: &FilledTransaction,
+    block_hash: BlockHash,
+    height: u32,
+) -> Result<(), Error> {
+    let txid = filled.transaction.txid();
+    match &filled.transaction.data {
+        TxData::SwapCreate {
+            swap_id,
+            parent_chain,
+            l1_txid_bytes,
+            required_confirmations,
+            l2_recipient,
+            l2_amount,
+            l1_recipient_address,
+            l1_amount,
+            expires_in_blocks,
+            require_l1_commitment,
+            allow_partial_fills,
+            l1_match_policy,
+        } => {
+            let swap_id = SwapId(*swap_id);
+
+            swap::validate_swap_create(
+                state,
+                rwtxn,
+                &filled.transaction,
+                filled,
+            )?;
+
+            // Reconstruct L1 txid
+            let l1_txid = SwapTxId::from_bytes(l1_txid_bytes);
+
+            // Check if swap already exists (might be from mempool or previous block)
+            // If it exists but is corrupted, delete it first to avoid issues
+            match state.get_swap(rwtxn, &swap_id) {
+                Ok(Some(ref existing)) => {
+                    tracing::warn!(
+                        swap_id = %swap_id,
+                        existing_state = ?existing.state,
+                        "Swap already exists in database, will overwrite during block connection"
+                    );
+                }
+                Ok(None) => {
+                    // Swap doesn't exist, that's fine
+                }
+                Err(_) => {
+                    // Swap exists but is corrupted - delete it first
+                    tracing::warn!(
+                        swap_id = %swap_id,
+                        "Existing swap is corrupted, deleting before saving new one"
+                    );
+                    // Try to delete the corrupted swap
+                    drop(state.swaps.delete(rwtxn, &swap_id));
+                }
+            }
+
+            // L2 creator = first input's address (only they may cancel)
+            let l2_creator_address =
+                filled.spent_utxos.first().map(|o| o.address);
+
+            // Reconstruct swap object
+            let direction = filled
+                .transaction
+                .data
+                .swap_direction()
+                .expect("SwapCreate has a swap direction");
+            let swap = Swap::new(
+                swap_id,
+                direction,
+                *parent_chain,
+                l1_txid,
+                Some(*required_confirmations),
+                *l2_recipient, // Now optional
+                bitcoin::Amount::from_sat(*l2_amount),
+                l1_recipient_address.clone(),
+                l1_amount.map(bitcoin::Amount::from_sat),
+                height,
+                Swap::expiry_height(height, *expires_in_blocks),
+                l2_creator_address,
+                *require_l1_commitment,
+                *allow_partial_fills,
+                *l1_match_policy,
+            );
+
+            // Verify swap ID matches
+            if swap.id.0 != swap_id.0 {
+                return Err(Error::InvalidTransaction(
+                    "Swap ID mismatch in SwapCreate".to_owned(),
+                ));
+            }
+
+            tracing::debug!(
+                swap_id = %swap_id,
+                l2_recipient = ?swap.l2_recipient,
+                l2_amount = %swap.l2_amount,
+                l1_amount = ?swap.l1_amount,
+                state = ?swap.state,
+                "Reconstructed swap from SwapCreate transaction, about to save"
+            );
+
+            // Lock outputs for L2 → L1 swaps
+            // Only lock outputs with SwapPending content, not change outputs
+            if l1_recipient_address.is_some() {
+                for (vout, output) in
+                    filled.transaction.outputs.iter().enumerate()
+                {
+                    // Only lock SwapPending outputs, not regular Value outputs (change)
+                    if matches!(
+                        output.content,
+                        crate::types::OutputContent::SwapPending { .. }
+                    ) {
+                        let outpoint = OutPoint::Regular {
+                            txid,
+                            vout: vout as u32,
+                        };
+                        state
+                            .lock_output_to_swap(rwtxn, &outpoint, &swap_id)?;
+                    }
+                }
+            }
+
+            // Save swap - this is where corruption might happen
+            tracing::debug!(
+                swap_id = %swap_id,
+                "About to save swap during block connection"
+            );
+            state.save_swap(rwtxn, &swap)?;
+            tracing::debug!(
+                swap_id = %swap_id,
+                "Swap saved during block connection"
+            );
+        }
+        TxData::SwapClaim { swap_id, .. } => {
+            let swap_id = SwapId(*swap_id);
+
+            let (proof, verified) = swap::validate_swap_claim(
+                state,
+                rwtxn,
+                &filled.transaction,
+                filled,
+                height,
+            )?;
+
+            // Get swap
+            let mut swap = state
+                .get_swap(rwtxn, &swap_id)?
+                .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+            let prev_swap = swap.clone();
+            let l1_fill = if swap.partial_fills {
+                Some(swap::claimed_l1_fill(&swap, &proof.transaction)?)
+            } else {
+                None
+            };
+
+            // Record the proven L1 transaction and headers
+            swap.update_l1_txid(SwapTxId::from_bitcoin_txid(&verified.txid));
+            swap.set_l1_txid_validation_block(block_hash, height);
+            swap::track_l1_headers(
+                state,
+                rwtxn,
+                swap.parent_chain,
+                &proof,
+                txid,
+            )?;
+
+            // Unlock outputs
+            let mut unlocked_outputs = Vec::new();
+            for (outpoint, _) in &filled.transaction.inputs {
+                if state.is_output_locked_to_swap(rwtxn, outpoint)?
+                    == Some(swap_id)
+                {
+                    state.unlock_output_from_swap(rwtxn, outpoint)?;
+                    unlocked_outputs.push(*outpoint);
+                }
+            }
+
+            // Mark swap as completed, or record a partial fill and lock
+            // the unfilled remainder to the swap
+            if let Some(l1_fill) = l1_fill {
+                swap.record_fill(l1_fill);
+                if !matches!(swap.state, SwapState::Completed) {
+                    swap::lock_swap_outputs(
+                        state,
+                        rwtxn,
+                        &filled.transaction,
+                        &swap_id,
+                    )?;
+                }
+            } else {
+                swap.mark_completed();
+            }
+            state.save_swap_update(
+                rwtxn,
+                prev_swap,
+                &swap,
+                unlocked_outputs,
+                SwapUpdateSource::Body,
+                height,
+            )?;
+        }
+        TxData::SwapCancel { swap_id } => {
+            let swap_id = SwapId(*swap_id);
+
+            let request_expiry =
+                swap::validate_swap_cancel(state, rwtxn, filled, height)?;
+
+            let mut swap = state
+                .get_swap(rwtxn, &swap_id)?
+                .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+            let prev_swap = swap.clone();
+
+            // A cancellation request only brings the expiry forward, so
+            // that the swap can still be claimed until then
+            let mut unlocked_outputs = Vec::new();
+            if let Some(expires_at) = request_expiry {
+                swap.expires_at_height = Some(expires_at);
+            } else {
+                // Unlock outputs
+                for (outpoint, _) in &filled.transaction.inputs {
+                    if state.is_output_locked_to_swap(rwtxn, outpoint)?
+                        == Some(swap_id)
+                    {
+                        state.unlock_output_from_swap(rwtxn, outpoint)?;
+                        unlocked_outputs.push(*outpoint);
+                    }
+                }
+                swap.state = SwapState::Cancelled;
+            }
+            state.save_swap_update(
+                rwtxn,
+                prev_swap,
+                &swap,
+                unlocked_outputs,
+                SwapUpdateSource::Body,
+                height,
+            )?;
+        }
+        TxData::SwapLock {
+            swap_id,
+            l1_recipient_address,
+        } => {
+            let swap_id = SwapId(*swap_id);
+
+            swap::validate_swap_lock(
+                state,
+                rwtxn,
+                &filled.transaction,
+                height,
+            )?;
+
+            let mut swap = state
+                .get_swap(rwtxn, &swap_id)?
+                .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+            let prev_swap = swap.clone();
+
+            swap::lock_swap_outputs(
+                state,
+                rwtxn,
+                &filled.transaction,
+                &swap_id,
+            )?;
+
+            swap.mark_locked(l1_recipient_address.clone());
+            state.save_swap_update(
+                rwtxn,
+                prev_swap,
+                &swap,
+                Vec::new(),
+                SwapUpdateSource::Body,
+                height,
+            )?;
+        }
+        TxData::TrackL1Headers { parent_chain, .. } => {
+            let (params, headers) = l1_headers::validate_track_headers(
+                state,
+                rwtxn,
+                &filled.transaction,
+            )?;
+            l1_headers::track_headers(
+                state,
+                rwtxn,
+                *parent_chain,
+                &params,
+                &headers,
+                txid,
+            )?;
+        }
+        TxData::Regular => {}
+    }
+    Ok(())
+}
+
 pub fn validate(
     state: &State,
     rotxn: &RoTxn,
@@ -541,13 +706,24 @@ pub fn validate(
     if coinbase_value > total_fees {
         return Err(Error::NotEnoughFees);
     }
-    let spent_utxos = filled_transactions
-        .iter()
-        .flat_map(|t| t.spent_utxos.iter());
-    for (authorization, spent_utxo) in
+    let spent_utxos = filled_transactions.iter().flat_map(|filled_tx| {
+        let transaction = &filled_tx.transaction;
+        filled_tx
+            .spent_utxos
+            .iter()
+            .map(move |utxo| (transaction, utxo))
+    });
+    for (authorization, (transaction, spent_utxo)) in
         body.authorizations.iter().zip(spent_utxos)
     {
-        if authorization.get_address() != spent_utxo.address {
+        if authorization.get_address() != spent_utxo.address
+            && !swap::is_swap_claim_input(
+                state,
+                rotxn,
+                transaction,
+                spent_utxo,
+            )?
+        {
             return Err(Error::WrongPubKeyForAddress);
         }
     }
@@ -682,6 +858,9 @@ pub fn disconnect_tip(
         };
         return Err(Error::InvalidHeader(err));
     }
+    let height = state
+        .try_get_height(rwtxn)?
+        .expect("Height should not be None");
     let mut accumulator = state
         .utreexo_accumulator
         .try_get(rwtxn, &())
@@ -693,59 +872,7 @@ pub fn disconnect_tip(
     body.transactions.iter().rev().try_for_each(|tx| {
         let txid = tx.txid();
 
-        // Rollback swap transactions
-        match &tx.data {
-            TxData::SwapCreate { swap_id, .. } => {
-                let swap_id = SwapId(*swap_id);
-
-                // Unlock outputs for L2 → L1 swaps
-                // Only unlock SwapPending outputs that were locked
-                for (vout, output) in tx.outputs.iter().enumerate().rev() {
-                    if matches!(
-                        output.content,
-                        crate::types::OutputContent::SwapPending { .. }
-                    ) {
-                        let outpoint = OutPoint::Regular {
-                            txid,
-                            vout: vout as u32,
-                        };
-                        if state.is_output_locked_to_swap(rwtxn, &outpoint)?
-                            == Some(swap_id)
-                        {
-                            state.unlock_output_from_swap(rwtxn, &outpoint)?;
-                        }
-                    }
-                }
-
-                // Delete swap (rollback: no creator check)
-                state.delete_swap_unchecked(rwtxn, &swap_id)?;
-            }
-            TxData::SwapClaim { swap_id, .. } => {
-                let swap_id = SwapId(*swap_id);
-
-                // Get swap
-                let mut swap = state
-                    .get_swap(rwtxn, &swap_id)?
-                    .ok_or_else(|| Error::SwapNotFound { swap_id })?;
-
-                // Re-lock outputs
-                for (outpoint, _) in tx.inputs.iter().rev() {
-                    if state
-                        .is_output_locked_to_swap(rwtxn, outpoint)?
-                        .is_none()
-                    {
-                        state.lock_output_to_swap(rwtxn, outpoint, &swap_id)?;
-                    }
-                }
-
-                // Revert swap state
-                if matches!(swap.state, SwapState::Completed) {
-                    swap.state = SwapState::ReadyToClaim;
-                    state.save_swap(rwtxn, &swap)?;
-                }
-            }
-            TxData::Regular => {}
-        }
+        let () = disconnect_swap_tx(state, rwtxn, tx, height)?;
 
         // delete UTXOs, last-to-first
         tx.outputs.iter().enumerate().rev().try_for_each(
@@ -807,9 +934,6 @@ pub fn disconnect_tip(
                 Err(Error::NoUtxo { outpoint })
             }
         })?;
-    let height = state
-        .try_get_height(rwtxn)?
-        .expect("Height should not be None");
     match (header.prev_side_hash, height) {
         (None, 0) => {
             state.tip.delete(rwtxn, &()).map_err(DbError::from)?;
@@ -834,3 +958,566 @@ pub fn disconnect_tip(
         .map_err(DbError::from)?;
     Ok(())
 }
+
+/// Revert the swap state changes of a transaction in the tip block
+fn disconnect_swap_tx(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    tx: &Transaction,
+    height: u32,
+) -> Result<(), Error> {
+    let txid = tx.txid();
+    match &tx.data {
+        TxData::SwapCreate { swap_id, .. } => {
+            let swap_id = SwapId(*swap_id);
+
+            // Unlock outputs for L2 → L1 swaps
+            // Only unlock SwapPending outputs that were locked
+            for (vout, output) in tx.outputs.iter().enumerate().rev() {
+                if matches!(
+                    output.content,
+                    crate::types::OutputContent::SwapPending { .. }
+                ) {
+                    let outpoint = OutPoint::Regular {
+                        txid,
+                        vout: vout as u32,
+                    };
+                    if state.is_output_locked_to_swap(rwtxn, &outpoint)?
+                        == Some(swap_id)
+                    {
+                        state.unlock_output_from_swap(rwtxn, &outpoint)?;
+                    }
+                }
+            }
+
+            state.delete_swap(rwtxn, &swap_id)?;
+        }
+        TxData::SwapClaim {
+            swap_id,
+            proof_data,
+            ..
+        } => {
+            let swap_id = SwapId(*swap_id);
+
+            // Get swap
+            let mut swap = state
+                .get_swap(rwtxn, &swap_id)?
+                .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+
+            // Stop tracking L1 headers first proven by this claim
+            if let Some(proof_data) = proof_data {
+                let proof = SpvProof::from_bytes(proof_data)
+                    .map_err(|source| Error::SpvProof { swap_id, source })?;
+                swap::untrack_l1_headers(
+                    state,
+                    rwtxn,
+                    swap.parent_chain,
+                    &proof,
+                    txid,
+                )?;
+            }
+
+            // Unlock the remainder of a partial fill, restore the swap
+            // record from before the claim, and re-lock its outputs.
+            // Claims connected without swap history only revert the
+            // state.
+            swap::unlock_swap_outputs(state, rwtxn, tx, &swap_id)?;
+            if !state.rollback_swap_update(
+                rwtxn,
+                &swap_id,
+                SwapUpdateSource::Body,
+                height,
+            )? {
+                for (outpoint, _) in tx.inputs.iter().rev() {
+                    if state
+                        .is_output_locked_to_swap(rwtxn, outpoint)?
+                        .is_none()
+                    {
+                        state.lock_output_to_swap(rwtxn, outpoint, &swap_id)?;
+                    }
+                }
+                if matches!(swap.state, SwapState::Completed) {
+                    swap.reset_l1_payment();
+                    state.save_swap(rwtxn, &swap)?;
+                }
+            }
+        }
+        TxData::SwapCancel { swap_id } => {
+            let swap_id = SwapId(*swap_id);
+
+            // Restore the swap record, and re-lock the outputs that the
+            // cancellation unlocked. Cancellation requests only restore the
+            // expiry.
+            if !state.rollback_swap_update(
+                rwtxn,
+                &swap_id,
+                SwapUpdateSource::Body,
+                height,
+            )? {
+                return Err(Error::InvalidTransaction(format!(
+                    "No swap update to roll back for SwapCancel of swap {swap_id}"
+                )));
+            }
+        }
+        TxData::SwapLock { swap_id, .. } => {
+            let swap_id = SwapId(*swap_id);
+
+            // Unlock the outputs locked by this transaction, and restore
+            // the swap to await a lock
+            swap::unlock_swap_outputs(state, rwtxn, tx, &swap_id)?;
+            if !state.rollback_swap_update(
+                rwtxn,
+                &swap_id,
+                SwapUpdateSource::Body,
+                height,
+            )? {
+                return Err(Error::InvalidTransaction(format!(
+                    "No swap update to roll back for SwapLock of swap {swap_id}"
+                )));
+            }
+        }
+        TxData::TrackL1Headers {
+            parent_chain,
+            headers,
+        } => {
+            let headers = decode_l1_headers(headers).map_err(|source| {
+                Error::L1Headers {
+                    parent_chain: *parent_chain,
+                    source,
+                }
+            })?;
+            l1_headers::untrack_headers(
+                state,
+                rwtxn,
+                *parent_chain,
+                headers.iter(),
+                txid,
+            )?;
+        }
+        TxData::Regular => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        CompactTarget, TxMerkleNode, block, constants::genesis_block,
+        hashes::Hash as _,
+    };
+    use sneed::RoTxn;
+
+    use super::{connect_swap_tx, disconnect_swap_tx};
+    use crate::{
+        state::{State, l1_headers, test_utils::open_state},
+        types::{
+            Address, BlockHash, FilledTransaction, L1MatchPolicy, OutPoint,
+            Output, OutputContent, ParentChainType, SpvProof, Swap,
+            SwapDirection, SwapId, SwapState, SwapTxId, Transaction, TxData,
+            Txid,
+        },
+    };
+
+    const REGTEST_BITS: u32 = 0x207fffff;
+
+    const L1_RECIPIENT: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    fn mine(
+        prev_blockhash: bitcoin::BlockHash,
+        merkle_root: TxMerkleNode,
+    ) -> block::Header {
+        let mut header = block::Header {
+            version: block::Version::TWO,
+            prev_blockhash,
+            merkle_root,
+            time: 1_700_000_000,
+            bits: CompactTarget::from_consensus(REGTEST_BITS),
+            nonce: 0,
+        };
+        while header.validate_pow(header.target()).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    /// Mine a regtest block on the genesis block that includes `l1_tx`, and
+    /// `confirmations - 1` blocks on top of it
+    fn spv_proof(
+        l1_tx: bitcoin::Transaction,
+        confirmations: usize,
+    ) -> SpvProof {
+        let txid = l1_tx.compute_txid();
+        let txids = [bitcoin::Txid::from_byte_array([7; 32]), txid];
+        let merkle_root =
+            bitcoin::merkle_tree::calculate_root(txids.iter().copied())
+                .map(|root| TxMerkleNode::from_raw_hash(root.to_raw_hash()))
+                .unwrap();
+        let genesis = genesis_block(bitcoin::Network::Regtest).block_hash();
+        let header = mine(genesis, merkle_root);
+        let merkle_block =
+            bitcoin::MerkleBlock::from_header_txids_with_predicate(
+                &header,
+                &txids,
+                |candidate| *candidate == txid,
+            );
+        let mut headers: Vec<block::Header> = Vec::new();
+        let mut prev_blockhash = header.block_hash();
+        for _ in 1..confirmations {
+            let next = mine(prev_blockhash, TxMerkleNode::all_zeros());
+            prev_blockhash = next.block_hash();
+            headers.push(next);
+        }
+        SpvProof {
+            ancestors: Vec::new(),
+            merkle_block,
+            transaction: l1_tx,
+            headers,
+        }
+    }
+
+    fn test_swap(direction: SwapDirection, creator: Address) -> Swap {
+        let l1_recipient = match direction {
+            SwapDirection::L1ToL2 => None,
+            SwapDirection::L2ToL1 => Some(L1_RECIPIENT.to_owned()),
+        };
+        Swap::new(
+            SwapId([1; 32]),
+            direction,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0; 32]),
+            Some(1),
+            None,
+            bitcoin::Amount::from_sat(100_000),
+            l1_recipient,
+            Some(bitcoin::Amount::from_sat(50_000)),
+            10,
+            None,
+            Some(creator),
+            false,
+            false,
+            L1MatchPolicy::Exact,
+        )
+    }
+
+    /// Assert that no update of the swap at `height` is left to roll back
+    fn assert_no_swap_history(
+        state: &State,
+        rotxn: &RoTxn,
+        swap_id: &SwapId,
+        height: u32,
+    ) {
+        assert!(
+            state
+                .swap_history
+                .try_get(rotxn, swap_id)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            state
+                .swap_history_heights
+                .try_get(rotxn, &height)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn spv_proven_claim_connects_and_disconnects() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let creator = Address([7; 20]);
+        let claimer = Address([8; 20]);
+        let l2_amount = bitcoin::Amount::from_sat(100_000);
+        let l1_amount = bitcoin::Amount::from_sat(50_000);
+        let swap = Swap::new(
+            SwapId([1; 32]),
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0; 32]),
+            Some(2),
+            Some(claimer),
+            l2_amount,
+            Some(L1_RECIPIENT.to_owned()),
+            Some(l1_amount),
+            10,
+            None,
+            Some(creator),
+            false,
+            false,
+            L1MatchPolicy::Exact,
+        );
+        let locked_outpoint = OutPoint::Regular {
+            txid: Txid([3; 32]),
+            vout: 0,
+        };
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        state
+            .lock_output_to_swap(&mut rwtxn, &locked_outpoint, &swap.id)
+            .unwrap();
+
+        let l1_tx = bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn::default()],
+            output: vec![bitcoin::TxOut {
+                value: l1_amount,
+                script_pubkey: ParentChainType::Regtest
+                    .consensus_script_pubkey(L1_RECIPIENT)
+                    .unwrap(),
+            }],
+        };
+        let proof = spv_proof(l1_tx, 2);
+        let l1_tip = proof.headers.last().unwrap().block_hash();
+        let claim = FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(locked_outpoint, [0; 32])],
+                outputs: vec![Output {
+                    address: claimer,
+                    content: OutputContent::Value(l2_amount),
+                }],
+                data: TxData::SwapClaim {
+                    swap_id: swap.id.0,
+                    proof_data: Some(proof.to_bytes()),
+                    l2_claimer_address: None,
+                },
+                ..Default::default()
+            },
+            spent_utxos: vec![Output {
+                address: creator,
+                content: OutputContent::SwapPending {
+                    value: l2_amount,
+                    swap_id: swap.id.0,
+                },
+            }],
+        };
+        let block_hash = BlockHash([9; 32]);
+
+        let () = connect_swap_tx(&state, &mut rwtxn, &claim, block_hash, 11)
+            .unwrap();
+        let claimed = state.get_swap(&rwtxn, &swap.id).unwrap().unwrap();
+        assert_eq!(claimed.state, SwapState::Completed);
+        assert_eq!(
+            claimed.l1_txid,
+            SwapTxId::from_bitcoin_txid(&proof.transaction.compute_txid())
+        );
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            None
+        );
+        let tip = l1_headers::tip(&state, &rwtxn, ParentChainType::Regtest)
+            .unwrap()
+            .unwrap();
+        assert_eq!((tip.height, tip.header.block_hash()), (2, l1_tip));
+
+        // Disconnecting the claim untracks its headers, and restores the
+        // swap and its locked output
+        let () = disconnect_swap_tx(&state, &mut rwtxn, &claim.transaction, 11)
+            .unwrap();
+        assert_eq!(state.get_swap(&rwtxn, &swap.id).unwrap(), Some(swap));
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            Some(SwapId([1; 32]))
+        );
+        assert!(
+            l1_headers::tip(&state, &rwtxn, ParentChainType::Regtest)
+                .unwrap()
+                .is_none()
+        );
+        assert_no_swap_history(&state, &rwtxn, &SwapId([1; 32]), 11);
+
+        // The claim can be connected again
+        let () = connect_swap_tx(&state, &mut rwtxn, &claim, block_hash, 11)
+            .unwrap();
+    }
+
+    #[test]
+    fn swap_lock_disconnects() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let holder = Address([8; 20]);
+        let swap = test_swap(SwapDirection::L1ToL2, Address([7; 20]));
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+
+        let lock = FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(
+                    OutPoint::Regular {
+                        txid: Txid([4; 32]),
+                        vout: 0,
+                    },
+                    [0; 32],
+                )],
+                outputs: vec![Output {
+                    address: holder,
+                    content: OutputContent::SwapPending {
+                        value: swap.l2_amount,
+                        swap_id: swap.id.0,
+                    },
+                }],
+                data: TxData::SwapLock {
+                    swap_id: swap.id.0,
+                    l1_recipient_address: L1_RECIPIENT.to_owned(),
+                },
+                ..Default::default()
+            },
+            spent_utxos: vec![Output {
+                address: holder,
+                content: OutputContent::Value(swap.l2_amount),
+            }],
+        };
+        let locked_outpoint = OutPoint::Regular {
+            txid: lock.transaction.txid(),
+            vout: 0,
+        };
+        let block_hash = BlockHash([9; 32]);
+
+        let () =
+            connect_swap_tx(&state, &mut rwtxn, &lock, block_hash, 11).unwrap();
+        let locked = state.get_swap(&rwtxn, &swap.id).unwrap().unwrap();
+        assert_eq!(locked.state, SwapState::Pending);
+        assert_eq!(locked.l1_recipient_address.as_deref(), Some(L1_RECIPIENT));
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            Some(swap.id)
+        );
+
+        // Disconnecting the lock unlocks its output, and restores the swap
+        // to await a lock
+        let () = disconnect_swap_tx(&state, &mut rwtxn, &lock.transaction, 11)
+            .unwrap();
+        assert_eq!(state.get_swap(&rwtxn, &swap.id).unwrap(), Some(swap));
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            None
+        );
+        assert_no_swap_history(&state, &rwtxn, &SwapId([1; 32]), 11);
+
+        // The lock can be connected again
+        let () =
+            connect_swap_tx(&state, &mut rwtxn, &lock, block_hash, 11).unwrap();
+    }
+
+    #[test]
+    fn swap_cancel_disconnects() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let creator = Address([7; 20]);
+        let mut swap = test_swap(SwapDirection::L2ToL1, creator);
+        let locked_outpoint = OutPoint::Regular {
+            txid: Txid([3; 32]),
+            vout: 0,
+        };
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        state
+            .lock_output_to_swap(&mut rwtxn, &locked_outpoint, &swap.id)
+            .unwrap();
+        let block_hash = BlockHash([9; 32]);
+
+        // Before expiry, a cancellation is a request that brings the expiry
+        // forward, and disconnecting it restores the expiry
+        let request = FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(
+                    OutPoint::Regular {
+                        txid: Txid([5; 32]),
+                        vout: 0,
+                    },
+                    [0; 32],
+                )],
+                outputs: vec![Output {
+                    address: creator,
+                    content: OutputContent::Value(bitcoin::Amount::from_sat(
+                        1_000,
+                    )),
+                }],
+                data: TxData::SwapCancel { swap_id: swap.id.0 },
+                ..Default::default()
+            },
+            spent_utxos: vec![Output {
+                address: creator,
+                content: OutputContent::Value(bitcoin::Amount::from_sat(1_000)),
+            }],
+        };
+        let () = connect_swap_tx(&state, &mut rwtxn, &request, block_hash, 11)
+            .unwrap();
+        let requested = state.get_swap(&rwtxn, &swap.id).unwrap().unwrap();
+        assert_eq!(requested.state, SwapState::Pending);
+        assert_eq!(
+            requested.expires_at_height,
+            Some(11 + Swap::CANCEL_GRACE_BLOCKS)
+        );
+        let () =
+            disconnect_swap_tx(&state, &mut rwtxn, &request.transaction, 11)
+                .unwrap();
+        assert_eq!(
+            state.get_swap(&rwtxn, &swap.id).unwrap().as_ref(),
+            Some(&swap)
+        );
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            Some(swap.id)
+        );
+        assert_no_swap_history(&state, &rwtxn, &swap.id, 11);
+
+        // After expiry, a cancellation spends the locked output, and
+        // disconnecting it re-locks the output
+        swap.expires_at_height = Some(12);
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        let cancel = FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(locked_outpoint, [0; 32])],
+                outputs: vec![Output {
+                    address: creator,
+                    content: OutputContent::Value(swap.l2_amount),
+                }],
+                data: TxData::SwapCancel { swap_id: swap.id.0 },
+                ..Default::default()
+            },
+            spent_utxos: vec![Output {
+                address: creator,
+                content: OutputContent::SwapPending {
+                    value: swap.l2_amount,
+                    swap_id: swap.id.0,
+                },
+            }],
+        };
+        let () = connect_swap_tx(&state, &mut rwtxn, &cancel, block_hash, 20)
+            .unwrap();
+        let cancelled = state.get_swap(&rwtxn, &swap.id).unwrap().unwrap();
+        assert_eq!(cancelled.state, SwapState::Cancelled);
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            None
+        );
+        let () =
+            disconnect_swap_tx(&state, &mut rwtxn, &cancel.transaction, 20)
+                .unwrap();
+        assert_eq!(
+            state.get_swap(&rwtxn, &swap.id).unwrap().as_ref(),
+            Some(&swap)
+        );
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            Some(swap.id)
+        );
+        assert_no_swap_history(&state, &rwtxn, &swap.id, 20);
+    }
+}
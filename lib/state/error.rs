@@ -4,8 +4,8 @@ use transitive::Transitive;
 
 use crate::types::{
     AmountOverflowError, AmountUnderflowError, BlockHash,
-    ComputeMerkleRootError, M6id, MerkleRoot, OutPoint, SwapId, Txid,
-    UtreexoError, WithdrawalBundleError,
+    ComputeMerkleRootError, M6id, MerkleRoot, OutPoint, ParentChainType,
+    SpvProofError, SwapId, Txid, UtreexoError, WithdrawalBundleError,
 };
 
 #[derive(Debug, Error)]
@@ -42,6 +42,10 @@ pub enum InvalidHeader {
 pub enum Error {
     #[error("failed to verify authorization")]
     Authorization,
+    #[error(
+        "wrong number of authorizations: expected {expected}, but received {received}"
+    )]
+    AuthorizationCount { expected: usize, received: usize },
     #[error(transparent)]
     AmountOverflow(#[from] AmountOverflowError),
     #[error(transparent)]
@@ -115,4 +119,24 @@ pub enum Error {
     },
     #[error(transparent)]
     ParentChainRpc(#[from] crate::parent_chain_rpc::Error),
+    #[error("Invalid SPV proof for swap {swap_id}")]
+    SpvProof {
+        swap_id: SwapId,
+        source: SpvProofError,
+    },
+    #[error("Invalid L1 headers for {parent_chain}")]
+    L1Headers {
+        parent_chain: ParentChainType,
+        source: SpvProofError,
+    },
+}
+
+impl Error {
+    /// Returns `true` if the error shows that a transaction is invalid in
+    /// any state, e.g. because it is malformed or has a bad signature.
+    /// Other errors may only reflect this node's view, e.g. a UTXO that was
+    /// spent by a conflicting transaction, or that is not yet known.
+    pub fn is_state_independent(&self) -> bool {
+        matches!(self, Self::Authorization | Self::AuthorizationCount { .. })
+    }
 }
@@ -0,0 +1,646 @@
+//! L1 header chains proven by SwapClaim SPV proofs and TrackL1Headers
+//! transactions.
+//!
+//! The headers of every SPV proof must extend the parent chain's SPV
+//! checkpoint, or headers tracked from earlier proofs, with the expected
+//! difficulty. The tracked chain with the most work since the checkpoint is
+//! the best tracked chain. A proof must end on the best tracked chain, or
+//! on a fork with more work than it, so that a proof cannot branch off an
+//! old tracked header with less work than the L1 chain.
+//!
+//! Proofs are limited to [`MAX_SPV_HEADERS`] headers, so TrackL1Headers
+//! transactions advance the best tracked chain in batches when it falls
+//! behind L1.
+
+use std::collections::HashMap;
+
+use bitcoin::{BlockHash as L1BlockHash, block::Header as L1Header};
+use serde::{Deserialize, Serialize};
+use sneed::{RoTxn, RwTxn, db::error::Error as DbError};
+
+use crate::{
+    state::{Error, State},
+    types::{
+        ParentChainType, SpvParams, Transaction, TxData, Txid,
+        decode_l1_headers, verify_l1_headers,
+    },
+};
+
+#[cfg(doc)]
+use crate::types::MAX_SPV_HEADERS;
+
+const DIFFICULTY_ADJUSTMENT_INTERVAL: u32 = 2016;
+
+/// An L1 header proven by a connected SwapClaim or TrackL1Headers
+/// transaction
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct TrackedL1Header {
+    pub header: L1Header,
+    pub height: u32,
+    /// Total work of the header and its ancestors since the checkpoint
+    pub total_work: bitcoin::Work,
+}
+
+/// The checkpoint, or a tracked header, that the next header builds on
+#[derive(Clone, Copy)]
+enum Prev {
+    Checkpoint,
+    Header(TrackedL1Header),
+}
+
+pub fn try_get(
+    state: &State,
+    rotxn: &RoTxn,
+    parent_chain: ParentChainType,
+    block_hash: L1BlockHash,
+) -> Result<Option<TrackedL1Header>, Error> {
+    let res = state
+        .l1_headers
+        .try_get(rotxn, &(parent_chain, block_hash))
+        .map_err(DbError::from)?
+        .map(|(tracked, _)| tracked);
+    Ok(res)
+}
+
+/// Tip of the best tracked chain, or `None` if no headers are tracked for
+/// the parent chain
+pub fn tip(
+    state: &State,
+    rotxn: &RoTxn,
+    parent_chain: ParentChainType,
+) -> Result<Option<TrackedL1Header>, Error> {
+    let Some((block_hash, _)) = state
+        .l1_header_tips
+        .try_get(rotxn, &parent_chain)
+        .map_err(DbError::from)?
+        .and_then(|tips| tips.last().copied())
+    else {
+        return Ok(None);
+    };
+    try_get(state, rotxn, parent_chain, block_hash)
+}
+
+/// Check the difficulty of `header` at `height`, which builds on `prev`.
+///
+/// Outside of retargets, the bits must not change. At a retarget, the bits
+/// are recomputed from the first header of the epoch if it is tracked.
+/// Otherwise, as for the first retarget after the checkpoint, the target
+/// may change by at most the factor of four that L1 consensus allows.
+fn check_bits(
+    lookup: impl Fn(L1BlockHash) -> Result<Option<TrackedL1Header>, Error>,
+    params: &SpvParams,
+    prev: Prev,
+    header: &L1Header,
+    height: u32,
+) -> Result<(), Error> {
+    let prev_bits = match prev {
+        Prev::Checkpoint => {
+            bitcoin::CompactTarget::from_consensus(params.checkpoint.bits)
+        }
+        Prev::Header(prev) => prev.header.bits,
+    };
+    let unexpected_bits = || {
+        Error::InvalidTransaction(format!(
+            "L1 header {} at height {height} has unexpected bits {:#010x}",
+            header.block_hash(),
+            header.bits.to_consensus()
+        ))
+    };
+    if height % DIFFICULTY_ADJUSTMENT_INTERVAL != 0 {
+        if header.bits != prev_bits {
+            return Err(unexpected_bits());
+        }
+        return Ok(());
+    }
+    if let Prev::Header(prev) = prev {
+        let epoch_start_height = height - DIFFICULTY_ADJUSTMENT_INTERVAL;
+        let mut epoch_start = Some(prev);
+        while let Some(tracked) = epoch_start
+            && tracked.height > epoch_start_height
+        {
+            epoch_start = lookup(tracked.header.prev_blockhash)?;
+        }
+        if let Some(epoch_start) = epoch_start {
+            let expected =
+                bitcoin::CompactTarget::from_header_difficulty_adjustment(
+                    epoch_start.header,
+                    prev.header,
+                    params.bitcoin_network,
+                );
+            if header.bits != expected {
+                return Err(unexpected_bits());
+            }
+            return Ok(());
+        }
+    }
+    let prev_target = bitcoin::Target::from_compact(prev_bits);
+    let min_target = bitcoin::Target::from_compact(
+        prev_target.min_transition_threshold().to_compact_lossy(),
+    );
+    let max_target =
+        prev_target.max_transition_threshold(params.bitcoin_network);
+    if !(min_target..=max_target).contains(&header.target()) {
+        return Err(unexpected_bits());
+    }
+    Ok(())
+}
+
+/// Connect the headers of an SPV proof to the checkpoint or to tracked
+/// headers. Returns every header in the proof, with the headers that are
+/// not tracked yet.
+///
+/// Proof of work and the target floor are checked by
+/// [`crate::types::SpvProof::verify`].
+fn connect_headers<'a>(
+    state: &State,
+    rotxn: &RoTxn,
+    parent_chain: ParentChainType,
+    params: &SpvParams,
+    headers: impl IntoIterator<Item = &'a L1Header>,
+) -> Result<(Vec<TrackedL1Header>, Vec<TrackedL1Header>), Error> {
+    let mut connected = Vec::<TrackedL1Header>::new();
+    let mut new_headers = HashMap::<L1BlockHash, TrackedL1Header>::new();
+    let mut untracked = Vec::new();
+    for header in headers {
+        let block_hash = header.block_hash();
+        if let Some(tracked) = try_get(state, rotxn, parent_chain, block_hash)?
+        {
+            connected.push(tracked);
+            continue;
+        }
+        let prev = match connected.last() {
+            Some(prev) => Prev::Header(*prev),
+            None if header.prev_blockhash == params.checkpoint.block_hash => {
+                Prev::Checkpoint
+            }
+            None => match try_get(
+                state,
+                rotxn,
+                parent_chain,
+                header.prev_blockhash,
+            )? {
+                Some(prev) => Prev::Header(prev),
+                None => {
+                    return Err(Error::InvalidTransaction(format!(
+                        "L1 header {block_hash} does not build on the {} SPV checkpoint or on a tracked header",
+                        parent_chain.coin_name()
+                    )));
+                }
+            },
+        };
+        let (height, prev_work) = match prev {
+            Prev::Checkpoint => (
+                params.checkpoint.height + 1,
+                bitcoin::Work::from_be_bytes([0; 32]),
+            ),
+            Prev::Header(prev) => (prev.height + 1, prev.total_work),
+        };
+        check_bits(
+            |block_hash| match new_headers.get(&block_hash) {
+                Some(tracked) => Ok(Some(*tracked)),
+                None => try_get(state, rotxn, parent_chain, block_hash),
+            },
+            params,
+            prev,
+            header,
+            height,
+        )?;
+        let tracked = TrackedL1Header {
+            header: *header,
+            height,
+            total_work: prev_work + header.work(),
+        };
+        new_headers.insert(block_hash, tracked);
+        untracked.push(tracked);
+        connected.push(tracked);
+    }
+    Ok((connected, untracked))
+}
+
+/// Returns `true` if `header` is the tip of the best tracked chain, or one
+/// of its ancestors
+fn is_on_best_chain(
+    state: &State,
+    rotxn: &RoTxn,
+    parent_chain: ParentChainType,
+    tip: &TrackedL1Header,
+    header: &TrackedL1Header,
+) -> Result<bool, Error> {
+    let block_hash = header.header.block_hash();
+    let mut ancestor = *tip;
+    while ancestor.height > header.height {
+        match try_get(
+            state,
+            rotxn,
+            parent_chain,
+            ancestor.header.prev_blockhash,
+        )? {
+            Some(prev) => ancestor = prev,
+            None => return Ok(false),
+        }
+    }
+    Ok(ancestor.height == header.height
+        && ancestor.header.block_hash() == block_hash)
+}
+
+/// Validate the headers of an SPV proof against the checkpoint and the
+/// tracked headers. The last header must be on the best tracked chain, or
+/// have more total work than its tip.
+pub fn validate_headers<'a>(
+    state: &State,
+    rotxn: &RoTxn,
+    parent_chain: ParentChainType,
+    params: &SpvParams,
+    headers: impl IntoIterator<Item = &'a L1Header>,
+) -> Result<(), Error> {
+    let (connected, _) =
+        connect_headers(state, rotxn, parent_chain, params, headers)?;
+    let Some(last) = connected.last() else {
+        return Err(Error::InvalidTransaction(
+            "SPV proof has no L1 headers".to_string(),
+        ));
+    };
+    let Some(tip) = tip(state, rotxn, parent_chain)? else {
+        return Ok(());
+    };
+    if last.total_work > tip.total_work
+        || is_on_best_chain(state, rotxn, parent_chain, &tip, last)?
+    {
+        return Ok(());
+    }
+    Err(Error::InvalidTransaction(format!(
+        "L1 header {} at height {} is not on the best tracked {} chain, and has less work than its tip {} at height {}",
+        last.header.block_hash(),
+        last.height,
+        parent_chain.coin_name(),
+        tip.header.block_hash(),
+        tip.height
+    )))
+}
+
+/// Validate a TrackL1Headers transaction, returning the parent chain's
+/// SPV parameters and the decoded headers.
+///
+/// The headers must connect to the checkpoint or to tracked headers, and
+/// the last header must have more work than the tip of the best tracked
+/// chain, so that each such transaction advances the best tracked chain.
+pub fn validate_track_headers(
+    state: &State,
+    rotxn: &RoTxn,
+    transaction: &Transaction,
+) -> Result<(SpvParams, Vec<L1Header>), Error> {
+    let TxData::TrackL1Headers {
+        parent_chain,
+        headers,
+    } = &transaction.data
+    else {
+        return Err(Error::InvalidTransaction(
+            "Expected TrackL1Headers transaction".to_string(),
+        ));
+    };
+    let parent_chain = *parent_chain;
+    let Some(params) = parent_chain.spv_params() else {
+        return Err(Error::InvalidTransaction(format!(
+            "SPV proofs are not supported for {}",
+            parent_chain.coin_name()
+        )));
+    };
+    let l1_headers_err = |source| Error::L1Headers {
+        parent_chain,
+        source,
+    };
+    let headers = decode_l1_headers(headers).map_err(l1_headers_err)?;
+    let () = verify_l1_headers(&headers, params.max_target)
+        .map_err(l1_headers_err)?;
+    let (connected, _) =
+        connect_headers(state, rotxn, parent_chain, &params, &headers)?;
+    let last = connected.last().expect("verified headers are not empty");
+    if let Some(tip) = tip(state, rotxn, parent_chain)?
+        && last.total_work <= tip.total_work
+    {
+        return Err(Error::InvalidTransaction(format!(
+            "L1 header {} at height {} does not have more work than the best tracked {} chain, with tip {} at height {}",
+            last.header.block_hash(),
+            last.height,
+            parent_chain.coin_name(),
+            tip.header.block_hash(),
+            tip.height
+        )));
+    }
+    Ok((params, headers))
+}
+
+/// Track the headers of a connected SwapClaim's SPV proof, or of a
+/// TrackL1Headers transaction. Headers that are already tracked are left
+/// unchanged. If the last header has more work than the tip, it becomes the
+/// tip of the best tracked chain.
+pub fn track_headers<'a>(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    parent_chain: ParentChainType,
+    params: &SpvParams,
+    headers: impl IntoIterator<Item = &'a L1Header>,
+    txid: Txid,
+) -> Result<(), Error> {
+    let (connected, untracked) =
+        connect_headers(state, rwtxn, parent_chain, params, headers)?;
+    for tracked in &untracked {
+        state
+            .l1_headers
+            .put(
+                rwtxn,
+                &(parent_chain, tracked.header.block_hash()),
+                &(*tracked, txid),
+            )
+            .map_err(DbError::from)?;
+    }
+    let Some(last) = connected.last() else {
+        return Ok(());
+    };
+    let tip = tip(state, rwtxn, parent_chain)?;
+    if tip.is_none_or(|tip| last.total_work > tip.total_work) {
+        let mut tips = state
+            .l1_header_tips
+            .try_get(rwtxn, &parent_chain)
+            .map_err(DbError::from)?
+            .unwrap_or_default();
+        tips.push((last.header.block_hash(), txid));
+        state
+            .l1_header_tips
+            .put(rwtxn, &parent_chain, &tips)
+            .map_err(DbError::from)?;
+    }
+    Ok(())
+}
+
+/// Stop tracking the headers that were added by a disconnected SwapClaim
+/// or TrackL1Headers transaction, and restore the tip from before it
+pub fn untrack_headers<'a>(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    parent_chain: ParentChainType,
+    headers: impl DoubleEndedIterator<Item = &'a L1Header>,
+    txid: Txid,
+) -> Result<(), Error> {
+    for header in headers.rev() {
+        let key = (parent_chain, header.block_hash());
+        if let Some((_, added_by)) = state
+            .l1_headers
+            .try_get(rwtxn, &key)
+            .map_err(DbError::from)?
+            && added_by == txid
+        {
+            state
+                .l1_headers
+                .delete(rwtxn, &key)
+                .map_err(DbError::from)?;
+        }
+    }
+    if let Some(mut tips) = state
+        .l1_header_tips
+        .try_get(rwtxn, &parent_chain)
+        .map_err(DbError::from)?
+        && tips.last().is_some_and(|(_, set_by)| *set_by == txid)
+    {
+        tips.pop();
+        if tips.is_empty() {
+            state
+                .l1_header_tips
+                .delete(rwtxn, &parent_chain)
+                .map_err(DbError::from)?;
+        } else {
+            state
+                .l1_header_tips
+                .put(rwtxn, &parent_chain, &tips)
+                .map_err(DbError::from)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{CompactTarget, TxMerkleNode, block, hashes::Hash as _};
+
+    use super::{
+        tip, track_headers, untrack_headers, validate_headers,
+        validate_track_headers,
+    };
+    use crate::{
+        state::{Error, test_utils::open_state},
+        types::{
+            MAX_SPV_HEADERS, ParentChainType, SpvCheckpoint, SpvParams,
+            SpvProofError, Transaction, TxData, Txid,
+        },
+    };
+
+    const REGTEST_BITS: u32 = 0x207fffff;
+
+    fn checkpoint_hash() -> bitcoin::BlockHash {
+        bitcoin::BlockHash::from_byte_array([1; 32])
+    }
+
+    fn spv_params() -> SpvParams {
+        SpvParams {
+            max_target: CompactTarget::from_consensus(REGTEST_BITS).into(),
+            checkpoint: SpvCheckpoint {
+                height: 100,
+                block_hash: checkpoint_hash(),
+                bits: REGTEST_BITS,
+            },
+            bitcoin_network: bitcoin::Network::Regtest,
+        }
+    }
+
+    /// Mine `len` regtest headers on top of `prev_blockhash`. `salt`
+    /// distinguishes forks.
+    fn mine_chain(
+        prev_blockhash: bitcoin::BlockHash,
+        len: usize,
+        salt: u8,
+        bits: u32,
+    ) -> Vec<block::Header> {
+        let mut headers: Vec<block::Header> = Vec::with_capacity(len);
+        let mut prev_blockhash = prev_blockhash;
+        for _ in 0..len {
+            let mut header = block::Header {
+                version: block::Version::TWO,
+                prev_blockhash,
+                merkle_root: TxMerkleNode::from_byte_array([salt; 32]),
+                time: 1_700_000_000,
+                bits: CompactTarget::from_consensus(bits),
+                nonce: 0,
+            };
+            while header.validate_pow(header.target()).is_err() {
+                header.nonce += 1;
+            }
+            prev_blockhash = header.block_hash();
+            headers.push(header);
+        }
+        headers
+    }
+
+    #[test]
+    fn first_proof_must_build_on_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let chain = ParentChainType::Regtest;
+        let params = spv_params();
+        let rotxn = env.read_txn().unwrap();
+        let unanchored = mine_chain(
+            bitcoin::BlockHash::from_byte_array([2; 32]),
+            3,
+            0,
+            REGTEST_BITS,
+        );
+        assert!(
+            validate_headers(&state, &rotxn, chain, &params, &unanchored)
+                .is_err()
+        );
+        let anchored = mine_chain(checkpoint_hash(), 3, 0, REGTEST_BITS);
+        validate_headers(&state, &rotxn, chain, &params, &anchored).unwrap();
+        let mut wrong_bits = anchored[..2].to_vec();
+        wrong_bits.extend(mine_chain(
+            anchored[1].block_hash(),
+            1,
+            0,
+            0x207ffffe,
+        ));
+        assert!(
+            validate_headers(&state, &rotxn, chain, &params, &wrong_bits)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn forks_need_more_work_than_best_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let chain = ParentChainType::Regtest;
+        let params = spv_params();
+        let claim_a = Txid([0xa; 32]);
+        let claim_b = Txid([0xb; 32]);
+        let chain_a = mine_chain(checkpoint_hash(), 3, 0, REGTEST_BITS);
+        let mut rwtxn = env.write_txn().unwrap();
+        track_headers(&state, &mut rwtxn, chain, &params, &chain_a, claim_a)
+            .unwrap();
+
+        // A proof within the best chain is valid
+        validate_headers(&state, &rwtxn, chain, &params, &chain_a[..2])
+            .unwrap();
+        // A fork with less work is not, even from a tracked header
+        let short_fork =
+            mine_chain(chain_a[0].block_hash(), 1, 1, REGTEST_BITS);
+        assert!(
+            validate_headers(&state, &rwtxn, chain, &params, &short_fork)
+                .is_err()
+        );
+        let short_fork = mine_chain(checkpoint_hash(), 3, 1, REGTEST_BITS);
+        assert!(
+            validate_headers(&state, &rwtxn, chain, &params, &short_fork)
+                .is_err()
+        );
+        // A fork with more work becomes the best chain
+        let chain_b = mine_chain(chain_a[0].block_hash(), 3, 2, REGTEST_BITS);
+        validate_headers(&state, &rwtxn, chain, &params, &chain_b).unwrap();
+        track_headers(&state, &mut rwtxn, chain, &params, &chain_b, claim_b)
+            .unwrap();
+        let best_tip = tip(&state, &rwtxn, chain).unwrap().unwrap();
+        assert_eq!(best_tip.header, chain_b[2]);
+        assert_eq!(best_tip.height, 104);
+        // The old tip is no longer on the best chain
+        assert!(
+            validate_headers(&state, &rwtxn, chain, &params, &chain_a[1..])
+                .is_err()
+        );
+
+        // Disconnecting the fork's claim restores the previous tip
+        untrack_headers(&state, &mut rwtxn, chain, chain_b.iter(), claim_b)
+            .unwrap();
+        let best_tip = tip(&state, &rwtxn, chain).unwrap().unwrap();
+        assert_eq!(best_tip.header, chain_a[2]);
+        validate_headers(&state, &rwtxn, chain, &params, &chain_a[1..])
+            .unwrap();
+        untrack_headers(&state, &mut rwtxn, chain, chain_a.iter(), claim_a)
+            .unwrap();
+        assert!(tip(&state, &rwtxn, chain).unwrap().is_none());
+    }
+
+    fn track_tx(
+        parent_chain: ParentChainType,
+        headers: &[block::Header],
+    ) -> Transaction {
+        Transaction {
+            data: TxData::TrackL1Headers {
+                parent_chain,
+                headers: headers
+                    .iter()
+                    .map(bitcoin::consensus::serialize)
+                    .collect(),
+            },
+            ..Transaction::default()
+        }
+    }
+
+    #[test]
+    fn tracked_headers_must_advance_best_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let chain = ParentChainType::Regtest;
+        let genesis =
+            bitcoin::constants::genesis_block(bitcoin::Network::Regtest)
+                .block_hash();
+        let headers = mine_chain(genesis, 3, 0, REGTEST_BITS);
+        let tx = track_tx(chain, &headers);
+        let mut rwtxn = env.write_txn().unwrap();
+        let (params, decoded) =
+            validate_track_headers(&state, &rwtxn, &tx).unwrap();
+        assert_eq!(decoded, headers);
+        track_headers(&state, &mut rwtxn, chain, &params, &decoded, tx.txid())
+            .unwrap();
+        assert_eq!(tip(&state, &rwtxn, chain).unwrap().unwrap().height, 3);
+
+        // Headers that are already tracked do not advance the best chain
+        assert!(validate_track_headers(&state, &rwtxn, &tx).is_err());
+        let tracked = track_tx(chain, &headers[..2]);
+        assert!(validate_track_headers(&state, &rwtxn, &tracked).is_err());
+        assert!(matches!(
+            validate_track_headers(&state, &rwtxn, &track_tx(chain, &[])),
+            Err(Error::L1Headers {
+                source: SpvProofError::NoHeaders,
+                ..
+            })
+        ));
+        let too_many = track_tx(chain, &vec![headers[0]; MAX_SPV_HEADERS + 1]);
+        assert!(matches!(
+            validate_track_headers(&state, &rwtxn, &too_many),
+            Err(Error::L1Headers {
+                source: SpvProofError::TooManyHeaders { .. },
+                ..
+            })
+        ));
+        let ltc = track_tx(ParentChainType::LTC, &headers);
+        assert!(validate_track_headers(&state, &rwtxn, &ltc).is_err());
+
+        // The next batch extends the best chain, and is untracked when its
+        // block is disconnected
+        let next = mine_chain(headers[2].block_hash(), 2, 0, REGTEST_BITS);
+        let next_tx = track_tx(chain, &next);
+        let (params, decoded) =
+            validate_track_headers(&state, &rwtxn, &next_tx).unwrap();
+        track_headers(
+            &state,
+            &mut rwtxn,
+            chain,
+            &params,
+            &decoded,
+            next_tx.txid(),
+        )
+        .unwrap();
+        assert_eq!(tip(&state, &rwtxn, chain).unwrap().unwrap().height, 5);
+        untrack_headers(&state, &mut rwtxn, chain, next.iter(), next_tx.txid())
+            .unwrap();
+        assert_eq!(tip(&state, &rwtxn, chain).unwrap().unwrap().height, 3);
+        validate_track_headers(&state, &rwtxn, &next_tx).unwrap();
+    }
+}
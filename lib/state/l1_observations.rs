@@ -0,0 +1,335 @@
+//! L1 observations for swaps, fetched ahead of block connection.
+//!
+//! L1 requests must not be awaited while the sidechain write transaction is
+//! held. Candidate L1 payments are fetched concurrently for every swap that
+//! block connection may check, and are then applied when the two-way peg
+//! data is connected.
+//!
+//! Observations depend on this node's L1 endpoints, so they are recorded
+//! as a [`SwapL1Status`] beside the swap, and never change consensus state.
+
+use std::{collections::HashMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use sneed::RoTxn;
+
+use crate::{
+    parent_chain_rpc::{self, ParentChainBackend, TransactionInfo},
+    state::{Error, State},
+    types::{
+        Address, BlockHash, Body, L1MatchPolicy, ParentChainType, Swap, SwapId,
+        SwapState, SwapTxId, TxData,
+    },
+};
+
+/// L1 payment of a Pending swap, as observed by this node's L1 endpoints,
+/// or set manually.
+///
+/// This is local to the node, and is never consulted when validating
+/// transactions or blocks. Claims must prove the payment with an SPV proof.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SwapL1Status {
+    pub l1_txid: SwapTxId,
+    /// `WaitingConfirmations` or `ReadyToClaim`
+    pub state: SwapState,
+    pub l1_claimer_address: Option<String>,
+    pub l2_claimer_address: Option<Address>,
+    /// Sidechain block at which the payment was last observed
+    pub observed_at: (BlockHash, u32),
+    /// L1 amount filled when the payment was observed. The status is stale
+    /// once another fill is claimed.
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub l1_amount_filled: bitcoin::Amount,
+}
+
+impl SwapL1Status {
+    /// Status of a swap to which an L1 payment was applied, or `None` if
+    /// the swap is Pending
+    pub fn from_swap(swap: &Swap) -> Option<Self> {
+        if !matches!(
+            swap.state,
+            SwapState::WaitingConfirmations(..) | SwapState::ReadyToClaim
+        ) {
+            return None;
+        }
+        Some(Self {
+            l1_txid: swap.l1_txid.clone(),
+            state: swap.state.clone(),
+            l1_claimer_address: swap.l1_claimer_address.clone(),
+            l2_claimer_address: swap.l2_claimer_address,
+            observed_at: (
+                swap.l1_txid_validated_at_block_hash?,
+                swap.l1_txid_validated_at_height?,
+            ),
+            l1_amount_filled: swap.l1_amount_filled,
+        })
+    }
+
+    /// Apply the observed payment to a Pending swap. Swaps in any other
+    /// state, or that were filled since, are left unchanged.
+    pub fn apply(&self, swap: &mut Swap) {
+        if !matches!(swap.state, SwapState::Pending)
+            || swap.l1_amount_filled != self.l1_amount_filled
+        {
+            return;
+        }
+        swap.l1_txid = self.l1_txid.clone();
+        swap.state = self.state.clone();
+        swap.l1_claimer_address = self.l1_claimer_address.clone();
+        swap.l2_claimer_address = self.l2_claimer_address;
+        let (block_hash, block_height) = self.observed_at;
+        swap.set_l1_txid_validation_block(block_hash, block_height);
+    }
+}
+
+/// Lookup of the L1 payments that may fill a swap
+#[derive(Clone, Debug)]
+pub struct L1Query {
+    pub swap_id: SwapId,
+    pub parent_chain: ParentChainType,
+    pub l1_recipient: String,
+    /// Amount that a payment must match under `match_policy`, or for swaps
+    /// that accept partial fills, the most that a payment may fill
+    pub l1_amount: bitcoin::Amount,
+    pub partial_fills: bool,
+    pub match_policy: L1MatchPolicy,
+    /// L1 payment recorded for a swap that is waiting for confirmations,
+    /// which is looked up even if it no longer matches
+    pub l1_txid: Option<SwapTxId>,
+}
+
+impl L1Query {
+    /// Query for a swap that is waiting for its L1 payment
+    pub fn for_swap(swap: &Swap) -> Option<Self> {
+        if !matches!(
+            swap.state,
+            SwapState::Pending | SwapState::WaitingConfirmations(..)
+        ) {
+            return None;
+        }
+        let l1_amount = if swap.partial_fills {
+            swap.remaining_l1_amount()
+        } else {
+            swap.l1_amount?
+        };
+        let l1_txid = matches!(swap.state, SwapState::WaitingConfirmations(..))
+            .then(|| swap.l1_txid.clone());
+        Some(Self {
+            swap_id: swap.id,
+            parent_chain: swap.parent_chain,
+            l1_recipient: swap.l1_recipient_address.clone()?,
+            l1_amount,
+            partial_fills: swap.partial_fills,
+            match_policy: swap.l1_match_policy,
+            l1_txid,
+        })
+    }
+
+    /// Find candidate L1 payments, as (sender_address, tx_info).
+    /// The recorded L1 payment is included if the backend still knows it,
+    /// so that its absence shows that it has left the L1 chain.
+    pub async fn fetch(
+        &self,
+        backend: &dyn ParentChainBackend,
+    ) -> Result<Vec<(String, TransactionInfo)>, parent_chain_rpc::Error> {
+        let mut candidates = if self.partial_fills {
+            backend
+                .find_transactions_by_address_up_to_amount(
+                    &self.l1_recipient,
+                    self.l1_amount.to_sat(),
+                )
+                .await?
+        } else {
+            backend
+                .find_transactions_by_address_and_amount(
+                    &self.l1_recipient,
+                    self.l1_amount.to_sat(),
+                    self.match_policy,
+                )
+                .await?
+        };
+        let Some(l1_txid) = &self.l1_txid else {
+            return Ok(candidates);
+        };
+        let is_recorded = |tx_info: &TransactionInfo| {
+            SwapTxId::from_hex_rpc(&tx_info.txid)
+                .is_ok_and(|txid| txid == *l1_txid)
+        };
+        if !candidates.iter().any(|(_, tx_info)| is_recorded(tx_info)) {
+            match backend.get_transaction(&l1_txid.to_hex_rpc()).await {
+                Ok(tx_info) => candidates.push(("unknown".to_owned(), tx_info)),
+                Err(parent_chain_rpc::Error::TransactionNotFound) => (),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+/// Queries for the swaps that may be checked against L1 when `bodies` are
+/// connected, in order, on top of the current tip. This includes swaps
+/// created or locked in `bodies`.
+pub fn l1_queries(
+    state: &State,
+    rotxn: &RoTxn,
+    bodies: &[Body],
+) -> Result<Vec<L1Query>, Error> {
+    let mut queries: HashMap<SwapId, L1Query> = state
+        .load_all_swaps_with_l1_status(rotxn)?
+        .iter()
+        .filter_map(L1Query::for_swap)
+        .map(|query| (query.swap_id, query))
+        .collect();
+    // L1 → L2 swaps created in `bodies`, that are not yet locked
+    let mut unlocked: HashMap<
+        SwapId,
+        (ParentChainType, bitcoin::Amount, L1MatchPolicy),
+    > = HashMap::new();
+    let txs = bodies.iter().flat_map(|body| &body.transactions);
+    for tx in txs {
+        match &tx.data {
+            TxData::SwapCreate {
+                swap_id,
+                parent_chain,
+                l1_recipient_address,
+                l1_amount: Some(l1_amount),
+                allow_partial_fills,
+                l1_match_policy,
+                ..
+            } => {
+                let swap_id = SwapId(*swap_id);
+                let l1_amount = bitcoin::Amount::from_sat(*l1_amount);
+                if let Some(l1_recipient) = l1_recipient_address {
+                    let query = L1Query {
+                        swap_id,
+                        parent_chain: *parent_chain,
+                        l1_recipient: l1_recipient.clone(),
+                        l1_amount,
+                        partial_fills: *allow_partial_fills,
+                        match_policy: *l1_match_policy,
+                        l1_txid: None,
+                    };
+                    queries.insert(swap_id, query);
+                } else {
+                    unlocked.insert(
+                        swap_id,
+                        (*parent_chain, l1_amount, *l1_match_policy),
+                    );
+                }
+            }
+            TxData::SwapLock {
+                swap_id,
+                l1_recipient_address,
+            } => {
+                let swap_id = SwapId(*swap_id);
+                let swap_params = match unlocked.remove(&swap_id) {
+                    Some(swap_params) => Some(swap_params),
+                    None => state.get_swap(rotxn, &swap_id)?.and_then(|swap| {
+                        Some((
+                            swap.parent_chain,
+                            swap.l1_amount?,
+                            swap.l1_match_policy,
+                        ))
+                    }),
+                };
+                if let Some((parent_chain, l1_amount, match_policy)) =
+                    swap_params
+                {
+                    let query = L1Query {
+                        swap_id,
+                        parent_chain,
+                        l1_recipient: l1_recipient_address.clone(),
+                        l1_amount,
+                        partial_fills: false,
+                        match_policy,
+                        l1_txid: None,
+                    };
+                    queries.insert(swap_id, query);
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(queries.into_values().collect())
+}
+
+/// Candidate L1 payments for swaps, as (sender_address, tx_info).
+/// Swaps that were not observed have no entry, e.g. because no backend is
+/// configured for their parent chain, or because the backend request failed.
+#[derive(Debug, Default)]
+pub struct L1Observations {
+    candidates: HashMap<SwapId, Vec<(String, TransactionInfo)>>,
+    /// Recorded L1 payments that were looked up for swaps
+    l1_txids: HashMap<SwapId, SwapTxId>,
+}
+
+impl L1Observations {
+    /// Run the queries concurrently, each against its backend
+    pub async fn fetch<I>(queries: I) -> Self
+    where
+        I: IntoIterator<Item = (L1Query, Arc<dyn ParentChainBackend>)>,
+    {
+        let queries = queries.into_iter().map(|(query, backend)| async move {
+            let res = query.fetch(backend.as_ref()).await;
+            (query, backend, res)
+        });
+        let mut observations = Self::default();
+        for (query, backend, res) in futures::future::join_all(queries).await {
+            match res {
+                Ok(candidates) => {
+                    observations.insert(&query, candidates);
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        swap_id = %query.swap_id,
+                        parent_chain = ?query.parent_chain,
+                        l1_recipient = %query.l1_recipient,
+                        endpoint = %backend.endpoint(),
+                        error = %err,
+                        "Failed to query L1 for swap; swap will stay pending until the query succeeds or l1_txid is set manually"
+                    );
+                }
+            }
+        }
+        observations
+    }
+
+    /// Record the result of a query
+    pub fn insert(
+        &mut self,
+        query: &L1Query,
+        candidates: Vec<(String, TransactionInfo)>,
+    ) {
+        self.candidates.insert(query.swap_id, candidates);
+        match &query.l1_txid {
+            Some(l1_txid) => {
+                self.l1_txids.insert(query.swap_id, l1_txid.clone());
+            }
+            None => {
+                self.l1_txids.remove(&query.swap_id);
+            }
+        }
+    }
+
+    /// Candidate L1 payments for a swap, if it was observed
+    pub fn get(
+        &self,
+        swap_id: &SwapId,
+    ) -> Option<&[(String, TransactionInfo)]> {
+        self.candidates.get(swap_id).map(Vec::as_slice)
+    }
+
+    /// Recorded L1 payment that was looked up for a swap. If it is not
+    /// among the swap's candidates, the backend did not know it.
+    pub fn l1_txid(&self, swap_id: &SwapId) -> Option<&SwapTxId> {
+        self.l1_txids.get(swap_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+}
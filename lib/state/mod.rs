@@ -19,21 +19,27 @@ use crate::{
         Accumulator, Address, AmountOverflowError, AmountUnderflowError,
         Authorized, AuthorizedTransaction, BlockHash, Body, FilledTransaction,
         GetAddress, GetValue, Header, InPoint, M6id, MerkleRoot, OutPoint,
-        OutPointKey, Output, ParentChainType, PointedOutput, SpentOutput, Swap,
-        SwapId, SwapState, SwapTxId, Transaction, TxData, VERSION, Verify,
-        Version, WithdrawalBundle, WithdrawalBundleStatus,
-        proto::mainchain::TwoWayPegData,
+        OutPointKey, Output, ParentChainType, PointedOutput, SpentOutput,
+        SpvProof, Swap, SwapId, SwapState, SwapTxId, Transaction, TxData, Txid,
+        VERSION, Verify, Version, WithdrawalBundle, WithdrawalBundleStatus,
+        decode_l1_headers, proto::mainchain::TwoWayPegData,
     },
     util::Watchable,
 };
 
 mod block;
 mod error;
+mod l1_headers;
+mod l1_observations;
 mod rollback;
 mod swap;
+#[cfg(test)]
+mod test_utils;
 mod two_way_peg_data;
 
 pub use error::Error;
+pub use l1_headers::TrackedL1Header;
+pub use l1_observations::{L1Observations, L1Query, SwapL1Status};
 use rollback::RollBack;
 
 pub const WITHDRAWAL_BUNDLE_FAILURE_GAP: u32 = 4;
@@ -90,6 +96,24 @@ impl WithdrawalBundleInfo {
     }
 }
 
+/// Where a swap update made while connecting a block came from
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+enum SwapUpdateSource {
+    /// A transaction in the block body
+    Body,
+    /// Two-way peg data connected with the block
+    TwoWayPegData,
+}
+
+/// A swap record as it was before an update made while connecting a block
+#[derive(Debug, Deserialize, Serialize)]
+struct SwapUpdate {
+    source: SwapUpdateSource,
+    prev_swap: Swap,
+    /// Outputs that were unlocked from the swap by the update
+    unlocked_outputs: Vec<OutPoint>,
+}
+
 #[derive(Clone)]
 pub struct State {
     /// Current tip
@@ -134,11 +158,38 @@ pub struct State {
         DatabaseUnique<SerdeBincode<Address>, SerdeBincode<Vec<SwapId>>>,
     /// Tracks which outputs are locked to which swap
     pub locked_swap_outputs: DatabaseUnique<OutPointKey, SerdeBincode<SwapId>>,
+    /// L1 headers proven by connected SwapClaim SPV proofs and
+    /// TrackL1Headers transactions, and the transaction that first added
+    /// each header
+    pub l1_headers: DatabaseUnique<
+        SerdeBincode<(ParentChainType, bitcoin::BlockHash)>,
+        SerdeBincode<(TrackedL1Header, Txid)>,
+    >,
+    /// Tips of the best tracked L1 header chain of each parent chain, and
+    /// the transactions that set them, oldest first
+    l1_header_tips: DatabaseUnique<
+        SerdeBincode<ParentChainType>,
+        SerdeBincode<Vec<(bitcoin::BlockHash, Txid)>>,
+    >,
+    /// L1 payments of Pending swaps observed by this node. Local to the
+    /// node, and never consulted by validation.
+    swap_l1_statuses:
+        DatabaseUnique<SerdeBincode<SwapId>, SerdeBincode<SwapL1Status>>,
+    /// Swap records as they were before each update made while connecting
+    /// blocks, stamped with the height of the update
+    swap_history: DatabaseUnique<
+        SerdeBincode<SwapId>,
+        SerdeBincode<RollBack<SwapUpdate>>,
+    >,
+    /// Swaps with an update in `swap_history` at each height, so that
+    /// disconnecting a block does not scan the history of every swap
+    swap_history_heights:
+        DatabaseUnique<SerdeBincode<u32>, SerdeBincode<Vec<SwapId>>>,
     _version: DatabaseUnique<UnitKey, SerdeBincode<Version>>,
 }
 
 impl State {
-    pub const NUM_DBS: u32 = 15;
+    pub const NUM_DBS: u32 = 20;
 
     pub fn new(env: &sneed::Env) -> Result<Self, Error> {
         let mut rwtxn = env.write_txn().map_err(EnvError::from)?;
@@ -188,6 +239,20 @@ impl State {
         let locked_swap_outputs =
             DatabaseUnique::create(env, &mut rwtxn, "locked_swap_outputs")
                 .map_err(EnvError::from)?;
+        let l1_headers = DatabaseUnique::create(env, &mut rwtxn, "l1_headers")
+            .map_err(EnvError::from)?;
+        let l1_header_tips =
+            DatabaseUnique::create(env, &mut rwtxn, "l1_header_tips")
+                .map_err(EnvError::from)?;
+        let swap_l1_statuses =
+            DatabaseUnique::create(env, &mut rwtxn, "swap_l1_statuses")
+                .map_err(EnvError::from)?;
+        let swap_history =
+            DatabaseUnique::create(env, &mut rwtxn, "swap_history")
+                .map_err(EnvError::from)?;
+        let swap_history_heights =
+            DatabaseUnique::create(env, &mut rwtxn, "swap_history_heights")
+                .map_err(EnvError::from)?;
         let version = DatabaseUnique::create(env, &mut rwtxn, "state_version")
             .map_err(EnvError::from)?;
         if version
@@ -215,6 +280,11 @@ impl State {
             swaps_by_l1_txid,
             swaps_by_recipient,
             locked_swap_outputs,
+            l1_headers,
+            l1_header_tips,
+            swap_l1_statuses,
+            swap_history,
+            swap_history_heights,
             _version: version,
         })
     }
@@ -387,13 +457,36 @@ impl State {
             .ok_or_else(|| AmountUnderflowError.into())
     }
 
+    /// Checks that do not depend on the state: that there is one
+    /// authorization per input, and that the signatures are valid
+    pub fn validate_transaction_stateless(
+        transaction: &AuthorizedTransaction,
+    ) -> Result<(), Error> {
+        let expected = transaction.transaction.inputs.len();
+        let received = transaction.authorizations.len();
+        if received != expected {
+            return Err(Error::AuthorizationCount { expected, received });
+        }
+        if Authorization::verify_transaction(transaction).is_err() {
+            return Err(Error::Authorization);
+        }
+        Ok(())
+    }
+
+    /// Stateless checks are run first, so that errors for which
+    /// [`Error::is_state_independent`] is `true` are reported even if the
+    /// transaction also conflicts with the state
     pub fn validate_transaction(
         &self,
         rotxn: &RoTxn,
         transaction: &AuthorizedTransaction,
     ) -> Result<bitcoin::Amount, Error> {
+        Self::validate_transaction_stateless(transaction)?;
         let filled_transaction =
             self.fill_transaction(rotxn, &transaction.transaction)?;
+        // Height of the next block, which may include the transaction
+        let next_height =
+            self.try_get_height(rotxn)?.map_or(0, |height| height + 1);
 
         // Validate swap transactions
         match &transaction.transaction.data {
@@ -411,6 +504,35 @@ impl State {
                     rotxn,
                     &transaction.transaction,
                     &filled_transaction,
+                    next_height,
+                )?;
+            }
+            TxData::SwapCancel { .. } => {
+                swap::validate_swap_cancel(
+                    self,
+                    rotxn,
+                    &filled_transaction,
+                    next_height,
+                )?;
+            }
+            TxData::SwapLock { .. } => {
+                swap::validate_swap_lock(
+                    self,
+                    rotxn,
+                    &transaction.transaction,
+                    next_height,
+                )?;
+            }
+            TxData::TrackL1Headers { .. } => {
+                l1_headers::validate_track_headers(
+                    self,
+                    rotxn,
+                    &transaction.transaction,
+                )?;
+                swap::validate_no_locked_outputs(
+                    self,
+                    rotxn,
+                    &transaction.transaction,
                 )?;
             }
             TxData::Regular => {
@@ -428,13 +550,17 @@ impl State {
             .iter()
             .zip(filled_transaction.spent_utxos.iter())
         {
-            if authorization.get_address() != spent_utxo.address {
+            if authorization.get_address() != spent_utxo.address
+                && !swap::is_swap_claim_input(
+                    self,
+                    rotxn,
+                    &transaction.transaction,
+                    spent_utxo,
+                )?
+            {
                 return Err(Error::WrongPubKeyForAddress);
             }
         }
-        if Authorization::verify_transaction(transaction).is_err() {
-            return Err(Error::Authorization);
-        }
         let fee = self.validate_filled_transaction(&filled_transaction)?;
         Ok(fee)
     }
@@ -773,6 +899,13 @@ impl State {
         self.swaps_by_l1_txid
             .put(rwtxn, &l1_txid_key, &swap.id)
             .map_err(DbError::from)?;
+        // L1 transactions that filled part of the swap stay indexed, so that
+        // they cannot be used for another swap
+        for l1_txid in &swap.filled_l1_txids {
+            self.swaps_by_l1_txid
+                .put(rwtxn, &(swap.parent_chain, l1_txid.clone()), &swap.id)
+                .map_err(DbError::from)?;
+        }
 
         // Update swaps_by_recipient index (only for pre-specified swaps)
         if let Some(recipient) = swap.l2_recipient {
@@ -792,118 +925,38 @@ impl State {
         Ok(())
     }
 
-    /// Cancel a swap (unlock outputs and mark as cancelled)
-    /// Only allowed for Pending swaps (before L1 transaction is detected).
-    /// Only the swap creator may cancel; pass `creator` from wallet (None = deny for old records).
-    pub fn cancel_swap(
-        &self,
-        rwtxn: &mut RwTxn,
-        swap_id: &SwapId,
-        creator: Option<&Address>,
-    ) -> Result<(), Error> {
-        // Get swap - use get_swap which handles deserialization errors gracefully
-        let mut swap = self
-            .get_swap(rwtxn, swap_id)?
-            .ok_or(Error::SwapNotFound { swap_id: *swap_id })?;
-
-        // Only the creator may cancel
-        match &swap.l2_creator_address {
-            Some(swap_creator) => {
-                let allowed =
-                    creator.map(|c| c == swap_creator).unwrap_or(false);
-                if !allowed {
-                    return Err(Error::SwapNotCreator);
-                }
-            }
-            None => {
-                // Old record without creator; deny to avoid allowing cross-user cancel
-                return Err(Error::SwapNotCreator);
-            }
-        }
-
-        // Only allow cancellation for Pending swaps
-        if !matches!(swap.state, SwapState::Pending) {
-            return Err(Error::InvalidTransaction(format!(
-                "Swap {} cannot be cancelled (state: {:?}). Only Pending swaps can be cancelled.",
-                swap_id, swap.state
-            )));
-        }
-
-        // Find and unlock all outputs locked to this swap
-        let mut unlocked_count = 0;
-        let locked_outputs: Vec<(OutPointKey, SwapId)> = self
-            .locked_swap_outputs
-            .iter(rwtxn)
-            .map_err(DbError::from)?
-            .map(|(key, swap_id)| Ok((key, swap_id)))
-            .collect()?;
-
-        for (outpoint_key, locked_swap_id) in locked_outputs {
-            if locked_swap_id == *swap_id {
-                let outpoint: OutPoint = outpoint_key.into();
-                self.unlock_output_from_swap(rwtxn, &outpoint)?;
-                unlocked_count += 1;
-            }
-        }
-
-        tracing::info!(
-            swap_id = %swap_id,
-            unlocked_outputs = %unlocked_count,
-            "Cancelling swap and unlocking outputs"
-        );
-
-        // Mark swap as cancelled
-        swap.state = SwapState::Cancelled;
-
-        // Save updated swap
-        self.save_swap(rwtxn, &swap)?;
-
-        Ok(())
-    }
-
-    /// Only the swap creator may delete; pass `creator` from wallet (None = deny for old records).
-    pub fn delete_swap(
-        &self,
-        rwtxn: &mut RwTxn,
-        swap_id: &SwapId,
-        creator: Option<&Address>,
-    ) -> Result<(), Error> {
-        if let Some(swap) = self.get_swap(rwtxn, swap_id)? {
-            // Only the creator may delete
-            match &swap.l2_creator_address {
-                Some(swap_creator) => {
-                    let allowed =
-                        creator.map(|c| c == swap_creator).unwrap_or(false);
-                    if !allowed {
-                        return Err(Error::SwapNotCreator);
-                    }
-                }
-                None => return Err(Error::SwapNotCreator),
-            }
-        }
-        self.delete_swap_unchecked(rwtxn, swap_id)
-    }
-
-    /// Delete swap without creator check. For internal use only (e.g. block rollback).
-    pub fn delete_swap_unchecked(
+    /// Delete a swap when the block with its SwapCreate is disconnected.
+    /// Swaps are consensus records, so they are never deleted otherwise.
+    fn delete_swap(
         &self,
         rwtxn: &mut RwTxn,
         swap_id: &SwapId,
     ) -> Result<(), Error> {
         if let Some(swap) = self.get_swap(rwtxn, swap_id)? {
-            // Only Pending or Cancelled swaps can be deleted (not WaitingConfirmations, ReadyToClaim, Completed)
-            if !matches!(swap.state, SwapState::Pending | SwapState::Cancelled)
-            {
+            // Only Pending, AwaitingLock or Cancelled swaps can be deleted (not WaitingConfirmations, ReadyToClaim, Completed)
+            if !matches!(
+                swap.state,
+                SwapState::Pending
+                    | SwapState::AwaitingLock
+                    | SwapState::Cancelled
+            ) {
                 return Err(Error::InvalidTransaction(format!(
-                    "Swap {} cannot be deleted (state: {:?}). Only Pending or Cancelled swaps can be deleted.",
+                    "Swap {} cannot be deleted (state: {:?}). Only Pending, AwaitingLock or Cancelled swaps can be deleted.",
                     swap_id, swap.state
                 )));
             }
+            let _: bool = self
+                .swap_l1_statuses
+                .delete(rwtxn, swap_id)
+                .map_err(DbError::from)?;
             // Delete from swaps_by_l1_txid
             let l1_txid_key = (swap.parent_chain, swap.l1_txid.clone());
             self.swaps_by_l1_txid
                 .delete(rwtxn, &l1_txid_key)
                 .map_err(DbError::from)?;
+            for l1_txid in &swap.filled_l1_txids {
+                self.delete_l1_txid_index_entry(rwtxn, &swap, l1_txid)?;
+            }
 
             // Update swaps_by_recipient index (only for pre-specified swaps)
             if let Some(recipient) = swap.l2_recipient
@@ -936,10 +989,248 @@ impl State {
 
         // Delete from main swaps database (even if swap was corrupted/unreadable)
         self.swaps.delete(rwtxn, swap_id).map_err(DbError::from)?;
+        if let Some(mut history) = self
+            .swap_history
+            .try_get(rwtxn, swap_id)
+            .map_err(DbError::from)?
+        {
+            loop {
+                let (rest, update) = history.pop();
+                self.delete_swap_history_height(rwtxn, swap_id, update.height)?;
+                match rest {
+                    Some(rest) => history = rest,
+                    None => break,
+                }
+            }
+            self.swap_history
+                .delete(rwtxn, swap_id)
+                .map_err(DbError::from)?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete the `swaps_by_l1_txid` entry for a swap's L1 txid, if it
+    /// refers to that swap
+    fn delete_swap_l1_txid_index(
+        &self,
+        rwtxn: &mut RwTxn,
+        swap: &Swap,
+    ) -> Result<(), Error> {
+        self.delete_l1_txid_index_entry(rwtxn, swap, &swap.l1_txid)
+    }
+
+    /// Delete the `swaps_by_l1_txid` entry for an L1 txid on the swap's
+    /// parent chain, if it refers to the swap
+    fn delete_l1_txid_index_entry(
+        &self,
+        rwtxn: &mut RwTxn,
+        swap: &Swap,
+        l1_txid: &SwapTxId,
+    ) -> Result<(), Error> {
+        let key = (swap.parent_chain, l1_txid.clone());
+        if self
+            .swaps_by_l1_txid
+            .try_get(rwtxn, &key)
+            .map_err(DbError::from)?
+            == Some(swap.id)
+        {
+            self.swaps_by_l1_txid
+                .delete(rwtxn, &key)
+                .map_err(DbError::from)?;
+        }
+        Ok(())
+    }
+
+    /// Save a swap that was updated while connecting a block, recording
+    /// `prev_swap` and the outputs unlocked from the swap, so that the
+    /// update can be rolled back on disconnect.
+    fn save_swap_update(
+        &self,
+        rwtxn: &mut RwTxn,
+        prev_swap: Swap,
+        swap: &Swap,
+        unlocked_outputs: Vec<OutPoint>,
+        source: SwapUpdateSource,
+        height: u32,
+    ) -> Result<(), Error> {
+        if prev_swap.l1_txid != swap.l1_txid {
+            self.delete_swap_l1_txid_index(rwtxn, &prev_swap)?;
+        }
+        let update = SwapUpdate {
+            source,
+            prev_swap,
+            unlocked_outputs,
+        };
+        let history = if let Some(mut history) = self
+            .swap_history
+            .try_get(rwtxn, &swap.id)
+            .map_err(DbError::from)?
+        {
+            history
+                .push(update, height)
+                .expect("Push swap update should be valid");
+            history
+        } else {
+            RollBack::new(update, height)
+        };
+        self.swap_history
+            .put(rwtxn, &swap.id, &history)
+            .map_err(DbError::from)?;
+        let mut updated_swaps = self
+            .swap_history_heights
+            .try_get(rwtxn, &height)
+            .map_err(DbError::from)?
+            .unwrap_or_default();
+        if !updated_swaps.contains(&swap.id) {
+            updated_swaps.push(swap.id);
+            self.swap_history_heights
+                .put(rwtxn, &height, &updated_swaps)
+                .map_err(DbError::from)?;
+        }
+        self.save_swap(rwtxn, swap)
+    }
+
+    /// Remove a swap from the swaps with an update at `height`
+    fn delete_swap_history_height(
+        &self,
+        rwtxn: &mut RwTxn,
+        swap_id: &SwapId,
+        height: u32,
+    ) -> Result<(), Error> {
+        let Some(mut updated_swaps) = self
+            .swap_history_heights
+            .try_get(rwtxn, &height)
+            .map_err(DbError::from)?
+        else {
+            return Ok(());
+        };
+        updated_swaps.retain(|updated_swap| updated_swap != swap_id);
+        if updated_swaps.is_empty() {
+            self.swap_history_heights
+                .delete(rwtxn, &height)
+                .map_err(DbError::from)?;
+        } else {
+            self.swap_history_heights
+                .put(rwtxn, &height, &updated_swaps)
+                .map_err(DbError::from)?;
+        }
+        Ok(())
+    }
+
+    /// Restore the swap record from before its latest update, and re-lock
+    /// the outputs it unlocked, if that update was made from `source` at
+    /// `height`.
+    /// Returns `true` if an update was rolled back.
+    fn rollback_swap_update(
+        &self,
+        rwtxn: &mut RwTxn,
+        swap_id: &SwapId,
+        source: SwapUpdateSource,
+        height: u32,
+    ) -> Result<bool, Error> {
+        let Some(history) = self
+            .swap_history
+            .try_get(rwtxn, swap_id)
+            .map_err(DbError::from)?
+        else {
+            return Ok(false);
+        };
+        let latest = history.latest();
+        if latest.height != height || latest.value.source != source {
+            return Ok(false);
+        }
+        let (history, update) = history.pop();
+        let updated_at_height = history
+            .as_ref()
+            .is_some_and(|history| history.latest().height == height);
+        if let Some(history) = history {
+            self.swap_history
+                .put(rwtxn, swap_id, &history)
+                .map_err(DbError::from)?;
+        } else {
+            self.swap_history
+                .delete(rwtxn, swap_id)
+                .map_err(DbError::from)?;
+        }
+        if !updated_at_height {
+            self.delete_swap_history_height(rwtxn, swap_id, height)?;
+        }
+        let SwapUpdate {
+            source: _,
+            prev_swap,
+            unlocked_outputs,
+        } = update.value;
+        if let Some(swap) = self.get_swap(rwtxn, swap_id)? {
+            if swap.l1_txid != prev_swap.l1_txid {
+                self.delete_swap_l1_txid_index(rwtxn, &swap)?;
+            }
+            for l1_txid in &swap.filled_l1_txids {
+                if !prev_swap.filled_l1_txids.contains(l1_txid)
+                    && *l1_txid != prev_swap.l1_txid
+                {
+                    self.delete_l1_txid_index_entry(rwtxn, &swap, l1_txid)?;
+                }
+            }
+        }
+        for outpoint in unlocked_outputs.iter().rev() {
+            self.lock_output_to_swap(rwtxn, outpoint, swap_id)?;
+        }
+        self.save_swap(rwtxn, &prev_swap)?;
+        Ok(true)
+    }
 
+    /// Roll back all swap updates made from `source` at `height`
+    fn rollback_swap_updates(
+        &self,
+        rwtxn: &mut RwTxn,
+        source: SwapUpdateSource,
+        height: u32,
+    ) -> Result<(), Error> {
+        let swap_ids = self
+            .swap_history_heights
+            .try_get(rwtxn, &height)
+            .map_err(DbError::from)?
+            .unwrap_or_default();
+        for swap_id in &swap_ids {
+            let _: bool =
+                self.rollback_swap_update(rwtxn, swap_id, source, height)?;
+        }
         Ok(())
     }
 
+    /// Cancel an expired swap while connecting two-way peg data at
+    /// `height`, unlocking its outputs so that the creator can spend them.
+    fn expire_swap(
+        &self,
+        rwtxn: &mut RwTxn,
+        mut swap: Swap,
+        height: u32,
+    ) -> Result<(), Error> {
+        let unlocked_outputs: Vec<OutPoint> = self
+            .locked_swap_outputs
+            .iter(rwtxn)
+            .map_err(DbError::from)?
+            .filter_map(|(key, locked_swap_id)| {
+                Ok((locked_swap_id == swap.id).then(|| OutPoint::from(key)))
+            })
+            .collect()
+            .map_err(DbError::from)?;
+        for outpoint in &unlocked_outputs {
+            self.unlock_output_from_swap(rwtxn, outpoint)?;
+        }
+        let prev_swap = swap.clone();
+        swap.state = SwapState::Cancelled;
+        self.save_swap_update(
+            rwtxn,
+            prev_swap,
+            &swap,
+            unlocked_outputs,
+            SwapUpdateSource::TwoWayPegData,
+            height,
+        )
+    }
+
     /// Unlock all outputs locked to a specific swap
     /// This is useful when a swap is corrupted and can't be read normally
     pub fn unlock_all_outputs_for_swap(
@@ -1121,6 +1412,128 @@ impl State {
         }
     }
 
+    /// Get the tip of the best tracked L1 header chain for the parent
+    /// chain. New SPV proofs can be anchored to it, or to the chain's SPV
+    /// checkpoint if no headers are tracked.
+    pub fn get_l1_header_tip(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: ParentChainType,
+    ) -> Result<Option<bitcoin::BlockHash>, Error> {
+        let tip = l1_headers::tip(self, rotxn, parent_chain)?;
+        Ok(tip.map(|tip| tip.header.block_hash()))
+    }
+
+    /// Apply the L1 payment observed by this node to a Pending swap
+    fn apply_swap_l1_status(
+        &self,
+        rotxn: &RoTxn,
+        swap: &mut Swap,
+    ) -> Result<(), Error> {
+        if matches!(swap.state, SwapState::Pending)
+            && let Some(status) = self
+                .swap_l1_statuses
+                .try_get(rotxn, &swap.id)
+                .map_err(DbError::from)?
+        {
+            status.apply(swap);
+        }
+        Ok(())
+    }
+
+    /// Get a swap as seen by this node: if the swap is Pending, the L1
+    /// payment observed by this node's L1 endpoints is applied.
+    ///
+    /// For display and for building claims only. Validation must use
+    /// [`Self::get_swap`].
+    pub fn get_swap_with_l1_status(
+        &self,
+        rotxn: &RoTxn,
+        swap_id: &SwapId,
+    ) -> Result<Option<Swap>, Error> {
+        let Some(mut swap) = self.get_swap(rotxn, swap_id)? else {
+            return Ok(None);
+        };
+        self.apply_swap_l1_status(rotxn, &mut swap)?;
+        Ok(Some(swap))
+    }
+
+    /// Load all swaps as seen by this node, see
+    /// [`Self::get_swap_with_l1_status`]
+    pub fn load_all_swaps_with_l1_status(
+        &self,
+        rotxn: &RoTxn,
+    ) -> Result<Vec<Swap>, Error> {
+        let mut swaps = self.load_all_swaps(rotxn)?;
+        for swap in &mut swaps {
+            self.apply_swap_l1_status(rotxn, swap)?;
+        }
+        Ok(swaps)
+    }
+
+    /// Get the swaps for a recipient as seen by this node, see
+    /// [`Self::get_swap_with_l1_status`]
+    pub fn get_swaps_by_recipient_with_l1_status(
+        &self,
+        rotxn: &RoTxn,
+        recipient: &Address,
+    ) -> Result<Vec<Swap>, Error> {
+        let mut swaps = self.get_swaps_by_recipient(rotxn, recipient)?;
+        for swap in &mut swaps {
+            self.apply_swap_l1_status(rotxn, swap)?;
+        }
+        Ok(swaps)
+    }
+
+    /// Record the L1 payment applied to a swap as seen by this node, or
+    /// forget it if the swap is Pending
+    pub(crate) fn put_swap_l1_status(
+        &self,
+        rwtxn: &mut RwTxn,
+        swap: &Swap,
+    ) -> Result<(), Error> {
+        match SwapL1Status::from_swap(swap) {
+            Some(status) => self
+                .swap_l1_statuses
+                .put(rwtxn, &swap.id, &status)
+                .map_err(DbError::from)?,
+            None => {
+                let _: bool = self
+                    .swap_l1_statuses
+                    .delete(rwtxn, &swap.id)
+                    .map_err(DbError::from)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the swap other than `swap_id` for which this node observed the
+    /// L1 payment `l1_txid`, if any
+    pub(crate) fn get_other_swap_by_observed_l1_txid(
+        &self,
+        rotxn: &RoTxn,
+        swap_id: &SwapId,
+        parent_chain: ParentChainType,
+        l1_txid: &SwapTxId,
+    ) -> Result<Option<SwapId>, Error> {
+        let mut iter =
+            self.swap_l1_statuses.iter(rotxn).map_err(DbError::from)?;
+        while let Some((other_id, status)) =
+            iter.next().map_err(DbError::from)?
+        {
+            if other_id == *swap_id || status.l1_txid != *l1_txid {
+                continue;
+            }
+            if self
+                .get_swap(rotxn, &other_id)?
+                .is_some_and(|other| other.parent_chain == parent_chain)
+            {
+                return Ok(Some(other_id));
+            }
+        }
+        Ok(None)
+    }
+
     pub fn get_swaps_by_recipient(
         &self,
         rotxn: &RoTxn,
@@ -1532,10 +1945,13 @@ impl State {
         Ok(swap_id)
     }
 
-    /// Update swap L1 transaction ID and state
+    /// Update swap L1 transaction ID and state, as seen by this node (see
+    /// [`SwapL1Status`]). Consensus state is unchanged: claims must still
+    /// prove the payment.
     /// Called when a coinshift transaction is detected on L1
     /// For open swaps, l1_claimer_address should be the address of the person who sent the L1 transaction;
-    /// l2_claimer_address is the L2 address the filler declared (claim only valid for this address).
+    /// l2_claimer_address is the L2 address the filler declared.
+    /// Not allowed for swaps that require an L1 commitment.
     /// block_hash and block_height are the sidechain block where this update occurs
     #[allow(clippy::too_many_arguments)]
     pub fn update_swap_l1_txid(
@@ -1550,7 +1966,7 @@ impl State {
         block_height: u32,
     ) -> Result<(), Error> {
         let mut swap = self
-            .get_swap(rwtxn, swap_id)?
+            .get_swap_with_l1_status(rwtxn, swap_id)?
             .ok_or_else(|| Error::SwapNotFound { swap_id: *swap_id })?;
 
         // Only Pending swaps can be filled (L1 tx set). Reject once already filled or waiting confirmations.
@@ -1561,6 +1977,15 @@ impl State {
             )));
         }
 
+        // The filler's L2 address comes from the L1 payment's commitment,
+        // so it cannot be declared here
+        if swap.l1_commitment_required {
+            return Err(Error::InvalidTransaction(format!(
+                "Swap {} requires an L1 commitment; its L1 payment is only accepted when detected on the parent chain",
+                swap_id
+            )));
+        }
+
         // Only accept confirmed L1 transactions (consistent with query_and_update_swap)
         if confirmations == 0 {
             return Err(Error::InvalidTransaction(format!(
@@ -1579,9 +2004,19 @@ impl State {
                 existing_swap_id: existing.id,
             });
         }
-
-        // Save the old l1_txid BEFORE updating the swap (needed for index deletion)
-        let old_l1_txid = swap.l1_txid.clone();
+        if let Some(existing_swap_id) = self
+            .get_other_swap_by_observed_l1_txid(
+                rwtxn,
+                swap_id,
+                swap.parent_chain,
+                &l1_txid,
+            )?
+        {
+            return Err(Error::L1TxidAlreadyUsed {
+                swap_id: *swap_id,
+                existing_swap_id,
+            });
+        }
 
         // Update L1 txid and claimer address (for open swaps)
         if let Some(claimer_addr) = l1_claimer_address {
@@ -1608,24 +2043,12 @@ impl State {
             );
         }
 
-        // Update indexes - use the old_l1_txid we saved before updating
-        let old_l1_txid_key = (swap.parent_chain, old_l1_txid);
-        self.swaps_by_l1_txid
-            .delete(rwtxn, &old_l1_txid_key)
-            .map_err(DbError::from)?;
-
-        let new_l1_txid_key = (swap.parent_chain, l1_txid);
-        self.swaps_by_l1_txid
-            .put(rwtxn, &new_l1_txid_key, swap_id)
-            .map_err(DbError::from)?;
-
-        // Save updated swap
-        self.save_swap(rwtxn, &swap)?;
-
-        Ok(())
+        // Only this node's view of the swap changes
+        self.put_swap_l1_status(rwtxn, &swap)
     }
 
-    /// Update confirmation count for a swap already in WaitingConfirmations.
+    /// Update confirmation count for a swap already in WaitingConfirmations,
+    /// as seen by this node.
     /// Only allowed when state is WaitingConfirmations; transitions to ReadyToClaim when confirmations >= required.
     pub fn update_swap_confirmations(
         &self,
@@ -1636,7 +2059,7 @@ impl State {
         block_height: u32,
     ) -> Result<(), Error> {
         let mut swap = self
-            .get_swap(rwtxn, swap_id)?
+            .get_swap_with_l1_status(rwtxn, swap_id)?
             .ok_or_else(|| Error::SwapNotFound { swap_id: *swap_id })?;
 
         let (current, required) = match swap.state {
@@ -1661,8 +2084,7 @@ impl State {
                 SwapState::WaitingConfirmations(new_confirmations, required);
         }
 
-        self.save_swap(rwtxn, &swap)?;
-        Ok(())
+        self.put_swap_l1_status(rwtxn, &swap)
     }
 
     pub fn validate_block(
@@ -1690,7 +2112,8 @@ impl State {
     /// - Syncing swap history for new nodes
     ///
     /// This function scans all blocks from genesis to tip and reconstructs
-    /// all swaps from SwapCreate and SwapClaim transactions.
+    /// all swaps from SwapCreate, SwapLock, SwapClaim and SwapCancel
+    /// transactions.
     pub fn reconstruct_swaps_from_blockchain(
         &self,
         rwtxn: &mut RwTxn,
@@ -1783,20 +2206,28 @@ impl State {
                         l2_amount,
                         l1_recipient_address,
                         l1_amount,
+                        expires_in_blocks,
+                        require_l1_commitment,
+                        allow_partial_fills,
+                        l1_match_policy,
                     } => {
                         let swap_id = SwapId(*swap_id);
 
                         // Reconstruct L1 txid
                         let l1_txid = SwapTxId::from_bytes(l1_txid_bytes);
 
-                        // L2 creator = first input's address (only they may cancel/delete)
+                        // L2 creator = first input's address (only they may cancel)
                         let l2_creator_address =
                             filled.spent_utxos.first().map(|o| o.address);
 
                         // Reconstruct swap object
+                        let direction = transaction
+                            .data
+                            .swap_direction()
+                            .expect("SwapCreate has a swap direction");
                         let swap = Swap::new(
                             swap_id,
-                            crate::types::SwapDirection::L2ToL1,
+                            direction,
                             *parent_chain,
                             l1_txid,
                             Some(*required_confirmations),
@@ -1805,8 +2236,11 @@ impl State {
                             l1_recipient_address.clone(),
                             l1_amount.map(bitcoin::Amount::from_sat),
                             height,
-                            None, // TODO: Add expiration support
+                            Swap::expiry_height(height, *expires_in_blocks),
                             l2_creator_address,
+                            *require_l1_commitment,
+                            *allow_partial_fills,
+                            *l1_match_policy,
                         );
 
                         // Lock outputs for L2 → L1 swaps
@@ -1850,13 +2284,46 @@ impl State {
                             );
                         }
                     }
-                    TxData::SwapClaim { swap_id, .. } => {
+                    TxData::SwapClaim {
+                        swap_id,
+                        proof_data,
+                        ..
+                    } => {
                         let swap_id = SwapId(*swap_id);
 
                         // Get swap and update its state
                         if let Some(mut swap) =
                             self.get_swap(rwtxn, &swap_id)?
                         {
+                            // Restore the L1 txid and headers proven by
+                            // the claim
+                            let mut l1_fill = None;
+                            if let Some(proof_data) = proof_data {
+                                let proof = SpvProof::from_bytes(proof_data)
+                                    .map_err(|source| Error::SpvProof {
+                                        swap_id,
+                                        source,
+                                    })?;
+                                if swap.partial_fills {
+                                    l1_fill = Some(swap::claimed_l1_fill(
+                                        &swap,
+                                        &proof.transaction,
+                                    )?);
+                                }
+                                swap.update_l1_txid(
+                                    SwapTxId::from_bitcoin_txid(
+                                        &proof.transaction.compute_txid(),
+                                    ),
+                                );
+                                swap::track_l1_headers(
+                                    self,
+                                    rwtxn,
+                                    swap.parent_chain,
+                                    &proof,
+                                    txid,
+                                )?;
+                            }
+
                             // Unlock outputs
                             for (outpoint, _) in &filled.transaction.inputs {
                                 if self
@@ -1869,8 +2336,21 @@ impl State {
                                 }
                             }
 
-                            // Mark swap as completed
-                            swap.mark_completed();
+                            // Mark swap as completed, or record a partial
+                            // fill and re-lock the unfilled remainder
+                            if let Some(l1_fill) = l1_fill {
+                                swap.record_fill(l1_fill);
+                                if !matches!(swap.state, SwapState::Completed) {
+                                    swap::lock_swap_outputs(
+                                        self,
+                                        rwtxn,
+                                        transaction,
+                                        &swap_id,
+                                    )?;
+                                }
+                            } else {
+                                swap.mark_completed();
+                            }
                             self.save_swap(rwtxn, &swap)?;
                         } else {
                             tracing::warn!(
@@ -1880,6 +2360,92 @@ impl State {
                             );
                         }
                     }
+                    TxData::SwapCancel { swap_id } => {
+                        let swap_id = SwapId(*swap_id);
+                        if let Some(mut swap) =
+                            self.get_swap(rwtxn, &swap_id)?
+                        {
+                            // Cancellation requests only bring the expiry
+                            // forward
+                            if let Some(expires_at) =
+                                swap.cancel_request_expiry(height)
+                            {
+                                swap.expires_at_height = Some(
+                                    swap.expires_at_height
+                                        .map_or(expires_at, |current| {
+                                            current.min(expires_at)
+                                        }),
+                                );
+                            } else {
+                                for (outpoint, _) in &filled.transaction.inputs
+                                {
+                                    if self.is_output_locked_to_swap(
+                                        rwtxn, outpoint,
+                                    )? == Some(swap_id)
+                                    {
+                                        self.unlock_output_from_swap(
+                                            rwtxn, outpoint,
+                                        )?;
+                                    }
+                                }
+                                swap.state = SwapState::Cancelled;
+                            }
+                            self.save_swap(rwtxn, &swap)?;
+                        } else {
+                            tracing::warn!(
+                                swap_id = %swap_id,
+                                block_height = height,
+                                "SwapCancel found but swap not found in database"
+                            );
+                        }
+                    }
+                    TxData::SwapLock {
+                        swap_id,
+                        l1_recipient_address,
+                    } => {
+                        let swap_id = SwapId(*swap_id);
+                        if let Some(mut swap) =
+                            self.get_swap(rwtxn, &swap_id)?
+                        {
+                            swap::lock_swap_outputs(
+                                self,
+                                rwtxn,
+                                transaction,
+                                &swap_id,
+                            )?;
+                            swap.mark_locked(l1_recipient_address.clone());
+                            self.save_swap(rwtxn, &swap)?;
+                        } else {
+                            tracing::warn!(
+                                swap_id = %swap_id,
+                                block_height = height,
+                                "SwapLock found but swap not found in database"
+                            );
+                        }
+                    }
+                    TxData::TrackL1Headers {
+                        parent_chain,
+                        headers,
+                    } => {
+                        // Restore the L1 headers tracked outside of claims
+                        let headers =
+                            decode_l1_headers(headers).map_err(|source| {
+                                Error::L1Headers {
+                                    parent_chain: *parent_chain,
+                                    source,
+                                }
+                            })?;
+                        if let Some(params) = parent_chain.spv_params() {
+                            l1_headers::track_headers(
+                                self,
+                                rwtxn,
+                                *parent_chain,
+                                &params,
+                                &headers,
+                                txid,
+                            )?;
+                        }
+                    }
                     TxData::Regular => {}
                 }
             }
@@ -1945,22 +2511,28 @@ impl State {
         block::disconnect_tip(self, rwtxn, header, body)
     }
 
+    /// Queries for the swaps that may be checked against L1 when `bodies`
+    /// are connected, in order, on top of the current tip
+    pub fn l1_queries(
+        &self,
+        rotxn: &RoTxn,
+        bodies: &[Body],
+    ) -> Result<Vec<L1Query>, Error> {
+        l1_observations::l1_queries(self, rotxn, bodies)
+    }
+
     pub fn connect_two_way_peg_data(
         &self,
         rwtxn: &mut RwTxn,
         two_way_peg_data: &TwoWayPegData,
-        rpc_config_getter: Option<
-            &dyn Fn(
-                ParentChainType,
-            ) -> Option<crate::parent_chain_rpc::RpcConfig>,
-        >,
+        l1_observations: &L1Observations,
         wallet: Option<&crate::wallet::Wallet>,
     ) -> Result<(), Error> {
         two_way_peg_data::connect(
             self,
             rwtxn,
             two_way_peg_data,
-            rpc_config_getter,
+            l1_observations,
             wallet,
         )
     }
@@ -1,11 +1,14 @@
 //! Swap validation and processing
 
-use sneed::RoTxn;
+use sneed::{RoTxn, RwTxn};
 
 use crate::{
-    state::{Error, State},
+    state::{Error, State, l1_headers},
     types::{
-        FilledTransaction, SwapId, SwapState, SwapTxId, Transaction, TxData,
+        Address, AmountOverflowError, FilledTransaction, GetValue as _,
+        L1MatchPolicy, OutPoint, Output, OutputContent, ParentChainType,
+        SpvParams, SpvProof, Swap, SwapCommitment, SwapDirection, SwapId,
+        SwapState, SwapTxId, Transaction, TxData, Txid, VerifiedL1Tx,
     },
 };
 
@@ -18,13 +21,17 @@ pub fn validate_swap_create(
 ) -> Result<(), Error> {
     let TxData::SwapCreate {
         swap_id,
-        parent_chain: _,
+        parent_chain,
         l1_txid_bytes: _,
         required_confirmations: _,
         l2_recipient,
         l2_amount,
         l1_recipient_address,
         l1_amount,
+        expires_in_blocks,
+        require_l1_commitment: _,
+        allow_partial_fills,
+        l1_match_policy,
     } = &transaction.data
     else {
         return Err(Error::InvalidTransaction(
@@ -32,30 +39,52 @@ pub fn validate_swap_create(
         ));
     };
 
-    // 1. Verify swap ID matches computed ID
-    let computed_swap_id = if let (Some(l1_addr), Some(l1_amt)) =
-        (l1_recipient_address.as_ref(), l1_amount)
-    {
-        // L2 → L1 swap
-        // We need the sender's address - get it from the first input
-        let first_input =
-            filled_transaction.spent_utxos.first().ok_or_else(|| {
-                Error::InvalidTransaction(
-                    "SwapCreate must have inputs".to_string(),
-                )
-            })?;
-        let l2_sender_address = first_input.address;
-        SwapId::from_l2_to_l1(
-            l1_addr,
-            bitcoin::Amount::from_sat(*l1_amt),
-            &l2_sender_address,
-            l2_recipient.as_ref(), // Now optional
-        )
-    } else {
-        return Err(Error::InvalidTransaction(
-            "L2 → L1 swap requires l1_recipient_address and l1_amount"
-                .to_string(),
-        ));
+    // 1. Verify swap ID matches computed ID. The creator is the owner of
+    // the first input.
+    let l2_creator_address = filled_transaction
+        .spent_utxos
+        .first()
+        .ok_or_else(|| {
+            Error::InvalidTransaction("SwapCreate must have inputs".to_string())
+        })?
+        .address;
+    let direction = transaction
+        .data
+        .swap_direction()
+        .expect("SwapCreate has a swap direction");
+    let computed_swap_id = match (direction, l1_recipient_address, l1_amount) {
+        (SwapDirection::L2ToL1, Some(l1_addr), Some(l1_amt)) => {
+            SwapId::from_l2_to_l1(
+                l1_addr,
+                bitcoin::Amount::from_sat(*l1_amt),
+                &l2_creator_address,
+                l2_recipient.as_ref(), // Now optional
+            )
+        }
+        (SwapDirection::L2ToL1, _, _) => {
+            return Err(Error::InvalidTransaction(
+                "L2 → L1 swap requires l1_recipient_address and l1_amount"
+                    .to_string(),
+            ));
+        }
+        (SwapDirection::L1ToL2, _, Some(l1_amt)) => {
+            let Some(l2_recipient) = l2_recipient else {
+                return Err(Error::InvalidTransaction(
+                    "L1 → L2 swap requires l2_recipient".to_string(),
+                ));
+            };
+            SwapId::from_l1_to_l2(
+                bitcoin::Amount::from_sat(*l1_amt),
+                bitcoin::Amount::from_sat(*l2_amount),
+                &l2_creator_address,
+                l2_recipient,
+            )
+        }
+        (SwapDirection::L1ToL2, _, None) => {
+            return Err(Error::InvalidTransaction(
+                "L1 → L2 swap requires l1_amount".to_string(),
+            ));
+        }
     };
 
     if computed_swap_id.0 != *swap_id {
@@ -81,14 +110,56 @@ pub fn validate_swap_create(
         ));
     }
 
-    // 4. Verify transaction has outputs
+    // 4. Verify expiry is within bounds
+    if let Some(expires_in_blocks) = *expires_in_blocks
+        && !(1..=Swap::MAX_EXPIRY_BLOCKS).contains(&expires_in_blocks)
+    {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap expiry must be between 1 and {} blocks, got {}",
+            Swap::MAX_EXPIRY_BLOCKS,
+            expires_in_blocks
+        )));
+    }
+
+    // 5. Verify the swap can be claimed. Claims must prove the L1 payment
+    // with an SPV proof, which also proves the amount of each partial fill.
+    if parent_chain.spv_params().is_none() {
+        return Err(Error::InvalidTransaction(format!(
+            "Swaps on {} cannot be claimed, since SPV proofs are not supported for it",
+            parent_chain.coin_name()
+        )));
+    }
+    if *allow_partial_fills {
+        if direction != SwapDirection::L2ToL1 {
+            return Err(Error::InvalidTransaction(
+                "Partial fills are only supported for L2 → L1 swaps"
+                    .to_string(),
+            ));
+        }
+        // Each fill is the sum of the outputs to the L1 recipient, up to
+        // the remaining amount, so no other match policy applies
+        if *l1_match_policy != L1MatchPolicy::Exact {
+            return Err(Error::InvalidTransaction(format!(
+                "Partial fills cannot be combined with the `{l1_match_policy}` L1 match policy"
+            )));
+        }
+    }
+
+    // 6. Verify the L1 recipient address is valid for the parent chain
+    if let Some(l1_recipient_address) = l1_recipient_address {
+        parent_chain
+            .consensus_validate_address(l1_recipient_address)
+            .map_err(|err| Error::InvalidTransaction(err.to_string()))?;
+    }
+
+    // 7. Verify transaction has outputs
     if transaction.outputs.is_empty() {
         return Err(Error::InvalidTransaction(
             "Transaction must have at least one output".to_string(),
         ));
     }
 
-    // 5. For L2 → L1 swaps, verify inputs aren't locked and sufficient funds
+    // 8. For L2 → L1 swaps, verify inputs aren't locked and sufficient funds
     if l1_recipient_address.is_some() {
         // Check that no inputs are locked to another swap
         for (outpoint, _) in &transaction.inputs {
@@ -163,19 +234,56 @@ pub fn validate_swap_create(
                 required_amount, total_input_value
             )));
         }
+    } else {
+        // 9. For L1 → L2 swaps, the L2 coins are locked later, by SwapLock
+        validate_no_locked_outputs(state, rotxn, transaction)?;
+        if transaction
+            .outputs
+            .iter()
+            .any(|output| output.content.is_swap_pending())
+        {
+            return Err(Error::InvalidTransaction(
+                "L1 → L2 SwapCreate cannot lock outputs; they are locked by SwapLock"
+                    .to_string(),
+            ));
+        }
     }
 
     Ok(())
 }
 
-/// Validate a SwapClaim transaction
+/// Validate a SwapClaim transaction, to be included in a block at
+/// `height`.
+///
+/// The claim must carry an SPV proof of the L1 payment, and the verified
+/// proof is returned so that it can be applied when connecting the claim.
+/// Swaps on parent chains without SPV proofs cannot be claimed, since their
+/// L1 payments could only be checked against each node's own L1 endpoint.
+/// If the swap requires an L1 commitment, the proven payment must carry it,
+/// and an open swap can only be claimed to the committed L2 address.
+///
+/// If the swap is keyless-claimable, its SwapPending outputs can be spent
+/// without the creator's authorization (see [`is_swap_claim_input`]), so
+/// the claim must pay at least their full value to the swap recipient. Open
+/// swaps without an L1 commitment must be signed by the creator, and are
+/// claimed to the `l2_claimer_address` that the creator signed for. For
+/// swaps that accept partial fills, an L1 payment of less than the
+/// remaining L1 amount only releases its share of the L2 amount, and the
+/// rest of the spent value must be locked back to the swap in SwapPending
+/// outputs owned by the creator.
 pub fn validate_swap_claim(
     state: &State,
     rotxn: &RoTxn,
     transaction: &Transaction,
-    _filled_transaction: &FilledTransaction,
-) -> Result<(), Error> {
-    let TxData::SwapClaim { swap_id, .. } = &transaction.data else {
+    filled_transaction: &FilledTransaction,
+    height: u32,
+) -> Result<(SpvProof, VerifiedL1Tx), Error> {
+    let TxData::SwapClaim {
+        swap_id,
+        proof_data,
+        l2_claimer_address,
+    } = &transaction.data
+    else {
         return Err(Error::InvalidTransaction(
             "Expected SwapClaim transaction".to_string(),
         ));
@@ -187,29 +295,31 @@ pub fn validate_swap_claim(
     let swap = state
         .get_swap(rotxn, &swap_id)?
         .ok_or_else(|| Error::SwapNotFound { swap_id })?;
-
-    // 2. Verify swap is in ReadyToClaim state
-    if !matches!(swap.state, SwapState::ReadyToClaim) {
+    if let Some(expires_at) = swap.expires_at_height
+        && height >= expires_at
+    {
         return Err(Error::InvalidTransaction(format!(
-            "Swap {} is not ready to claim (state: {:?})",
-            swap_id, swap.state
+            "Swap {swap_id} expired at height {expires_at}"
         )));
     }
 
-    // 2.5. For open swaps, verify L1 transaction exists (someone filled it)
-    if swap.l2_recipient.is_none() {
-        // Open swap - verify L1 transaction was detected
-        let zero_hash32 = [0u8; 32];
-        let has_l1_tx = !matches!(swap.l1_txid, SwapTxId::Hash32(h) if h == zero_hash32)
-            && !matches!(swap.l1_txid, SwapTxId::Hash(ref v) if v.is_empty() || v.iter().all(|&b| b == 0));
-
-        if !has_l1_tx {
-            return Err(Error::InvalidTransaction(
-                "Open swap cannot be claimed until L1 transaction is detected"
-                    .to_string(),
-            ));
-        }
-    }
+    // 2. Verify the L1 payment from the SPV proof
+    let Some(spv_params) = swap.parent_chain.spv_params() else {
+        return Err(Error::InvalidTransaction(format!(
+            "{} swap {} cannot be claimed: its L1 payments cannot be proven with SPV proofs",
+            swap.parent_chain.coin_name(),
+            swap_id
+        )));
+    };
+    let Some(proof_data) = proof_data else {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapClaim for {} swap {} must carry an SPV proof of the L1 payment",
+            swap.parent_chain.coin_name(),
+            swap_id
+        )));
+    };
+    let (proof, verified) =
+        validate_spv_proof(state, rotxn, &swap, proof_data, &spv_params)?;
 
     // 3. Verify at least one input is locked to this swap
     let mut found_locked_input = false;
@@ -235,40 +345,79 @@ pub fn validate_swap_claim(
     }
 
     // 4. Verify output goes to correct recipient
-    let TxData::SwapClaim {
-        l2_claimer_address, ..
-    } = &transaction.data
-    else {
-        unreachable!()
-    };
-
     let expected_recipient = if let Some(recipient) = swap.l2_recipient {
         // Pre-specified swap: must go to specified recipient
         recipient
+    } else if swap.l1_commitment_required {
+        // The L1 payment declares the claimer in its commitment
+        let committed = committed_l2_address(&swap, &proof.transaction)?;
+        if l2_claimer_address.is_some_and(|addr| addr != committed) {
+            return Err(Error::InvalidTransaction(format!(
+                "Open swap claim must pay {committed}, the L2 address committed to by the L1 payment"
+            )));
+        }
+        committed
     } else {
-        // Open swap
-        if let Some(stored_l2) = swap.l2_claimer_address {
-            // Claim only valid for the L2 address the filler declared when providing L1 tx details
-            if l2_claimer_address.as_ref() != Some(&stored_l2) {
-                return Err(Error::InvalidTransaction(
-                    "Open swap claim must use the L2 address declared when the L1 transaction was submitted".to_string(),
-                ));
-            }
-            stored_l2
-        } else if let Some(claimer_addr) = l2_claimer_address {
-            // No stored L2 (e.g. auto-detected L1 tx): accept claimer address from tx
-            *claimer_addr
-        } else {
-            return Err(Error::InvalidTransaction(
+        // Open swap without a commitment: nothing ties the L1 payment to an
+        // L2 address, so the creator signs the claim (see
+        // `is_swap_claim_input`), naming the claimer
+        (*l2_claimer_address).ok_or_else(|| {
+            Error::InvalidTransaction(
                 "Open swap claim requires l2_claimer_address".to_string(),
-            ));
+            )
+        })?
+    };
+
+    let mut swap_input_value = bitcoin::Amount::ZERO;
+    for spent_utxo in &filled_transaction.spent_utxos {
+        if is_swap_output(transaction, spent_utxo) {
+            swap_input_value = swap_input_value
+                .checked_add(spent_utxo.get_value())
+                .ok_or(AmountOverflowError)?;
+        }
+    }
+
+    // L1 payments that leave part of the swap unfilled release their share
+    // of the L2 amount
+    let partial_fill_share = if swap.partial_fills {
+        let l1_fill = claimed_l1_fill(&swap, &proof.transaction)?;
+        if l1_fill < swap.remaining_l1_amount() {
+            swap.l2_amount_for_fill(l1_fill)
+        } else {
+            None
         }
+    } else {
+        None
     };
 
-    let recipient_receives = transaction
-        .outputs
-        .iter()
-        .any(|output| output.address == expected_recipient);
+    let mut recipient_value = bitcoin::Amount::ZERO;
+    let mut recipient_receives = false;
+    let mut relocked_value = bitcoin::Amount::ZERO;
+    for output in &transaction.outputs {
+        if partial_fill_share.is_some()
+            && let OutputContent::SwapPending {
+                value,
+                swap_id: output_swap_id,
+            } = output.content
+            && output_swap_id == swap_id.0
+        {
+            if swap.l2_creator_address != Some(output.address) {
+                return Err(Error::InvalidTransaction(format!(
+                    "SwapClaim for a partial fill of swap {swap_id} must lock the unfilled remainder to the swap creator"
+                )));
+            }
+            relocked_value = relocked_value
+                .checked_add(value)
+                .ok_or(AmountOverflowError)?;
+            continue;
+        }
+        if output.address == expected_recipient {
+            recipient_receives = true;
+            recipient_value = recipient_value
+                .checked_add(output.get_value())
+                .ok_or(AmountOverflowError)?;
+        }
+    }
 
     if !recipient_receives {
         return Err(Error::InvalidTransaction(format!(
@@ -276,18 +425,569 @@ pub fn validate_swap_claim(
             expected_recipient
         )));
     }
+    if let Some(share) = partial_fill_share {
+        let remainder =
+            swap_input_value.checked_sub(share).ok_or_else(|| {
+                Error::InvalidTransaction(format!(
+                    "SwapClaim spends {swap_input_value} locked to the swap, but the L1 payment releases {share}"
+                ))
+            })?;
+        if relocked_value < remainder {
+            return Err(Error::InvalidTransaction(format!(
+                "SwapClaim locks {relocked_value} back to swap {swap_id}, but {remainder} is left unfilled"
+            )));
+        }
+        if recipient_value < share {
+            return Err(Error::InvalidTransaction(format!(
+                "SwapClaim pays {recipient_value} to {expected_recipient}, but the L1 payment releases {share}"
+            )));
+        }
+    } else if recipient_value < swap_input_value {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapClaim pays {} to {}, but spends {} locked to the swap",
+            recipient_value, expected_recipient, swap_input_value
+        )));
+    }
+
+    Ok((proof, verified))
+}
+
+/// Returns `true` if `spent_output` is a SwapPending output of the swap
+/// claimed by `transaction`
+fn is_swap_output(transaction: &Transaction, spent_output: &Output) -> bool {
+    let TxData::SwapClaim { swap_id, .. } = &transaction.data else {
+        return false;
+    };
+    matches!(
+        spent_output.content,
+        OutputContent::SwapPending {
+            swap_id: output_swap_id,
+            ..
+        } if output_swap_id == *swap_id
+    )
+}
+
+/// Returns `true` if `spent_output` is a SwapPending output of the swap
+/// claimed by `transaction`, and the swap is keyless-claimable (see
+/// [`Swap::is_keyless_claimable`]).
+///
+/// Such inputs do not need to be authorized by the owner of the spent
+/// output, so that a claimer can claim a swap without the creator's keys.
+/// The authorization for the input must still be a valid signature of the
+/// transaction, by any key. Claims of other open swaps must be signed by
+/// the creator, since the L1 payment does not name the claimer.
+pub fn is_swap_claim_input(
+    state: &State,
+    rotxn: &RoTxn,
+    transaction: &Transaction,
+    spent_output: &Output,
+) -> Result<bool, Error> {
+    let TxData::SwapClaim { swap_id, .. } = &transaction.data else {
+        return Ok(false);
+    };
+    if !is_swap_output(transaction, spent_output) {
+        return Ok(false);
+    }
+    let swap = state.get_swap(rotxn, &SwapId(*swap_id))?;
+    Ok(swap.is_some_and(|swap| swap.is_keyless_claimable()))
+}
+
+/// Validate a SwapCancel transaction, to be included in a block at
+/// `height`.
+///
+/// Before the swap expires, a SwapCancel is a cancellation request by the
+/// creator: it brings the expiry forward to the end of a claim grace
+/// period, see [`Swap::cancel_request_expiry`], and returns the new expiry
+/// height. Claims are refused from the expiry height, so cancellation can
+/// never race a claim of an L1 payment, whatever the L1 payment observed
+/// locally.
+///
+/// Once the swap has expired, and unless it was completed, a SwapCancel
+/// spends at least one SwapPending output of the swap. For L2 → L1 swaps,
+/// every such output must be owned by the swap creator, so that the
+/// creator's signature is required. For L1 → L2 swaps, the outputs belong
+/// to the L2 holder that locked them.
+///
+/// Cancellation requests, and cancellations of L1 → L2 offers that are
+/// still awaiting a lock, spend any of the creator's own outputs instead.
+pub fn validate_swap_cancel(
+    state: &State,
+    rotxn: &RoTxn,
+    filled_transaction: &FilledTransaction,
+    height: u32,
+) -> Result<Option<u32>, Error> {
+    let transaction = &filled_transaction.transaction;
+    let TxData::SwapCancel { swap_id } = &transaction.data else {
+        return Err(Error::InvalidTransaction(
+            "Expected SwapCancel transaction".to_string(),
+        ));
+    };
+    let swap_id = SwapId(*swap_id);
+
+    // 1. Verify swap exists, and has a known creator
+    let swap = state
+        .get_swap(rotxn, &swap_id)?
+        .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+    let creator = swap.l2_creator_address.ok_or(Error::SwapNotCreator)?;
+
+    // An L1 → L2 offer awaiting a lock has no outputs to spend, and the
+    // outputs of a swap that has not expired stay locked to it
+    let request_expiry = swap.cancel_request_expiry(height);
+    if matches!(swap.state, SwapState::AwaitingLock) || request_expiry.is_some()
+    {
+        if !filled_transaction
+            .spent_utxos
+            .iter()
+            .any(|spent_utxo| spent_utxo.address == creator)
+        {
+            return Err(Error::SwapNotCreator);
+        }
+        for (outpoint, _) in &transaction.inputs {
+            if let Some(locked_swap_id) =
+                state.is_output_locked_to_swap(rotxn, outpoint)?
+            {
+                return Err(Error::InvalidTransaction(format!(
+                    "Cannot spend locked output {} (locked to swap {})",
+                    outpoint, locked_swap_id
+                )));
+            }
+        }
+        if let Some(request_expiry) = request_expiry
+            && let Some(expires_at) = swap.expires_at_height
+            && expires_at <= request_expiry
+        {
+            return Err(Error::InvalidTransaction(format!(
+                "Swap {swap_id} already expires at height {expires_at}, within the cancellation grace period"
+            )));
+        }
+        return Ok(request_expiry);
+    }
+
+    // 2. Verify the swap has expired, and was not completed
+    if matches!(swap.state, SwapState::Completed) {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {swap_id} cannot be cancelled: it was completed"
+        )));
+    }
+    if !swap.is_expired(height) {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {swap_id} cannot be cancelled: it was already cancelled"
+        )));
+    }
+
+    // 3. Verify the swap outputs spent belong to the creator. Outputs of
+    // expired swaps are already unlocked, so swap outputs are identified
+    // by their content.
+    let mut found_swap_input = false;
+    for ((outpoint, _), spent_utxo) in transaction
+        .inputs
+        .iter()
+        .zip(&filled_transaction.spent_utxos)
+    {
+        if let Some(locked_swap_id) =
+            state.is_output_locked_to_swap(rotxn, outpoint)?
+            && locked_swap_id != swap_id
+        {
+            return Err(Error::InvalidTransaction(format!(
+                "Input {} is locked to different swap {}",
+                outpoint, locked_swap_id
+            )));
+        }
+        let OutputContent::SwapPending {
+            swap_id: output_swap_id,
+            ..
+        } = spent_utxo.content
+        else {
+            continue;
+        };
+        if output_swap_id != swap_id.0 {
+            continue;
+        }
+        if swap.direction == SwapDirection::L2ToL1
+            && spent_utxo.address != creator
+        {
+            return Err(Error::SwapNotCreator);
+        }
+        found_swap_input = true;
+    }
+    if !found_swap_input {
+        return Err(Error::InvalidTransaction(
+            "SwapCancel must spend at least one output of the swap".to_string(),
+        ));
+    }
+
+    Ok(None)
+}
+
+/// Validate a SwapLock transaction, to be included in a block at `height`.
+///
+/// The swap must be an L1 → L2 offer awaiting a lock. The transaction's
+/// SwapPending outputs for the swap must hold at least the swap's L2 amount,
+/// and it must not create SwapPending outputs for any other swap.
+pub fn validate_swap_lock(
+    state: &State,
+    rotxn: &RoTxn,
+    transaction: &Transaction,
+    height: u32,
+) -> Result<(), Error> {
+    let TxData::SwapLock {
+        swap_id,
+        l1_recipient_address,
+    } = &transaction.data
+    else {
+        return Err(Error::InvalidTransaction(
+            "Expected SwapLock transaction".to_string(),
+        ));
+    };
+    let swap_id = SwapId(*swap_id);
+
+    // 1. Verify swap exists, and is waiting to be locked
+    let swap = state
+        .get_swap(rotxn, &swap_id)?
+        .ok_or_else(|| Error::SwapNotFound { swap_id })?;
+    if swap.direction != SwapDirection::L1ToL2
+        || !matches!(swap.state, SwapState::AwaitingLock)
+    {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} cannot be locked (direction: {:?}, state: {:?}). Only L1 → L2 swaps awaiting a lock can be locked.",
+            swap_id, swap.direction, swap.state
+        )));
+    }
+    if swap.is_expired(height) {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {swap_id} has expired"
+        )));
+    }
+    // Offers recorded before SwapCreate required SPV proofs could never
+    // be claimed, so the locked coins would be stuck until expiry
+    spv_params(swap.parent_chain)?;
+
+    // 2. Verify the L1 address that will receive the payment
+    if l1_recipient_address.trim().is_empty() {
+        return Err(Error::InvalidTransaction(
+            "SwapLock requires an L1 recipient address".to_string(),
+        ));
+    }
+    swap.parent_chain
+        .consensus_validate_address(l1_recipient_address)
+        .map_err(|err| Error::InvalidTransaction(err.to_string()))?;
+
+    // 3. Verify no locked outputs are spent
+    validate_no_locked_outputs(state, rotxn, transaction)?;
+
+    // 4. Verify enough L2 coins are locked to the swap
+    let mut locked_value = bitcoin::Amount::ZERO;
+    for output in &transaction.outputs {
+        let OutputContent::SwapPending {
+            value,
+            swap_id: output_swap_id,
+        } = output.content
+        else {
+            continue;
+        };
+        if output_swap_id != swap_id.0 {
+            return Err(Error::InvalidTransaction(format!(
+                "SwapLock for swap {} cannot lock outputs to swap {}",
+                swap_id,
+                hex::encode(output_swap_id)
+            )));
+        }
+        locked_value =
+            locked_value.checked_add(value).ok_or(AmountOverflowError)?;
+    }
+    if locked_value < swap.l2_amount {
+        return Err(Error::InvalidTransaction(format!(
+            "SwapLock locks {}, but swap {} requires {}",
+            locked_value, swap_id, swap.l2_amount
+        )));
+    }
+
+    Ok(())
+}
+
+/// Lock the SwapPending outputs for the swap created by a SwapLock, or by
+/// a SwapClaim that partially fills the swap
+pub fn lock_swap_outputs(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    transaction: &Transaction,
+    swap_id: &SwapId,
+) -> Result<(), Error> {
+    let txid = transaction.txid();
+    for (vout, output) in transaction.outputs.iter().enumerate() {
+        if let OutputContent::SwapPending {
+            swap_id: output_swap_id,
+            ..
+        } = output.content
+            && output_swap_id == swap_id.0
+        {
+            let outpoint = OutPoint::Regular {
+                txid,
+                vout: vout as u32,
+            };
+            state.lock_output_to_swap(rwtxn, &outpoint, swap_id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Unlock the outputs locked by [`lock_swap_outputs`], when disconnecting
+/// the transaction that created them
+pub fn unlock_swap_outputs(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    transaction: &Transaction,
+    swap_id: &SwapId,
+) -> Result<(), Error> {
+    let txid = transaction.txid();
+    for (vout, output) in transaction.outputs.iter().enumerate().rev() {
+        if output.content.is_swap_pending() {
+            let outpoint = OutPoint::Regular {
+                txid,
+                vout: vout as u32,
+            };
+            if state.is_output_locked_to_swap(rwtxn, &outpoint)?
+                == Some(*swap_id)
+            {
+                state.unlock_output_from_swap(rwtxn, &outpoint)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// L1 amount paid by the L1 transaction proven in a claim of a swap that
+/// accepts partial fills. The payment must fill part or all of the
+/// remaining L1 amount.
+pub fn claimed_l1_fill(
+    swap: &Swap,
+    l1_tx: &bitcoin::Transaction,
+) -> Result<bitcoin::Amount, Error> {
+    let l1_fill = swap
+        .l1_payment_value(l1_tx)
+        .unwrap_or(bitcoin::Amount::ZERO);
+    let remaining = swap.remaining_l1_amount();
+    if l1_fill == bitcoin::Amount::ZERO || l1_fill > remaining {
+        return Err(Error::InvalidTransaction(format!(
+            "L1 transaction {} pays {} to the L1 recipient of swap {}, but must pay more than zero and at most the remaining {}",
+            l1_tx.compute_txid(),
+            l1_fill,
+            swap.id,
+            remaining
+        )));
+    }
+    Ok(l1_fill)
+}
+
+/// L2 address committed to by an L1 payment for a swap that requires a
+/// commitment. For swaps with an L2 recipient, it must be the recipient.
+fn committed_l2_address(
+    swap: &Swap,
+    l1_tx: &bitcoin::Transaction,
+) -> Result<Address, Error> {
+    let commitment = SwapCommitment::find(
+        l1_tx
+            .output
+            .iter()
+            .map(|output| output.script_pubkey.as_script()),
+        &swap.id,
+    )
+    .ok_or_else(|| {
+        Error::InvalidTransaction(format!(
+            "L1 transaction {} does not commit to swap {}",
+            l1_tx.compute_txid(),
+            swap.id
+        ))
+    })?;
+    if let Some(l2_recipient) = swap.l2_recipient
+        && l2_recipient != commitment.l2_claimer_address
+    {
+        return Err(Error::InvalidTransaction(format!(
+            "L1 transaction {} commits swap {} to {}, not its L2 recipient {}",
+            l1_tx.compute_txid(),
+            swap.id,
+            commitment.l2_claimer_address,
+            l2_recipient
+        )));
+    }
+    Ok(commitment.l2_claimer_address)
+}
+
+/// Decode and verify the SPV proof in a SwapClaim against the swap terms.
+///
+/// Only consensus state is consulted: the swap terms from SwapCreate,
+/// completed swaps, and the L1 headers tracked from earlier proofs. The
+/// proof's headers must extend the SPV checkpoint or the best tracked L1
+/// chain, see [`l1_headers`].
+fn validate_spv_proof(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    proof_data: &[u8],
+    spv_params: &SpvParams,
+) -> Result<(SpvProof, VerifiedL1Tx), Error> {
+    let spv_err = |source| Error::SpvProof {
+        swap_id: swap.id,
+        source,
+    };
+    let proof = SpvProof::from_bytes(proof_data).map_err(spv_err)?;
+    let verified = proof.verify(spv_params.max_target).map_err(spv_err)?;
+
+    if matches!(swap.state, SwapState::Completed | SwapState::Cancelled) {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} cannot be claimed (state: {:?})",
+            swap.id, swap.state
+        )));
+    }
+
+    if verified.confirmations < swap.required_confirmations {
+        return Err(Error::InvalidTransaction(format!(
+            "SPV proof for swap {} has {} confirmations, {} required",
+            swap.id, verified.confirmations, swap.required_confirmations
+        )));
+    }
+
+    // The L1 transaction must not have been used to claim another swap, or
+    // to fill part of this one
+    let l1_txid = SwapTxId::from_bitcoin_txid(&verified.txid);
+    if swap.filled_l1_txids.contains(&l1_txid) {
+        return Err(Error::InvalidTransaction(format!(
+            "L1 transaction {} already filled part of swap {}",
+            verified.txid, swap.id
+        )));
+    }
+    if let Some(existing) =
+        state.get_swap_by_l1_txid(rotxn, &swap.parent_chain, &l1_txid)?
+        && existing.id != swap.id
+        && (matches!(existing.state, SwapState::Completed)
+            || existing.filled_l1_txids.contains(&l1_txid))
+    {
+        return Err(Error::L1TxidAlreadyUsed {
+            swap_id: swap.id,
+            existing_swap_id: existing.id,
+        });
+    }
+
+    validate_l1_payment(swap, &proof.transaction)?;
+
+    l1_headers::validate_headers(
+        state,
+        rotxn,
+        swap.parent_chain,
+        spv_params,
+        proof.l1_headers(),
+    )?;
 
+    Ok((proof, verified))
+}
+
+fn spv_params(parent_chain: ParentChainType) -> Result<SpvParams, Error> {
+    parent_chain.spv_params().ok_or_else(|| {
+        Error::InvalidTransaction(format!(
+            "SPV proofs are not supported for {}",
+            parent_chain.coin_name()
+        ))
+    })
+}
+
+/// Track the L1 headers in a connected SwapClaim's SPV proof
+pub fn track_l1_headers(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    parent_chain: ParentChainType,
+    proof: &SpvProof,
+    claim_txid: Txid,
+) -> Result<(), Error> {
+    let spv_params = spv_params(parent_chain)?;
+    l1_headers::track_headers(
+        state,
+        rwtxn,
+        parent_chain,
+        &spv_params,
+        proof.l1_headers(),
+        claim_txid,
+    )
+}
+
+/// Stop tracking the L1 headers that were added by a disconnected
+/// SwapClaim
+pub fn untrack_l1_headers(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    parent_chain: ParentChainType,
+    proof: &SpvProof,
+    claim_txid: Txid,
+) -> Result<(), Error> {
+    l1_headers::untrack_headers(
+        state,
+        rwtxn,
+        parent_chain,
+        proof.l1_headers(),
+        claim_txid,
+    )
+}
+
+/// Validate that `l1_tx` pays the swap's L1 recipient, as required by the
+/// swap's match policy or partial fills, and carries the swap's L1
+/// commitment if it requires one. The recipient's script_pubkey is derived
+/// by `rust-bitcoin` for the parent chain's Bitcoin network.
+fn validate_l1_payment(
+    swap: &Swap,
+    l1_tx: &bitcoin::Transaction,
+) -> Result<(), Error> {
+    let (Some(l1_recipient_address), Some(l1_amount)) =
+        (swap.l1_recipient_address.as_ref(), swap.l1_amount)
+    else {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap {} has no L1 recipient address or amount",
+            swap.id
+        )));
+    };
+    let l1_recipient_script = swap
+        .parent_chain
+        .consensus_script_pubkey(l1_recipient_address)
+        .map_err(|err| {
+            Error::InvalidTransaction(format!(
+                "Invalid L1 recipient address for swap {}: {err}",
+                swap.id
+            ))
+        })?;
+    if swap.partial_fills {
+        let _: bitcoin::Amount = claimed_l1_fill(swap, l1_tx)?;
+    } else if !swap.l1_match_policy.matches(
+        l1_tx
+            .output
+            .iter()
+            .filter(|output| output.script_pubkey == l1_recipient_script)
+            .map(|output| output.value),
+        l1_amount,
+    ) {
+        return Err(Error::InvalidTransaction(format!(
+            "L1 transaction {} does not pay {} to {} under the `{}` match policy",
+            l1_tx.compute_txid(),
+            l1_amount,
+            l1_recipient_address,
+            swap.l1_match_policy
+        )));
+    }
+    if swap.l1_commitment_required {
+        let _: Address = committed_l2_address(swap, l1_tx)?;
+    }
     Ok(())
 }
 
-/// Validate that non-SwapClaim transactions don't spend locked outputs
+/// Validate that transactions other than SwapClaim and SwapCancel don't
+/// spend locked outputs
 pub fn validate_no_locked_outputs(
     state: &State,
     rotxn: &RoTxn,
     transaction: &Transaction,
 ) -> Result<(), Error> {
-    // Skip validation for SwapClaim transactions
-    if matches!(transaction.data, TxData::SwapClaim { .. }) {
+    // Skip validation for SwapClaim and SwapCancel transactions
+    if matches!(
+        transaction.data,
+        TxData::SwapClaim { .. } | TxData::SwapCancel { .. }
+    ) {
         return Ok(());
     }
 
@@ -305,3 +1005,214 @@ pub fn validate_no_locked_outputs(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_swap_claim_input, validate_swap_cancel};
+    use crate::{
+        state::{Error, test_utils::open_state},
+        types::{
+            Address, FilledTransaction, L1MatchPolicy, OutPoint, Output,
+            OutputContent, ParentChainType, Swap, SwapDirection, SwapId,
+            SwapState, SwapTxId, Transaction, TxData, Txid,
+        },
+    };
+
+    #[test]
+    fn cancel_requires_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let creator = Address([7; 20]);
+        let swap = Swap::new(
+            SwapId([1; 32]),
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0; 32]),
+            None,
+            None,
+            bitcoin::Amount::from_sat(100_000),
+            Some("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_owned()),
+            Some(bitcoin::Amount::from_sat(50_000)),
+            10,
+            Swap::expiry_height(10, Some(5)),
+            Some(creator),
+            false,
+            false,
+            L1MatchPolicy::Exact,
+        );
+        let locked_outpoint = OutPoint::Regular {
+            txid: Txid([3; 32]),
+            vout: 0,
+        };
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        state
+            .lock_output_to_swap(&mut rwtxn, &locked_outpoint, &swap.id)
+            .unwrap();
+        let cancel = FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(locked_outpoint, [0; 32])],
+                data: TxData::SwapCancel { swap_id: swap.id.0 },
+                ..Default::default()
+            },
+            spent_utxos: vec![Output {
+                address: creator,
+                content: OutputContent::SwapPending {
+                    value: bitcoin::Amount::from_sat(100_000),
+                    swap_id: swap.id.0,
+                },
+            }],
+        };
+
+        // The locked outputs of a Pending swap cannot be spent before it
+        // expires, whatever L1 payments the creator may have seen
+        assert!(validate_swap_cancel(&state, &rwtxn, &cancel, 14).is_err());
+        assert_eq!(
+            validate_swap_cancel(&state, &rwtxn, &cancel, 15).unwrap(),
+            None
+        );
+
+        // A completed swap cannot be cancelled, even once it has expired
+        let mut completed = swap.clone();
+        completed.state = SwapState::Completed;
+        state.save_swap(&mut rwtxn, &completed).unwrap();
+        assert!(validate_swap_cancel(&state, &rwtxn, &cancel, 15).is_err());
+    }
+
+    #[test]
+    fn cancel_before_expiry_is_a_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let creator = Address([7; 20]);
+        let mut swap = Swap::new(
+            SwapId([1; 32]),
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0; 32]),
+            None,
+            None,
+            bitcoin::Amount::from_sat(100_000),
+            Some("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_owned()),
+            Some(bitcoin::Amount::from_sat(50_000)),
+            10,
+            None,
+            Some(creator),
+            false,
+            false,
+            L1MatchPolicy::Exact,
+        );
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        let swap_id = swap.id.0;
+        let request = move |address| FilledTransaction {
+            transaction: Transaction {
+                inputs: vec![(
+                    OutPoint::Regular {
+                        txid: Txid([4; 32]),
+                        vout: 0,
+                    },
+                    [0; 32],
+                )],
+                data: TxData::SwapCancel { swap_id },
+                ..Default::default()
+            },
+            spent_utxos: vec![Output {
+                address,
+                content: OutputContent::Value(bitcoin::Amount::from_sat(1)),
+            }],
+        };
+
+        // The creator's request brings the expiry forward, leaving the swap
+        // claimable for the grace period
+        assert_eq!(
+            validate_swap_cancel(&state, &rwtxn, &request(creator), 20)
+                .unwrap(),
+            Some(20 + Swap::CANCEL_GRACE_BLOCKS)
+        );
+        assert!(matches!(
+            validate_swap_cancel(
+                &state,
+                &rwtxn,
+                &request(Address([8; 20])),
+                20
+            ),
+            Err(Error::SwapNotCreator)
+        ));
+
+        // A request cannot postpone the expiry
+        swap.expires_at_height = Some(20 + Swap::CANCEL_GRACE_BLOCKS);
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        assert!(
+            validate_swap_cancel(&state, &rwtxn, &request(creator), 20)
+                .is_err()
+        );
+        assert_eq!(
+            validate_swap_cancel(&state, &rwtxn, &request(creator), 19)
+                .unwrap(),
+            Some(19 + Swap::CANCEL_GRACE_BLOCKS)
+        );
+    }
+
+    #[test]
+    fn open_swap_claims_need_creator_signature() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let creator = Address([7; 20]);
+        let claimer = Address([8; 20]);
+        let cases = [
+            (1, Some(claimer), false, true),
+            (2, None, true, true),
+            (3, None, false, false),
+        ];
+        for (id, l2_recipient, l1_commitment_required, keyless) in cases {
+            let swap = Swap::new(
+                SwapId([id; 32]),
+                SwapDirection::L2ToL1,
+                ParentChainType::BTC,
+                SwapTxId::Hash32([0; 32]),
+                None,
+                l2_recipient,
+                bitcoin::Amount::from_sat(100_000),
+                Some("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_owned()),
+                Some(bitcoin::Amount::from_sat(50_000)),
+                10,
+                None,
+                Some(creator),
+                l1_commitment_required,
+                false,
+                L1MatchPolicy::Exact,
+            );
+            state.save_swap(&mut rwtxn, &swap).unwrap();
+            let claim = Transaction {
+                data: TxData::SwapClaim {
+                    swap_id: swap.id.0,
+                    proof_data: None,
+                    l2_claimer_address: Some(claimer),
+                },
+                ..Default::default()
+            };
+            let swap_output = Output {
+                address: creator,
+                content: OutputContent::SwapPending {
+                    value: bitcoin::Amount::from_sat(100_000),
+                    swap_id: swap.id.0,
+                },
+            };
+            assert_eq!(
+                is_swap_claim_input(&state, &rwtxn, &claim, &swap_output)
+                    .unwrap(),
+                keyless,
+                "swap {id}"
+            );
+            let value_output = Output {
+                address: creator,
+                content: OutputContent::Value(bitcoin::Amount::from_sat(1)),
+            };
+            assert!(
+                !is_swap_claim_input(&state, &rwtxn, &claim, &value_output)
+                    .unwrap()
+            );
+        }
+    }
+}
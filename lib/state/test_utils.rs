@@ -0,0 +1,16 @@
+//! Helpers shared by the state unit tests
+
+use sneed::Env;
+
+use crate::state::State;
+
+/// Open an empty [`State`] in `dir`
+pub fn open_state(dir: &tempfile::TempDir) -> (Env, State) {
+    let mut env_open_opts = heed::EnvOpenOptions::new();
+    env_open_opts
+        .map_size(16 * 1024 * 1024)
+        .max_dbs(State::NUM_DBS);
+    let env = unsafe { Env::open(&env_open_opts, dir.path()) }.unwrap();
+    let state = State::new(&env).unwrap();
+    (env, state)
+}
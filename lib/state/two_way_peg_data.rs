@@ -5,16 +5,17 @@ use std::collections::{BTreeMap, HashMap};
 use fallible_iterator::FallibleIterator;
 use sneed::{RoTxn, RwTxn, db::error::Error as DbError};
 
-use crate::parent_chain_rpc::{ParentChainRpcClient, RpcConfig};
 use crate::{
+    parent_chain_rpc::TransactionInfo,
     state::{
-        Error, State, WITHDRAWAL_BUNDLE_FAILURE_GAP, WithdrawalBundleInfo,
+        Error, L1Observations, State, SwapUpdateSource,
+        WITHDRAWAL_BUNDLE_FAILURE_GAP, WithdrawalBundleInfo,
         rollback::RollBack,
     },
     types::{
         AccumulatorDiff, AggregatedWithdrawal, AmountOverflowError, BlockHash,
         GetValue, InPoint, M6id, OutPoint, OutPointKey, Output, OutputContent,
-        ParentChainType, PointedOutput, PointedOutputRef, SpentOutput, Swap,
+        PointedOutput, PointedOutputRef, SpentOutput, Swap, SwapError,
         SwapState, SwapTxId, WithdrawalBundle, WithdrawalBundleEvent,
         WithdrawalBundleStatus, hash,
         proto::mainchain::{BlockEvent, TwoWayPegData},
@@ -538,16 +539,26 @@ fn connect_event(
 ///
 /// Flow:
 /// 1. Get all pending swaps
-/// 2. For each swap, query swap.parent_chain (e.g., Signet) for transactions
-/// 3. Match transactions by: l1_recipient_address and l1_amount
-/// 4. Update swap state based on found transactions and confirmations
+/// 2. For each swap, take the transactions observed on swap.parent_chain
+///    (e.g., Signet) before the block was connected, see [`L1Observations`]
+/// 3. Match transactions by: l1_recipient_address and l1_amount (or, for
+///    swaps that accept partial fills, any amount up to the unfilled
+///    amount), and for swaps that require one, an OP_RETURN commitment to
+///    the swap
+/// 4. Update this node's view of the swap (see `SwapL1Status`) based on
+///    found transactions and confirmations. Consensus swap state is only
+///    changed by SwapClaim SPV proofs.
 ///
-/// **BMM / header chain / merkle proof:** None of these are used for swap L1
-/// verification in this repo. L1 presence and confirmations are taken from the
-/// configured parent chain RPC only (no BMM reports, no per–parent-chain header
-/// chain for swaps, no merkle proof of L1 tx in block).
+/// **BMM / merkle proof:** Neither is used to detect swap L1 payments. L1
+/// presence is taken from the configured parent chain backend, and where
+/// the L1 header chain is tracked, confirmations are counted against it.
 ///
-/// Query L1 blockchain for matching transactions and update swap.
+/// Update swap from the L1 transactions observed for it.
+///
+/// A swap that is waiting for confirmations only follows its recorded L1
+/// payment, which is looked up as `recorded_l1_txid`. If the recorded payment is no
+/// longer in the L1 chain, e.g. after an L1 reorg, the swap reverts to
+/// Pending, and may then be filled by another L1 payment.
 ///
 /// `block_hash` and `block_height` are the sidechain block where this
 /// validation occurs.
@@ -556,25 +567,105 @@ fn connect_event(
 /// not be associated with more than one swap. Uses `get_swap_by_l1_txid` before
 /// accepting a new L1 tx.
 #[allow(clippy::too_many_arguments)]
-fn query_and_update_swap(
+fn update_swap_from_l1(
     state: &State,
     rwtxn: &mut RwTxn,
-    rpc_config: &RpcConfig,
+    observed: &[(String, TransactionInfo)],
+    recorded_l1_txid: Option<&SwapTxId>,
     swap: &mut Swap,
     l1_recipient: &str,
     l1_amount: bitcoin::Amount,
     block_hash: BlockHash,
     block_height: u32,
 ) -> Result<bool, Error> {
-    let client = ParentChainRpcClient::new(rpc_config.clone());
     let amount_sats = l1_amount.to_sat();
 
-    // Find transactions matching address and amount
-    let matches = client
-        .find_transactions_by_address_and_amount(l1_recipient, amount_sats)?;
+    let mut reverted = false;
+    if let SwapState::WaitingConfirmations(current_confirmations, _) =
+        swap.state
+    {
+        // The recorded payment was not looked up, e.g. because it was
+        // recorded after the observation was made
+        if recorded_l1_txid != Some(&swap.l1_txid) {
+            return Ok(false);
+        }
+        let recorded = observed.iter().find_map(|(_, tx_info)| {
+            SwapTxId::from_hex_rpc(&tx_info.txid)
+                .is_ok_and(|txid| txid == swap.l1_txid)
+                .then_some(tx_info)
+        });
+        match recorded {
+            Some(tx_info)
+                if tx_info.confirmations > 0
+                    && tx_info.blockheight.is_some() =>
+            {
+                if tx_info.confirmations <= current_confirmations {
+                    return Ok(false);
+                }
+                tracing::debug!(
+                    swap_id = %swap.id,
+                    old_confirmations = %current_confirmations,
+                    new_confirmations = %tx_info.confirmations,
+                    "Updating swap confirmations"
+                );
+                if tx_info.confirmations >= swap.required_confirmations {
+                    swap.state = SwapState::ReadyToClaim;
+                } else {
+                    swap.state = SwapState::WaitingConfirmations(
+                        tx_info.confirmations,
+                        swap.required_confirmations,
+                    );
+                }
+                return Ok(true);
+            }
+            _ => {
+                tracing::warn!(
+                    swap_id = %swap.id,
+                    parent_chain = ?swap.parent_chain,
+                    l1_txid = %swap.l1_txid.to_hex(),
+                    in_mempool = recorded.is_some(),
+                    %block_height,
+                    error = %SwapError::TransactionDisappeared,
+                    "L1 payment for swap is no longer in the L1 chain; reverting swap to Pending"
+                );
+                swap.reset_l1_payment();
+                reverted = true;
+            }
+        }
+    }
+
+    // Find transactions paying the address under the swap's match policy.
+    // Swaps that accept partial fills match any payment up to the unfilled
+    // amount, that has not already filled part of the swap. Blocks
+    // connected since the observation was made may have filled part of the
+    // swap, so the amounts are checked again.
+    let matches: Vec<_> = if swap.partial_fills {
+        let remaining_sats = swap.remaining_l1_amount().to_sat();
+        observed
+            .iter()
+            .filter(|(_, tx_info)| {
+                (1..=remaining_sats).contains(&tx_info.value_to(l1_recipient))
+                    && SwapTxId::from_hex_rpc(&tx_info.txid)
+                        .is_ok_and(|txid| !swap.filled_l1_txids.contains(&txid))
+            })
+            .cloned()
+            .collect()
+    } else {
+        observed
+            .iter()
+            .filter(|(_, tx_info)| {
+                tx_info.pays_amount_to(
+                    l1_recipient,
+                    amount_sats,
+                    swap.l1_match_policy,
+                )
+            })
+            .cloned()
+            .collect()
+    };
 
     if matches.is_empty() {
-        return Ok(false);
+        return Ok(reverted);
     }
 
     // Only accept transactions that are confirmed and included in a block
@@ -589,95 +680,132 @@ fn query_and_update_swap(
             swap_id = %swap.id,
             "No confirmed or block-included L1 match; rejecting unconfirmed or mempool-only tx"
         );
-        return Ok(false);
+        return Ok(reverted);
+    }
+
+    // Swaps that require a commitment only match L1 payments that commit
+    // to them. The commitment declares the filler's L2 address, which must
+    // be the swap's L2 recipient if it has one.
+    let matches: Vec<_> = matches
+        .into_iter()
+        .filter_map(|(sender_address, tx_info)| {
+            if !swap.l1_commitment_required {
+                return Some((sender_address, tx_info, None));
+            }
+            let commitment = tx_info.swap_commitment(&swap.id)?;
+            if let Some(l2_recipient) = swap.l2_recipient
+                && l2_recipient != commitment.l2_claimer_address
+            {
+                tracing::debug!(
+                    swap_id = %swap.id,
+                    l1_txid = %tx_info.txid,
+                    committed_l2_address = %commitment.l2_claimer_address,
+                    "Ignoring L1 match committing to another L2 recipient"
+                );
+                return None;
+            }
+            Some((sender_address, tx_info, Some(commitment.l2_claimer_address)))
+        })
+        .collect();
+    if matches.is_empty() {
+        tracing::debug!(
+            swap_id = %swap.id,
+            "No L1 match carries a commitment to the swap"
+        );
+        return Ok(reverted);
     }
 
     // Use the first valid match (most recent transaction)
     // In a production system, you might want to handle multiple matches differently
-    let (sender_address, tx_info) = &matches[0];
+    let (sender_address, tx_info, committed_l2_address) = &matches[0];
 
-    // Convert txid string from parent chain RPC (RPC byte order) to SwapTxId (canonical storage)
+    // Convert txid string from parent chain RPC to SwapTxId
     let l1_txid = SwapTxId::from_hex_rpc(&tx_info.txid)
         .map_err(|_| crate::parent_chain_rpc::Error::InvalidResponse)?;
 
-    // Check if this is an update or new detection
-    let zero_hash32 = [0u8; 32];
-    let is_new = matches!(swap.l1_txid, SwapTxId::Hash32(h) if h == zero_hash32)
-        || matches!(swap.l1_txid, SwapTxId::Hash(ref v) if v.is_empty() || v.iter().all(|&b| b == 0));
-
-    if is_new {
-        // L1 transaction uniqueness: do not accept an L1 tx already used by another swap
-        if let Some(existing) =
-            state.get_swap_by_l1_txid(rwtxn, &swap.parent_chain, &l1_txid)?
-            && existing.id != swap.id
-        {
-            tracing::info!(
-                swap_id = %swap.id,
-                existing_swap_id = %existing.id,
-                l1_txid = %tx_info.txid,
-                "Rejecting L1 tx already associated with another swap"
-            );
-            return Ok(false);
-        }
-
-        // New L1 transaction detected
+    // L1 transaction uniqueness: do not accept an L1 tx already used by another swap
+    let existing_swap_id =
+        match state.get_swap_by_l1_txid(rwtxn, &swap.parent_chain, &l1_txid)? {
+            Some(existing) if existing.id != swap.id => Some(existing.id),
+            _ => state.get_other_swap_by_observed_l1_txid(
+                rwtxn,
+                &swap.id,
+                swap.parent_chain,
+                &l1_txid,
+            )?,
+        };
+    if let Some(existing_swap_id) = existing_swap_id {
         tracing::info!(
             swap_id = %swap.id,
+            %existing_swap_id,
             l1_txid = %tx_info.txid,
-            confirmations = %tx_info.confirmations,
-            sender = %sender_address,
-            is_open_swap = %swap.l2_recipient.is_none(),
-            "Detected new L1 transaction for swap"
+            "Rejecting L1 tx already associated with another swap"
         );
+        return Ok(reverted);
+    }
 
-        // Update swap with L1 transaction
-        // For open swaps, we don't store the sender address here - the claimer will provide
-        // their L2 address when claiming, and we'll verify they sent the L1 transaction
-        swap.update_l1_txid(l1_txid);
+    // New L1 transaction detected
+    tracing::info!(
+        swap_id = %swap.id,
+        l1_txid = %tx_info.txid,
+        confirmations = %tx_info.confirmations,
+        sender = %sender_address,
+        is_open_swap = %swap.l2_recipient.is_none(),
+        "Detected new L1 transaction for swap"
+    );
 
-        // Save the sidechain block reference where this validation occurred
-        swap.set_l1_txid_validation_block(block_hash, block_height);
+    // Update swap with L1 transaction
+    // For open swaps, we don't store the sender address here - the claimer will provide
+    // their L2 address when claiming, and we'll verify they sent the L1 transaction
+    swap.update_l1_txid(l1_txid);
 
-        // Update state based on confirmations
-        if tx_info.confirmations >= swap.required_confirmations {
-            swap.state = SwapState::ReadyToClaim;
-        } else {
-            swap.state = SwapState::WaitingConfirmations(
-                tx_info.confirmations,
-                swap.required_confirmations,
-            );
-        }
+    // With a commitment, the claim is only valid for the committed
+    // L2 address
+    if let Some(l2_address) = committed_l2_address {
+        swap.set_l2_claimer_address(*l2_address);
+    }
 
-        Ok(true)
-    } else {
-        // Update confirmations for existing transaction
-        let current_confirmations = match swap.state {
-            SwapState::WaitingConfirmations(current, _) => current,
-            _ => 0,
-        };
+    // Save the sidechain block reference where this validation occurred
+    swap.set_l1_txid_validation_block(block_hash, block_height);
 
-        if tx_info.confirmations > current_confirmations {
-            tracing::debug!(
-                swap_id = %swap.id,
-                old_confirmations = %current_confirmations,
-                new_confirmations = %tx_info.confirmations,
-                "Updating swap confirmations"
-            );
+    // Update state based on confirmations
+    if tx_info.confirmations >= swap.required_confirmations {
+        swap.state = SwapState::ReadyToClaim;
+    } else {
+        swap.state = SwapState::WaitingConfirmations(
+            tx_info.confirmations,
+            swap.required_confirmations,
+        );
+    }
 
-            if tx_info.confirmations >= swap.required_confirmations {
-                swap.state = SwapState::ReadyToClaim;
-            } else {
-                swap.state = SwapState::WaitingConfirmations(
-                    tx_info.confirmations,
-                    swap.required_confirmations,
-                );
-            }
+    Ok(true)
+}
 
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+/// Cancel swaps that have expired by `block_height`, so that their locked
+/// outputs can be spent by the swap creator
+fn expire_swaps(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    block_height: u32,
+) -> Result<(), Error> {
+    let expired_swaps: Vec<Swap> = state
+        .load_all_swaps(rwtxn)?
+        .into_iter()
+        .filter(|swap| {
+            !matches!(swap.state, SwapState::Completed | SwapState::Cancelled)
+                && swap.is_expired(block_height)
+        })
+        .collect();
+    for swap in expired_swaps {
+        tracing::info!(
+            swap_id = %swap.id,
+            %block_height,
+            expires_at = ?swap.expires_at_height,
+            "Swap expired, unlocking outputs and marking as cancelled"
+        );
+        let () = state.expire_swap(rwtxn, swap, block_height)?;
     }
+    Ok(())
 }
 
 fn process_coinshift_transactions(
@@ -685,7 +813,7 @@ fn process_coinshift_transactions(
     rwtxn: &mut RwTxn,
     block_height: u32,
     block_hash: BlockHash,
-    rpc_config_getter: Option<&dyn Fn(ParentChainType) -> Option<RpcConfig>>,
+    l1_observations: &L1Observations,
 ) -> Result<(), Error> {
     tracing::debug!(%block_height, "Starting to scan enforcer for coinshift transactions");
 
@@ -699,17 +827,20 @@ fn process_coinshift_transactions(
     );
 
     let mut pending_swaps_count = 0;
-    let mut expired_swaps_count = 0;
     let mut scanned_swaps_count = 0;
 
-    for mut swap in swaps {
-        // Only process L2 → L1 swaps that are pending or waiting for confirmations
-        if !matches!(
-            swap.state,
-            SwapState::Pending | SwapState::WaitingConfirmations(..)
-        ) {
+    for swap in swaps {
+        // Only process swaps that are pending. L1 → L2 swaps are pending
+        // once locked, when the L1 recipient is known. Observations only
+        // change this node's view of the swap, see `SwapL1Status`.
+        if !matches!(swap.state, SwapState::Pending) {
+            state.put_swap_l1_status(rwtxn, &swap)?;
             continue;
         }
+        let Some(mut swap) = state.get_swap_with_l1_status(rwtxn, &swap.id)?
+        else {
+            continue;
+        };
 
         pending_swaps_count += 1;
         let l1_amount_str = swap
@@ -728,22 +859,6 @@ fn process_coinshift_transactions(
             "Checking swap for matching L1 transactions"
         );
 
-        // Check if swap has expired
-        if let Some(expires_at) = swap.expires_at_height
-            && block_height >= expires_at
-        {
-            tracing::info!(
-                swap_id = %swap.id,
-                block_height = %block_height,
-                expires_at = %expires_at,
-                "Swap expired, marking as cancelled"
-            );
-            swap.state = SwapState::Cancelled;
-            state.save_swap(rwtxn, &swap)?;
-            expired_swaps_count += 1;
-            continue;
-        }
-
         // For L2 → L1 swaps, we need to check if the L1 transaction exists
         // on the SWAP TARGET CHAIN (swap.parent_chain), NOT the sidechain's mainchain.
         //
@@ -772,34 +887,31 @@ fn process_coinshift_transactions(
             l1_amount_str
         );
 
-        // Swap L1 presence and confirmation count rely on the configured RPC for
-        // the swap target chain (swap.parent_chain). If no RPC is configured here,
-        // we skip L1 lookup and the swap stays Pending until RPC is set or the user
-        // manually updates via update_swap_l1_txid.
-        // Query L1 blockchain for matching transactions if RPC config is available.
-        // URL is chosen by swap.parent_chain: we look up that chain in l1_rpc_configs.json.
-        // If no RPC config exists for this chain, we skip L1 lookup and the swap stays
+        // Swap L1 presence and confirmation count rely on the configured backend
+        // for the swap target chain (swap.parent_chain), which was queried before
+        // this block was connected. If the swap was not observed, e.g. because no
+        // backend is configured for its chain, we skip L1 lookup and the swap stays
         // Pending until config is set or the user updates via update_swap_l1_txid.
         let l1_recipient_clone = swap.l1_recipient_address.clone();
         let l1_amount_clone = swap.l1_amount;
         let parent_chain_clone = swap.parent_chain;
         if let (Some(l1_recipient), Some(l1_amount)) =
             (l1_recipient_clone.as_deref(), l1_amount_clone)
-            && let Some(get_rpc_config) = rpc_config_getter
-            && let Some(rpc_config) = get_rpc_config(parent_chain_clone)
+            && let Some(observed) = l1_observations.get(&swap.id)
         {
             tracing::info!(
                 swap_id = %swap.id,
                 parent_chain = ?parent_chain_clone,
                 l1_recipient = %l1_recipient,
                 l1_amount_sats = %l1_amount.to_sat(),
-                url = %rpc_config.url,
-                "Querying L1 for swap"
+                observed_txs = observed.len(),
+                "Checking L1 observations for swap"
             );
-            match query_and_update_swap(
+            match update_swap_from_l1(
                 state,
                 rwtxn,
-                &rpc_config,
+                observed,
+                l1_observations.l1_txid(&swap.id),
                 &mut swap,
                 l1_recipient,
                 l1_amount,
@@ -814,7 +926,7 @@ fn process_coinshift_transactions(
                             state = ?swap.state,
                             "Updated swap with L1 transaction"
                         );
-                        state.save_swap(rwtxn, &swap)?;
+                        state.put_swap_l1_status(rwtxn, &swap)?;
                     }
                 }
                 Err(e) => {
@@ -822,9 +934,8 @@ fn process_coinshift_transactions(
                         swap_id = %swap.id,
                         parent_chain = ?parent_chain_clone,
                         l1_recipient = %l1_recipient,
-                        url = %rpc_config.url,
                         error = %e,
-                        "Failed to query L1 for swap; swap will stay pending until RPC succeeds or l1_txid is set manually"
+                        "Failed to update swap from L1 observations; swap will stay pending until they match or l1_txid is set manually"
                     );
                 }
             }
@@ -832,7 +943,7 @@ fn process_coinshift_transactions(
             tracing::debug!(
                 swap_id = %swap.id,
                 parent_chain = ?parent_chain_clone,
-                "Skipping L1 lookup: no RPC config for this parent chain (swap stays Pending until config is set or l1_txid is set manually)"
+                "Skipping L1 lookup: swap was not observed on its parent chain (swap stays Pending until config is set or l1_txid is set manually)"
             );
         }
 
@@ -843,7 +954,6 @@ fn process_coinshift_transactions(
         %block_height,
         total_swaps = total_swaps_count,
         pending_swaps = pending_swaps_count,
-        expired_swaps = expired_swaps_count,
         scanned_swaps = scanned_swaps_count,
         "Finished scanning enforcer for coinshift transactions"
     );
@@ -855,7 +965,7 @@ pub fn connect(
     state: &State,
     rwtxn: &mut RwTxn,
     two_way_peg_data: &TwoWayPegData,
-    rpc_config_getter: Option<&dyn Fn(ParentChainType) -> Option<RpcConfig>>,
+    l1_observations: &L1Observations,
     wallet: Option<&Wallet>,
 ) -> Result<(), Error> {
     let block_height = state.try_get_height(rwtxn)?.ok_or(Error::NoTip)?;
@@ -884,6 +994,8 @@ pub fn connect(
         }
     }
 
+    // Expire swaps before scanning L1 for the remaining ones
+    let () = expire_swaps(state, rwtxn, block_height)?;
     // Process coinshift transactions after processing deposits/withdrawals
     let block_hash = state.try_get_tip(rwtxn)?.ok_or(Error::NoTip)?;
     process_coinshift_transactions(
@@ -891,7 +1003,7 @@ pub fn connect(
         rwtxn,
         block_height,
         block_hash,
-        rpc_config_getter,
+        l1_observations,
     )?;
     // Handle deposits.
     if let Some(latest_deposit_block_hash) = latest_deposit_block_hash {
@@ -1255,6 +1367,12 @@ pub fn disconnect(
     let mut accumulator_diff = AccumulatorDiff::default();
     let mut latest_deposit_block_hash = None;
     let mut latest_withdrawal_bundle_event_block_hash = None;
+    // Revert swap updates, which were applied after all events
+    let () = state.rollback_swap_updates(
+        rwtxn,
+        SwapUpdateSource::TwoWayPegData,
+        block_height,
+    )?;
     // Restore pending withdrawal bundle
     for (event_block_hash, event_block_info) in
         two_way_peg_data.block_info.iter().rev()
@@ -1345,3 +1463,433 @@ pub fn disconnect(
         .map_err(DbError::from)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{connect, disconnect};
+    use crate::{
+        parent_chain_rpc::{MockBackend, ScriptPubKey, TransactionInfo, Vout},
+        state::{
+            L1Observations, L1Query, SwapUpdateSource, test_utils::open_state,
+        },
+        types::{
+            BlockHash, L1MatchPolicy, OutPoint, ParentChainType, Swap,
+            SwapDirection, SwapId, SwapState, SwapTxId, Txid,
+            proto::mainchain::TwoWayPegData,
+        },
+    };
+
+    fn test_swap(expires_at_height: Option<u32>) -> Swap {
+        Swap::new(
+            SwapId([1; 32]),
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0; 32]),
+            None,
+            None,
+            bitcoin::Amount::from_sat(100_000),
+            Some("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_owned()),
+            Some(bitcoin::Amount::from_sat(50_000)),
+            0,
+            expires_at_height,
+            None,
+            false,
+            false,
+            L1MatchPolicy::Exact,
+        )
+    }
+
+    #[test]
+    fn disconnect_restores_expired_swap() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let swap = test_swap(Some(1));
+        let locked_outpoint = OutPoint::Regular {
+            txid: Txid([3; 32]),
+            vout: 0,
+        };
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        state
+            .lock_output_to_swap(&mut rwtxn, &locked_outpoint, &swap.id)
+            .unwrap();
+        state.tip.put(&mut rwtxn, &(), &BlockHash([2; 32])).unwrap();
+        state.height.put(&mut rwtxn, &(), &1).unwrap();
+
+        let two_way_peg_data = TwoWayPegData::default();
+        connect(
+            &state,
+            &mut rwtxn,
+            &two_way_peg_data,
+            &L1Observations::default(),
+            None,
+        )
+        .unwrap();
+        let expired = state.get_swap(&rwtxn, &swap.id).unwrap().unwrap();
+        assert_eq!(expired.state, SwapState::Cancelled);
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            None
+        );
+
+        disconnect(&state, &mut rwtxn, &two_way_peg_data).unwrap();
+        let restored = state.get_swap(&rwtxn, &swap.id).unwrap().unwrap();
+        assert_eq!(restored, swap);
+        assert_eq!(
+            state
+                .is_output_locked_to_swap(&rwtxn, &locked_outpoint)
+                .unwrap(),
+            Some(swap.id)
+        );
+        assert!(
+            state
+                .swap_history
+                .try_get(&rwtxn, &swap.id)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn rollback_is_per_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let original = test_swap(None);
+        state.save_swap(&mut rwtxn, &original).unwrap();
+
+        // A claim in the block body, then a 2WPD update at the same height
+        let mut claimed = original.clone();
+        claimed.update_l1_txid(SwapTxId::Hash32([3; 32]));
+        claimed.mark_completed();
+        state
+            .save_swap_update(
+                &mut rwtxn,
+                original.clone(),
+                &claimed,
+                Vec::new(),
+                SwapUpdateSource::Body,
+                5,
+            )
+            .unwrap();
+        let mut cancelled = claimed.clone();
+        cancelled.state = SwapState::Cancelled;
+        state
+            .save_swap_update(
+                &mut rwtxn,
+                claimed.clone(),
+                &cancelled,
+                Vec::new(),
+                SwapUpdateSource::TwoWayPegData,
+                5,
+            )
+            .unwrap();
+
+        assert_eq!(
+            state.swap_history_heights.try_get(&rwtxn, &5).unwrap(),
+            Some(vec![original.id])
+        );
+
+        // Body updates are not rolled back by a 2WPD disconnect
+        assert!(
+            !state
+                .rollback_swap_update(
+                    &mut rwtxn,
+                    &original.id,
+                    SwapUpdateSource::Body,
+                    5,
+                )
+                .unwrap()
+        );
+        state
+            .rollback_swap_updates(
+                &mut rwtxn,
+                SwapUpdateSource::TwoWayPegData,
+                5,
+            )
+            .unwrap();
+        assert_eq!(
+            state.get_swap(&rwtxn, &original.id).unwrap().unwrap(),
+            claimed
+        );
+
+        assert!(
+            state
+                .rollback_swap_update(
+                    &mut rwtxn,
+                    &original.id,
+                    SwapUpdateSource::Body,
+                    5,
+                )
+                .unwrap()
+        );
+        assert_eq!(
+            state.get_swap(&rwtxn, &original.id).unwrap().unwrap(),
+            original
+        );
+        let claimed_key = (claimed.parent_chain, claimed.l1_txid.clone());
+        assert!(
+            state
+                .swaps_by_l1_txid
+                .try_get(&rwtxn, &claimed_key)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            state
+                .swap_history_heights
+                .try_get(&rwtxn, &5)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn observed_payment_only_changes_local_view() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let swap = test_swap(None);
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        state.tip.put(&mut rwtxn, &(), &BlockHash([2; 32])).unwrap();
+        state.height.put(&mut rwtxn, &(), &1).unwrap();
+
+        // Observe an L1 payment of the swap amount, with one confirmation
+        let l1_recipient = swap.l1_recipient_address.clone().unwrap();
+        let backend = MockBackend::new();
+        backend.set_tip_height(100);
+        backend.insert_transaction(TransactionInfo {
+            txid: "ab".repeat(32),
+            confirmations: 0,
+            blockheight: Some(100),
+            blockhash: None,
+            vout: vec![Vout {
+                value: swap.l1_amount.unwrap().to_btc(),
+                script_pub_key: ScriptPubKey {
+                    address: Some(l1_recipient),
+                    addresses: None,
+                    hex: None,
+                },
+            }],
+            vin: Vec::new(),
+        });
+        let query = L1Query::for_swap(&swap).unwrap();
+        let mut l1_observations = L1Observations::default();
+        l1_observations.insert(
+            &query,
+            futures::executor::block_on(query.fetch(&backend)).unwrap(),
+        );
+
+        let two_way_peg_data = TwoWayPegData::default();
+        connect(
+            &state,
+            &mut rwtxn,
+            &two_way_peg_data,
+            &l1_observations,
+            None,
+        )
+        .unwrap();
+        assert_eq!(state.get_swap(&rwtxn, &swap.id).unwrap().unwrap(), swap);
+        let updated = state
+            .get_swap_with_l1_status(&rwtxn, &swap.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            updated.l1_txid,
+            SwapTxId::from_hex_rpc(&"ab".repeat(32)).unwrap()
+        );
+        let expected_state = if swap.required_confirmations <= 1 {
+            SwapState::ReadyToClaim
+        } else {
+            SwapState::WaitingConfirmations(1, swap.required_confirmations)
+        };
+        assert_eq!(updated.state, expected_state);
+
+        // Without observations, the local view is left as it is
+        disconnect(&state, &mut rwtxn, &two_way_peg_data).unwrap();
+        connect(
+            &state,
+            &mut rwtxn,
+            &two_way_peg_data,
+            &L1Observations::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(state.get_swap(&rwtxn, &swap.id).unwrap().unwrap(), swap);
+        assert_eq!(
+            state
+                .get_swap_with_l1_status(&rwtxn, &swap.id)
+                .unwrap()
+                .unwrap(),
+            updated
+        );
+    }
+
+    fn l1_payment(
+        swap: &Swap,
+        txid: &str,
+        blockheight: Option<u32>,
+    ) -> TransactionInfo {
+        TransactionInfo {
+            txid: txid.to_owned(),
+            confirmations: 0,
+            blockheight,
+            blockhash: None,
+            vout: vec![Vout {
+                value: swap.l1_amount.unwrap().to_btc(),
+                script_pub_key: ScriptPubKey {
+                    address: swap.l1_recipient_address.clone(),
+                    addresses: None,
+                    hex: None,
+                },
+            }],
+            vin: Vec::new(),
+        }
+    }
+
+    fn observe(swap: &Swap, backend: &MockBackend) -> L1Observations {
+        let query = L1Query::for_swap(swap).unwrap();
+        let mut l1_observations = L1Observations::default();
+        l1_observations.insert(
+            &query,
+            futures::executor::block_on(query.fetch(backend)).unwrap(),
+        );
+        l1_observations
+    }
+
+    #[test]
+    fn split_payment_only_matches_sum_of_outputs_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let swap = test_swap(None);
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        state.tip.put(&mut rwtxn, &(), &BlockHash([2; 32])).unwrap();
+        state.height.put(&mut rwtxn, &(), &1).unwrap();
+
+        // The swap amount is paid in two outputs
+        let txid = "ab".repeat(32);
+        let mut payment = l1_payment(&swap, &txid, Some(100));
+        let half = swap.l1_amount.unwrap() / 2;
+        payment.vout[0].value = half.to_btc();
+        payment.vout.push(payment.vout[0].clone());
+        let backend = MockBackend::new();
+        backend.set_tip_height(100);
+        backend.insert_transaction(payment);
+        let two_way_peg_data = TwoWayPegData::default();
+        connect(
+            &state,
+            &mut rwtxn,
+            &two_way_peg_data,
+            &observe(&swap, &backend),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            state
+                .get_swap_with_l1_status(&rwtxn, &swap.id)
+                .unwrap()
+                .unwrap(),
+            swap
+        );
+        disconnect(&state, &mut rwtxn, &two_way_peg_data).unwrap();
+
+        let swap = Swap {
+            l1_match_policy: L1MatchPolicy::SumOfOutputs,
+            ..swap
+        };
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        connect(
+            &state,
+            &mut rwtxn,
+            &two_way_peg_data,
+            &observe(&swap, &backend),
+            None,
+        )
+        .unwrap();
+        let filled = state
+            .get_swap_with_l1_status(&rwtxn, &swap.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(filled.l1_txid, SwapTxId::from_hex_rpc(&txid).unwrap());
+        assert_ne!(filled.state, SwapState::Pending);
+        assert_eq!(state.get_swap(&rwtxn, &swap.id).unwrap().unwrap(), swap);
+    }
+
+    #[test]
+    fn reorged_out_payment_reverts_swap_to_pending() {
+        let dir = tempfile::tempdir().unwrap();
+        let (env, state) = open_state(&dir);
+        let mut rwtxn = env.write_txn().unwrap();
+        let mut swap = test_swap(None);
+        swap.required_confirmations = 3;
+        state.save_swap(&mut rwtxn, &swap).unwrap();
+        state.tip.put(&mut rwtxn, &(), &BlockHash([2; 32])).unwrap();
+        state.height.put(&mut rwtxn, &(), &1).unwrap();
+
+        // This node saw a payment, which is back in the mempool after an
+        // L1 reorg
+        let reorged_txid = "ab".repeat(32);
+        let mut observed = swap.clone();
+        observed.update_l1_txid(SwapTxId::from_hex_rpc(&reorged_txid).unwrap());
+        observed.state = SwapState::WaitingConfirmations(1, 3);
+        observed.set_l1_txid_validation_block(BlockHash([2; 32]), 1);
+        state.put_swap_l1_status(&mut rwtxn, &observed).unwrap();
+        let backend = MockBackend::new();
+        backend.set_tip_height(101);
+        backend.insert_transaction(l1_payment(&swap, &reorged_txid, None));
+        let two_way_peg_data = TwoWayPegData::default();
+        connect(
+            &state,
+            &mut rwtxn,
+            &two_way_peg_data,
+            &observe(&observed, &backend),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            state
+                .get_swap_with_l1_status(&rwtxn, &swap.id)
+                .unwrap()
+                .unwrap(),
+            swap
+        );
+        disconnect(&state, &mut rwtxn, &two_way_peg_data).unwrap();
+
+        // The recorded payment is gone, and another payment fills the swap
+        backend.remove_transaction(&reorged_txid);
+        let replacement_txid = "cd".repeat(32);
+        backend.insert_transaction(l1_payment(
+            &swap,
+            &replacement_txid,
+            Some(101),
+        ));
+        connect(
+            &state,
+            &mut rwtxn,
+            &two_way_peg_data,
+            &observe(&swap, &backend),
+            None,
+        )
+        .unwrap();
+        let refilled = state
+            .get_swap_with_l1_status(&rwtxn, &swap.id)
+            .unwrap()
+            .unwrap();
+        let replacement = SwapTxId::from_hex_rpc(&replacement_txid).unwrap();
+        assert_eq!(refilled.l1_txid, replacement);
+        assert_eq!(refilled.state, SwapState::WaitingConfirmations(1, 3));
+
+        // Consensus state is never changed by observations
+        assert_eq!(state.get_swap(&rwtxn, &swap.id).unwrap().unwrap(), swap);
+        assert!(
+            state
+                .swaps_by_l1_txid
+                .try_get(&rwtxn, &(swap.parent_chain, replacement))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Validation of L1 addresses in the formats used by each parent chain
+
+use bitcoin::{
+    PubkeyHash, ScriptBuf, ScriptHash, WitnessProgram, WitnessVersion,
+    address::NetworkUnchecked,
+    bech32,
+    hashes::Hash as _,
+    opcodes::all::{OP_EQUAL, OP_HASH256},
+    script,
+};
+use thiserror::Error;
+
+use super::{AddressFormat, ParentChainParams, ParentChainType};
+
+#[derive(Debug, Error)]
+pub enum L1AddressError {
+    #[error("`{address}` is not a valid {} address", .parent_chain.coin_name())]
+    Invalid {
+        address: String,
+        parent_chain: ParentChainType,
+    },
+    #[error(
+        "`{address}` is an address for another network than {}",
+        .parent_chain.coin_name()
+    )]
+    WrongNetwork {
+        address: String,
+        parent_chain: ParentChainType,
+    },
+}
+
+/// script_pubkey of a segwit address with the human-readable part `hrp`
+fn bech32_script_pubkey(address: &str, hrp: &str) -> Option<ScriptBuf> {
+    let (decoded_hrp, version, program) =
+        bech32::segwit::decode(address).ok()?;
+    if !decoded_hrp.as_str().eq_ignore_ascii_case(hrp) {
+        return None;
+    }
+    let version = WitnessVersion::try_from(version).ok()?;
+    let program = WitnessProgram::new(version, &program).ok()?;
+    Some(ScriptBuf::new_witness_program(&program))
+}
+
+/// script_pubkey of a Base58Check address with one of the version bytes
+fn base58_script_pubkey(
+    address: &str,
+    p2pkh: u8,
+    p2sh: u8,
+) -> Option<ScriptBuf> {
+    let payload = bitcoin::base58::decode_check(address).ok()?;
+    let (version, hash) = payload.split_first()?;
+    let hash: [u8; 20] = hash.try_into().ok()?;
+    if *version == p2pkh {
+        Some(ScriptBuf::new_p2pkh(&PubkeyHash::from_byte_array(hash)))
+    } else if *version == p2sh {
+        Some(ScriptBuf::new_p2sh(&ScriptHash::from_byte_array(hash)))
+    } else {
+        None
+    }
+}
+
+/// Checksum of a CashAddr address, which is zero for valid addresses
+fn cash_addr_polymod<I>(values: I) -> u64
+where
+    I: IntoIterator<Item = u8>,
+{
+    const GENERATORS: [u64; 5] = [
+        0x98f2bc8e61,
+        0x79b76d99e2,
+        0xf33e5fb3c4,
+        0xae2eabe2a8,
+        0x1e4f43e470,
+    ];
+    let mut checksum: u64 = 1;
+    for value in values {
+        let top = checksum >> 35;
+        checksum = ((checksum & 0x07ffffffff) << 5) ^ u64::from(value);
+        for (idx, generator) in GENERATORS.iter().enumerate() {
+            if (top >> idx) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum ^ 1
+}
+
+/// script_pubkey of a CashAddr address, with or without its prefix, e.g.
+/// `bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a`.
+/// Only the hash sizes used by P2PKH, P2SH and P2SH32 are accepted.
+fn cash_addr_script_pubkey(address: &str, prefix: &str) -> Option<ScriptBuf> {
+    /// Hash sizes in bytes, by the size bits of the version byte
+    const HASH_SIZES: [usize; 8] = [20, 24, 28, 32, 40, 48, 56, 64];
+    /// Checksum length, in 5-bit values
+    const CHECKSUM_LEN: usize = 8;
+    if address.chars().any(|c| c.is_ascii_lowercase())
+        && address.chars().any(|c| c.is_ascii_uppercase())
+    {
+        return None;
+    }
+    let address = address.to_ascii_lowercase();
+    let payload = match address.split_once(':') {
+        Some((address_prefix, payload))
+            if address_prefix.eq_ignore_ascii_case(prefix) =>
+        {
+            payload
+        }
+        Some(_) => return None,
+        None => address.as_str(),
+    };
+    let Some(values) = payload
+        .chars()
+        .map(|c| bech32::Fe32::from_char(c).ok().map(|fe| fe.to_u8()))
+        .collect::<Option<Vec<u8>>>()
+    else {
+        return None;
+    };
+    if values.len() <= CHECKSUM_LEN {
+        return None;
+    }
+    let prefix_values = prefix.bytes().map(|b| b.to_ascii_lowercase() & 0x1f);
+    let checksum_input = prefix_values.chain([0]).chain(values.iter().copied());
+    if cash_addr_polymod(checksum_input) != 0 {
+        return None;
+    }
+    // Convert the 5-bit values before the checksum to bytes
+    let data = &values[..values.len() - CHECKSUM_LEN];
+    let mut bytes = Vec::with_capacity(data.len() * 5 / 8);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for value in data {
+        acc = (acc << 5) | u32::from(*value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits >= 5 || acc != 0 {
+        return None;
+    }
+    let (version, hash) = bytes.split_first()?;
+    if hash.len() != HASH_SIZES[usize::from(version & 0x07)] {
+        return None;
+    }
+    // Address types 0 and 1 are P2PKH and P2SH, 2 and 3 are their
+    // token-aware forms
+    match (version >> 3, hash.len()) {
+        (0 | 2, 20) => Some(ScriptBuf::new_p2pkh(
+            &PubkeyHash::from_byte_array(hash.try_into().ok()?),
+        )),
+        (1 | 3, 20) => Some(ScriptBuf::new_p2sh(&ScriptHash::from_byte_array(
+            hash.try_into().ok()?,
+        ))),
+        (1 | 3, 32) => {
+            let hash: [u8; 32] = hash.try_into().ok()?;
+            Some(
+                script::Builder::new()
+                    .push_opcode(OP_HASH256)
+                    .push_slice(hash)
+                    .push_opcode(OP_EQUAL)
+                    .into_script(),
+            )
+        }
+        _ => None,
+    }
+}
+
+impl AddressFormat {
+    /// Returns `true` if `address` is valid in this format
+    pub fn matches(&self, address: &str) -> bool {
+        match self {
+            Self::Bech32 { hrp } => {
+                bech32_script_pubkey(address, hrp).is_some()
+            }
+            Self::Base58 { p2pkh, p2sh } => {
+                base58_script_pubkey(address, *p2pkh, *p2sh).is_some()
+            }
+            Self::CashAddr { prefix } => {
+                cash_addr_script_pubkey(address, prefix).is_some()
+            }
+        }
+    }
+}
+
+impl ParentChainParams {
+    /// Check that `address` is a valid L1 address for the chain. Bitcoin
+    /// networks are checked by `rust-bitcoin`, other chains against their
+    /// address formats. Chains without address formats accept any
+    /// non-empty address.
+    pub fn validate_address(
+        &self,
+        address: &str,
+    ) -> Result<(), L1AddressError> {
+        let parent_chain = ParentChainType::from_id(self.id);
+        let invalid = || L1AddressError::Invalid {
+            address: address.to_owned(),
+            parent_chain,
+        };
+        if address.trim().is_empty() {
+            return Err(invalid());
+        }
+        if let Some(network) = self.bitcoin_network {
+            let unchecked: bitcoin::Address<NetworkUnchecked> =
+                address.parse().map_err(|_| invalid())?;
+            unchecked.require_network(network).map_err(|_| {
+                L1AddressError::WrongNetwork {
+                    address: address.to_owned(),
+                    parent_chain,
+                }
+            })?;
+            return Ok(());
+        }
+        if self.address_formats.is_empty()
+            || self
+                .address_formats
+                .iter()
+                .any(|format| format.matches(address))
+        {
+            Ok(())
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash as _;
+
+    use super::*;
+
+    const CASH_ADDR_P2PKH: &str =
+        "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a";
+    const CASH_ADDR_P2SH: &str =
+        "bitcoincash:ppm2qsznhks23z7629mms6s4cwef74vcwvn0h829pq";
+    /// Legacy form of [`CASH_ADDR_P2PKH`]
+    const LEGACY_P2PKH: &str = "1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu";
+
+    fn p2wsh(network: bitcoin::Network) -> String {
+        bitcoin::Address::p2wsh(&bitcoin::ScriptBuf::new(), network).to_string()
+    }
+
+    #[test]
+    fn bitcoin_addresses_are_checked_against_the_network() {
+        let signet = ParentChainType::Signet;
+        signet
+            .validate_address(&p2wsh(bitcoin::Network::Signet))
+            .unwrap();
+        assert!(matches!(
+            signet.validate_address(&p2wsh(bitcoin::Network::Bitcoin)),
+            Err(L1AddressError::WrongNetwork { .. })
+        ));
+        ParentChainType::BTC.validate_address(LEGACY_P2PKH).unwrap();
+        ParentChainType::BTC
+            .validate_address(&p2wsh(bitcoin::Network::Bitcoin).to_uppercase())
+            .unwrap();
+        assert!(matches!(
+            ParentChainType::BTC.validate_address("bc1qnotanaddress"),
+            Err(L1AddressError::Invalid { .. })
+        ));
+        assert!(ParentChainType::Regtest.validate_address("").is_err());
+    }
+
+    #[test]
+    fn bitcoin_cash_accepts_cash_addr_and_legacy() {
+        let bch = ParentChainType::BCH;
+        bch.validate_address(CASH_ADDR_P2PKH).unwrap();
+        bch.validate_address(CASH_ADDR_P2SH).unwrap();
+        bch.validate_address(LEGACY_P2PKH).unwrap();
+        // Prefix is optional, and either case is accepted, but not both
+        let (_, payload) = CASH_ADDR_P2PKH.split_once(':').unwrap();
+        bch.validate_address(payload).unwrap();
+        bch.validate_address(&CASH_ADDR_P2PKH.to_uppercase())
+            .unwrap();
+        assert!(
+            bch.validate_address(&payload.replacen('q', "Q", 1))
+                .is_err()
+        );
+        // Checksum, prefix
+        let typo = CASH_ADDR_P2PKH.replace("gdx6a", "gdx6q");
+        assert!(bch.validate_address(&typo).is_err());
+        let wrong_prefix = CASH_ADDR_P2PKH.replace("bitcoincash", "bchtest");
+        assert!(bch.validate_address(&wrong_prefix).is_err());
+        assert!(
+            bch.validate_address(&p2wsh(bitcoin::Network::Bitcoin))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn litecoin_accepts_ltc_bech32_and_legacy() {
+        let ltc = ParentChainType::LTC;
+        let hrp = bech32::Hrp::parse("ltc").unwrap();
+        let segwit =
+            bech32::segwit::encode(hrp, bech32::segwit::VERSION_0, &[7; 20])
+                .unwrap();
+        ltc.validate_address(&segwit).unwrap();
+        let mut legacy = vec![48];
+        legacy.extend_from_slice(&[7; 20]);
+        ltc.validate_address(&bitcoin::base58::encode_check(&legacy))
+            .unwrap();
+        let btc_legacy = bitcoin::Address::p2pkh(
+            bitcoin::PubkeyHash::from_byte_array([7; 20]),
+            bitcoin::Network::Bitcoin,
+        );
+        assert!(ltc.validate_address(&btc_legacy.to_string()).is_err());
+        assert!(
+            ltc.validate_address(&p2wsh(bitcoin::Network::Bitcoin))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn consensus_only_checks_builtin_chains() {
+        let unregistered = ParentChainType::from_id(200);
+        unregistered.validate_address("anything").unwrap();
+        unregistered.consensus_validate_address("anything").unwrap();
+        assert!(
+            ParentChainType::Signet
+                .consensus_validate_address("anything")
+                .is_err()
+        );
+    }
+}
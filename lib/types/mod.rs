@@ -20,8 +20,11 @@ use crate::{
 
 mod address;
 pub mod hashes;
+mod l1_address;
+mod parent_chain;
 pub mod proto;
 pub mod schema;
+mod spv;
 mod swap;
 mod transaction;
 
@@ -29,9 +32,19 @@ pub use address::Address;
 pub use hashes::{
     BlockHash, Hash, M6id, MerkleRoot, Txid, hash, hash_with_scratch_buffer,
 };
+pub use l1_address::L1AddressError;
+pub use parent_chain::{
+    AddressFormat, KnownNetwork, ParentChainParams, ParentChainRegistry,
+    ParentChainRegistryError, ParentChainType, ParseParentChainTypeError,
+    SpvCheckpoint, SpvParams, registry as parent_chain_registry,
+};
+pub use spv::{
+    MAX_SPV_HEADERS, SpvProof, SpvProofError, VerifiedL1Tx, decode_l1_headers,
+    verify_l1_headers,
+};
 pub use swap::{
-    ParentChainType, Swap, SwapDirection, SwapError, SwapId, SwapState,
-    SwapTxId,
+    L1MatchPolicy, ParseL1MatchPolicyError, Swap, SwapCommitment,
+    SwapDirection, SwapError, SwapId, SwapState, SwapTxId,
 };
 pub use transaction::{
     Authorized, AuthorizedTransaction, Content as OutputContent,
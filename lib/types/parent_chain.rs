@@ -0,0 +1,752 @@
+//! Registry of the L1 chains that swaps can target
+//!
+//! The built-in chains are listed in `parent_chains.json`. Further chains
+//! can be registered from a config file of the same format, without
+//! recompiling.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{LazyLock, OnceLock},
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeAs, DisplayFromStr, SerializeAs};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use super::{L1AddressError, Network};
+
+/// Parent chain type for swaps
+/// Note: This can be different from the sidechain's mainchain network.
+/// For example, sidechain may be on Regtest, but swaps can target Signet, Mainnet, etc.
+///
+/// The id is recorded in swaps, so it must never be reassigned. The ids of
+/// the built-in chains are encoded like the variants of the enum that this
+/// type replaced, so that existing swap records remain readable.
+#[derive(
+    BorshDeserialize, BorshSerialize, Clone, Copy, Eq, Hash, PartialEq, ToSchema,
+)]
+#[schema(value_type = String)]
+pub struct ParentChainType(u8);
+
+// Named after the enum variants that they replace
+#[allow(non_upper_case_globals)]
+impl ParentChainType {
+    /// Bitcoin Mainnet
+    pub const BTC: Self = Self(0);
+    /// Bitcoin Cash
+    pub const BCH: Self = Self(1);
+    /// Litecoin
+    pub const LTC: Self = Self(2);
+    /// Bitcoin Signet (for cross-chain swaps)
+    pub const Signet: Self = Self(3);
+    /// Bitcoin Regtest (for testing)
+    pub const Regtest: Self = Self(4);
+}
+
+impl ParentChainType {
+    /// Confirmations required by consensus for swaps that do not set them,
+    /// on chains that are not built in. Each node registers its own custom
+    /// chains, so their configured defaults cannot be used.
+    const CUSTOM_CHAIN_CONSENSUS_CONFIRMATIONS: u32 = 6;
+
+    pub const fn from_id(id: u8) -> Self {
+        Self(id)
+    }
+
+    pub const fn id(&self) -> u8 {
+        self.0
+    }
+
+    /// Registry entry for this chain, if it is registered
+    pub fn params(&self) -> Option<&'static ParentChainParams> {
+        registry().get(*self)
+    }
+
+    /// Get default required confirmations for this chain
+    pub fn default_confirmations(&self) -> u32 {
+        self.params()
+            .map_or(Self::CUSTOM_CHAIN_CONSENSUS_CONFIRMATIONS, |params| {
+                params.default_confirmations
+            })
+    }
+
+    /// Required confirmations applied by consensus to swaps that do not set
+    /// them. Unlike [`Self::default_confirmations`], this does not depend on
+    /// the local registry.
+    pub fn consensus_default_confirmations(&self) -> u32 {
+        BUILTIN_REGISTRY
+            .get(*self)
+            .map_or(Self::CUSTOM_CHAIN_CONSENSUS_CONFIRMATIONS, |params| {
+                params.default_confirmations
+            })
+    }
+
+    /// Get the Bitcoin network enum for this chain type, or `None` if the
+    /// chain is not a Bitcoin network, e.g. BCH and LTC
+    pub fn to_bitcoin_network(&self) -> Option<bitcoin::Network> {
+        self.params()?.bitcoin_network
+    }
+
+    /// Get the default RPC port for this chain
+    ///
+    /// These are the standard mainnet RPC ports. Testnet/regtest ports differ.
+    pub fn default_rpc_port(&self) -> Option<u16> {
+        self.params().map(|params| params.default_rpc_port)
+    }
+
+    /// Get the human-readable coin name for display
+    pub fn coin_name(&self) -> &'static str {
+        self.params()
+            .map_or("Unknown chain", |params| params.coin_name.as_str())
+    }
+
+    /// Get the number of satoshis (smallest unit) per coin
+    ///
+    /// All Bitcoin-derivative chains use 100,000,000 satoshis per coin.
+    pub fn sats_per_coin(&self) -> u64 {
+        100_000_000
+    }
+
+    /// Get the ticker symbol for this chain
+    pub fn ticker(&self) -> &'static str {
+        self.params().map_or("?", |params| params.ticker.as_str())
+    }
+
+    /// Get the default RPC URL hint for this chain
+    pub fn default_rpc_url_hint(&self) -> String {
+        match self.default_rpc_port() {
+            Some(port) => format!("http://localhost:{port}"),
+            None => "http://localhost".to_owned(),
+        }
+    }
+
+    /// Easiest L1 block target accepted for tracked L1 headers, or `None`
+    /// if L1 headers cannot be verified for this chain.
+    ///
+    /// For BTC this is a floor far below current difficulty. Test networks
+    /// use their proof-of-work limit, so their headers are cheap to forge.
+    pub fn spv_max_target(&self) -> Option<bitcoin::Target> {
+        let bits = BUILTIN_REGISTRY.get(*self)?.spv_max_bits?;
+        Some(bitcoin::CompactTarget::from_consensus(bits).into())
+    }
+
+    /// Consensus parameters for SPV proofs in swap claims, or `None` if
+    /// swaps on this chain cannot be claimed with SPV proofs.
+    ///
+    /// Only chains with a checkpoint support SPV claims, since every proof
+    /// must extend the checkpoint, and the L1 chain with the most work that
+    /// has been proven since. Difficulty is checked with the retargeting
+    /// rules of the chain's Bitcoin network, so chains with other rules,
+    /// e.g. BCH, cannot have a checkpoint. On Signet and Regtest, the
+    /// checkpoint is the genesis block, and proofs are only as strong as the
+    /// network's proof of work. This is consensus-critical, so only
+    /// built-in chains can set it.
+    pub fn spv_params(&self) -> Option<SpvParams> {
+        let params = BUILTIN_REGISTRY.get(*self)?;
+        let bits = params.spv_max_bits?;
+        Some(SpvParams {
+            max_target: bitcoin::CompactTarget::from_consensus(bits).into(),
+            checkpoint: params.spv_checkpoint?,
+            bitcoin_network: params.bitcoin_network?,
+        })
+    }
+
+    /// Check that `address` is a valid L1 address for this chain, as
+    /// registered locally. Any address is accepted for unregistered chains.
+    pub fn validate_address(
+        &self,
+        address: &str,
+    ) -> Result<(), L1AddressError> {
+        match self.params() {
+            Some(params) => params.validate_address(address),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that `address` is a valid L1 address for this chain, as
+    /// required by consensus. Unlike [`Self::validate_address`], this does
+    /// not depend on the local registry, so any address is accepted for
+    /// chains that are not built in.
+    pub fn consensus_validate_address(
+        &self,
+        address: &str,
+    ) -> Result<(), L1AddressError> {
+        match BUILTIN_REGISTRY.get(*self) {
+            Some(params) => params.validate_address(address),
+            None => Ok(()),
+        }
+    }
+
+    /// script_pubkey paid by `address` on this chain, used by consensus to
+    /// find the outputs of L1 payments. Only built-in Bitcoin networks can
+    /// have SPV-proven payments, so other chains are rejected.
+    pub fn consensus_script_pubkey(
+        &self,
+        address: &str,
+    ) -> Result<bitcoin::ScriptBuf, L1AddressError> {
+        let Some(network) = BUILTIN_REGISTRY
+            .get(*self)
+            .and_then(|params| params.bitcoin_network)
+        else {
+            return Err(L1AddressError::Invalid {
+                address: address.to_owned(),
+                parent_chain: *self,
+            });
+        };
+        let unchecked: bitcoin::Address<bitcoin::address::NetworkUnchecked> =
+            address.parse().map_err(|_| L1AddressError::Invalid {
+                address: address.to_owned(),
+                parent_chain: *self,
+            })?;
+        let address = unchecked.require_network(network).map_err(|_| {
+            L1AddressError::WrongNetwork {
+                address: address.to_owned(),
+                parent_chain: *self,
+            }
+        })?;
+        Ok(address.script_pubkey())
+    }
+
+    /// Get all supported parent chain types
+    pub fn all() -> &'static [ParentChainType] {
+        &registry().ids
+    }
+}
+
+/// Parent chain that a sidechain network runs on
+impl From<Network> for ParentChainType {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Signet => Self::Signet,
+            Network::Regtest => Self::Regtest,
+        }
+    }
+}
+
+/// Registered name, or the id for chains that are not registered
+impl std::fmt::Display for ParentChainType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.params() {
+            Some(params) => f.write_str(&params.name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl std::fmt::Debug for ParentChainType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown parent chain `{input}`, use: {names}")]
+pub struct ParseParentChainTypeError {
+    input: String,
+    names: String,
+}
+
+/// Parses case-insensitive names, e.g. `signet` or `BCH`, or the ids of
+/// chains that are not registered, e.g. in swaps created by nodes that
+/// register other chains
+impl std::str::FromStr for ParentChainType {
+    type Err = ParseParentChainTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let registry = registry();
+        if let Some(params) = registry.get_by_name(s) {
+            return Ok(Self(params.id));
+        }
+        if let Ok(id) = s.parse() {
+            return Ok(Self(id));
+        }
+        let names: Vec<_> = registry
+            .chains
+            .iter()
+            .map(|params| params.name.to_lowercase())
+            .collect();
+        Err(ParseParentChainTypeError {
+            input: s.to_owned(),
+            names: names.join(", "),
+        })
+    }
+}
+
+/// Human-readable forms use the name. Other forms use the id, encoded as
+/// an enum variant index.
+impl<'de> Deserialize<'de> for ParentChainType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            DisplayFromStr::deserialize_as(deserializer)
+        } else {
+            let id = u32::deserialize(deserializer)?;
+            let id = u8::try_from(id).map_err(|_| {
+                <D::Error as serde::de::Error>::invalid_value(
+                    serde::de::Unexpected::Unsigned(id.into()),
+                    &"a parent chain id",
+                )
+            })?;
+            Ok(Self(id))
+        }
+    }
+}
+
+impl Serialize for ParentChainType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if serializer.is_human_readable() {
+            DisplayFromStr::serialize_as(self, serializer)
+        } else {
+            u32::from(self.0).serialize(serializer)
+        }
+    }
+}
+
+/// Network that an L1 endpoint for a parent chain may serve
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct KnownNetwork {
+    /// Chain names reported by `getblockchaininfo`, lowercase
+    pub chain_names: Vec<String>,
+    pub genesis_hash: bitcoin::BlockHash,
+}
+
+/// Address encoding used by a parent chain
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum AddressFormat {
+    /// Segwit addresses with a human-readable part, e.g. `bc`
+    Bech32 { hrp: String },
+    /// Base58Check addresses, with the version bytes of P2PKH and P2SH
+    Base58 { p2pkh: u8, p2sh: u8 },
+    /// CashAddr addresses with a prefix, e.g. `bitcoincash`
+    CashAddr { prefix: String },
+}
+
+/// L1 block that SPV proofs for swap claims are anchored to
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SpvCheckpoint {
+    pub height: u32,
+    pub block_hash: bitcoin::BlockHash,
+    /// Compact target of the checkpoint block
+    pub bits: u32,
+}
+
+/// See [`ParentChainType::spv_params`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SpvParams {
+    /// Easiest L1 block target accepted in SPV proofs
+    pub max_target: bitcoin::Target,
+    pub checkpoint: SpvCheckpoint,
+    /// Network with the same difficulty adjustment rules
+    pub bitcoin_network: bitcoin::Network,
+}
+
+/// Registry entry for a parent chain
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ParentChainParams {
+    /// Identifier recorded in swaps
+    pub id: u8,
+    /// Name used in config files, the RPC API and the CLI, e.g. `BTC`.
+    /// Parsed case-insensitively.
+    pub name: String,
+    pub coin_name: String,
+    pub ticker: String,
+    pub default_confirmations: u32,
+    pub default_rpc_port: u16,
+    /// Network with the same consensus and address rules, if the chain is
+    /// a Bitcoin network
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitcoin_network: Option<bitcoin::Network>,
+    #[serde(default)]
+    pub address_formats: Vec<AddressFormat>,
+    /// Networks that endpoints for the chain may serve
+    #[serde(default)]
+    pub networks: Vec<KnownNetwork>,
+    /// How to set up a node for the chain
+    #[serde(default)]
+    pub setup_hint: String,
+    /// Compact form of the easiest L1 block target accepted in SPV proofs,
+    /// see [`ParentChainType::spv_max_target`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spv_max_bits: Option<u32>,
+    /// See [`ParentChainType::spv_params`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spv_checkpoint: Option<SpvCheckpoint>,
+}
+
+#[derive(Debug, Error)]
+pub enum ParentChainRegistryError {
+    #[error("failed to read parent chains from `{path}`")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse parent chains from `{path}`")]
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("parent chain id {0} is registered more than once")]
+    DuplicateId(u8),
+    #[error("parent chain name `{0}` is registered more than once")]
+    DuplicateName(String),
+    #[error(
+        "parent chain `{0}` sets `spv_max_bits` or `spv_checkpoint`, which are consensus-critical and can only be set for built-in chains"
+    )]
+    CustomSpvParams(String),
+    #[error(
+        "parent chain `{0}` sets `spv_checkpoint`, which requires `spv_max_bits` and a `bitcoin_network` with the same difficulty adjustment rules"
+    )]
+    IncompleteSpvParams(String),
+    #[error("the parent chain registry is already in use")]
+    AlreadyInstalled,
+}
+
+/// Parent chains known to the node, ordered by id
+#[derive(Clone, Debug)]
+pub struct ParentChainRegistry {
+    chains: Vec<ParentChainParams>,
+    ids: Vec<ParentChainType>,
+}
+
+static BUILTIN_REGISTRY: LazyLock<ParentChainRegistry> = LazyLock::new(|| {
+    let chains = serde_json::from_str(include_str!("parent_chains.json"))
+        .expect("built-in parent chains should be valid");
+    ParentChainRegistry::new(chains)
+        .expect("built-in parent chains should be valid")
+});
+
+static REGISTRY: OnceLock<ParentChainRegistry> = OnceLock::new();
+
+/// Registry used by [`ParentChainType`]. Only the built-in chains are
+/// registered, unless another registry was installed before first use.
+pub fn registry() -> &'static ParentChainRegistry {
+    REGISTRY.get_or_init(|| BUILTIN_REGISTRY.clone())
+}
+
+impl ParentChainRegistry {
+    fn new(
+        mut chains: Vec<ParentChainParams>,
+    ) -> Result<Self, ParentChainRegistryError> {
+        chains.sort_by_key(|params| params.id);
+        let mut names = HashSet::new();
+        for (idx, params) in chains.iter().enumerate() {
+            if idx > 0 && chains[idx - 1].id == params.id {
+                return Err(ParentChainRegistryError::DuplicateId(params.id));
+            }
+            if !names.insert(params.name.to_lowercase()) {
+                return Err(ParentChainRegistryError::DuplicateName(
+                    params.name.clone(),
+                ));
+            }
+            if params.spv_checkpoint.is_some()
+                && (params.spv_max_bits.is_none()
+                    || params.bitcoin_network.is_none())
+            {
+                return Err(ParentChainRegistryError::IncompleteSpvParams(
+                    params.name.clone(),
+                ));
+            }
+        }
+        let ids = chains
+            .iter()
+            .map(|params| ParentChainType(params.id))
+            .collect();
+        Ok(Self { chains, ids })
+    }
+
+    /// Built-in chains
+    pub fn builtin() -> Self {
+        BUILTIN_REGISTRY.clone()
+    }
+
+    /// Built-in chains, plus custom chains. Custom chains must not reuse
+    /// the id or name of another chain.
+    pub fn with_custom_chains<I>(
+        custom_chains: I,
+    ) -> Result<Self, ParentChainRegistryError>
+    where
+        I: IntoIterator<Item = ParentChainParams>,
+    {
+        let mut chains = BUILTIN_REGISTRY.chains.clone();
+        for params in custom_chains {
+            if params.spv_max_bits.is_some() || params.spv_checkpoint.is_some()
+            {
+                return Err(ParentChainRegistryError::CustomSpvParams(
+                    params.name,
+                ));
+            }
+            chains.push(params);
+        }
+        Self::new(chains)
+    }
+
+    /// Built-in chains, plus the custom chains listed in the JSON file at
+    /// `path`, if it exists
+    pub fn load_from_path(
+        path: &Path,
+    ) -> Result<Self, ParentChainRegistryError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::builtin());
+            }
+            Err(source) => {
+                return Err(ParentChainRegistryError::Read {
+                    path: path.to_owned(),
+                    source,
+                });
+            }
+        };
+        let custom_chains: Vec<ParentChainParams> =
+            serde_json::from_str(&contents).map_err(|source| {
+                ParentChainRegistryError::Parse {
+                    path: path.to_owned(),
+                    source,
+                }
+            })?;
+        Self::with_custom_chains(custom_chains)
+    }
+
+    /// Use this registry for [`ParentChainType`]. Must be called before
+    /// the registry is first used.
+    pub fn install(self) -> Result<(), ParentChainRegistryError> {
+        REGISTRY
+            .set(self)
+            .map_err(|_| ParentChainRegistryError::AlreadyInstalled)
+    }
+
+    pub fn get(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> Option<&ParentChainParams> {
+        self.chains
+            .binary_search_by_key(&parent_chain.0, |params| params.id)
+            .ok()
+            .map(|idx| &self.chains[idx])
+    }
+
+    /// Look up a chain by its case-insensitive name
+    pub fn get_by_name(&self, name: &str) -> Option<&ParentChainParams> {
+        self.chains
+            .iter()
+            .find(|params| params.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn chains(&self) -> &[ParentChainParams] {
+        &self.chains
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash as _;
+
+    use super::*;
+
+    fn custom_chain(id: u8, name: &str) -> ParentChainParams {
+        ParentChainParams {
+            id,
+            name: name.to_owned(),
+            coin_name: "Dogecoin".to_owned(),
+            ticker: "DOGE".to_owned(),
+            default_confirmations: 40,
+            default_rpc_port: 22555,
+            bitcoin_network: None,
+            address_formats: vec![AddressFormat::Base58 {
+                p2pkh: 30,
+                p2sh: 22,
+            }],
+            networks: Vec::new(),
+            setup_hint: String::new(),
+            spv_max_bits: None,
+            spv_checkpoint: None,
+        }
+    }
+
+    /// Encodings of the enum that `ParentChainType` replaced
+    #[test]
+    fn builtin_ids_keep_enum_encodings() {
+        let signet = ParentChainType::Signet;
+        assert_eq!(borsh::to_vec(&signet).unwrap(), [3]);
+        assert_eq!(bincode::serialize(&signet).unwrap(), [3, 0, 0, 0]);
+        assert_eq!(serde_json::to_string(&signet).unwrap(), "\"Signet\"");
+        let key = (ParentChainType::Regtest, [7u8; 2]);
+        assert_eq!(bincode::serialize(&key).unwrap(), [4, 0, 0, 0, 7, 7]);
+        assert_eq!(
+            bincode::deserialize::<ParentChainType>(&[2, 0, 0, 0]).unwrap(),
+            ParentChainType::LTC
+        );
+        assert!(
+            bincode::deserialize::<ParentChainType>(&[0, 1, 0, 0]).is_err()
+        );
+        assert_eq!(
+            serde_json::from_str::<ParentChainType>("\"BCH\"").unwrap(),
+            ParentChainType::BCH
+        );
+    }
+
+    #[test]
+    fn builtin_spv_max_targets() {
+        for (parent_chain, bits) in [
+            (ParentChainType::BTC, Some(0x1800ffff)),
+            (ParentChainType::BCH, Some(0x1d00ffff)),
+            (ParentChainType::LTC, None),
+            (ParentChainType::Signet, Some(0x1e0377ae)),
+            (ParentChainType::Regtest, Some(0x207fffff)),
+        ] {
+            assert_eq!(
+                parent_chain.spv_max_target(),
+                bits.map(
+                    |bits| bitcoin::CompactTarget::from_consensus(bits).into()
+                )
+            );
+        }
+    }
+
+    /// SPV claims are enabled on the chains with Bitcoin's difficulty
+    /// adjustment rules
+    #[test]
+    fn builtin_spv_params() {
+        for (parent_chain, height, network) in [
+            (ParentChainType::BTC, 840_000, bitcoin::Network::Bitcoin),
+            (ParentChainType::Signet, 0, bitcoin::Network::Signet),
+            (ParentChainType::Regtest, 0, bitcoin::Network::Regtest),
+        ] {
+            let params = parent_chain.spv_params().unwrap();
+            assert_eq!(params.checkpoint.height, height);
+            assert_eq!(params.bitcoin_network, network);
+            assert_eq!(
+                params.max_target,
+                parent_chain.spv_max_target().unwrap()
+            );
+        }
+        let regtest = ParentChainType::Regtest.spv_params().unwrap();
+        assert_eq!(
+            regtest.checkpoint.block_hash,
+            bitcoin::constants::genesis_block(bitcoin::Network::Regtest)
+                .block_hash()
+        );
+        let signet = ParentChainType::Signet.spv_params().unwrap();
+        assert_eq!(
+            signet.checkpoint.block_hash,
+            bitcoin::constants::genesis_block(bitcoin::Network::Signet)
+                .block_hash()
+        );
+        for parent_chain in [ParentChainType::BCH, ParentChainType::LTC] {
+            assert_eq!(parent_chain.spv_params(), None);
+        }
+    }
+
+    /// Checkpoints are only checked with Bitcoin's difficulty adjustment
+    /// rules
+    #[test]
+    fn spv_checkpoints_need_a_bitcoin_network() {
+        let mut chains = ParentChainRegistry::builtin().chains;
+        let bch = chains
+            .iter_mut()
+            .find(|params| params.id == ParentChainType::BCH.id())
+            .unwrap();
+        bch.spv_checkpoint = Some(SpvCheckpoint {
+            height: 1,
+            block_hash: bitcoin::BlockHash::all_zeros(),
+            bits: 0x1d00ffff,
+        });
+        assert!(matches!(
+            ParentChainRegistry::new(chains),
+            Err(ParentChainRegistryError::IncompleteSpvParams(_))
+        ));
+    }
+
+    #[test]
+    fn unregistered_ids_round_trip() {
+        let unknown = ParentChainType::from_id(200);
+        assert_eq!(unknown.to_string(), "200");
+        assert_eq!("200".parse::<ParentChainType>().unwrap(), unknown);
+        let json = serde_json::to_string(&unknown).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ParentChainType>(&json).unwrap(),
+            unknown
+        );
+        assert_eq!(unknown.spv_max_target(), None);
+        assert_eq!(unknown.spv_params(), None);
+        assert_eq!(unknown.consensus_default_confirmations(), 6);
+        assert!("dogecoin".parse::<ParentChainType>().is_err());
+    }
+
+    #[test]
+    fn custom_chains_extend_builtin_chains() {
+        let registry =
+            ParentChainRegistry::with_custom_chains([custom_chain(9, "DOGE")])
+                .unwrap();
+        let doge = registry.get_by_name("doge").unwrap();
+        assert_eq!(doge.id, 9);
+        assert_eq!(
+            registry.get(ParentChainType::from_id(9)).unwrap().ticker,
+            "DOGE"
+        );
+        assert_eq!(
+            registry.get(ParentChainType::Signet).unwrap().name,
+            "Signet"
+        );
+        assert!(matches!(
+            ParentChainRegistry::with_custom_chains([custom_chain(3, "DOGE")]),
+            Err(ParentChainRegistryError::DuplicateId(3))
+        ));
+        assert!(matches!(
+            ParentChainRegistry::with_custom_chains([custom_chain(9, "btc")]),
+            Err(ParentChainRegistryError::DuplicateName(_))
+        ));
+        let mut with_spv = custom_chain(9, "DOGE");
+        with_spv.spv_max_bits = Some(0x1e0fffff);
+        assert!(matches!(
+            ParentChainRegistry::with_custom_chains([with_spv]),
+            Err(ParentChainRegistryError::CustomSpvParams(_))
+        ));
+        let mut with_checkpoint = custom_chain(9, "DOGE");
+        with_checkpoint.spv_checkpoint = Some(SpvCheckpoint {
+            height: 1,
+            block_hash: bitcoin::BlockHash::all_zeros(),
+            bits: 0x1e0fffff,
+        });
+        assert!(matches!(
+            ParentChainRegistry::with_custom_chains([with_checkpoint]),
+            Err(ParentChainRegistryError::CustomSpvParams(_))
+        ));
+    }
+
+    #[test]
+    fn load_custom_chains_from_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("parent_chains.json");
+        let registry = ParentChainRegistry::load_from_path(&path).unwrap();
+        assert_eq!(registry.chains().len(), 5);
+        let custom = serde_json::json!([{
+            "id": 9,
+            "name": "DOGE",
+            "coin_name": "Dogecoin",
+            "ticker": "DOGE",
+            "default_confirmations": 40,
+            "default_rpc_port": 22555,
+            "address_formats": [{ "kind": "base58", "p2pkh": 30, "p2sh": 22 }],
+        }]);
+        std::fs::write(&path, custom.to_string()).unwrap();
+        let registry = ParentChainRegistry::load_from_path(&path).unwrap();
+        assert_eq!(registry.chains().len(), 6);
+        assert_eq!(
+            registry.get_by_name("DOGE").unwrap().address_formats,
+            [AddressFormat::Base58 {
+                p2pkh: 30,
+                p2sh: 22
+            }]
+        );
+    }
+}
@@ -95,6 +95,21 @@ impl ToSchema for UtreexoProof {
     }
 }
 
+pub struct IpAddr;
+
+impl PartialSchema for IpAddr {
+    fn schema() -> RefOr<Schema> {
+        let obj = utoipa::openapi::Object::with_type(openapi::Type::String);
+        RefOr::T(Schema::Object(obj))
+    }
+}
+
+impl ToSchema for IpAddr {
+    fn name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("net.IpAddr")
+    }
+}
+
 pub struct SocketAddr;
 
 impl PartialSchema for SocketAddr {
@@ -0,0 +1,334 @@
+//! SPV proofs of L1 transaction inclusion
+//!
+//! A filler attaches an [`SpvProof`] to `TxData::SwapClaim::proof_data` so
+//! that every sidechain node can check the L1 payment from block data alone,
+//! instead of asking its own parent chain RPC.
+//!
+//! Proofs carry at most [`MAX_SPV_HEADERS`] headers. `TxData::TrackL1Headers`
+//! carries headers without a payment, so that the tracked L1 header chain
+//! can catch up with L1 in batches before a claim.
+
+use bitcoin::{
+    BlockHash as L1BlockHash, MerkleBlock, Target, Txid as L1Txid,
+    block::Header as L1Header, consensus,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use thiserror::Error;
+
+/// Most L1 headers in an SPV proof, or in a `TxData::TrackL1Headers`
+/// transaction. A difficulty period of headers takes about 170 kB.
+pub const MAX_SPV_HEADERS: usize = 2016;
+
+#[derive(Debug, Error)]
+pub enum SpvProofError {
+    #[error("failed to decode SPV proof")]
+    Decode(#[source] std::io::Error),
+    #[error("failed to decode L1 {item} in SPV proof")]
+    DecodeConsensus {
+        item: &'static str,
+        source: consensus::encode::Error,
+    },
+    #[error("SPV proof headers are not continuous at L1 block {block_hash}")]
+    Discontinuous { block_hash: L1BlockHash },
+    #[error("invalid proof of work for L1 block {block_hash}")]
+    InvalidPow { block_hash: L1BlockHash },
+    #[error("invalid merkle proof")]
+    MerkleBlock(#[from] bitcoin::merkle_tree::MerkleBlockError),
+    #[error("L1 transaction {txid} is not included in the merkle proof")]
+    NotIncluded { txid: L1Txid },
+    #[error("SPV proofs are not supported for this parent chain")]
+    Unsupported,
+    #[error("L1 block {block_hash} has a target easier than the SPV limit")]
+    TargetTooEasy { block_hash: L1BlockHash },
+    #[error(
+        "{count} L1 headers exceed the limit of {MAX_SPV_HEADERS}; track the L1 headers up to the payment first"
+    )]
+    TooManyHeaders { count: usize },
+    #[error("no L1 headers")]
+    NoHeaders,
+}
+
+/// Wire format for [`SpvProof`]. L1 items are consensus-encoded, exactly as
+/// returned by `gettxoutproof`, `getrawtransaction` and `getblockheader`.
+#[derive(BorshDeserialize, BorshSerialize)]
+struct EncodedSpvProof {
+    ancestors: Vec<Vec<u8>>,
+    merkle_block: Vec<u8>,
+    transaction: Vec<u8>,
+    headers: Vec<Vec<u8>>,
+}
+
+/// Decode consensus-encoded L1 headers, as carried by SPV proofs and by
+/// `TxData::TrackL1Headers`
+pub fn decode_l1_headers(
+    headers: &[Vec<u8>],
+) -> Result<Vec<L1Header>, SpvProofError> {
+    if headers.len() > MAX_SPV_HEADERS {
+        return Err(SpvProofError::TooManyHeaders {
+            count: headers.len(),
+        });
+    }
+    headers
+        .iter()
+        .map(|header| {
+            consensus::deserialize(header).map_err(|source| {
+                SpvProofError::DecodeConsensus {
+                    item: "header",
+                    source,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Proof that an L1 transaction was mined, and buried under `headers`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpvProof {
+    /// Headers linking an already tracked L1 block to the including block,
+    /// in order. Empty if the including block builds on a tracked block.
+    pub ancestors: Vec<L1Header>,
+    /// Header of the block that includes the transaction, and a partial
+    /// merkle tree proving inclusion
+    pub merkle_block: MerkleBlock,
+    /// The L1 transaction
+    pub transaction: bitcoin::Transaction,
+    /// Headers of the blocks built on top of the including block, in order
+    pub headers: Vec<L1Header>,
+}
+
+/// Result of verifying an [`SpvProof`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VerifiedL1Tx {
+    pub txid: L1Txid,
+    /// Block that includes the transaction
+    pub block_hash: L1BlockHash,
+    /// Number of blocks in the proof, including the block that includes the
+    /// transaction
+    pub confirmations: u32,
+}
+
+impl SpvProof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let encoded = EncodedSpvProof {
+            ancestors: self
+                .ancestors
+                .iter()
+                .map(consensus::serialize)
+                .collect(),
+            merkle_block: consensus::serialize(&self.merkle_block),
+            transaction: consensus::serialize(&self.transaction),
+            headers: self.headers.iter().map(consensus::serialize).collect(),
+        };
+        borsh::to_vec(&encoded).expect("serializing to a Vec cannot fail")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SpvProofError> {
+        let encoded: EncodedSpvProof =
+            borsh::from_slice(bytes).map_err(SpvProofError::Decode)?;
+        let ancestors = decode_l1_headers(&encoded.ancestors)?;
+        let merkle_block = consensus::deserialize(&encoded.merkle_block)
+            .map_err(|source| SpvProofError::DecodeConsensus {
+                item: "merkle block",
+                source,
+            })?;
+        let transaction = consensus::deserialize(&encoded.transaction)
+            .map_err(|source| SpvProofError::DecodeConsensus {
+                item: "transaction",
+                source,
+            })?;
+        let headers = decode_l1_headers(&encoded.headers)?;
+        Ok(Self {
+            ancestors,
+            merkle_block,
+            transaction,
+            headers,
+        })
+    }
+
+    /// All L1 headers in the proof, in order
+    pub fn l1_headers(&self) -> impl DoubleEndedIterator<Item = &L1Header> {
+        self.ancestors
+            .iter()
+            .chain(std::iter::once(&self.merkle_block.header))
+            .chain(self.headers.iter())
+    }
+
+    /// Verify merkle inclusion, header continuity, and proof of work.
+    /// Every header must meet its own target, and no target may be easier
+    /// than `max_target`.
+    pub fn verify(
+        &self,
+        max_target: Target,
+    ) -> Result<VerifiedL1Tx, SpvProofError> {
+        let txid = self.transaction.compute_txid();
+        let mut matches = Vec::new();
+        let mut indexes = Vec::new();
+        let () = self
+            .merkle_block
+            .extract_matches(&mut matches, &mut indexes)?;
+        if !matches.contains(&txid) {
+            return Err(SpvProofError::NotIncluded { txid });
+        }
+        let count = self.ancestors.len() + 1 + self.headers.len();
+        if count > MAX_SPV_HEADERS {
+            return Err(SpvProofError::TooManyHeaders { count });
+        }
+        let () = verify_l1_headers(self.l1_headers(), max_target)?;
+        Ok(VerifiedL1Tx {
+            txid,
+            block_hash: self.merkle_block.header.block_hash(),
+            confirmations: self.headers.len() as u32 + 1,
+        })
+    }
+}
+
+/// Verify header continuity and proof of work. Every header must meet its
+/// own target, and no target may be easier than `max_target`.
+pub fn verify_l1_headers<'a>(
+    headers: impl IntoIterator<Item = &'a L1Header>,
+    max_target: Target,
+) -> Result<(), SpvProofError> {
+    let mut prev_block_hash: Option<L1BlockHash> = None;
+    for header in headers {
+        let block_hash = header.block_hash();
+        if let Some(prev_block_hash) = prev_block_hash
+            && header.prev_blockhash != prev_block_hash
+        {
+            return Err(SpvProofError::Discontinuous { block_hash });
+        }
+        let target = header.target();
+        if target > max_target {
+            return Err(SpvProofError::TargetTooEasy { block_hash });
+        }
+        header
+            .validate_pow(target)
+            .map_err(|_| SpvProofError::InvalidPow { block_hash })?;
+        prev_block_hash = Some(block_hash);
+    }
+    if prev_block_hash.is_none() {
+        return Err(SpvProofError::NoHeaders);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        Amount, BlockHash, CompactTarget, ScriptBuf, TxIn, TxMerkleNode, TxOut,
+        absolute::LockTime, block, hashes::Hash as _, transaction,
+    };
+
+    use super::{MAX_SPV_HEADERS, SpvProof, SpvProofError};
+    use crate::state::State;
+
+    const REGTEST_BITS: u32 = 0x207fffff;
+
+    fn mine(
+        prev_blockhash: BlockHash,
+        merkle_root: TxMerkleNode,
+    ) -> block::Header {
+        let mut header = block::Header {
+            version: block::Version::TWO,
+            prev_blockhash,
+            merkle_root,
+            time: 1_700_000_000,
+            bits: CompactTarget::from_consensus(REGTEST_BITS),
+            nonce: 0,
+        };
+        while header.validate_pow(header.target()).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    fn regtest_proof(num_headers: usize) -> SpvProof {
+        let transaction = bitcoin::Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: Amount::from_sat(50_000),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        };
+        let txid = transaction.compute_txid();
+        let other_txid = bitcoin::Txid::from_byte_array([7; 32]);
+        let txids = [other_txid, txid];
+        let merkle_root =
+            bitcoin::merkle_tree::calculate_root(txids.iter().copied())
+                .map(|root| TxMerkleNode::from_raw_hash(root.to_raw_hash()))
+                .unwrap();
+        let header = mine(BlockHash::all_zeros(), merkle_root);
+        let merkle_block =
+            bitcoin::MerkleBlock::from_header_txids_with_predicate(
+                &header,
+                &txids,
+                |candidate| *candidate == txid,
+            );
+        let mut headers = Vec::with_capacity(num_headers);
+        let mut prev = header.block_hash();
+        for _ in 0..num_headers {
+            let next = mine(prev, TxMerkleNode::all_zeros());
+            prev = next.block_hash();
+            headers.push(next);
+        }
+        SpvProof {
+            ancestors: Vec::new(),
+            merkle_block,
+            transaction,
+            headers,
+        }
+    }
+
+    #[test]
+    fn roundtrip_and_verify() {
+        let proof = regtest_proof(2);
+        let decoded = SpvProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert_eq!(decoded, proof);
+        let verified = decoded
+            .verify(CompactTarget::from_consensus(REGTEST_BITS).into())
+            .unwrap();
+        assert_eq!(verified.txid, proof.transaction.compute_txid());
+        assert_eq!(verified.confirmations, 3);
+    }
+
+    #[test]
+    fn rejects_discontinuous_headers() {
+        let mut proof = regtest_proof(2);
+        proof.headers.remove(0);
+        assert!(matches!(
+            proof.verify(CompactTarget::from_consensus(REGTEST_BITS).into()),
+            Err(SpvProofError::Discontinuous { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_target_above_limit() {
+        let proof = regtest_proof(0);
+        // Signet limit is harder than the regtest target
+        assert!(matches!(
+            proof.verify(CompactTarget::from_consensus(0x1e0377ae).into()),
+            Err(SpvProofError::TargetTooEasy { .. })
+        ));
+    }
+
+    /// The largest proof takes a small fraction of the smallest block
+    #[test]
+    fn max_headers_fit_in_a_block() {
+        let max_target = CompactTarget::from_consensus(REGTEST_BITS).into();
+        let proof = regtest_proof(MAX_SPV_HEADERS - 1);
+        let bytes = proof.to_bytes();
+        assert!(bytes.len() < State::body_size_limit(0) / 32);
+        let decoded = SpvProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.verify(max_target).unwrap().confirmations, 2016);
+
+        let mut too_long = proof;
+        too_long
+            .ancestors
+            .push(mine(BlockHash::all_zeros(), TxMerkleNode::all_zeros()));
+        assert!(matches!(
+            too_long.verify(max_target),
+            Err(SpvProofError::TooManyHeaders { count: 2017 })
+        ));
+    }
+}
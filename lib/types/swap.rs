@@ -6,7 +6,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use super::{Address, BlockHash};
+use super::{Address, BlockHash, ParentChainType};
 
 /// 32-byte swap identifier
 #[derive(
@@ -50,13 +50,21 @@ impl SwapId {
         Self(*hash.as_bytes())
     }
 
-    /// Generate swap ID for L1 → L2 swaps (for future use)
+    /// Generate swap ID for L1 → L2 swaps.
+    /// The L1 recipient is only known once the swap is locked, so it is not
+    /// part of the ID.
     pub fn from_l1_to_l2(
-        l1_txid: &bitcoin::Txid,
+        l1_amount: bitcoin::Amount,
+        l2_amount: bitcoin::Amount,
+        l2_creator_address: &Address,
         l2_recipient_address: &Address,
     ) -> Self {
         let mut id_data = Vec::new();
-        id_data.extend_from_slice(l1_txid.as_ref());
+        // Domain separation from L2 → L1 swap IDs
+        id_data.extend_from_slice(b"L1_TO_L2");
+        id_data.extend_from_slice(&l1_amount.to_sat().to_le_bytes());
+        id_data.extend_from_slice(&l2_amount.to_sat().to_le_bytes());
+        id_data.extend_from_slice(&l2_creator_address.0);
         id_data.extend_from_slice(&l2_recipient_address.0);
         let hash = blake3::hash(&id_data);
         Self(*hash.as_bytes())
@@ -69,7 +77,82 @@ impl std::fmt::Display for SwapId {
     }
 }
 
+/// Commitment carried in an OP_RETURN output of the L1 payment for a swap
+/// that requires one. It binds the payment to a single swap, and declares
+/// the L2 address of the filler that made it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SwapCommitment {
+    pub swap_id: SwapId,
+    pub l2_claimer_address: Address,
+}
+
+impl SwapCommitment {
+    /// Prefix of the OP_RETURN data, identifying it as a swap commitment
+    pub const TAG: [u8; 4] = *b"CSWP";
+
+    /// Length of the OP_RETURN data: tag, swap ID and L2 address
+    pub const DATA_LEN: usize = Self::TAG.len() + 32 + 20;
+
+    /// OP_RETURN data to include in the L1 payment
+    pub fn to_data(&self) -> [u8; Self::DATA_LEN] {
+        let mut data = [0; Self::DATA_LEN];
+        data[..4].copy_from_slice(&Self::TAG);
+        data[4..36].copy_from_slice(&self.swap_id.0);
+        data[36..].copy_from_slice(&self.l2_claimer_address.0);
+        data
+    }
+
+    /// OP_RETURN script committing to the swap
+    pub fn to_script(&self) -> bitcoin::ScriptBuf {
+        let data = self.to_data();
+        let push: &bitcoin::script::PushBytes = data
+            .as_slice()
+            .try_into()
+            .expect("commitment data fits in a single push");
+        bitcoin::ScriptBuf::new_op_return(push)
+    }
+
+    /// Parse a commitment from an output script. Returns `None` if the
+    /// script is not an OP_RETURN with a single push of commitment data.
+    pub fn from_script(script: &bitcoin::Script) -> Option<Self> {
+        use bitcoin::script::Instruction;
+        if !script.is_op_return() {
+            return None;
+        }
+        let mut instructions = script.instructions().skip(1);
+        let Some(Ok(Instruction::PushBytes(data))) = instructions.next() else {
+            return None;
+        };
+        if instructions.next().is_some() {
+            return None;
+        }
+        let data = data.as_bytes();
+        if data.len() != Self::DATA_LEN || data[..4] != Self::TAG {
+            return None;
+        }
+        Some(Self {
+            swap_id: SwapId(data[4..36].try_into().ok()?),
+            l2_claimer_address: Address(data[36..].try_into().ok()?),
+        })
+    }
+
+    /// Find the commitment to `swap_id` in the outputs of an L1 transaction
+    pub fn find<'a, I>(scripts: I, swap_id: &SwapId) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a bitcoin::Script>,
+    {
+        scripts
+            .into_iter()
+            .filter_map(Self::from_script)
+            .find(|commitment| commitment.swap_id == *swap_id)
+    }
+}
+
 /// Swap direction
+///
+/// In an L2 → L1 swap, the creator locks L2 coins and asks for an L1
+/// payment. In an L1 → L2 swap, the creator offers an L1 payment, and an L2
+/// holder locks the L2 coins with a SwapLock transaction.
 #[derive(
     BorshSerialize,
     BorshDeserialize,
@@ -87,114 +170,81 @@ pub enum SwapDirection {
     L2ToL1,
 }
 
-/// Parent chain type for swaps
-/// Note: This can be different from the sidechain's mainchain network.
-/// For example, sidechain may be on Regtest, but swaps can target Signet, Mainnet, etc.
+/// Rule by which an L1 transaction is matched as the payment of a swap.
+/// Recorded in the SwapCreate transaction, so that every node applies the
+/// same rule.
 #[derive(
     BorshSerialize,
     BorshDeserialize,
     Clone,
     Copy,
     Debug,
+    Default,
     Deserialize,
     Eq,
-    Hash,
     PartialEq,
     Serialize,
     utoipa::ToSchema,
 )]
-pub enum ParentChainType {
-    /// Bitcoin Mainnet
-    BTC,
-    /// Bitcoin Cash
-    BCH,
-    /// Litecoin
-    LTC,
-    /// Bitcoin Signet (for cross-chain swaps)
-    Signet,
-    /// Bitcoin Regtest (for testing)
-    Regtest,
+#[serde(rename_all = "snake_case")]
+pub enum L1MatchPolicy {
+    /// One output to the L1 recipient pays exactly the L1 amount
+    #[default]
+    Exact,
+    /// One output to the L1 recipient pays at least the L1 amount
+    AtLeast,
+    /// The outputs to the L1 recipient in one transaction pay at least the
+    /// L1 amount in total
+    SumOfOutputs,
 }
 
-impl ParentChainType {
-    /// Get default required confirmations for this chain
-    pub fn default_confirmations(&self) -> u32 {
-        match self {
-            Self::BTC => 6,
-            Self::BCH | Self::LTC | Self::Signet | Self::Regtest => 3,
-        }
-    }
-
-    /// Get the Bitcoin network enum for this chain type
-    pub fn to_bitcoin_network(&self) -> bitcoin::Network {
-        match self {
-            Self::BTC => bitcoin::Network::Bitcoin,
-            Self::Signet => bitcoin::Network::Signet,
-            Self::Regtest => bitcoin::Network::Regtest,
-            Self::BCH | Self::LTC => {
-                // BCH and LTC are separate networks, would need separate handling
-                bitcoin::Network::Bitcoin // Placeholder
-            }
-        }
-    }
-
-    /// Get the default RPC port for this chain
-    ///
-    /// These are the standard mainnet RPC ports. Testnet/regtest ports differ.
-    pub fn default_rpc_port(&self) -> u16 {
+impl L1MatchPolicy {
+    /// Returns `true` if a transaction whose outputs to the L1 recipient
+    /// have the given values pays `l1_amount` under this policy
+    pub fn matches<I>(&self, values: I, l1_amount: bitcoin::Amount) -> bool
+    where
+        I: IntoIterator<Item = bitcoin::Amount>,
+    {
+        let mut values = values.into_iter();
         match self {
-            Self::BTC => 8332,
-            Self::BCH => 8332, // Bitcoin Cash ABC/BCHN default
-            Self::LTC => 9332, // Litecoin Core default
-            Self::Signet => 38332, // Bitcoin Signet default
-            Self::Regtest => 18443, // Bitcoin Regtest default
+            Self::Exact => values.any(|value| value == l1_amount),
+            Self::AtLeast => values.any(|value| value >= l1_amount),
+            Self::SumOfOutputs => values
+                .try_fold(bitcoin::Amount::ZERO, |total, value| {
+                    total.checked_add(value)
+                })
+                .is_some_and(|total| total >= l1_amount),
         }
     }
+}
 
-    /// Get the human-readable coin name for display
-    pub fn coin_name(&self) -> &'static str {
+impl std::fmt::Display for L1MatchPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::BTC => "Bitcoin",
-            Self::BCH => "Bitcoin Cash",
-            Self::LTC => "Litecoin",
-            Self::Signet => "Bitcoin Signet",
-            Self::Regtest => "Bitcoin Regtest",
+            Self::Exact => write!(f, "exact"),
+            Self::AtLeast => write!(f, "at_least"),
+            Self::SumOfOutputs => write!(f, "sum_of_outputs"),
         }
     }
+}
 
-    /// Get the number of satoshis (smallest unit) per coin
-    ///
-    /// All Bitcoin-derivative chains use 100,000,000 satoshis per coin.
-    pub fn sats_per_coin(&self) -> u64 {
-        100_000_000
-    }
+#[derive(Debug, Error)]
+#[error(
+    "unknown L1 match policy `{0}`: expected `exact`, `at_least` or `sum_of_outputs`"
+)]
+pub struct ParseL1MatchPolicyError(String);
 
-    /// Get the ticker symbol for this chain
-    pub fn ticker(&self) -> &'static str {
-        match self {
-            Self::BTC => "BTC",
-            Self::BCH => "BCH",
-            Self::LTC => "LTC",
-            Self::Signet => "sBTC",
-            Self::Regtest => "rBTC",
-        }
-    }
+impl std::str::FromStr for L1MatchPolicy {
+    type Err = ParseL1MatchPolicyError;
 
-    /// Get the default RPC URL hint for this chain
-    pub fn default_rpc_url_hint(&self) -> &'static str {
-        match self {
-            Self::BTC => "http://localhost:8332",
-            Self::BCH => "http://localhost:8332",
-            Self::LTC => "http://localhost:9332",
-            Self::Signet => "http://localhost:38332",
-            Self::Regtest => "http://localhost:18443",
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(Self::Exact),
+            "at_least" => Ok(Self::AtLeast),
+            "sum_of_outputs" => Ok(Self::SumOfOutputs),
+            _ => Err(ParseL1MatchPolicyError(s.to_owned())),
         }
     }
-
-    /// Get all supported parent chain types
-    pub fn all() -> &'static [ParentChainType] {
-        &[Self::BTC, Self::BCH, Self::LTC, Self::Signet, Self::Regtest]
-    }
 }
 
 /// Swap state
@@ -223,6 +273,8 @@ pub enum SwapState {
     Completed,
     /// Swap expired or cancelled
     Cancelled,
+    /// L1 → L2 offer created, waiting for an L2 holder to lock coins
+    AwaitingLock,
 }
 
 impl SwapState {
@@ -263,7 +315,7 @@ pub enum SwapTxId {
     Hash(Vec<u8>),
 }
 
-/// Reverse bytes in place (used for Bitcoin txid RPC order ↔ canonical order).
+/// Reverse bytes in place (used for internal ↔ canonical txid byte order).
 fn reverse_32(buf: &mut [u8; 32]) {
     buf.reverse();
 }
@@ -275,10 +327,10 @@ fn reversed_32(bytes: &[u8; 32]) -> [u8; 32] {
 }
 
 impl SwapTxId {
-    /// Create from a bitcoin::Txid (RPC/internal byte order). Stored in canonical order.
+    /// Create from a bitcoin::Txid (internal byte order). Stored in canonical order.
     pub fn from_bitcoin_txid(txid: &bitcoin::Txid) -> Self {
-        let rpc_order = *txid.as_ref();
-        Self::Hash32(reversed_32(&rpc_order))
+        let internal = *txid.as_ref();
+        Self::Hash32(reversed_32(&internal))
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Self {
@@ -311,27 +363,11 @@ impl SwapTxId {
         Ok(Self::from_bytes(&bytes))
     }
 
-    /// Parse L1 txid from a hex string in **RPC** order (e.g. as returned by Bitcoin Core
-    /// getrawtransaction / listunspent). Converts to canonical order for storage.
+    /// Parse L1 txid from a hex string as returned by Bitcoin Core RPCs
+    /// (e.g. getrawtransaction / listunspent). Bitcoin Core prints txids in
+    /// the same order as block explorers, so this is [`Self::from_hex`].
     pub fn from_hex_rpc(hex_str: &str) -> Result<Self, String> {
-        let s = hex_str.trim();
-        if s.len() != 64 {
-            return Err(format!(
-                "L1 txid must be exactly 64 hex characters (32 bytes), got {}",
-                s.len()
-            ));
-        }
-        let bytes = hex::decode(s).map_err(|e| format!("Invalid hex: {e}"))?;
-        if bytes.len() != 32 {
-            return Err(format!(
-                "Decoded L1 txid must be 32 bytes, got {}",
-                bytes.len()
-            ));
-        }
-        let mut arr = [0u8; 32];
-        arr.copy_from_slice(&bytes);
-        reverse_32(&mut arr);
-        Ok(Self::Hash32(arr))
+        Self::from_hex(hex_str)
     }
 
     pub fn to_bitcoin_txid(&self) -> Option<bitcoin::Txid> {
@@ -351,24 +387,19 @@ impl SwapTxId {
         }
     }
 
-    /// Hex encoding in **RPC** order (for Bitcoin Core getrawtransaction, etc.).
+    /// Hex encoding for Bitcoin Core RPCs (getrawtransaction, etc.), which
+    /// is the same as [`Self::to_hex`]
     pub fn to_hex_rpc(&self) -> String {
-        match self {
-            Self::Hash32(hash) => hex::encode(reversed_32(hash)),
-            Self::Hash(bytes) if bytes.len() == 32 => {
-                let mut arr = [0u8; 32];
-                arr.copy_from_slice(bytes);
-                reverse_32(&mut arr);
-                hex::encode(arr)
-            }
-            Self::Hash(bytes) => hex::encode(bytes),
-        }
+        self.to_hex()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::SwapTxId;
+    use super::{
+        Address, L1MatchPolicy, ParentChainType, Swap, SwapCommitment,
+        SwapDirection, SwapId, SwapState, SwapTxId,
+    };
 
     #[test]
     fn from_hex_requires_64_chars() {
@@ -391,20 +422,174 @@ mod tests {
     }
 
     #[test]
-    fn txid_canonical_vs_rpc_byte_order() {
-        // Canonical (block explorer) vs RPC (Bitcoin Core) are byte-reversed
-        let canonical =
-            "ceaa5bbe14a2fe2658115f32ea90a11c073a5028df5713adbcdb35c70c3e9127";
+    fn txid_rpc_order_matches_bitcoin_txid() {
+        // Bitcoin Core and block explorers print txids in the same order
         let rpc_order =
-            "27913e0cc735dbbcad1357df28503a071ca190ea325f115826fea214be5baace";
-        let from_canonical = SwapTxId::from_hex(canonical).unwrap();
+            "ceaa5bbe14a2fe2658115f32ea90a11c073a5028df5713adbcdb35c70c3e9127";
+        let txid: bitcoin::Txid = rpc_order.parse().unwrap();
         let from_rpc = SwapTxId::from_hex_rpc(rpc_order).unwrap();
-        assert_eq!(from_canonical.to_hex(), from_rpc.to_hex());
-        assert_eq!(from_canonical.to_hex(), canonical);
-        assert_eq!(from_canonical.to_hex_rpc(), rpc_order);
-        assert_eq!(from_rpc.to_hex(), canonical);
+        assert_eq!(from_rpc, SwapTxId::from_hex(rpc_order).unwrap());
+        assert_eq!(from_rpc, SwapTxId::from_bitcoin_txid(&txid));
+        assert_eq!(from_rpc.to_bitcoin_txid(), Some(txid));
+        assert_eq!(from_rpc.to_hex(), rpc_order);
         assert_eq!(from_rpc.to_hex_rpc(), rpc_order);
     }
+
+    #[test]
+    fn swap_commitment_roundtrip() {
+        let commitment = SwapCommitment {
+            swap_id: SwapId([7; 32]),
+            l2_claimer_address: Address([9; 20]),
+        };
+        let script = commitment.to_script();
+        assert_eq!(SwapCommitment::from_script(&script), Some(commitment));
+
+        // Commitments to other swaps are ignored
+        let other = SwapCommitment {
+            swap_id: SwapId([8; 32]),
+            ..commitment
+        };
+        let scripts = [other.to_script(), script];
+        assert_eq!(
+            SwapCommitment::find(
+                scripts.iter().map(|script| script.as_script()),
+                &commitment.swap_id
+            ),
+            Some(commitment)
+        );
+        assert_eq!(
+            SwapCommitment::find(
+                scripts[..1].iter().map(|script| script.as_script()),
+                &commitment.swap_id
+            ),
+            None
+        );
+
+        // OP_RETURN outputs with other data are not commitments
+        let mut data = commitment.to_data();
+        data[0] ^= 1;
+        let push: &bitcoin::script::PushBytes =
+            data.as_slice().try_into().unwrap();
+        let script = bitcoin::ScriptBuf::new_op_return(push);
+        assert_eq!(SwapCommitment::from_script(&script), None);
+    }
+
+    #[test]
+    fn match_policies() {
+        let amount = bitcoin::Amount::from_sat;
+        let exact = [amount(1_000)];
+        let overpaid = [amount(1_001)];
+        let split = [amount(600), amount(400)];
+        let matches = |policy: L1MatchPolicy, values: &[bitcoin::Amount]| {
+            policy.matches(values.iter().copied(), amount(1_000))
+        };
+        assert!(matches(L1MatchPolicy::Exact, &exact));
+        assert!(!matches(L1MatchPolicy::Exact, &overpaid));
+        assert!(!matches(L1MatchPolicy::Exact, &split));
+        assert!(matches(L1MatchPolicy::AtLeast, &overpaid));
+        assert!(!matches(L1MatchPolicy::AtLeast, &split));
+        assert!(matches(L1MatchPolicy::SumOfOutputs, &split));
+        assert!(matches(L1MatchPolicy::SumOfOutputs, &overpaid));
+        assert!(!matches(L1MatchPolicy::SumOfOutputs, &split[..1]));
+        assert!(!matches(L1MatchPolicy::SumOfOutputs, &[]));
+        for policy in [
+            L1MatchPolicy::Exact,
+            L1MatchPolicy::AtLeast,
+            L1MatchPolicy::SumOfOutputs,
+        ] {
+            assert_eq!(
+                policy.to_string().parse::<L1MatchPolicy>().unwrap(),
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn l1_to_l2_swap_awaits_lock() {
+        let creator = Address([1; 20]);
+        let recipient = Address([2; 20]);
+        let l1_amount = bitcoin::Amount::from_sat(50_000);
+        let l2_amount = bitcoin::Amount::from_sat(100_000);
+        let swap_id =
+            SwapId::from_l1_to_l2(l1_amount, l2_amount, &creator, &recipient);
+        let mut swap = Swap::new(
+            swap_id,
+            SwapDirection::L1ToL2,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0; 32]),
+            None,
+            Some(recipient),
+            l2_amount,
+            None,
+            Some(l1_amount),
+            0,
+            None,
+            Some(creator),
+            false,
+            false,
+            L1MatchPolicy::Exact,
+        );
+        assert_eq!(swap.state, SwapState::AwaitingLock);
+
+        let l1_recipient = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+        swap.mark_locked(l1_recipient.to_owned());
+        assert_eq!(swap.state, SwapState::Pending);
+        assert_eq!(swap.l1_recipient_address.as_deref(), Some(l1_recipient));
+    }
+
+    #[test]
+    fn partial_fills_release_proportional_shares() {
+        let creator = Address([1; 20]);
+        let l1_recipient = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+        let l1_amount = bitcoin::Amount::from_sat(3);
+        let l2_amount = bitcoin::Amount::from_sat(100);
+        let swap_id =
+            SwapId::from_l2_to_l1(l1_recipient, l1_amount, &creator, None);
+        let mut swap = Swap::new(
+            swap_id,
+            SwapDirection::L2ToL1,
+            ParentChainType::Regtest,
+            SwapTxId::Hash32([0; 32]),
+            None,
+            None,
+            l2_amount,
+            Some(l1_recipient.to_owned()),
+            Some(l1_amount),
+            0,
+            None,
+            Some(creator),
+            false,
+            true,
+            L1MatchPolicy::Exact,
+        );
+
+        // Fills must be positive, and no more than the remaining amount
+        assert_eq!(swap.l2_amount_for_fill(bitcoin::Amount::ZERO), None);
+        assert_eq!(swap.l2_amount_for_fill(bitcoin::Amount::from_sat(4)), None);
+
+        let one_sat = bitcoin::Amount::from_sat(1);
+        let mut released = bitcoin::Amount::ZERO;
+        for (fill, l1_txid) in
+            [[1; 32], [2; 32], [3; 32]].into_iter().enumerate()
+        {
+            swap.update_l1_txid(SwapTxId::Hash32(l1_txid));
+            swap.state = SwapState::ReadyToClaim;
+            let share = swap.l2_amount_for_fill(one_sat).unwrap();
+            // Rounding is carried over, so shares differ by at most 1 sat
+            assert!((33..=34).contains(&share.to_sat()), "fill {fill}");
+            released += share;
+            swap.record_fill(one_sat);
+            if fill < 2 {
+                assert_eq!(swap.state, SwapState::Pending);
+                assert_eq!(swap.l1_txid, SwapTxId::Hash32([0; 32]));
+            }
+        }
+        assert_eq!(released, l2_amount);
+        assert_eq!(swap.state, SwapState::Completed);
+        assert_eq!(swap.l1_amount_filled, l1_amount);
+        assert_eq!(swap.remaining_l1_amount(), bitcoin::Amount::ZERO);
+        assert_eq!(swap.filled_l1_txids.len(), 3);
+    }
 }
 
 // Custom serde module for Option<Amount> that serializes as Option<u64>
@@ -470,9 +655,28 @@ pub struct Swap {
     pub l1_txid_validated_at_block_hash: Option<BlockHash>,
     /// Sidechain block height where L1 txid was validated via parent chain RPC
     pub l1_txid_validated_at_height: Option<u32>,
-    /// L2 address that created the swap (first input of SwapCreate). Used to restrict cancel/delete to creator.
+    /// L2 address that created the swap (first input of SwapCreate). Used to restrict cancellation to the creator.
     #[serde(default)]
     pub l2_creator_address: Option<Address>,
+    /// If set, the L1 payment must carry a [`SwapCommitment`] to this swap,
+    /// which also declares the L2 address of the filler
+    #[serde(default)]
+    pub l1_commitment_required: bool,
+    /// If set, the swap can be filled by several L1 payments, each
+    /// releasing a proportional share of the L2 amount
+    #[serde(default)]
+    pub partial_fills: bool,
+    /// Total paid by the L1 payments that have filled the swap so far
+    #[serde(default, with = "bitcoin::amount::serde::as_sat")]
+    #[schema(value_type = u64)]
+    pub l1_amount_filled: bitcoin::Amount,
+    /// L1 transactions that have filled part of the swap
+    #[serde(default)]
+    pub filled_l1_txids: Vec<SwapTxId>,
+    /// Rule by which an L1 transaction is matched as the payment of the
+    /// swap
+    #[serde(default)]
+    pub l1_match_policy: L1MatchPolicy,
 }
 
 // Custom Borsh serialization for Swap (needed for integration tests)
@@ -507,6 +711,11 @@ impl BorshSerialize for Swap {
         )?;
         BorshSerialize::serialize(&self.l1_txid_validated_at_height, writer)?;
         BorshSerialize::serialize(&self.l2_creator_address, writer)?;
+        BorshSerialize::serialize(&self.l1_commitment_required, writer)?;
+        BorshSerialize::serialize(&self.partial_fills, writer)?;
+        BorshSerialize::serialize(&self.l1_amount_filled.to_sat(), writer)?;
+        BorshSerialize::serialize(&self.filled_l1_txids, writer)?;
+        BorshSerialize::serialize(&self.l1_match_policy, writer)?;
         Ok(())
     }
 }
@@ -543,11 +752,38 @@ impl BorshDeserialize for Swap {
                 reader,
             )?,
             l2_creator_address: BorshDeserialize::deserialize_reader(reader)?,
+            l1_commitment_required: BorshDeserialize::deserialize_reader(
+                reader,
+            )?,
+            partial_fills: BorshDeserialize::deserialize_reader(reader)?,
+            l1_amount_filled: bitcoin::Amount::from_sat(
+                BorshDeserialize::deserialize_reader(reader)?,
+            ),
+            filled_l1_txids: BorshDeserialize::deserialize_reader(reader)?,
+            l1_match_policy: BorshDeserialize::deserialize_reader(reader)?,
         })
     }
 }
 
 impl Swap {
+    /// Longest expiry that a SwapCreate may set, in sidechain blocks.
+    /// About one year at one block per 10 minutes.
+    pub const MAX_EXPIRY_BLOCKS: u32 = 52_560;
+
+    /// Sidechain blocks during which a swap can still be claimed after its
+    /// creator requests cancellation. About one day at one block per 10
+    /// minutes.
+    pub const CANCEL_GRACE_BLOCKS: u32 = 144;
+
+    /// Sidechain height at which a swap created at `height` expires, if
+    /// it expires
+    pub fn expiry_height(
+        height: u32,
+        expires_in_blocks: Option<u32>,
+    ) -> Option<u32> {
+        expires_in_blocks.map(|blocks| height.saturating_add(blocks))
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: SwapId,
@@ -562,16 +798,23 @@ impl Swap {
         created_at_height: u32,
         expires_at_height: Option<u32>,
         l2_creator_address: Option<Address>,
+        l1_commitment_required: bool,
+        partial_fills: bool,
+        l1_match_policy: L1MatchPolicy,
     ) -> Self {
         let required_confirmations = required_confirmations
-            .unwrap_or_else(|| parent_chain.default_confirmations());
+            .unwrap_or_else(|| parent_chain.consensus_default_confirmations());
+        let state = match direction {
+            SwapDirection::L1ToL2 => SwapState::AwaitingLock,
+            SwapDirection::L2ToL1 => SwapState::Pending,
+        };
         Self {
             id,
             direction,
             parent_chain,
             l1_txid,
             required_confirmations,
-            state: SwapState::Pending,
+            state,
             l2_recipient,
             l2_amount,
             l1_recipient_address,
@@ -583,13 +826,135 @@ impl Swap {
             l1_txid_validated_at_block_hash: None,
             l1_txid_validated_at_height: None,
             l2_creator_address,
+            l1_commitment_required,
+            partial_fills,
+            l1_amount_filled: bitcoin::Amount::ZERO,
+            filled_l1_txids: Vec::new(),
+            l1_match_policy,
         }
     }
 
+    /// Returns `true` if the swap has expired by the given sidechain height
+    pub fn is_expired(&self, height: u32) -> bool {
+        self.expires_at_height
+            .is_some_and(|expires_at| height >= expires_at)
+    }
+
+    /// Expiry set by a SwapCancel at `height` for a swap whose locked
+    /// outputs can still be claimed. Such a SwapCancel only requests the
+    /// cancellation: claims stay valid for [`Self::CANCEL_GRACE_BLOCKS`],
+    /// so that the request cannot race a claim of an L1 payment.
+    /// Returns `None` if the swap is awaiting a lock, is final, or has
+    /// expired by `height`.
+    pub fn cancel_request_expiry(&self, height: u32) -> Option<u32> {
+        if matches!(
+            self.state,
+            SwapState::AwaitingLock
+                | SwapState::Completed
+                | SwapState::Cancelled
+        ) || self.is_expired(height)
+        {
+            return None;
+        }
+        Some(height.saturating_add(Self::CANCEL_GRACE_BLOCKS))
+    }
+
+    /// Lock an L1 → L2 swap, recording the L1 address of the L2 holder that
+    /// locked it. The swap then waits for the L1 payment.
+    pub fn mark_locked(&mut self, l1_recipient_address: String) {
+        self.l1_recipient_address = Some(l1_recipient_address);
+        self.state = SwapState::Pending;
+    }
+
     pub fn mark_completed(&mut self) {
         self.state = SwapState::Completed;
     }
 
+    /// Returns `true` if the L2 address paid by a claim is fixed by the swap,
+    /// or by an L1 commitment. Anyone who can prove the L1 payment may then
+    /// claim the swap without the creator's signature. Other open swaps are
+    /// claimed to the address that the creator signs for.
+    pub fn is_keyless_claimable(&self) -> bool {
+        self.l2_recipient.is_some() || self.l1_commitment_required
+    }
+
+    /// L1 amount that has not been paid yet
+    pub fn remaining_l1_amount(&self) -> bitcoin::Amount {
+        self.l1_amount
+            .unwrap_or(bitcoin::Amount::ZERO)
+            .checked_sub(self.l1_amount_filled)
+            .unwrap_or(bitcoin::Amount::ZERO)
+    }
+
+    /// Total paid to the swap's L1 recipient by `l1_tx`.
+    /// Returns `None` if the swap has no valid L1 recipient address.
+    pub fn l1_payment_value(
+        &self,
+        l1_tx: &bitcoin::Transaction,
+    ) -> Option<bitcoin::Amount> {
+        let l1_recipient_script = self
+            .parent_chain
+            .consensus_script_pubkey(self.l1_recipient_address.as_ref()?)
+            .ok()?;
+        l1_tx
+            .output
+            .iter()
+            .filter(|output| output.script_pubkey == l1_recipient_script)
+            .try_fold(bitcoin::Amount::ZERO, |acc, output| {
+                acc.checked_add(output.value)
+            })
+    }
+
+    /// L2 amount released by an L1 payment of `l1_fill`, that fills part of
+    /// the remaining L1 amount.
+    /// Shares are rounded down against the total filled so far, so that the
+    /// payment that completes the swap releases all of the remaining L2
+    /// amount. Returns `None` if `l1_fill` is zero or exceeds the remaining
+    /// L1 amount.
+    pub fn l2_amount_for_fill(
+        &self,
+        l1_fill: bitcoin::Amount,
+    ) -> Option<bitcoin::Amount> {
+        if l1_fill == bitcoin::Amount::ZERO
+            || l1_fill > self.remaining_l1_amount()
+        {
+            return None;
+        }
+        let l1_amount = self.l1_amount?.to_sat() as u128;
+        let l2_amount = self.l2_amount.to_sat() as u128;
+        let released = |l1_filled: bitcoin::Amount| {
+            (l2_amount * l1_filled.to_sat() as u128 / l1_amount) as u64
+        };
+        let l1_filled = self.l1_amount_filled.checked_add(l1_fill)?;
+        Some(bitcoin::Amount::from_sat(
+            released(l1_filled) - released(self.l1_amount_filled),
+        ))
+    }
+
+    /// Record an L1 payment of `l1_fill` by the swap's current L1
+    /// transaction. The swap is completed once the full L1 amount has been
+    /// paid, and otherwise waits for the next L1 payment.
+    pub fn record_fill(&mut self, l1_fill: bitcoin::Amount) {
+        self.l1_amount_filled += l1_fill;
+        self.filled_l1_txids.push(self.l1_txid.clone());
+        if self.remaining_l1_amount() == bitcoin::Amount::ZERO {
+            self.mark_completed();
+            return;
+        }
+        self.reset_l1_payment();
+    }
+
+    /// Forget the swap's current L1 transaction, and wait for the next L1
+    /// payment
+    pub fn reset_l1_payment(&mut self) {
+        self.state = SwapState::Pending;
+        self.l1_txid = SwapTxId::Hash32([0; 32]);
+        self.l1_claimer_address = None;
+        self.l2_claimer_address = None;
+        self.l1_txid_validated_at_block_hash = None;
+        self.l1_txid_validated_at_height = None;
+    }
+
     pub fn update_l1_txid(&mut self, l1_txid: SwapTxId) {
         self.l1_txid = l1_txid;
     }
@@ -9,8 +9,8 @@ use thiserror::Error;
 use utoipa::ToSchema;
 
 use super::{
-    Address, AmountOverflowError, Hash, M6id, MerkleRoot, ParentChainType,
-    Txid, hash, hash_with_scratch_buffer,
+    Address, AmountOverflowError, Hash, L1MatchPolicy, M6id, MerkleRoot,
+    ParentChainType, SwapDirection, Txid, hash, hash_with_scratch_buffer,
 };
 use crate::authorization::Authorization;
 
@@ -518,7 +518,8 @@ pub enum TxData {
     /// Regular transaction (no special data)
     #[default]
     Regular,
-    /// Swap creation transaction
+    /// Swap creation transaction. Without an L1 recipient address, this is
+    /// an L1 → L2 offer, which is funded later by a SwapLock transaction.
     SwapCreate {
         swap_id: [u8; 32],
         parent_chain: ParentChainType,
@@ -529,6 +530,17 @@ pub enum TxData {
         l2_amount: u64,
         l1_recipient_address: Option<String>,
         l1_amount: Option<u64>,
+        /// Number of sidechain blocks after creation at which the swap
+        /// expires. None means the swap never expires.
+        expires_in_blocks: Option<u32>,
+        /// Require the L1 payment to carry an OP_RETURN commitment to the
+        /// swap and the filler's L2 address
+        require_l1_commitment: bool,
+        /// Accept several L1 payments, each releasing a proportional share
+        /// of the L2 amount. Only supported for L2 → L1 swaps.
+        allow_partial_fills: bool,
+        /// Rule by which an L1 transaction is matched as the payment
+        l1_match_policy: L1MatchPolicy,
     },
     /// Swap claim transaction
     SwapClaim {
@@ -537,6 +549,42 @@ pub enum TxData {
         l2_claimer_address: Option<Address>,
         proof_data: Option<Vec<u8>>,
     },
+    /// Swap cancellation transaction. Before the swap expires, this is a
+    /// cancellation request by the creator, which brings the expiry forward
+    /// to `Swap::CANCEL_GRACE_BLOCKS` after it. Once the swap has expired,
+    /// and unless it was completed, it spends the swap's locked outputs.
+    SwapCancel { swap_id: [u8; 32] },
+    /// Locks L2 coins to an L1 → L2 swap, in its SwapPending outputs.
+    /// The L2 holder receives the L1 payment at `l1_recipient_address`.
+    SwapLock {
+        swap_id: [u8; 32],
+        l1_recipient_address: String,
+    },
+    /// Extends the L1 header chain tracked for SPV proofs with
+    /// consensus-encoded headers, so that claims only need to prove the
+    /// headers after the tracked tip. The headers must advance the best
+    /// tracked chain.
+    TrackL1Headers {
+        parent_chain: ParentChainType,
+        headers: Vec<Vec<u8>>,
+    },
+}
+
+impl TxData {
+    /// Direction of the swap created by a SwapCreate transaction
+    pub fn swap_direction(&self) -> Option<SwapDirection> {
+        match self {
+            Self::SwapCreate {
+                l1_recipient_address: Some(_),
+                ..
+            } => Some(SwapDirection::L2ToL1),
+            Self::SwapCreate {
+                l1_recipient_address: None,
+                ..
+            } => Some(SwapDirection::L1ToL2),
+            _ => None,
+        }
+    }
 }
 
 // Manual ToSchema implementation for TxData
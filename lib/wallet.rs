@@ -27,8 +27,8 @@ pub use crate::{
 };
 use crate::{
     types::{
-        Accumulator, AmountOverflowError, AmountUnderflowError, PointedOutput,
-        UtreexoError, VERSION, Version, hash,
+        Accumulator, AmountOverflowError, AmountUnderflowError, L1MatchPolicy,
+        PointedOutput, UtreexoError, VERSION, Version, hash,
     },
     util::Watchable,
 };
@@ -64,6 +64,8 @@ pub enum Error {
     DbEnv(#[from] EnvError),
     #[error("Database write error")]
     DbWrite(#[from] RwTxnError),
+    #[error(transparent)]
+    InvalidL1Address(#[from] crate::types::L1AddressError),
     #[error("io error")]
     Io(#[from] std::io::Error),
     #[error(
@@ -291,6 +293,12 @@ impl Wallet {
 
     /// Create a SwapCreate transaction for L2 → L1 swaps
     /// If l2_recipient is None, creates an open swap (anyone can fill it)
+    /// If require_l1_commitment is set, the L1 payment must commit to the
+    /// swap in an OP_RETURN output
+    /// If allow_partial_fills is set, several L1 payments may each fill part
+    /// of the swap
+    /// `l1_match_policy` is the rule by which an L1 payment matches the L1
+    /// amount
     /// `is_locked` is an optional function that returns true if an outpoint is locked to a swap
     #[allow(clippy::too_many_arguments)]
     pub fn create_swap_create_tx<F>(
@@ -302,6 +310,10 @@ impl Wallet {
         l2_recipient: Option<Address>, // Optional - None = open swap
         l2_amount: bitcoin::Amount,
         required_confirmations: Option<u32>,
+        expires_in_blocks: Option<u32>, // None = never expires
+        require_l1_commitment: bool,
+        allow_partial_fills: bool,
+        l1_match_policy: L1MatchPolicy,
         fee: bitcoin::Amount,
         is_locked: F,
     ) -> Result<(Transaction, SwapId), Error>
@@ -314,9 +326,14 @@ impl Wallet {
             l1_amount = %l1_amount.display_dynamic(),
             ?l2_recipient,
             l2_amount = %l2_amount.display_dynamic(),
+            ?expires_in_blocks,
+            require_l1_commitment,
+            allow_partial_fills,
+            %l1_match_policy,
             fee = %fee.display_dynamic(),
             "Creating swap create transaction"
         );
+        let () = parent_chain.validate_address(&l1_recipient_address)?;
 
         // 1. Select UTXOs first (we need the sender address from the UTXOs)
         // IMPORTANT: We must use the address from the first UTXO being spent, not a new address.
@@ -397,15 +414,177 @@ impl Wallet {
                 l2_amount: l2_amount.to_sat(),
                 l1_recipient_address: Some(l1_recipient_address),
                 l1_amount: Some(l1_amount.to_sat()),
+                expires_in_blocks,
+                require_l1_commitment,
+                allow_partial_fills,
+                l1_match_policy,
+            },
+        };
+
+        Ok((tx, swap_id))
+    }
+
+    /// Create a SwapCreate transaction for L1 → L2 swaps.
+    /// The creator offers to pay `l1_amount` on the parent chain, in return
+    /// for `l2_amount` paid to `l2_recipient`. No L2 coins are locked until
+    /// an L2 holder accepts the offer with a SwapLock transaction, so the
+    /// inputs only pay the fee.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_l1_to_l2_swap_create_tx<F>(
+        &self,
+        accumulator: &Accumulator,
+        parent_chain: ParentChainType,
+        l1_amount: bitcoin::Amount,
+        l2_recipient: Address,
+        l2_amount: bitcoin::Amount,
+        required_confirmations: Option<u32>,
+        expires_in_blocks: Option<u32>, // None = never expires
+        require_l1_commitment: bool,
+        l1_match_policy: L1MatchPolicy,
+        fee: bitcoin::Amount,
+        is_locked: F,
+    ) -> Result<(Transaction, SwapId), Error>
+    where
+        F: Fn(&OutPoint) -> bool,
+    {
+        tracing::trace!(
+            ?parent_chain,
+            l1_amount = %l1_amount.display_dynamic(),
+            %l2_recipient,
+            l2_amount = %l2_amount.display_dynamic(),
+            ?expires_in_blocks,
+            require_l1_commitment,
+            %l1_match_policy,
+            fee = %fee.display_dynamic(),
+            "Creating L1 → L2 swap create transaction"
+        );
+
+        // The swap ID commits to the creator, which validation takes from
+        // the first input, so at least one coin is spent
+        let (total, coins) = self.select_coins_with_filter(fee, is_locked)?;
+        let l2_creator_address =
+            coins.values().next().ok_or(Error::NotEnoughFunds)?.address;
+        let change = total - fee;
+        let swap_id = SwapId::from_l1_to_l2(
+            l1_amount,
+            l2_amount,
+            &l2_creator_address,
+            &l2_recipient,
+        );
+
+        let inputs: Vec<_> = coins
+            .into_iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput { outpoint, output });
+                (outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+        let outputs = vec![Output {
+            address: self.get_new_address()?,
+            content: OutputContent::Value(change),
+        }];
+
+        let required_confirmations = required_confirmations
+            .unwrap_or_else(|| parent_chain.default_confirmations());
+        let tx = Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::SwapCreate {
+                swap_id: swap_id.0,
+                parent_chain,
+                l1_txid_bytes: vec![0u8; 32], // Set once the L1 tx is sent
+                required_confirmations,
+                l2_recipient: Some(l2_recipient),
+                l2_amount: l2_amount.to_sat(),
+                l1_recipient_address: None, // Set by SwapLock
+                l1_amount: Some(l1_amount.to_sat()),
+                expires_in_blocks,
+                require_l1_commitment,
+                allow_partial_fills: false,
+                l1_match_policy,
             },
         };
 
         Ok((tx, swap_id))
     }
 
+    /// Create a SwapLock transaction, locking `l2_amount` to an L1 → L2
+    /// swap in return for the L1 payment to `l1_recipient_address`
+    pub fn create_swap_lock_tx<F>(
+        &self,
+        accumulator: &Accumulator,
+        swap_id: SwapId,
+        l2_amount: bitcoin::Amount,
+        l1_recipient_address: String,
+        fee: bitcoin::Amount,
+        is_locked: F,
+    ) -> Result<Transaction, Error>
+    where
+        F: Fn(&OutPoint) -> bool,
+    {
+        tracing::trace!(
+            swap_id = %swap_id,
+            l2_amount = %l2_amount.display_dynamic(),
+            %l1_recipient_address,
+            fee = %fee.display_dynamic(),
+            "Creating swap lock transaction"
+        );
+
+        let required_total =
+            l2_amount.checked_add(fee).ok_or(AmountOverflowError)?;
+        let (total, coins) =
+            self.select_coins_with_filter(required_total, is_locked)?;
+        let change = total - l2_amount - fee;
+        // The locked output is owned by the locker, so that they can cancel
+        // the swap while it is Pending
+        let locker_address =
+            coins.values().next().ok_or(Error::NotEnoughFunds)?.address;
+
+        let inputs: Vec<_> = coins
+            .into_iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput { outpoint, output });
+                (outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+        let outputs = vec![
+            Output {
+                address: locker_address,
+                content: OutputContent::SwapPending {
+                    value: l2_amount,
+                    swap_id: swap_id.0,
+                },
+            },
+            Output {
+                address: self.get_new_address()?,
+                content: OutputContent::Value(change),
+            },
+        ];
+
+        Ok(Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::SwapLock {
+                swap_id: swap_id.0,
+                l1_recipient_address,
+            },
+        })
+    }
+
     /// Create a SwapClaim transaction
     /// For pre-specified swaps: recipient should be swap.l2_recipient
     /// For open swaps: recipient should be the claimer's L2 address (l2_claimer_address)
+    /// For partial fills: released_value is the share of the L2 amount paid
+    /// for by the L1 payment, and the rest stays locked to the swap
+    #[allow(clippy::too_many_arguments)]
     pub fn create_swap_claim_tx(
         &self,
         accumulator: &Accumulator,
@@ -413,11 +592,14 @@ impl Wallet {
         recipient: Address,
         locked_outputs: Vec<(OutPoint, Output)>,
         l2_claimer_address: Option<Address>, // Required for open swaps
+        proof_data: Option<Vec<u8>>,         // SPV proof of the L1 payment
+        released_value: Option<bitcoin::Amount>, // None = full value
     ) -> Result<Transaction, Error> {
         tracing::trace!(
             swap_id = %swap_id,
             ?recipient,
             num_outputs = locked_outputs.len(),
+            ?released_value,
             "Creating swap claim transaction"
         );
 
@@ -446,11 +628,30 @@ impl Wallet {
                 .ok_or(AmountOverflowError)?;
         }
 
-        // 3. Create output to swap recipient
-        let outputs = vec![Output {
+        // 3. Create output to swap recipient. For partial fills, the
+        // remainder is locked back to the swap, owned by the creator.
+        let mut outputs = vec![Output {
             address: recipient,
-            content: OutputContent::Value(total_value),
+            content: OutputContent::Value(
+                released_value.unwrap_or(total_value),
+            ),
         }];
+        if let Some(released_value) = released_value {
+            let remainder = total_value
+                .checked_sub(released_value)
+                .ok_or(Error::NotEnoughFunds)?;
+            let creator = locked_outputs
+                .first()
+                .map(|(_, output)| output.address)
+                .ok_or(Error::NotEnoughFunds)?;
+            outputs.push(Output {
+                address: creator,
+                content: OutputContent::SwapPending {
+                    value: remainder,
+                    swap_id: swap_id.0,
+                },
+            });
+        }
 
         // 4. Create transaction with SwapClaim data
         let tx = Transaction {
@@ -460,13 +661,101 @@ impl Wallet {
             data: TxData::SwapClaim {
                 swap_id: swap_id.0,
                 l2_claimer_address, // For open swaps
-                proof_data: None,
+                proof_data,
             },
         };
 
         Ok(tx)
     }
 
+    /// Create a SwapCancel transaction, returning the value of the swap's
+    /// locked outputs to a new wallet address
+    pub fn create_swap_cancel_tx(
+        &self,
+        accumulator: &Accumulator,
+        swap_id: SwapId,
+        locked_outputs: Vec<(OutPoint, Output)>,
+    ) -> Result<Transaction, Error> {
+        tracing::trace!(
+            swap_id = %swap_id,
+            num_outputs = locked_outputs.len(),
+            "Creating swap cancel transaction"
+        );
+
+        let inputs: Vec<_> = locked_outputs
+            .iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput {
+                    outpoint: *outpoint,
+                    output: output.clone(),
+                });
+                (*outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+
+        use crate::types::GetValue;
+        let mut total_value = bitcoin::Amount::ZERO;
+        for (_, output) in &locked_outputs {
+            total_value = total_value
+                .checked_add(output.get_value())
+                .ok_or(AmountOverflowError)?;
+        }
+        let outputs = vec![Output {
+            address: self.get_new_address()?,
+            content: OutputContent::Value(total_value),
+        }];
+
+        Ok(Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::SwapCancel { swap_id: swap_id.0 },
+        })
+    }
+
+    /// Create a TrackL1Headers transaction for consensus-encoded L1
+    /// headers, paying `fee` from the wallet
+    pub fn create_track_l1_headers_tx<F>(
+        &self,
+        accumulator: &Accumulator,
+        parent_chain: ParentChainType,
+        headers: Vec<Vec<u8>>,
+        fee: bitcoin::Amount,
+        is_locked: F,
+    ) -> Result<Transaction, Error>
+    where
+        F: Fn(&OutPoint) -> bool,
+    {
+        let (total, coins) = self.select_coins_with_filter(fee, is_locked)?;
+        let change = total - fee;
+        let inputs: Vec<_> = coins
+            .into_iter()
+            .map(|(outpoint, output)| {
+                let utxo_hash = hash(&PointedOutput { outpoint, output });
+                (outpoint, utxo_hash)
+            })
+            .collect();
+        let input_utxo_hashes: Vec<BitcoinNodeHash> =
+            inputs.iter().map(|(_, hash)| hash.into()).collect();
+        let proof = accumulator.prove(&input_utxo_hashes)?;
+        let outputs = vec![Output {
+            address: self.get_new_address()?,
+            content: OutputContent::Value(change),
+        }];
+        Ok(Transaction {
+            inputs,
+            proof,
+            outputs,
+            data: TxData::TrackL1Headers {
+                parent_chain,
+                headers,
+            },
+        })
+    }
+
     pub fn create_transaction(
         &self,
         accumulator: &Accumulator,
@@ -780,6 +1069,35 @@ impl Wallet {
         })
     }
 
+    /// Authorize a SwapClaim transaction created by
+    /// [`Self::create_swap_claim_tx`].
+    ///
+    /// Consensus accepts any valid signature of a SwapClaim for the swap's
+    /// SwapPending outputs, even though they are owned by the swap creator.
+    /// Every input is signed with the key for a fresh wallet address.
+    pub fn authorize_swap_claim(
+        &self,
+        transaction: Transaction,
+    ) -> Result<AuthorizedTransaction, Error> {
+        let address = self.get_new_address()?;
+        let txn = self.env.read_txn().map_err(EnvError::from)?;
+        let index = self
+            .address_to_index
+            .try_get(&txn, &address)
+            .map_err(DbError::from)?
+            .ok_or(Error::NoIndex { address })?;
+        let signing_key =
+            self.get_signing_key(&txn, BigEndian::read_u32(&index))?;
+        let authorization = Authorization {
+            verifying_key: signing_key.verifying_key(),
+            signature: crate::authorization::sign(&signing_key, &transaction)?,
+        };
+        Ok(AuthorizedTransaction {
+            authorizations: vec![authorization; transaction.inputs.len()],
+            transaction,
+        })
+    }
+
     pub fn get_new_address(&self) -> Result<Address, Error> {
         let mut txn = self.env.write_txn().map_err(EnvError::from)?;
         let (last_index, _) = self
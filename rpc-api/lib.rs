@@ -2,14 +2,18 @@
 
 #![allow(clippy::too_many_arguments)]
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use coinshift::{
-    net::Peer,
+    net::{BannedPeer, NodeId, Peer},
+    node::WriteTxnStats,
+    parent_chain_rpc::{
+        CircuitState, EndpointHealth, L1ChainStatus, L1Warning, QuorumPolicy,
+    },
     types::{
-        Address, MerkleRoot, OutPoint, Output, OutputContent, ParentChainType,
-        PointedOutput, Swap, SwapId, SwapState, Txid, WithdrawalBundle,
-        schema as coinshift_schema,
+        Address, L1MatchPolicy, MerkleRoot, OutPoint, Output, OutputContent,
+        ParentChainType, PointedOutput, Swap, SwapId, SwapState, Txid,
+        WithdrawalBundle, schema as coinshift_schema,
     },
     wallet::Balance,
 };
@@ -19,7 +23,8 @@ use l2l_openapi::open_api;
 mod schema;
 
 #[open_api(ref_schemas[
-    Address, MerkleRoot, OutPoint, Output, OutputContent, ParentChainType,
+    Address, CircuitState, EndpointHealth, L1ChainStatus, L1MatchPolicy,
+    L1Warning, MerkleRoot, NodeId, OutPoint, Output, OutputContent, ParentChainType, QuorumPolicy,
     Swap, SwapId, SwapState, Txid, schema::BitcoinTxid,
     coinshift_schema::BitcoinAddr, coinshift_schema::BitcoinOutPoint,
 ])]
@@ -30,6 +35,19 @@ pub trait Rpc {
     #[method(name = "balance")]
     async fn balance(&self) -> RpcResult<Balance>;
 
+    /// Ban a peer IP address, disconnecting from any peers at that
+    /// address. Bans last for 24 hours unless a duration is specified.
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "ban_peer")]
+    async fn ban_peer(
+        &self,
+        #[open_api_method_arg(schema(
+            PartialSchema = "coinshift_schema::IpAddr"
+        ))]
+        ip: IpAddr,
+        duration_secs: Option<u64>,
+    ) -> RpcResult<()>;
+
     /// Connect to a peer
     #[open_api_method(output_schema(ToSchema))]
     #[method(name = "connect_peer")]
@@ -130,10 +148,26 @@ pub trait Rpc {
         &self,
     ) -> RpcResult<Option<u32>>;
 
+    /// List banned peer IP addresses
+    #[method(name = "list_banned")]
+    async fn list_banned(&self) -> RpcResult<Vec<BannedPeer>>;
+
     /// List peers
     #[method(name = "list_peers")]
     async fn list_peers(&self) -> RpcResult<Vec<Peer>>;
 
+    /// Get this node's ID, the fingerprint of its persistent certificate.
+    /// Other nodes can pin it to only connect to known nodes.
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "get_node_id")]
+    async fn get_node_id(&self) -> RpcResult<NodeId>;
+
+    /// Configured L1 endpoints for each parent chain, with the health of
+    /// each endpoint and recent disagreements between redundant endpoints
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "l1_status")]
+    async fn l1_status(&self) -> RpcResult<Vec<L1ChainStatus>>;
+
     /// List all UTXOs
     #[method(name = "list_utxos")]
     async fn list_utxos(&self) -> RpcResult<Vec<PointedOutput>>;
@@ -182,6 +216,17 @@ pub trait Rpc {
         fee_sats: u64,
     ) -> RpcResult<Txid>;
 
+    /// Remove the ban for a peer IP address
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "unban_peer")]
+    async fn unban_peer(
+        &self,
+        #[open_api_method_arg(schema(
+            PartialSchema = "coinshift_schema::IpAddr"
+        ))]
+        ip: IpAddr,
+    ) -> RpcResult<()>;
+
     /// Initiate a withdrawal to the specified mainchain address
     #[method(name = "withdraw")]
     async fn withdraw(
@@ -197,8 +242,24 @@ pub trait Rpc {
         mainchain_fee_sats: u64,
     ) -> RpcResult<Txid>;
 
+    /// Time for which block connection has held the database write
+    /// transaction since the node started, in microseconds
+    #[open_api_method(output_schema(ToSchema))]
+    #[method(name = "write_txn_stats")]
+    async fn write_txn_stats(&self) -> RpcResult<WriteTxnStats>;
+
     /// Create a swap (L2 → L1)
     /// If l2_recipient is None, creates an open swap (anyone can fill it)
+    /// If expires_in_blocks is set, the swap expires that many sidechain
+    /// blocks after creation, after which the locked L2 coins can be
+    /// returned to the creator with `cancel_swap`
+    /// If require_l1_commitment is set, the L1 payment must carry the
+    /// OP_RETURN returned by `get_swap_commitment`
+    /// If allow_partial_fills is set, several L1 payments may each pay part
+    /// of l1_amount_sats, and release a proportional share of the L2 amount
+    /// l1_match_policy is the rule by which an L1 payment matches
+    /// l1_amount_sats: `exact` (the default), `at_least`, or
+    /// `sum_of_outputs` to the L1 recipient in one transaction
     #[open_api_method(output_schema(
         PartialSchema = "schema::Tuple<SwapId, Txid>"
     ))]
@@ -211,9 +272,56 @@ pub trait Rpc {
         l2_recipient: Option<Address>, // Optional - None = open swap
         l2_amount_sats: u64,
         required_confirmations: Option<u32>,
+        expires_in_blocks: Option<u32>,
+        require_l1_commitment: bool,
+        allow_partial_fills: bool,
+        l1_match_policy: Option<L1MatchPolicy>,
         fee_sats: u64,
     ) -> RpcResult<(SwapId, Txid)>;
 
+    /// Create an L1 → L2 swap offer: pay l1_amount_sats on the parent chain
+    /// in return for l2_amount_sats to l2_recipient.
+    /// No L2 coins are locked until an L2 holder calls `lock_swap`.
+    /// If l2_recipient is None, a new wallet address is used.
+    /// l1_match_policy is as for `create_swap`.
+    #[open_api_method(output_schema(
+        PartialSchema = "schema::Tuple<SwapId, Txid>"
+    ))]
+    #[method(name = "create_l1_to_l2_swap")]
+    async fn create_l1_to_l2_swap(
+        &self,
+        parent_chain: ParentChainType,
+        l1_amount_sats: u64,
+        l2_recipient: Option<Address>,
+        l2_amount_sats: u64,
+        required_confirmations: Option<u32>,
+        expires_in_blocks: Option<u32>,
+        require_l1_commitment: bool,
+        l1_match_policy: Option<L1MatchPolicy>,
+        fee_sats: u64,
+    ) -> RpcResult<(SwapId, Txid)>;
+
+    /// Accept an L1 → L2 swap offer, locking the swap's L2 amount from this
+    /// wallet. The L1 payment is expected at l1_recipient_address.
+    #[method(name = "lock_swap")]
+    async fn lock_swap(
+        &self,
+        swap_id: SwapId,
+        l1_recipient_address: String,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
+    /// Broadcast a TrackL1Headers transaction with the next L1 headers
+    /// after the best tracked L1 header chain, from the configured L1 node.
+    /// A claim's SPV proof can only carry a limited number of L1 headers
+    /// after the tracked ones, so this may be needed before a claim.
+    #[method(name = "track_l1_headers")]
+    async fn track_l1_headers(
+        &self,
+        parent_chain: ParentChainType,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
     /// Reconstruct all swaps from the blockchain
     /// This is useful for recovering from database corruption or verifying swap integrity
     /// Returns the number of swaps reconstructed
@@ -222,6 +330,7 @@ pub trait Rpc {
 
     /// Update swap L1 transaction ID (called when L1 transaction is detected).
     /// For open swaps, pass l2_claimer_address so the claim is only valid for that address.
+    /// Not allowed for swaps that require an L1 commitment.
     #[method(name = "update_swap_l1_txid")]
     async fn update_swap_l1_txid(
         &self,
@@ -231,6 +340,16 @@ pub trait Rpc {
         l2_claimer_address: Option<Address>,
     ) -> RpcResult<()>;
 
+    /// Get the hex-encoded OP_RETURN data that an L1 payment must carry to
+    /// fill a swap that requires an L1 commitment, declaring
+    /// l2_claimer_address as the filler's L2 address
+    #[method(name = "get_swap_commitment")]
+    async fn get_swap_commitment(
+        &self,
+        swap_id: SwapId,
+        l2_claimer_address: Address,
+    ) -> RpcResult<String>;
+
     /// Get swap status
     #[open_api_method(output_schema(ToSchema))]
     #[method(name = "get_swap_status")]
@@ -239,6 +358,8 @@ pub trait Rpc {
 
     /// Claim a swap (after L1 transaction has required confirmations)
     /// For open swaps, l2_claimer_address is required (the claimer's L2 address)
+    /// Open swaps without an L1 commitment can only be claimed from the swap
+    /// creator's wallet, which signs for the claimer
     #[method(name = "claim_swap")]
     async fn claim_swap(
         &self,
@@ -259,13 +380,12 @@ pub trait Rpc {
         recipient: Address,
     ) -> RpcResult<Vec<Swap>>;
 
-    /// Cancel a swap (unlock outputs and mark as cancelled).
-    /// Only allowed for Pending swaps (before L1 transaction is detected).
+    /// Broadcast a SwapCancel transaction. For swaps that have expired by
+    /// the next block, this returns the swap's locked outputs to the wallet
+    /// that locked them. For other swaps, the creator requests the
+    /// cancellation, and the swap can still be claimed until it expires
+    /// `Swap::CANCEL_GRACE_BLOCKS` after the request. L1 → L2 offers that
+    /// are awaiting a lock are cancelled right away.
     #[method(name = "cancel_swap")]
-    async fn cancel_swap(&self, swap_id: SwapId) -> RpcResult<()>;
-
-    /// Delete a swap from the database.
-    /// Only allowed for Pending or Cancelled swaps.
-    #[method(name = "delete_swap")]
-    async fn delete_swap(&self, swap_id: SwapId) -> RpcResult<()>;
+    async fn cancel_swap(&self, swap_id: SwapId) -> RpcResult<Txid>;
 }